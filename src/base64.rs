@@ -0,0 +1,103 @@
+//! Base64 for binary data held in strings
+
+use alloc::{string::String, vec::Vec};
+
+use crate::Error;
+#[cfg(feature = "base64")]
+use crate::Json;
+
+/// The characters used by base64
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes bytes as base64 with padding
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    let mut result = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk
+            .iter()
+            .chain([0, 0].iter())
+            .take(3)
+            .fold(0u32, |bits, &byte| (bits << 8) | u32::from(byte));
+        for index in 0..4 {
+            if index <= chunk.len() {
+                result.push(char::from(
+                    BASE64[(bits >> (18 - 6 * index)) as usize & 0x3f],
+                ));
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+/// Decodes padded or unpadded base64
+pub(crate) fn decode_base64(text: &str) -> Result<Vec<u8>, Error> {
+    // A single character left over can't hold a whole byte
+    let text = text.trim_end_matches('=');
+    if text.len() % 4 == 1 {
+        return Err(Error::InvalidValue);
+    }
+
+    let mut result = Vec::new();
+    let (mut bits, mut count) = (0u32, 0);
+    for byte in text.bytes() {
+        let value = BASE64
+            .iter()
+            .position(|&ch| ch == byte)
+            .ok_or(Error::InvalidValue)?;
+        bits = (bits << 6) | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            result.push((bits >> count) as u8);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(feature = "base64")]
+impl Json {
+    /// Writes bytes as a base64 string with padding
+    pub fn from_bytes_base64(bytes: &[u8]) -> Self {
+        Json::String(encode_base64(bytes))
+    }
+
+    /// Returns the bytes in a padded or unpadded base64 string
+    pub fn as_base64_bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            Json::String(text) => decode_base64(text).ok(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "base64"))]
+mod tests {
+    use alloc::borrow::ToOwned;
+
+    use crate::Json;
+
+    #[test]
+    fn base64() {
+        for bytes in [&b""[..], b"h", b"he", b"hel", b"hello"] {
+            assert_eq!(
+                Json::from_bytes_base64(bytes).as_base64_bytes().as_deref(),
+                Some(bytes)
+            );
+        }
+        assert_eq!(
+            Json::from_bytes_base64(b"hello"),
+            Json::String("aGVsbG8=".to_owned())
+        );
+        assert_eq!(
+            Json::String("aGVsbG8".to_owned())
+                .as_base64_bytes()
+                .as_deref(),
+            Some(&b"hello"[..])
+        );
+        assert_eq!(Json::String("a?".to_owned()).as_base64_bytes(), None);
+        assert_eq!(Json::String("aGVsb".to_owned()).as_base64_bytes(), None);
+        assert_eq!(Json::Null.as_base64_bytes(), None);
+    }
+}
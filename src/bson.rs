@@ -0,0 +1,488 @@
+//! Conversion between JSON objects and BSON documents
+//!
+//! BSON specific types are mapped to and from objects in the canonical MongoDB extended JSON
+//! style, e.g. object IDs become `{"$oid": "..."}` and dates become
+//! `{"$date": {"$numberLong": "..."}}`.
+
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    Error, FromJson, Json, MAX_DEPTH,
+    base64::{decode_base64, encode_base64},
+};
+
+/// Returns the value of an object's only property if it has the given name
+fn single<'a>(items: &'a [(String, Json)], name: &str) -> Option<&'a Json> {
+    match items {
+        [(key, value)] if key == name => Some(value),
+        _ => None,
+    }
+}
+
+/// Returns the property with the given name
+fn property<'a>(items: &'a [(String, Json)], name: &str) -> Option<&'a Json> {
+    items
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value)
+}
+
+/// Writes a cstring, which can't contain null bytes
+fn write_cstring(out: &mut Vec<u8>, string: &str) -> Result<(), Error> {
+    if string.contains('\0') {
+        return Err(Error::InvalidValue);
+    }
+    out.extend(string.as_bytes());
+    out.push(0);
+    Ok(())
+}
+
+/// Writes a string with its length
+fn write_string(out: &mut Vec<u8>, string: &str) {
+    out.extend((string.len() as i32 + 1).to_le_bytes());
+    out.extend(string.as_bytes());
+    out.push(0);
+}
+
+/// Writes a document, whose keys are given by the iterator
+fn write_document<'a, I: Iterator<Item = (String, &'a Json)>>(
+    out: &mut Vec<u8>,
+    items: I,
+) -> Result<(), Error> {
+    // Reserve space for the length, which is known afterwards
+    let start = out.len();
+    out.extend([0; 4]);
+
+    // Write the elements
+    for (key, value) in items {
+        let type_index = out.len();
+        out.push(0);
+        write_cstring(out, &key)?;
+        out[type_index] = write_element(out, value)?;
+    }
+    out.push(0);
+
+    // Fill in the length of the document
+    let length = (out.len() - start) as i32;
+    out[start..start + 4].copy_from_slice(&length.to_le_bytes());
+    Ok(())
+}
+
+/// Writes an extended JSON object as its BSON type, returning `None` if it's a normal object
+fn write_extended(out: &mut Vec<u8>, items: &[(String, Json)]) -> Result<Option<u8>, Error> {
+    // Write object IDs
+    if let Some(Json::String(id)) = single(items, "$oid") {
+        if id.len() != 24 {
+            return Err(Error::InvalidValue);
+        }
+        for index in (0..24).step_by(2) {
+            let byte = id
+                .get(index..index + 2)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or(Error::InvalidValue)?;
+            out.push(byte);
+        }
+        return Ok(Some(0x07));
+    }
+
+    // Write dates as milliseconds since the epoch
+    if let Some(date) = single(items, "$date") {
+        let milliseconds = match date {
+            Json::Object(date) => match single(date, "$numberLong") {
+                Some(Json::String(number)) => number.parse().map_err(|_| Error::InvalidValue)?,
+                _ => return Err(Error::InvalidValue),
+            },
+            date => i64::from_json(date).ok_or(Error::InvalidValue)?,
+        };
+        out.extend(milliseconds.to_le_bytes());
+        return Ok(Some(0x09));
+    }
+
+    // Write binary data with its subtype
+    if let Some(Json::Object(binary)) = single(items, "$binary") {
+        let (Some(Json::String(data)), Some(Json::String(subtype))) =
+            (property(binary, "base64"), property(binary, "subType"))
+        else {
+            return Err(Error::InvalidValue);
+        };
+        let data = decode_base64(data)?;
+        out.extend((data.len() as i32).to_le_bytes());
+        out.push(u8::from_str_radix(subtype, 16).map_err(|_| Error::InvalidValue)?);
+        out.extend(data);
+        return Ok(Some(0x05));
+    }
+
+    // Write regular expressions
+    if let Some(Json::Object(regex)) = single(items, "$regularExpression") {
+        let (Some(Json::String(pattern)), Some(Json::String(options))) =
+            (property(regex, "pattern"), property(regex, "options"))
+        else {
+            return Err(Error::InvalidValue);
+        };
+        write_cstring(out, pattern)?;
+        write_cstring(out, options)?;
+        return Ok(Some(0x0b));
+    }
+
+    // Write timestamps
+    if let Some(Json::Object(timestamp)) = single(items, "$timestamp") {
+        let (Some(time), Some(increment)) = (
+            property(timestamp, "t").and_then(u32::from_json),
+            property(timestamp, "i").and_then(u32::from_json),
+        ) else {
+            return Err(Error::InvalidValue);
+        };
+        out.extend(increment.to_le_bytes());
+        out.extend(time.to_le_bytes());
+        return Ok(Some(0x11));
+    }
+
+    // Write the minimum and maximum keys
+    if single(items, "$minKey").is_some() {
+        return Ok(Some(0xff));
+    }
+    if single(items, "$maxKey").is_some() {
+        return Ok(Some(0x7f));
+    }
+    Ok(None)
+}
+
+/// Writes the value of an element, returning its type
+fn write_element(out: &mut Vec<u8>, value: &Json) -> Result<u8, Error> {
+    Ok(match value {
+        // Write objects as extended JSON types or embedded documents
+        Json::Object(items) => match write_extended(out, items)? {
+            Some(element_type) => element_type,
+            None => {
+                write_document(out, items.iter().map(|(key, value)| (key.clone(), value)))?;
+                0x03
+            }
+        },
+
+        // Write lists as documents with the indices as keys
+        Json::List(values) => {
+            write_document(
+                out,
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, value)| (index.to_string(), value)),
+            )?;
+            0x04
+        }
+
+        // Write strings
+        Json::String(string) => {
+            write_string(out, string);
+            0x02
+        }
+
+        // Write whole numbers as the smallest integer they fit in, as doubles otherwise
+        Json::NumberRaw(_) => write_element(out, &value.plain())?,
+        Json::Number(number) => {
+            let integer = *number as i64;
+            if (integer as f64).to_bits() != number.to_bits() || integer == i64::MAX {
+                out.extend(number.to_le_bytes());
+                0x01
+            } else if let Ok(integer) = i32::try_from(integer) {
+                out.extend(integer.to_le_bytes());
+                0x10
+            } else {
+                out.extend(integer.to_le_bytes());
+                0x12
+            }
+        }
+
+        // Write booleans and null
+        Json::Bool(value) => {
+            out.push(u8::from(*value));
+            0x08
+        }
+        Json::Null => 0x0a,
+    })
+}
+
+/// Reads BSON data from a byte slice
+struct Reader<'a> {
+    /// The bytes that haven't been read yet
+    bytes: &'a [u8],
+
+    /// The number of documents around the bytes
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Reads a number of bytes
+    fn take(&mut self, count: usize) -> Result<&'a [u8], Error> {
+        if self.bytes.len() < count {
+            return Err(Error::UnexpectedEndOfFile);
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    /// Reads a fixed number of bytes
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    /// Reads a length, which can't be negative
+    fn length(&mut self) -> Result<usize, Error> {
+        usize::try_from(i32::from_le_bytes(self.array()?)).map_err(|_| Error::InvalidValue)
+    }
+
+    /// Reads a null terminated string
+    fn cstring(&mut self) -> Result<String, Error> {
+        let end = self
+            .bytes
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or(Error::UnclosedString)?;
+        let string = core::str::from_utf8(self.take(end)?).map_err(|_| Error::InvalidValue)?;
+        self.take(1)?;
+        Ok(string.to_owned())
+    }
+
+    /// Reads a string with its length
+    fn string(&mut self) -> Result<String, Error> {
+        let length = self.length()?;
+        match self.take(length)?.split_last() {
+            Some((0, string)) => core::str::from_utf8(string)
+                .map(ToOwned::to_owned)
+                .map_err(|_| Error::InvalidValue),
+            _ => Err(Error::UnclosedString),
+        }
+    }
+
+    /// Reads a document as a list of elements
+    fn document(&mut self) -> Result<Vec<(String, Json)>, Error> {
+        // Refuse to nest deeper than parsing does by default
+        if self.depth >= MAX_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
+
+        // Limit reading to the length of the document
+        let length = self.length()?;
+        let mut document = Reader {
+            bytes: self.take(length.checked_sub(4).ok_or(Error::InvalidValue)?)?,
+            depth: self.depth + 1,
+        };
+
+        // Read the elements until the terminating null byte
+        let mut items = Vec::new();
+        loop {
+            match document.array()? {
+                [0] if document.bytes.is_empty() => return Ok(items),
+                [0] => return Err(Error::TrailingCharacters),
+                [element_type] => {
+                    let key = document.cstring()?;
+                    items.push((key, document.element(element_type)?));
+                }
+            }
+        }
+    }
+
+    /// Reads the value of an element of the given type
+    fn element(&mut self, element_type: u8) -> Result<Json, Error> {
+        /// Creates an object with a single property
+        fn single(name: &str, value: Json) -> Json {
+            Json::Object(Vec::from([(name.to_owned(), value)]))
+        }
+
+        Ok(match element_type {
+            // Read doubles, strings, documents and arrays
+            0x01 => Json::Number(f64::from_le_bytes(self.array()?)),
+            0x02 => Json::String(self.string()?),
+            0x03 => Json::Object(self.document()?),
+            0x04 => Json::List(
+                self.document()?
+                    .into_iter()
+                    .map(|(_, value)| value)
+                    .collect(),
+            ),
+
+            // Read binary data as base64 with its subtype
+            0x05 => {
+                let length = self.length()?;
+                let [subtype] = self.array()?;
+                let data = encode_base64(self.take(length)?);
+                single(
+                    "$binary",
+                    Json::Object(Vec::from([
+                        ("base64".to_owned(), Json::String(data)),
+                        ("subType".to_owned(), Json::String(format!("{subtype:02x}"))),
+                    ])),
+                )
+            }
+
+            // Read object IDs as hexadecimal strings
+            0x07 => single(
+                "$oid",
+                Json::String(
+                    self.take(12)?
+                        .iter()
+                        .map(|byte| format!("{byte:02x}"))
+                        .collect(),
+                ),
+            ),
+
+            // Read booleans
+            0x08 => match self.array()? {
+                [0] => Json::Bool(false),
+                [1] => Json::Bool(true),
+                _ => return Err(Error::InvalidValue),
+            },
+
+            // Read dates as milliseconds since the epoch
+            0x09 => single(
+                "$date",
+                single(
+                    "$numberLong",
+                    Json::String(i64::from_le_bytes(self.array()?).to_string()),
+                ),
+            ),
+
+            // Read null and the deprecated undefined value as null
+            0x06 | 0x0a => Json::Null,
+
+            // Read regular expressions
+            0x0b => {
+                let pattern = self.cstring()?;
+                let options = self.cstring()?;
+                single(
+                    "$regularExpression",
+                    Json::Object(Vec::from([
+                        ("pattern".to_owned(), Json::String(pattern)),
+                        ("options".to_owned(), Json::String(options)),
+                    ])),
+                )
+            }
+
+            // Read integers
+            0x10 => Json::Number(f64::from(i32::from_le_bytes(self.array()?))),
+            0x12 => Json::Number(i64::from_le_bytes(self.array()?) as f64),
+
+            // Read timestamps
+            0x11 => {
+                let increment = u32::from_le_bytes(self.array()?);
+                let time = u32::from_le_bytes(self.array()?);
+                single(
+                    "$timestamp",
+                    Json::Object(Vec::from([
+                        ("t".to_owned(), Json::Number(f64::from(time))),
+                        ("i".to_owned(), Json::Number(f64::from(increment))),
+                    ])),
+                )
+            }
+
+            // Read the minimum and maximum keys
+            0xff => single("$minKey", Json::Number(1.0)),
+            0x7f => single("$maxKey", Json::Number(1.0)),
+
+            // Decimals, code and deprecated types aren't supported
+            _ => return Err(Error::InvalidValue),
+        })
+    }
+}
+
+impl Json {
+    /// Encodes an object as a BSON document
+    pub fn to_bson(&self) -> Result<Vec<u8>, Error> {
+        let Json::Object(items) = self else {
+            return Err(Error::InvalidValue);
+        };
+        let mut out = Vec::new();
+        write_document(
+            &mut out,
+            items.iter().map(|(key, value)| (key.clone(), value)),
+        )?;
+        Ok(out)
+    }
+
+    /// Decodes a BSON document as an object, failing if there are bytes left after it
+    ///
+    /// Documents and arrays nested deeper than 128 levels fail with [`Error::NestingTooDeep`],
+    /// like when parsing with the default options.
+    pub fn from_bson(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader { bytes, depth: 0 };
+        let document = reader.document()?;
+        if !reader.bytes.is_empty() {
+            return Err(Error::TrailingCharacters);
+        }
+        Ok(Json::Object(document))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::ToOwned;
+
+    use crate::{Error, Json};
+
+    #[test]
+    fn bson_encoding() {
+        assert_eq!(
+            "{\"hello\":\"world\"}"
+                .parse::<Json>()
+                .unwrap()
+                .to_bson()
+                .unwrap(),
+            b"\x16\x00\x00\x00\x02hello\x00\x06\x00\x00\x00world\x00\x00"
+        );
+        assert!(Json::Null.to_bson().is_err());
+
+        // Dates and timestamps that don't fit are refused instead of being cut off
+        for invalid in [
+            "{\"date\":{\"$date\":1.5}}",
+            "{\"date\":{\"$date\":1e30}}",
+            "{\"time\":{\"$timestamp\":{\"t\":-1,\"i\":1}}}",
+            "{\"time\":{\"$timestamp\":{\"t\":5,\"i\":4294967296}}}",
+            "{\"data\":{\"$binary\":{\"base64\":\"aGVsb\",\"subType\":\"00\"}}}",
+        ] {
+            assert!(
+                invalid.parse::<Json>().unwrap().to_bson().is_err(),
+                "{invalid}"
+            );
+        }
+        let date = "{\"date\":{\"$date\":1700000000000}}";
+        assert!(date.parse::<Json>().unwrap().to_bson().is_ok());
+    }
+
+    #[test]
+    fn bson_round_trip() {
+        let value = "{\"list\":[1,-2.25,\"text\",false,null,{}],\"big\":-12345678901,\
+            \"id\":{\"$oid\":\"507f1f77bcf86cd799439011\"},\
+            \"date\":{\"$date\":{\"$numberLong\":\"1700000000000\"}},\
+            \"data\":{\"$binary\":{\"base64\":\"aGVsbG8=\",\"subType\":\"00\"}},\
+            \"regex\":{\"$regularExpression\":{\"pattern\":\"^a\",\"options\":\"i\"}},\
+            \"time\":{\"$timestamp\":{\"t\":5,\"i\":1}},\"min\":{\"$minKey\":1}}"
+            .parse::<Json>()
+            .unwrap();
+        assert_eq!(Json::from_bson(&value.to_bson().unwrap()).unwrap(), value);
+    }
+
+    #[test]
+    fn bson_decoding_errors() {
+        assert!(Json::from_bson(b"\x05\x00\x00\x00").is_err());
+        assert!(Json::from_bson(b"\x05\x00\x00\x00\x00\x00").is_err());
+        assert!(Json::from_bson(b"\x0c\x00\x00\x00\x08a\x00\x02\x00").is_err());
+
+        // Documents nested too deeply are refused instead of running out of stack
+        let nested = |depth| {
+            (1..depth).fold(Json::object(), |value, _| {
+                Json::Object([("a".to_owned(), value)].into())
+            })
+        };
+        assert!(Json::from_bson(&nested(128).to_bson().unwrap()).is_ok());
+        assert!(matches!(
+            Json::from_bson(&nested(129).to_bson().unwrap()),
+            Err(Error::NestingTooDeep)
+        ));
+    }
+}
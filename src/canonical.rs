@@ -0,0 +1,150 @@
+//! Canonical serialization following the JSON Canonicalization Scheme (RFC 8785)
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::{self, Write};
+
+use crate::{Json, ser::write_string};
+
+/// Writes a number the way ECMAScript converts numbers to strings, writing numbers JSON can't
+/// represent as `null`
+fn write_number<W: Write>(out: &mut W, number: f64) -> fmt::Result {
+    if !number.is_finite() {
+        return out.write_str("null");
+    }
+
+    // Zero has no sign, and other numbers are written from their shortest digits
+    if number == 0.0 {
+        return out.write_char('0');
+    }
+    if number < 0.0 {
+        out.write_char('-')?;
+    }
+    let scientific = format!("{:e}", number.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let digits = mantissa.replace('.', "");
+    let count = digits.len() as i32;
+    let point = exponent.parse::<i32>().unwrap_or(0) + 1;
+
+    match point {
+        // Integers with up to 21 digits are written in full
+        _ if count <= point && point <= 21 => {
+            write!(out, "{digits}{:0>1$}", "", (point - count) as usize)
+        }
+
+        // Other numbers above 10^-7 are written with a decimal point
+        1..=21 => {
+            let (integer, fraction) = digits.split_at(point as usize);
+            write!(out, "{integer}.{fraction}")
+        }
+        -5..=0 => write!(out, "0.{:0>1$}{digits}", "", -point as usize),
+
+        // Anything else is written with an exponent
+        _ => {
+            let (first, rest) = digits.split_at(1);
+            out.write_str(first)?;
+            if !rest.is_empty() {
+                write!(out, ".{rest}")?;
+            }
+            let sign = if point > 0 { '+' } else { '-' };
+            write!(out, "e{sign}{}", (point - 1).abs())
+        }
+    }
+}
+
+/// Writes a value in canonical form
+fn write_value<W: Write>(out: &mut W, value: &Json) -> fmt::Result {
+    match value {
+        // Write a list without whitespace
+        Json::List(values) => {
+            out.write_char('[')?;
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    out.write_char(',')?;
+                }
+                write_value(out, value)?;
+            }
+            out.write_char(']')
+        }
+
+        // Write an object with its properties sorted by the UTF-16 code units of their keys
+        Json::Object(items) => {
+            let mut items = items.iter().collect::<Vec<_>>();
+            items.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.write_char('{')?;
+            for (index, (key, value)) in items.into_iter().enumerate() {
+                if index > 0 {
+                    out.write_char(',')?;
+                }
+                write_string(out, key)?;
+                out.write_char(':')?;
+                write_value(out, value)?;
+            }
+            out.write_char('}')
+        }
+
+        // Write a number in the ECMAScript format
+        Json::Number(number) => write_number(out, *number),
+        Json::NumberRaw(_) => write_value(out, &value.plain()),
+
+        // Write any other value like the compact format does
+        _ => write!(out, "{value}"),
+    }
+}
+
+impl Json {
+    /// Writes the value in the canonical form of RFC 8785
+    ///
+    /// Objects are sorted by key and numbers use the shortest form ECMAScript would produce, so
+    /// equal documents always produce the same bytes.
+    pub fn write_canonical<W: Write>(&self, out: &mut W) -> fmt::Result {
+        write_value(out, self)
+    }
+
+    /// Returns the value in the canonical form of RFC 8785
+    pub fn to_canonical_string(&self) -> String {
+        let mut out = String::new();
+        self.write_canonical(&mut out)
+            .expect("writing to a string can't fail");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Json;
+
+    #[test]
+    fn sorting() {
+        let value =
+            "{\"b\": [1, {\"z\": null, \"\\ufb01\": 1, \"\\ud83d\\ude00\": 2}], \"a\": true}"
+                .parse::<Json>()
+                .unwrap();
+        assert_eq!(
+            value.to_canonical_string(),
+            "{\"a\":true,\"b\":[1,{\"z\":null,\"😀\":2,\"ﬁ\":1}]}"
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        for (number, text) in [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-1.5, "-1.5"),
+            (100.0, "100"),
+            (0.000001, "0.000001"),
+            (0.0000001, "1e-7"),
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (123456.789, "123456.789"),
+            (4.5e-300, "4.5e-300"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+            (f64::NAN, "null"),
+            (f64::INFINITY, "null"),
+            (f64::NEG_INFINITY, "null"),
+        ] {
+            assert_eq!(Json::Number(number).to_canonical_string(), text);
+        }
+    }
+}
@@ -0,0 +1,435 @@
+//! Conversion between JSON values and CBOR (RFC 8949)
+
+use alloc::{string::String, vec::Vec};
+use core::convert::Infallible;
+
+use crate::{Error, Event, EventSink, Json, MAX_DEPTH};
+
+/// The major type of unsigned integers
+const UNSIGNED: u8 = 0;
+
+/// The major type of negative integers
+const NEGATIVE: u8 = 1;
+
+/// The major type of byte strings
+const BYTES: u8 = 2;
+
+/// The major type of text strings
+const TEXT: u8 = 3;
+
+/// The major type of arrays
+const ARRAY: u8 = 4;
+
+/// The major type of maps
+const MAP: u8 = 5;
+
+/// The major type of tagged values
+const TAG: u8 = 6;
+
+/// The major type of simple values and floats
+const SIMPLE: u8 = 7;
+
+/// The additional info marking an indefinite length
+const INDEFINITE: u8 = 31;
+
+/// The byte ending an indefinite length item
+const BREAK: u8 = 0xff;
+
+/// Writes the head of an item with its major type and argument
+fn write_head(out: &mut Vec<u8>, major: u8, argument: u64) {
+    let major = major << 5;
+    match argument {
+        0..=23 => out.push(major | argument as u8),
+        24..=0xff => out.extend([major | 24, argument as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend((argument as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend((argument as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend(argument.to_be_bytes());
+        }
+    }
+}
+
+/// Writes whole numbers as integers if they fit, as double precision floats otherwise
+fn write_number(out: &mut Vec<u8>, number: f64) {
+    let integer = number as i128;
+    if (integer as f64).to_bits() != number.to_bits() || !(-1 << 64..1 << 64).contains(&integer) {
+        out.push(SIMPLE << 5 | 27);
+        out.extend(number.to_be_bytes());
+    } else if integer >= 0 {
+        write_head(out, UNSIGNED, integer as u64);
+    } else {
+        write_head(out, NEGATIVE, (-1 - integer) as u64);
+    }
+}
+
+/// Writes a text string
+fn write_text(out: &mut Vec<u8>, text: &str) {
+    write_head(out, TEXT, text.len() as u64);
+    out.extend(text.as_bytes());
+}
+
+/// Writes a value as CBOR
+fn write_value(out: &mut Vec<u8>, value: &Json) {
+    match value {
+        // Write lists as arrays
+        Json::List(values) => {
+            write_head(out, ARRAY, values.len() as u64);
+            values.iter().for_each(|value| write_value(out, value));
+        }
+
+        // Write objects as maps with text keys
+        Json::Object(items) => {
+            write_head(out, MAP, items.len() as u64);
+            for (key, value) in items {
+                write_text(out, key);
+                write_value(out, value);
+            }
+        }
+
+        // Write strings as text strings
+        Json::String(string) => write_text(out, string),
+
+        // Write numbers
+        Json::Number(number) => write_number(out, *number),
+        Json::NumberRaw(_) => write_value(out, &value.plain()),
+
+        // Write the simple values false, true and null
+        Json::Bool(false) => out.push(SIMPLE << 5 | 20),
+        Json::Bool(true) => out.push(SIMPLE << 5 | 21),
+        Json::Null => out.push(SIMPLE << 5 | 22),
+    }
+}
+
+/// Encodes events as CBOR, writing lists and objects with indefinite lengths
+///
+/// This allows transcoding JSON to CBOR with [`EventSink::transcode`] without building the value
+/// in memory.
+#[derive(Debug, Default)]
+pub struct CborEncoder {
+    /// The encoded bytes
+    out: Vec<u8>,
+}
+
+impl CborEncoder {
+    /// Creates an encoder without any output yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the encoded bytes
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.out
+    }
+}
+
+impl EventSink for CborEncoder {
+    type Error = Infallible;
+
+    fn event(&mut self, event: Event) -> Result<(), Self::Error> {
+        match event {
+            Event::StartObject => self.out.push(MAP << 5 | INDEFINITE),
+            Event::StartList => self.out.push(ARRAY << 5 | INDEFINITE),
+            Event::EndObject | Event::EndList => self.out.push(BREAK),
+            Event::Key(text) | Event::String(text) => write_text(&mut self.out, &text),
+            Event::Number(number) => write_number(&mut self.out, number),
+            Event::Bool(false) => self.out.push(SIMPLE << 5 | 20),
+            Event::Bool(true) => self.out.push(SIMPLE << 5 | 21),
+            Event::Null => self.out.push(SIMPLE << 5 | 22),
+        }
+        Ok(())
+    }
+}
+
+/// Reads CBOR items from a byte slice
+struct Reader<'a> {
+    /// The bytes that haven't been read yet
+    bytes: &'a [u8],
+
+    /// The number of arrays, maps and tags around the next item
+    depth: usize,
+}
+
+impl Reader<'_> {
+    /// Reads a number of bytes
+    fn take(&mut self, count: usize) -> Result<&[u8], Error> {
+        if self.bytes.len() < count {
+            return Err(Error::UnexpectedEndOfFile);
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    /// Reads a single byte
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads the argument of an item with the given additional info
+    fn argument(&mut self, info: u8) -> Result<u64, Error> {
+        Ok(match info {
+            0..=23 => u64::from(info),
+            24 => u64::from(self.byte()?),
+            25 => u64::from(u16::from_be_bytes(self.take(2)?.try_into().unwrap())),
+            26 => u64::from(u32::from_be_bytes(self.take(4)?.try_into().unwrap())),
+            27 => u64::from_be_bytes(self.take(8)?.try_into().unwrap()),
+            _ => return Err(Error::InvalidValue),
+        })
+    }
+
+    /// Returns whether the next byte ends an indefinite length item, skipping it if it does
+    fn at_break(&mut self) -> Result<bool, Error> {
+        match self.bytes.first() {
+            Some(&BREAK) => {
+                self.bytes = &self.bytes[1..];
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(Error::UnexpectedEndOfFile),
+        }
+    }
+
+    /// Reads a text string, which may be split into chunks
+    fn text(&mut self, info: u8) -> Result<String, Error> {
+        // Read a definite length string directly
+        if info != INDEFINITE {
+            let length = usize::try_from(self.argument(info)?).map_err(|_| Error::InvalidValue)?;
+            return String::from_utf8(self.take(length)?.to_vec()).map_err(|_| Error::InvalidValue);
+        }
+
+        // Concatenate the definite length chunks of an indefinite length string
+        let mut result = String::new();
+        while !self.at_break()? {
+            let head = self.byte()?;
+            if head >> 5 != TEXT || head & 0x1f == INDEFINITE {
+                return Err(Error::InvalidValue);
+            }
+            result.push_str(&self.text(head & 0x1f)?);
+        }
+        Ok(result)
+    }
+
+    /// Reads the number of items of an array or map, or `None` for an indefinite length
+    fn length(&mut self, info: u8) -> Result<Option<usize>, Error> {
+        if info == INDEFINITE {
+            return Ok(None);
+        }
+        usize::try_from(self.argument(info)?)
+            .map(Some)
+            .map_err(|_| Error::InvalidValue)
+    }
+
+    /// Returns whether there is another item in an array or map
+    fn has_item(&mut self, length: Option<usize>, read: usize) -> Result<bool, Error> {
+        match length {
+            Some(length) => Ok(read < length),
+            None => Ok(!self.at_break()?),
+        }
+    }
+
+    /// Reads the items of an array, map or tag, refusing to nest deeper than parsing does by
+    /// default
+    fn nested(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<Json, Error>,
+    ) -> Result<Json, Error> {
+        if self.depth >= MAX_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
+        self.depth += 1;
+        let value = read(self);
+        self.depth -= 1;
+        value
+    }
+
+    /// Reads a value
+    fn value(&mut self) -> Result<Json, Error> {
+        let head = self.byte()?;
+        let info = head & 0x1f;
+        Ok(match head >> 5 {
+            // Read integers as numbers
+            UNSIGNED => Json::Number(self.argument(info)? as f64),
+            NEGATIVE => Json::Number(-1.0 - self.argument(info)? as f64),
+
+            // JSON has no binary data
+            BYTES => return Err(Error::InvalidValue),
+
+            // Read text strings as strings
+            TEXT => Json::String(self.text(info)?),
+
+            // Read arrays as lists
+            ARRAY => {
+                let length = self.length(info)?;
+                self.nested(|reader| {
+                    let mut values = Vec::new();
+                    while reader.has_item(length, values.len())? {
+                        values.push(reader.value()?);
+                    }
+                    Ok(Json::List(values))
+                })?
+            }
+
+            // Read maps with text keys as objects
+            MAP => {
+                let length = self.length(info)?;
+                self.nested(|reader| {
+                    let mut items = Vec::new();
+                    while reader.has_item(length, items.len())? {
+                        let key = match reader.value()? {
+                            Json::String(key) => key,
+                            _ => return Err(Error::InvalidValue),
+                        };
+                        items.push((key, reader.value()?));
+                    }
+                    Ok(Json::Object(items))
+                })?
+            }
+
+            // Ignore tags, keeping the tagged value
+            TAG => {
+                self.argument(info)?;
+                self.nested(Self::value)?
+            }
+
+            // Read simple values and floats
+            _ => match info {
+                20 => Json::Bool(false),
+                21 => Json::Bool(true),
+                22 | 23 => Json::Null,
+                25 => Json::Number(half_to_f64(u16::from_be_bytes(
+                    self.take(2)?.try_into().unwrap(),
+                ))),
+                26 => Json::Number(f64::from(f32::from_be_bytes(
+                    self.take(4)?.try_into().unwrap(),
+                ))),
+                27 => Json::Number(f64::from_be_bytes(self.take(8)?.try_into().unwrap())),
+                _ => return Err(Error::InvalidValue),
+            },
+        })
+    }
+}
+
+/// Converts a half precision float to a double precision float
+fn half_to_f64(half: u16) -> f64 {
+    let exponent = i32::from((half >> 10) & 0x1f);
+    let mantissa = f64::from(half & 0x3ff);
+    let power_of_two = |power: i32| f64::from_bits(((power + 1023) as u64) << 52);
+    let magnitude = match exponent {
+        0 => mantissa * power_of_two(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1024.0 + mantissa) * power_of_two(exponent - 25),
+    };
+    if half & 0x8000 == 0 {
+        magnitude
+    } else {
+        -magnitude
+    }
+}
+
+impl Json {
+    /// Encodes the value as CBOR
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_value(&mut out, self);
+        out
+    }
+
+    /// Decodes a value from CBOR, failing if there are bytes left after it
+    ///
+    /// Arrays, maps and tags nested deeper than 128 levels fail with [`Error::NestingTooDeep`],
+    /// like when parsing with the default options.
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader { bytes, depth: 0 };
+        let value = reader.value()?;
+        if !reader.bytes.is_empty() {
+            return Err(Error::TrailingCharacters);
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, string::ToString, vec::Vec};
+
+    use super::CborEncoder;
+    use crate::{Error, EventSink, Json};
+
+    #[test]
+    fn cbor_encoding() {
+        assert_eq!(Json::Number(10.0).to_cbor(), [0x0a]);
+        assert_eq!(Json::Number(-500.0).to_cbor(), [0x39, 0x01, 0xf3]);
+        assert_eq!(
+            Json::Number(1.5).to_cbor(),
+            [0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            Json::Object(Vec::from([(
+                "a".to_owned(),
+                Json::List(Vec::from([Json::Bool(true), Json::Null]))
+            )]))
+            .to_cbor(),
+            [0xa1, 0x61, b'a', 0x82, 0xf5, 0xf6]
+        );
+    }
+
+    #[test]
+    fn cbor_round_trip() {
+        let value = "{\"list\":[1,-2.25,\"text\",false,null,{}],\"big\":12345678901}"
+            .parse::<Json>()
+            .unwrap();
+        assert_eq!(Json::from_cbor(&value.to_cbor()).unwrap(), value);
+        assert!(matches!(
+            Json::from_cbor(&Json::Number(-0.0).to_cbor()).unwrap(),
+            Json::Number(zero) if zero.is_sign_negative()
+        ));
+
+        // Transcoding events gives the same value with indefinite lengths
+        let mut encoder = CborEncoder::new();
+        let text = value.to_string();
+        encoder.transcode(Json::events(text.chars())).unwrap();
+        let bytes = encoder.into_bytes();
+        assert_eq!(bytes[0], 0xbf);
+        assert_eq!(Json::from_cbor(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn cbor_decoding() {
+        // Indefinite length arrays and strings, half floats and tags
+        assert_eq!(
+            Json::from_cbor(&[
+                0x9f, 0xf9, 0x3e, 0x00, 0x7f, 0x61, b'a', 0x61, b'b', 0xff, 0xff
+            ])
+            .unwrap(),
+            Json::List(Vec::from([
+                Json::Number(1.5),
+                Json::String("ab".to_owned())
+            ]))
+        );
+        assert_eq!(
+            Json::from_cbor(&[0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0]).unwrap(),
+            Json::Number(1_363_896_240.0)
+        );
+        assert!(Json::from_cbor(&[0x82, 0x01]).is_err());
+        assert!(Json::from_cbor(&[0x01, 0x01]).is_err());
+        assert!(Json::from_cbor(&[0xa1, 0x01, 0x01]).is_err());
+
+        // Arrays and tags nested too deeply are refused instead of running out of stack
+        let deepest = [[0x81; 128].as_slice(), &[0xf6]].concat();
+        assert!(Json::from_cbor(&deepest).is_ok());
+        for byte in [0x81, 0xc1] {
+            assert!(matches!(
+                Json::from_cbor(&[byte; 1_000_000]),
+                Err(Error::NestingTooDeep)
+            ));
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Tracking which parts of a document changed since it was loaded
+
+use alloc::{string::ToString, vec::Vec};
+
+use crate::{Json, PatchOperation, Path};
+
+/// A document that marks the values edited through it as dirty, so only the changed parts
+/// have to be written back
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeTracker {
+    /// The document as it was loaded or last marked clean
+    original: Json,
+
+    /// The current version of the document
+    document: Json,
+
+    /// The paths to the edited values, none of them inside another
+    dirty: Vec<Path>,
+}
+
+impl ChangeTracker {
+    /// Starts tracking the changes to a document
+    pub fn new(document: Json) -> Self {
+        Self {
+            original: document.clone(),
+            document,
+            dirty: Vec::new(),
+        }
+    }
+
+    /// Returns the current version of the document
+    pub fn document(&self) -> &Json {
+        &self.document
+    }
+
+    /// Returns the current version of the document, forgetting what changed
+    pub fn into_document(self) -> Json {
+        self.document
+    }
+
+    /// Returns the value at a path for editing, marking it as dirty
+    pub fn get_mut(&mut self, path: &Path) -> Option<&mut Json> {
+        self.document.get_path(path)?;
+        self.mark_dirty(path);
+        self.document.get_path_mut(path)
+    }
+
+    /// Marks the value at a path as dirty, which also covers the values in it
+    fn mark_dirty(&mut self, path: &Path) {
+        if self.dirty.iter().any(|dirty| path.starts_with(dirty)) {
+            return;
+        }
+        self.dirty.retain(|dirty| !dirty.starts_with(path));
+        self.dirty.push(path.clone());
+    }
+
+    /// Returns the paths to the values that were edited, none of them inside another
+    pub fn dirty_paths(&self) -> &[Path] {
+        &self.dirty
+    }
+
+    /// Returns whether the value at a path may have changed: whether it was edited, is inside
+    /// an edited value or holds one
+    pub fn is_dirty(&self, path: &Path) -> bool {
+        self.dirty
+            .iter()
+            .any(|dirty| path.starts_with(dirty) || dirty.starts_with(path))
+    }
+
+    /// Returns the JSON Patch operations turning the original document into the current one,
+    /// comparing only the values that were edited
+    pub fn changes(&self) -> Vec<PatchOperation> {
+        let mut operations = Vec::new();
+        for path in &self.dirty {
+            let pointer = path.to_string();
+            match (self.original.get_path(path), self.document.get_path(path)) {
+                (Some(original), Some(current)) => operations.extend(
+                    original
+                        .diff(current)
+                        .into_iter()
+                        .map(|operation| operation.prefixed(&pointer)),
+                ),
+                (None, Some(current)) => operations.push(PatchOperation::Add {
+                    path: pointer,
+                    value: current.clone(),
+                }),
+                (Some(_), None) => operations.push(PatchOperation::Remove { path: pointer }),
+                (None, None) => {}
+            }
+        }
+        operations
+    }
+
+    /// Forgets the changes, like after they were written back
+    pub fn mark_clean(&mut self) {
+        self.original = self.document.clone();
+        self.dirty.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::ChangeTracker;
+    use crate::{Json, Path};
+
+    #[test]
+    fn tracking() {
+        let document = "{\"server\":{\"host\":\"a\",\"port\":80},\"users\":[1,2]}"
+            .parse::<Json>()
+            .unwrap();
+        let mut tracker = ChangeTracker::new(document.clone());
+        let port = Path::parse("/server/port").unwrap();
+        *tracker.get_mut(&port).unwrap() = Json::Number(8080.0);
+        assert!(tracker.get_mut(&Path::parse("/missing").unwrap()).is_none());
+
+        // Only the edited value, what holds it and what's in it are dirty
+        assert!(tracker.is_dirty(&port));
+        assert!(tracker.is_dirty(&Path::parse("/server").unwrap()));
+        assert!(!tracker.is_dirty(&Path::parse("/server/host").unwrap()));
+        assert!(!tracker.is_dirty(&Path::parse("/users").unwrap()));
+
+        // Editing a value holding a dirty one replaces it in the dirty paths
+        if let Some(Json::List(users)) = tracker.get_mut(&Path::parse("/users").unwrap()) {
+            users.push(Json::Number(3.0));
+        }
+        tracker.get_mut(&Path::new().join("users").join(0));
+        assert_eq!(tracker.dirty_paths().len(), 2);
+
+        // The changes turn the original into the current document
+        let mut patched = document;
+        patched.apply_patch(&tracker.changes()).unwrap();
+        assert_eq!(&patched, tracker.document());
+        tracker.mark_clean();
+        assert_eq!(tracker.changes(), Vec::new());
+    }
+}
@@ -0,0 +1,189 @@
+//! The subcommands of the command line interface
+
+pub mod color;
+pub mod convert;
+pub mod diff;
+pub mod edit;
+#[cfg(feature = "explore")]
+pub mod explore;
+pub mod filter;
+pub mod patch;
+pub mod print;
+pub mod redact;
+pub mod repair;
+pub mod schema;
+pub mod stats;
+pub mod stream;
+#[cfg(feature = "csv")]
+pub mod to_csv;
+pub mod validate;
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read, stdin},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use json_parser::{Error, Json, ParseOptions};
+
+/// Why a subcommand failed
+pub enum Failure {
+    /// The arguments are invalid
+    Usage(String),
+
+    /// The subcommand couldn't do its work
+    Message(String),
+}
+
+impl From<&str> for Failure {
+    fn from(message: &str) -> Self {
+        Failure::Usage(message.into())
+    }
+}
+
+/// Prints the reason work failed without stopping, for commands that keep running
+pub fn report(result: Result<(), Failure>) {
+    if let Err(Failure::Usage(message) | Failure::Message(message)) = result {
+        eprintln!("json_parser: {message}");
+    }
+}
+
+/// Does the work now and again whenever one of the files changes, until interrupted
+///
+/// Files are polled for changes to their modification time and size, which works on every
+/// platform and file system. Files that are missing for a moment, like while an editor replaces
+/// them, are skipped until they are back.
+pub fn watch<F: FnMut()>(paths: &[&str], mut work: F) -> ! {
+    let stamp = |path: &&str| -> Option<(SystemTime, u64)> {
+        let metadata = fs::metadata(path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    };
+    let mut stamps = paths.iter().map(stamp).collect::<Vec<_>>();
+    work();
+    loop {
+        thread::sleep(Duration::from_millis(250));
+        let current = paths.iter().map(stamp).collect::<Vec<_>>();
+        if current != stamps && current.iter().all(Option::is_some) {
+            stamps = current;
+            eprintln!("json_parser: change detected, running again");
+            work();
+        }
+    }
+}
+
+/// Splits a JSON pointer into its unescaped tokens
+pub fn pointer_tokens(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    let tokens = pointer.strip_prefix('/')?.split('/');
+    Some(
+        tokens
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// Returns the name of the input for messages
+pub fn input_name(path: Option<&str>) -> &str {
+    path.unwrap_or("standard input")
+}
+
+/// Turns a path argument into a path, `-` meaning standard input
+pub fn input_path(arg: String) -> Option<String> {
+    Some(arg).filter(|arg| arg != "-")
+}
+
+/// Wraps the input in a decoder if it starts with the magic bytes of a compression format
+fn decompress(mut input: Box<dyn BufRead>) -> io::Result<Box<dyn BufRead>> {
+    let magic = input.fill_buf()?;
+
+    // Decompress gzip, or explain why it can't be
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        #[cfg(feature = "gzip")]
+        return Ok(Box::new(BufReader::new(
+            flate2::bufread::MultiGzDecoder::new(input),
+        )));
+        #[cfg(not(feature = "gzip"))]
+        return Err(io::Error::other("gzip input needs the gzip feature"));
+    }
+
+    // Decompress zstd, or explain why it can't be
+    if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        #[cfg(feature = "zstd")]
+        return ruzstd::decoding::StreamingDecoder::new(input)
+            .map(|decoder| Box::new(BufReader::new(decoder)) as Box<dyn BufRead>)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error));
+        #[cfg(not(feature = "zstd"))]
+        return Err(io::Error::other("zstd input needs the zstd feature"));
+    }
+    Ok(input)
+}
+
+/// Opens the file for buffered reading, or standard input if there is no path
+///
+/// Input compressed with gzip or zstd is decompressed transparently when the matching feature is
+/// enabled.
+pub fn open_input(path: Option<&str>) -> Result<Box<dyn BufRead>, String> {
+    match path {
+        Some(path) => {
+            fs::File::open(path).map(|file| Box::new(BufReader::new(file)) as Box<dyn BufRead>)
+        }
+        None => Ok(Box::new(stdin().lock()) as Box<dyn BufRead>),
+    }
+    .and_then(decompress)
+    .map_err(|error| format!("can't read {}: {error}", input_name(path)))
+}
+
+/// Reads the raw bytes of the input from the file, or from standard input if there is no path
+pub fn read_bytes(path: Option<&str>) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    open_input(path)?
+        .read_to_end(&mut bytes)
+        .map_err(|error| format!("can't read {}: {error}", input_name(path)))?;
+    Ok(bytes)
+}
+
+/// Reads the input from the file, or from standard input if there is no path
+pub fn read_input(path: Option<&str>) -> Result<String, String> {
+    let mut text = String::new();
+    open_input(path)?
+        .read_to_string(&mut text)
+        .map_err(|error| format!("can't read {}: {error}", input_name(path)))?;
+    Ok(text)
+}
+
+/// The deepest nesting documents may have, 512 like in the repair command unless `--max-depth`
+/// changes it
+static MAX_DEPTH: AtomicUsize = AtomicUsize::new(512);
+
+/// Changes the deepest nesting documents may have, for `--max-depth`
+///
+/// Parsing recurses once per level, so much deeper documents also need a larger stack, like
+/// with `ulimit -s`.
+pub fn set_max_depth(depth: usize) {
+    MAX_DEPTH.store(depth, Ordering::Relaxed);
+}
+
+/// Returns the options documents are parsed with, which don't limit their size
+pub fn parse_options() -> ParseOptions {
+    ParseOptions {
+        max_depth: Some(MAX_DEPTH.load(Ordering::Relaxed)),
+        max_size: None,
+        ..ParseOptions::default()
+    }
+}
+
+/// Parses a complete document, adding the line and column to errors
+pub fn parse(text: &str) -> Result<Json, Error> {
+    Json::from_chars_with(text.chars(), &parse_options())
+}
+
+/// Reads and parses the document from the file, or from standard input if there is no path
+pub fn read_document(path: Option<&str>) -> Result<Json, Failure> {
+    let text = read_input(path).map_err(Failure::Message)?;
+    parse(&text)
+        .map_err(|error| Failure::Message(format!("can't parse {}: {error}", input_name(path))))
+}
@@ -0,0 +1,81 @@
+//! Syntax highlighting of JSON output for terminals
+
+use std::{
+    env,
+    io::{IsTerminal, stdout},
+};
+
+use json_parser::{TokenKind, Tokens};
+
+/// The color of object keys
+pub const KEY: &str = "\x1b[1;34m";
+
+/// The color of string values
+pub const STRING: &str = "\x1b[32m";
+
+/// The color of numbers
+pub const NUMBER: &str = "\x1b[36m";
+
+/// The color of `true` and `false`
+pub const BOOL: &str = "\x1b[33m";
+
+/// The color of `null`
+pub const NULL: &str = "\x1b[35m";
+
+/// Resets the color
+pub const RESET: &str = "\x1b[0m";
+
+/// When output is colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    /// Never color the output
+    Never,
+
+    /// Always color the output
+    Always,
+
+    /// Color the output if standard output is a terminal and `NO_COLOR` isn't set
+    Auto,
+}
+
+impl When {
+    /// Parses the value of the `--color` option
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "never" => Some(When::Never),
+            "always" => Some(When::Always),
+            "auto" => Some(When::Auto),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the output should be colored
+    pub fn enabled(self) -> bool {
+        match self {
+            When::Never => false,
+            When::Always => true,
+            When::Auto => stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+        }
+    }
+}
+
+/// Adds colors to serialized JSON
+pub fn colorize(json: &str) -> String {
+    let mut out = String::with_capacity(json.len() * 2);
+    for token in Tokens::new(json) {
+        // Color keys and values, leaving punctuation and whitespace as they are
+        let color = match (token.kind, token.text) {
+            (TokenKind::Key, _) => KEY,
+            (TokenKind::String, _) => STRING,
+            (TokenKind::Number, _) => NUMBER,
+            (TokenKind::Literal, "null") => NULL,
+            (TokenKind::Literal, _) => BOOL,
+            _ => {
+                out.push_str(token.text);
+                continue;
+            }
+        };
+        out.extend([color, token.text, RESET]);
+    }
+    out
+}
@@ -0,0 +1,149 @@
+//! Converting between JSON and the other data formats the library supports
+
+use std::{
+    io::{Write, stdout},
+    process::ExitCode,
+};
+
+#[cfg(feature = "csv")]
+use json_parser::CsvOptions;
+use json_parser::{Error, Json};
+
+use super::{Failure, input_name, input_path, parse, read_bytes};
+
+/// A data format documents can be converted from and to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DataFormat {
+    /// JSON, printed compactly
+    Json,
+
+    /// YAML, reading only the first document
+    #[cfg(feature = "yaml")]
+    Yaml,
+
+    /// TOML, which can only hold a table at the top
+    #[cfg(feature = "toml")]
+    Toml,
+
+    /// CBOR (RFC 8949)
+    #[cfg(feature = "cbor")]
+    Cbor,
+
+    /// MessagePack
+    #[cfg(feature = "msgpack")]
+    Msgpack,
+
+    /// BSON, using extended JSON for the types JSON lacks
+    #[cfg(feature = "bson")]
+    Bson,
+
+    /// CSV with a header, as a list of objects
+    #[cfg(feature = "csv")]
+    Csv,
+}
+
+impl DataFormat {
+    /// Looks up a format by its name
+    fn parse(name: Option<String>) -> Result<Self, Failure> {
+        Ok(match name.as_deref() {
+            Some("json") => DataFormat::Json,
+            #[cfg(feature = "yaml")]
+            Some("yaml") => DataFormat::Yaml,
+            #[cfg(feature = "toml")]
+            Some("toml") => DataFormat::Toml,
+            #[cfg(feature = "cbor")]
+            Some("cbor") => DataFormat::Cbor,
+            #[cfg(feature = "msgpack")]
+            Some("msgpack") => DataFormat::Msgpack,
+            #[cfg(feature = "bson")]
+            Some("bson") => DataFormat::Bson,
+            #[cfg(feature = "csv")]
+            Some("csv") => DataFormat::Csv,
+            Some(name) => {
+                return Err(Failure::Usage(format!(
+                    "unknown format {name}, or it isn't enabled in this build"
+                )));
+            }
+            None => return Err("--from and --to expect a format".into()),
+        })
+    }
+
+    /// Decodes a document in this format
+    fn decode(self, bytes: &[u8]) -> Result<Json, Error> {
+        // Text formats have to be valid UTF-8
+        let text = || str::from_utf8(bytes).map_err(|_| Error::InvalidValue);
+        match self {
+            DataFormat::Json => parse(text()?),
+            #[cfg(feature = "yaml")]
+            DataFormat::Yaml => Json::from_yaml_str(text()?),
+            #[cfg(feature = "toml")]
+            DataFormat::Toml => Json::from_toml_str(text()?),
+            #[cfg(feature = "cbor")]
+            DataFormat::Cbor => Json::from_cbor(bytes),
+            #[cfg(feature = "msgpack")]
+            DataFormat::Msgpack => Json::from_msgpack(bytes),
+            #[cfg(feature = "bson")]
+            DataFormat::Bson => Json::from_bson(bytes),
+            #[cfg(feature = "csv")]
+            DataFormat::Csv => Json::from_csv(text()?, &CsvOptions::default()),
+        }
+    }
+
+    /// Encodes a document in this format
+    fn encode(self, value: &Json) -> Result<Vec<u8>, Error> {
+        Ok(match self {
+            DataFormat::Json => format!("{value}\n").into_bytes(),
+            #[cfg(feature = "yaml")]
+            DataFormat::Yaml => format!("{}\n", value.to_yaml_string()).into_bytes(),
+            #[cfg(feature = "toml")]
+            DataFormat::Toml => value.to_toml_string()?.into_bytes(),
+            #[cfg(feature = "cbor")]
+            DataFormat::Cbor => value.to_cbor(),
+            #[cfg(feature = "msgpack")]
+            DataFormat::Msgpack => value.to_msgpack(),
+            #[cfg(feature = "bson")]
+            DataFormat::Bson => value.to_bson()?,
+            #[cfg(feature = "csv")]
+            DataFormat::Csv => value
+                .to_csv(&CsvOptions {
+                    flatten: true,
+                    ..CsvOptions::default()
+                })?
+                .into_bytes(),
+        })
+    }
+}
+
+/// Converts the input from one format to another and writes it to standard output
+pub fn run<I: Iterator<Item = String>>(mut args: I) -> Result<ExitCode, Failure> {
+    // Parse the arguments
+    let mut from = DataFormat::Json;
+    let mut to = DataFormat::Json;
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => from = DataFormat::parse(args.next())?,
+            "--to" => to = DataFormat::parse(args.next())?,
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {arg}")));
+            }
+            _ if path.is_none() => path = Some(input_path(arg)),
+            _ => return Err(Failure::Usage(format!("unexpected argument {arg}"))),
+        }
+    }
+    let path = path.flatten();
+    let name = input_name(path.as_deref());
+
+    // Decode the input and encode it in the other format
+    let bytes = read_bytes(path.as_deref()).map_err(Failure::Message)?;
+    let value = from
+        .decode(&bytes)
+        .map_err(|error| Failure::Message(format!("can't parse {name}: {error}")))?;
+    let bytes = to
+        .encode(&value)
+        .map_err(|error| Failure::Message(format!("can't convert {name}: {error}")))?;
+    stdout()
+        .write_all(&bytes)
+        .map_err(|error| Failure::Message(format!("can't write the output: {error}")))?;
+    Ok(ExitCode::SUCCESS)
+}
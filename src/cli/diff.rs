@@ -0,0 +1,58 @@
+//! Comparing two documents
+
+use std::process::ExitCode;
+
+use json_parser::{Json, PatchOperation};
+
+use super::{Failure, input_path, read_document};
+
+/// Prints the differences between two documents, returning failure if they differ
+///
+/// With `--invertible`, the patch records the previous values so `patch --invert` can undo it.
+pub fn run<I: Iterator<Item = String>>(args: I) -> Result<ExitCode, Failure> {
+    // Parse the arguments
+    let mut patch = false;
+    let mut invertible = false;
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--patch" => patch = true,
+            "--invertible" => (patch, invertible) = (true, true),
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {arg}")));
+            }
+            _ => paths.push(input_path(arg)),
+        }
+    }
+    let [source, target] = <[_; 2]>::try_from(paths).map_err(|_| "diff expects two inputs")?;
+
+    // Compare the documents
+    let source = read_document(source.as_deref())?;
+    let target = read_document(target.as_deref())?;
+    let operations = match invertible {
+        true => source.diff_invertible(&target),
+        false => source.diff(&target),
+    };
+
+    // Print the patch, or a line per difference
+    if patch {
+        println!("{:#}", Json::from_patch(&operations));
+    } else {
+        for operation in &operations {
+            let old = source.pointer(operation.path());
+            match (operation, old) {
+                (PatchOperation::Add { path, value }, _) => println!("+ {path}: {value}"),
+                (PatchOperation::Remove { path }, Some(old)) => println!("- {path}: {old}"),
+                (PatchOperation::Replace { path, value }, Some(old)) => {
+                    println!("~ {path}: {old} -> {value}");
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(if operations.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
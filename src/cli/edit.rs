@@ -0,0 +1,136 @@
+//! Changing a value in a file in place
+
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{self, ExitCode},
+};
+
+use json_parser::{Cst, Format, Json, PatchError, PatchOperation};
+
+use super::{Failure, parse};
+
+/// Writes the file by renaming a temporary file over it, so readers never see a partial write
+fn write_atomic(path: &str, contents: &str) -> Result<(), Failure> {
+    // Put the temporary file next to the original, as renaming only works on one file system
+    let path = Path::new(path);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temporary = path.with_file_name(format!(".{name}.{}.tmp", process::id()));
+
+    // Write and sync the new contents, then replace the original
+    let result = fs::File::create(&temporary)
+        .and_then(|mut file| {
+            if let Ok(metadata) = fs::metadata(path) {
+                file.set_permissions(metadata.permissions())?;
+            }
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temporary, path));
+    result.map_err(|error| {
+        let _ = fs::remove_file(&temporary);
+        Failure::Message(format!("can't write {}: {error}", path.display()))
+    })
+}
+
+/// Parses the arguments shared by `set` and `del`, returning the format and the other arguments
+fn parse_args<I: Iterator<Item = String>>(
+    args: I,
+    count: usize,
+) -> Result<(Option<Format>, Vec<String>), Failure> {
+    let mut format = None;
+    let mut rest = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--pretty" => format = Some(Format::pretty()),
+            "--minify" => format = Some(Format::compact()),
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {arg}")));
+            }
+            _ => rest.push(arg),
+        }
+    }
+    if rest.len() != count {
+        return Err(Failure::Usage(format!("expected {count} arguments")));
+    }
+    Ok((format, rest))
+}
+
+/// Reads the file, naming it in the error message
+fn read(path: &str) -> Result<String, Failure> {
+    // Compressed files aren't decompressed, as they would be written back uncompressed
+    fs::read_to_string(path)
+        .map_err(|error| Failure::Message(format!("can't read {path}: {error}")))
+}
+
+/// Applies the operation built for the document to the file and writes it back in the format
+fn edit<F: FnOnce(&Json) -> PatchOperation>(
+    path: &str,
+    format: &Format,
+    operation: F,
+) -> Result<ExitCode, Failure> {
+    let mut document = parse(&read(path)?)
+        .map_err(|error| Failure::Message(format!("can't parse {path}: {error}")))?;
+
+    // Apply the change and write the document back
+    let operation = operation(&document);
+    document.apply_patch(&[operation]).map_err(|error| {
+        // There's only one operation, so its index isn't worth mentioning
+        let error = match error {
+            PatchError::At { error, .. } => *error,
+            error => error,
+        };
+        Failure::Message(format!("can't edit {path}: {error}"))
+    })?;
+    write_atomic(path, &format!("{}\n", document.display(format)))?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Applies the change to the file in place, keeping its comments and formatting everywhere else
+fn edit_in_place<F: FnOnce(&mut Cst) -> Result<(), PatchError>>(
+    path: &str,
+    change: F,
+) -> Result<ExitCode, Failure> {
+    let mut document = read(path)?
+        .parse::<Cst>()
+        .map_err(|error| Failure::Message(format!("can't parse {path}: {error}")))?;
+    change(&mut document)
+        .map_err(|error| Failure::Message(format!("can't edit {path}: {error}")))?;
+    write_atomic(path, document.as_str())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Sets the value at a pointer, adding it if it doesn't exist yet
+pub fn run_set<I: Iterator<Item = String>>(args: I) -> Result<ExitCode, Failure> {
+    let (format, args) = parse_args(args, 3)?;
+    let [path, pointer, value] = <[_; 3]>::try_from(args).expect("the count was checked");
+
+    // Values that aren't valid JSON are set as strings
+    let value = parse(&value).unwrap_or(Json::String(value));
+    let Some(format) = format else {
+        return edit_in_place(&path, |document| document.set(&pointer, &value));
+    };
+    edit(&path, &format, |document| {
+        match document.pointer(&pointer) {
+            Some(_) => PatchOperation::Replace {
+                path: pointer,
+                value,
+            },
+            None => PatchOperation::Add {
+                path: pointer,
+                value,
+            },
+        }
+    })
+}
+
+/// Removes the value at a pointer
+pub fn run_delete<I: Iterator<Item = String>>(args: I) -> Result<ExitCode, Failure> {
+    let (format, args) = parse_args(args, 2)?;
+    let [path, pointer] = <[_; 2]>::try_from(args).expect("the count was checked");
+    match format {
+        Some(format) => edit(&path, &format, |_| PatchOperation::Remove { path: pointer }),
+        None => edit_in_place(&path, |document| document.remove(&pointer).map(drop)),
+    }
+}
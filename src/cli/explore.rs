@@ -0,0 +1,447 @@
+//! An interactive tree view of a document in the terminal
+
+use std::{
+    collections::HashSet,
+    io::{self, Write, stdout},
+    process::ExitCode,
+};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{
+        self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode,
+        enable_raw_mode,
+    },
+};
+use json_parser::Json;
+
+use super::{Failure, input_path, read_document};
+
+/// The help shown in the status line
+const HELP: &str = "↑↓ move  ←→ fold  / search  n next  y copy path  q quit";
+
+/// A line of the tree view
+struct Row {
+    /// The JSON pointer to the value
+    pointer: String,
+
+    /// How deeply the value is nested
+    depth: usize,
+
+    /// The key or index of the value in its parent
+    label: String,
+
+    /// The number of children, or `None` if the value isn't an object or list
+    children: Option<usize>,
+
+    /// The value itself, shown for strings, numbers, booleans and null
+    value: Option<String>,
+}
+
+/// Appends a key to a JSON pointer, escaping ~ and /
+fn child_pointer(pointer: &str, key: &str) -> String {
+    format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// Returns the children of a value with their labels and pointers
+fn children<'a>(value: &'a Json, pointer: &str) -> Vec<(String, String, &'a Json)> {
+    match value {
+        Json::Object(items) => items
+            .iter()
+            .map(|(key, value)| (key.clone(), child_pointer(pointer, key), value))
+            .collect(),
+        Json::List(values) => values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let key = index.to_string();
+                (format!("[{index}]"), child_pointer(pointer, &key), value)
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Adds the rows of a value and its visible children
+fn add_rows(
+    rows: &mut Vec<Row>,
+    value: &Json,
+    label: String,
+    pointer: String,
+    depth: usize,
+    expanded: Option<&HashSet<String>>,
+) {
+    // Containers show their size, other values themselves
+    let (count, scalar) = match value {
+        Json::Object(items) => (Some(items.len()), None),
+        Json::List(values) => (Some(values.len()), None),
+        value => (None, Some(value.to_string())),
+    };
+    let open = expanded.is_none_or(|expanded| expanded.contains(&pointer));
+    let children = if open && count.is_some() {
+        children(value, &pointer)
+    } else {
+        Vec::new()
+    };
+    rows.push(Row {
+        pointer,
+        depth,
+        label,
+        children: count,
+        value: scalar,
+    });
+
+    // Add the children of expanded containers
+    for (label, pointer, value) in children {
+        add_rows(rows, value, label, pointer, depth + 1, expanded);
+    }
+}
+
+/// Encodes bytes as base64, for copying to the clipboard through the terminal
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0, |bits, (index, &byte)| {
+            bits | u32::from(byte) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            out.push(match index <= chunk.len() {
+                true => ALPHABET[(bits >> (18 - 6 * index) & 0x3f) as usize] as char,
+                false => '=',
+            });
+        }
+    }
+    out
+}
+
+/// The state of the explorer
+struct Explorer<'a> {
+    /// The document being explored
+    document: &'a Json,
+
+    /// The pointers of the expanded containers
+    expanded: HashSet<String>,
+
+    /// The visible rows
+    rows: Vec<Row>,
+
+    /// The index of the selected row
+    selected: usize,
+
+    /// The index of the first row on screen
+    scroll: usize,
+
+    /// The search being typed, if any
+    typing: Option<String>,
+
+    /// The last search
+    query: String,
+
+    /// A message shown in the status line until the next key
+    message: Option<String>,
+}
+
+impl<'a> Explorer<'a> {
+    /// Creates an explorer with only the top level value expanded
+    fn new(document: &'a Json) -> Self {
+        let mut explorer = Self {
+            document,
+            expanded: HashSet::from([String::new()]),
+            rows: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            typing: None,
+            query: String::new(),
+            message: None,
+        };
+        explorer.refresh(None);
+        explorer
+    }
+
+    /// Rebuilds the visible rows, selecting the row with the pointer if it's given
+    fn refresh(&mut self, select: Option<&str>) {
+        let pointer = select
+            .map(str::to_owned)
+            .or_else(|| self.rows.get(self.selected).map(|row| row.pointer.clone()));
+        self.rows.clear();
+        let root = "(root)".to_owned();
+        add_rows(
+            &mut self.rows,
+            self.document,
+            root,
+            String::new(),
+            0,
+            Some(&self.expanded),
+        );
+        self.selected = pointer
+            .and_then(|pointer| self.rows.iter().position(|row| row.pointer == pointer))
+            .unwrap_or(0);
+    }
+
+    /// Expands or collapses the selected container
+    fn set_expanded(&mut self, expand: bool) {
+        let row = &self.rows[self.selected];
+        if row.children.is_some() {
+            if expand {
+                self.expanded.insert(row.pointer.clone());
+            } else {
+                self.expanded.remove(&row.pointer);
+            }
+            self.refresh(None);
+        }
+    }
+
+    /// Selects the next value after the selected one whose key or value contains the query
+    fn search(&mut self) {
+        // Search all values, not just the visible ones, starting after the selected one
+        let query = self.query.to_lowercase();
+        let mut all = Vec::new();
+        add_rows(
+            &mut all,
+            self.document,
+            String::new(),
+            String::new(),
+            0,
+            None,
+        );
+        let current = &self.rows[self.selected].pointer;
+        let start = all
+            .iter()
+            .position(|row| row.pointer == *current)
+            .map_or(0, |index| index + 1);
+        let found = all[start..].iter().chain(&all[..start]).find(|row| {
+            row.label.to_lowercase().contains(&query)
+                || row
+                    .value
+                    .as_ref()
+                    .is_some_and(|value| value.to_lowercase().contains(&query))
+        });
+
+        // Expand the containers around the match and select it
+        let Some(found) = found else {
+            self.message = Some(format!("no match for {}", self.query));
+            return;
+        };
+        let pointer = found.pointer.clone();
+        for (index, _) in pointer.match_indices('/') {
+            self.expanded.insert(pointer[..index].to_owned());
+        }
+        self.refresh(Some(&pointer));
+    }
+
+    /// Handles a key, returning `false` when the explorer should close
+    fn key(&mut self, key: KeyEvent, height: usize) -> io::Result<bool> {
+        self.message = None;
+
+        // Edit the search being typed
+        if let Some(typing) = &mut self.typing {
+            match key.code {
+                KeyCode::Char(ch) => typing.push(ch),
+                KeyCode::Backspace => {
+                    typing.pop();
+                }
+                KeyCode::Enter => {
+                    self.query = self.typing.take().unwrap_or_default();
+                    self.search();
+                }
+                KeyCode::Esc => self.typing = None,
+                _ => {}
+            }
+            return Ok(true);
+        }
+
+        // Move, fold, search and copy
+        let last = self.rows.len() - 1;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(height),
+            KeyCode::PageDown => self.selected = (self.selected + height).min(last),
+            KeyCode::Home | KeyCode::Char('g') => self.selected = 0,
+            KeyCode::End | KeyCode::Char('G') => self.selected = last,
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter => self.set_expanded(true),
+            KeyCode::Left | KeyCode::Char('h') => {
+                // Collapse an expanded container, or go to the parent otherwise
+                let row = &self.rows[self.selected];
+                if self.expanded.contains(&row.pointer) && row.children.is_some() {
+                    self.set_expanded(false);
+                } else if let Some((parent, _)) = row.pointer.rsplit_once('/') {
+                    let parent = parent.to_owned();
+                    self.refresh(Some(&parent));
+                }
+            }
+            KeyCode::Char(' ') => {
+                let expanded = self.expanded.contains(&self.rows[self.selected].pointer);
+                self.set_expanded(!expanded);
+            }
+            KeyCode::Char('/') => self.typing = Some(String::new()),
+            KeyCode::Char('n') if !self.query.is_empty() => self.search(),
+            KeyCode::Char('y') => {
+                // Ask the terminal to copy the pointer with the OSC 52 sequence
+                let pointer = &self.rows[self.selected].pointer;
+                let encoded = base64(pointer.as_bytes());
+                execute!(stdout(), Print(format!("\x1b]52;c;{encoded}\x07")))?;
+                self.message = Some(format!("copied {pointer:?}"));
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// Draws the visible rows and the status line
+    fn draw(&mut self, width: usize, height: usize) -> io::Result<()> {
+        // Scroll to keep the selected row on screen
+        let rows = height.saturating_sub(1).max(1);
+        self.scroll = self.scroll.min(self.selected);
+        if self.selected >= self.scroll + rows {
+            self.scroll = self.selected + 1 - rows;
+        }
+
+        // Draw the rows, highlighting the selected one
+        let mut out = stdout();
+        queue!(out, Clear(ClearType::All))?;
+        for (line, (index, row)) in self
+            .rows
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(rows)
+            .enumerate()
+        {
+            let marker = match row.children {
+                Some(_) if self.expanded.contains(&row.pointer) => "▾ ",
+                Some(_) => "▸ ",
+                None => "  ",
+            };
+            let detail = match (&row.value, row.children) {
+                (Some(value), _) => value.clone(),
+                (None, Some(count)) => format!("({count})"),
+                (None, None) => String::new(),
+            };
+            let (indent, label) = (row.depth * 2, &row.label);
+            let text = format!("{:indent$}{marker}{label}: {detail}", "");
+            let text = text.chars().take(width).collect::<String>();
+            queue!(out, MoveTo(0, line as u16))?;
+            if index == self.selected {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reverse),
+                    Print(text),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(out, Print(text))?;
+            }
+        }
+
+        // Draw the search being typed, a message, or the selected pointer
+        let status = match (&self.typing, &self.message) {
+            (Some(typing), _) => format!("/{typing}"),
+            (None, Some(message)) => message.clone(),
+            (None, None) => {
+                let pointer = &self.rows[self.selected].pointer;
+                format!(
+                    "{}   {HELP}",
+                    if pointer.is_empty() { "/" } else { pointer }
+                )
+            }
+        };
+        let status = status.chars().take(width).collect::<String>();
+        queue!(
+            out,
+            MoveTo(0, rows as u16),
+            SetAttribute(Attribute::Dim),
+            Print(status),
+            SetAttribute(Attribute::Reset)
+        )?;
+        out.flush()
+    }
+}
+
+/// Restores the terminal when the explorer closes, even after an error
+struct Terminal;
+
+impl Terminal {
+    /// Switches the terminal to raw mode on the alternate screen
+    fn open() -> io::Result<Self> {
+        enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = execute!(stdout(), Show, LeaveAlternateScreen);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Opens the document in the interactive explorer
+pub fn run<I: Iterator<Item = String>>(args: I) -> Result<ExitCode, Failure> {
+    // Parse the arguments
+    let mut path = None;
+    for arg in args {
+        match arg.as_str() {
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {arg}")));
+            }
+            _ if path.is_none() => path = Some(input_path(arg)),
+            _ => return Err(Failure::Usage(format!("unexpected argument {arg}"))),
+        }
+    }
+    let document = read_document(path.flatten().as_deref())?;
+
+    // Draw and handle keys until the explorer is closed
+    let error = |error: io::Error| Failure::Message(format!("terminal error: {error}"));
+    let _terminal = Terminal::open().map_err(error)?;
+    let mut explorer = Explorer::new(&document);
+    loop {
+        let (width, height) = match terminal::size().map_err(error)? {
+            (0, _) | (_, 0) => (80, 24),
+            size => size,
+        };
+        explorer
+            .draw(usize::from(width), usize::from(height))
+            .map_err(error)?;
+        if let Event::Key(key) = event::read().map_err(error)?
+            && key.kind == KeyEventKind::Press
+            && !explorer.key(key, usize::from(height)).map_err(error)?
+        {
+            return Ok(ExitCode::SUCCESS);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use json_parser::Json;
+
+    use super::{Explorer, base64};
+
+    #[test]
+    fn encoding() {
+        assert_eq!(base64(b"/a"), "L2E=");
+        assert_eq!(base64(b"/ab"), "L2Fi");
+        assert_eq!(base64(b"/"), "Lw==");
+    }
+
+    #[test]
+    fn searching() {
+        let document = r#"{"a": {"b": [1, {"needle": true}]}, "c": "needle"}"#
+            .parse::<Json>()
+            .unwrap();
+        let mut explorer = Explorer::new(&document);
+        assert_eq!(explorer.rows.len(), 3);
+        explorer.query = "NEEDLE".into();
+        explorer.search();
+        assert_eq!(explorer.rows[explorer.selected].pointer, "/a/b/1/needle");
+        explorer.search();
+        assert_eq!(explorer.rows[explorer.selected].pointer, "/c");
+    }
+}
@@ -0,0 +1,670 @@
+//! A practical subset of the jq filter language
+
+use std::{iter::Peekable, str::Chars};
+
+use json_parser::Json;
+
+/// A token of a filter expression
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A name like `select` or `and`
+    Ident(String),
+
+    /// A string literal
+    Str(String),
+
+    /// A number literal
+    Num(f64),
+
+    /// An operator or punctuation like `|` or `==`
+    Symbol(&'static str),
+}
+
+/// The operators and punctuation, longest first so `==` isn't read as `=`
+const SYMBOLS: [&str; 17] = [
+    "==", "!=", "<=", ">=", "<", ">", ".", "|", ",", ":", "?", "(", ")", "[", "]", "{", "}",
+];
+
+/// Reads a string literal after its opening quote
+fn lex_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    let mut string = String::new();
+    loop {
+        match chars.next().ok_or("unclosed string")? {
+            '"' => return Ok(string),
+            '\\' => string.push(match chars.next().ok_or("unclosed string")? {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                ch => ch,
+            }),
+            ch => string.push(ch),
+        }
+    }
+}
+
+/// Splits a filter expression into tokens
+fn lex(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            // Skip whitespace
+            _ if ch.is_whitespace() => {
+                chars.next();
+            }
+
+            // Read a string
+            '"' => {
+                chars.next();
+                tokens.push(Token::Str(lex_string(&mut chars)?));
+            }
+
+            // Read a number, which may be negative
+            _ if ch.is_ascii_digit()
+                || ch == '-' && chars.clone().nth(1).is_some_and(|ch| ch.is_ascii_digit()) =>
+            {
+                let mut number = String::from(ch);
+                chars.next();
+                while let Some(ch) = chars.next_if(|ch| ch.is_ascii_digit() || *ch == '.') {
+                    number.push(ch);
+                }
+                let number = number
+                    .parse()
+                    .map_err(|_| format!("invalid number {number}"))?;
+                tokens.push(Token::Num(number));
+            }
+
+            // Read a name
+            _ if ch.is_alphabetic() || ch == '_' => {
+                let mut name = String::new();
+                while let Some(ch) = chars.next_if(|ch| ch.is_alphanumeric() || *ch == '_') {
+                    name.push(ch);
+                }
+                tokens.push(Token::Ident(name));
+            }
+
+            // Read an operator
+            _ => {
+                let rest = chars.clone().collect::<String>();
+                let symbol = SYMBOLS
+                    .into_iter()
+                    .find(|symbol| rest.starts_with(symbol))
+                    .ok_or_else(|| format!("unexpected character {ch}"))?;
+                for _ in 0..symbol.len() {
+                    chars.next();
+                }
+                tokens.push(Token::Symbol(symbol));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// A parsed filter expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// `.`, the input itself
+    Identity,
+
+    /// `f.name`, a property of every output of `f`
+    Field(Box<Filter>, String),
+
+    /// `f[i]`, an element or property of every output of `f`
+    Index(Box<Filter>, Box<Filter>),
+
+    /// `f[]`, every element or property value of the outputs of `f`
+    Iterate(Box<Filter>),
+
+    /// `f?`, the outputs of `f`, ignoring errors
+    Try(Box<Filter>),
+
+    /// `f | g`, `g` applied to every output of `f`
+    Pipe(Box<Filter>, Box<Filter>),
+
+    /// `f, g`, the outputs of `f` followed by those of `g`
+    Comma(Box<Filter>, Box<Filter>),
+
+    /// A comparison like `f == g`
+    Compare(Box<Filter>, &'static str, Box<Filter>),
+
+    /// `f and g`
+    And(Box<Filter>, Box<Filter>),
+
+    /// `f or g`
+    Or(Box<Filter>, Box<Filter>),
+
+    /// A constant value
+    Literal(Json),
+
+    /// `[f]`, a list of all outputs of `f`
+    Collect(Option<Box<Filter>>),
+
+    /// `{key: f}`, objects built from every combination of property values
+    Object(Vec<(Filter, Filter)>),
+
+    /// A builtin function like `length` or `select(f)`
+    Call(String, Vec<Filter>),
+}
+
+/// Parses tokens into a filter
+struct Parser {
+    /// The tokens, reversed so the next one can be popped
+    tokens: Vec<Token>,
+}
+
+impl Parser {
+    /// Returns the next token without consuming it
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.last()
+    }
+
+    /// Consumes the next token if it's the given symbol
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(next)) if *next == symbol);
+        if found {
+            self.tokens.pop();
+        }
+        found
+    }
+
+    /// Consumes the given symbol, failing if it's missing
+    fn expect(&mut self, symbol: &str) -> Result<(), String> {
+        match self.eat(symbol) {
+            true => Ok(()),
+            false => Err(format!("expected {symbol}")),
+        }
+    }
+
+    /// Consumes the next token if it's the given keyword
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Ident(next)) if next == keyword);
+        if found {
+            self.tokens.pop();
+        }
+        found
+    }
+
+    /// Parses filters joined by `|`, the lowest precedence
+    fn pipe(&mut self) -> Result<Filter, String> {
+        let mut filter = self.comma()?;
+        while self.eat("|") {
+            filter = Filter::Pipe(Box::new(filter), Box::new(self.comma()?));
+        }
+        Ok(filter)
+    }
+
+    /// Parses filters joined by `,`
+    fn comma(&mut self) -> Result<Filter, String> {
+        let mut filter = self.or()?;
+        while self.eat(",") {
+            filter = Filter::Comma(Box::new(filter), Box::new(self.or()?));
+        }
+        Ok(filter)
+    }
+
+    /// Parses filters joined by `or`
+    fn or(&mut self) -> Result<Filter, String> {
+        let mut filter = self.and()?;
+        while self.eat_keyword("or") {
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    /// Parses filters joined by `and`
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut filter = self.compare()?;
+        while self.eat_keyword("and") {
+            filter = Filter::And(Box::new(filter), Box::new(self.compare()?));
+        }
+        Ok(filter)
+    }
+
+    /// Parses a comparison, or a single filter without one
+    fn compare(&mut self) -> Result<Filter, String> {
+        let filter = self.postfix()?;
+        for operator in ["==", "!=", "<=", ">=", "<", ">"] {
+            if self.eat(operator) {
+                return Ok(Filter::Compare(
+                    Box::new(filter),
+                    operator,
+                    Box::new(self.postfix()?),
+                ));
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Parses a field name after a dot, if there is one
+    fn field(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(Token::Ident(_) | Token::Str(_)) => match self.tokens.pop() {
+                Some(Token::Ident(name) | Token::Str(name)) => Some(name),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Parses a filter followed by any number of `.name`, `[...]` and `?`
+    fn postfix(&mut self) -> Result<Filter, String> {
+        let mut filter = self.primary()?;
+        loop {
+            if self.eat(".") {
+                let name = self.field().ok_or("expected a name after .")?;
+                filter = Filter::Field(Box::new(filter), name);
+            } else if self.eat("[") {
+                filter = if self.eat("]") {
+                    Filter::Iterate(Box::new(filter))
+                } else {
+                    let index = self.pipe()?;
+                    self.expect("]")?;
+                    Filter::Index(Box::new(filter), Box::new(index))
+                };
+            } else if self.eat("?") {
+                filter = Filter::Try(Box::new(filter));
+            } else {
+                return Ok(filter);
+            }
+        }
+    }
+
+    /// Parses a filter that doesn't contain operators outside of brackets
+    fn primary(&mut self) -> Result<Filter, String> {
+        match self.tokens.pop().ok_or("unexpected end of filter")? {
+            // The input, or one of its properties
+            Token::Symbol(".") => Ok(match self.field() {
+                Some(name) => Filter::Field(Box::new(Filter::Identity), name),
+                None => Filter::Identity,
+            }),
+
+            // Grouping, lists and objects
+            Token::Symbol("(") => {
+                let filter = self.pipe()?;
+                self.expect(")")?;
+                Ok(filter)
+            }
+            Token::Symbol("[") if self.eat("]") => Ok(Filter::Collect(None)),
+            Token::Symbol("[") => {
+                let filter = self.pipe()?;
+                self.expect("]")?;
+                Ok(Filter::Collect(Some(Box::new(filter))))
+            }
+            Token::Symbol("{") => self.object(),
+
+            // Literals
+            Token::Str(string) => Ok(Filter::Literal(Json::String(string))),
+            Token::Num(number) => Ok(Filter::Literal(Json::Number(number))),
+            Token::Ident(name) if name == "true" => Ok(Filter::Literal(Json::Bool(true))),
+            Token::Ident(name) if name == "false" => Ok(Filter::Literal(Json::Bool(false))),
+            Token::Ident(name) if name == "null" => Ok(Filter::Literal(Json::Null)),
+
+            // Builtin functions, which take at most one argument
+            Token::Ident(name) => {
+                let mut arguments = Vec::new();
+                if self.eat("(") {
+                    arguments.push(self.pipe()?);
+                    self.expect(")")?;
+                }
+                Ok(Filter::Call(name, arguments))
+            }
+            Token::Symbol(symbol) => Err(format!("unexpected {symbol}")),
+        }
+    }
+
+    /// Parses the properties of an object construction after the opening brace
+    fn object(&mut self) -> Result<Filter, String> {
+        let mut items = Vec::new();
+        while !self.eat("}") {
+            if !items.is_empty() {
+                self.expect(",")?;
+            }
+
+            // A bare key takes the property of the input with the same name
+            let key = match self.tokens.pop() {
+                Some(Token::Ident(key) | Token::Str(key)) => key,
+                Some(Token::Symbol("(")) => {
+                    let key = self.pipe()?;
+                    self.expect(")")?;
+                    self.expect(":")?;
+                    items.push((key, self.or()?));
+                    continue;
+                }
+                _ => return Err("expected a key".into()),
+            };
+            let value = match self.eat(":") {
+                true => self.or()?,
+                false => Filter::Field(Box::new(Filter::Identity), key.clone()),
+            };
+            items.push((Filter::Literal(Json::String(key)), value));
+        }
+        Ok(Filter::Object(items))
+    }
+}
+
+/// Returns whether a value counts as true, which is anything but `false` and `null`
+fn truthy(value: &Json) -> bool {
+    !matches!(value, Json::Bool(false) | Json::Null)
+}
+
+/// Returns a short description of a value's type for error messages
+fn type_name(value: &Json) -> &'static str {
+    match value {
+        Json::Null => "null",
+        Json::Bool(_) => "boolean",
+        Json::Number(_) | Json::NumberRaw(_) => "number",
+        Json::String(_) => "string",
+        Json::List(_) => "array",
+        Json::Object(_) => "object",
+    }
+}
+
+/// Looks up an element or property of a value
+fn index(value: &Json, index: &Json) -> Result<Json, String> {
+    match (value, index) {
+        (Json::Null, _) => Ok(Json::Null),
+        (Json::Object(items), Json::String(key)) => Ok(items
+            .iter()
+            .rev()
+            .find(|(name, _)| name == key)
+            .map_or(Json::Null, |(_, value)| value.clone())),
+        (Json::List(values), Json::Number(index)) => {
+            let index = if *index < 0.0 {
+                values.len() as f64 + index
+            } else {
+                *index
+            };
+            Ok(match index >= 0.0 {
+                true => values.get(index as usize).cloned().unwrap_or(Json::Null),
+                false => Json::Null,
+            })
+        }
+        _ => Err(format!(
+            "cannot index {} with {}",
+            type_name(value),
+            type_name(index)
+        )),
+    }
+}
+
+/// Calls a builtin function
+fn call(name: &str, arguments: &[Filter], input: &Json) -> Result<Vec<Json>, String> {
+    Ok(match (name, arguments) {
+        ("empty", []) => Vec::new(),
+        ("not", []) => vec![Json::Bool(!truthy(input))],
+        ("type", []) => vec![Json::String(type_name(input).into())],
+        ("length", []) => vec![Json::Number(match input {
+            Json::Null => 0.0,
+            Json::Bool(_) => return Err("boolean has no length".into()),
+            Json::Number(number) => number.abs(),
+            Json::NumberRaw(text) => text.parse::<f64>().map_or(f64::NAN, f64::abs),
+            Json::String(string) => string.chars().count() as f64,
+            Json::List(values) => values.len() as f64,
+            Json::Object(items) => items.len() as f64,
+        })],
+        ("keys", []) => match input {
+            Json::Object(items) => {
+                let mut keys = items.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
+                keys.sort();
+                keys.dedup();
+                vec![Json::List(keys.into_iter().map(Json::String).collect())]
+            }
+            Json::List(values) => vec![Json::List(
+                (0..values.len())
+                    .map(|index| Json::Number(index as f64))
+                    .collect(),
+            )],
+            _ => return Err(format!("{} has no keys", type_name(input))),
+        },
+        ("add", []) => {
+            let values = Filter::Iterate(Box::new(Filter::Identity)).apply(input)?;
+            let mut values = values.into_iter();
+            let first = values.next().unwrap_or(Json::Null);
+            vec![values.try_fold(first, |sum, value| match (sum, value) {
+                (Json::Null, value) => Ok(value),
+                (Json::Number(a), Json::Number(b)) => Ok(Json::Number(a + b)),
+                (Json::String(a), Json::String(b)) => Ok(Json::String(a + &b)),
+                (Json::List(mut a), Json::List(b)) => {
+                    a.extend(b);
+                    Ok(Json::List(a))
+                }
+                (a, b) => Err(format!(
+                    "cannot add {} and {}",
+                    type_name(&a),
+                    type_name(&b)
+                )),
+            })?]
+        }
+        ("select", [filter]) => match filter.apply(input)?.iter().any(truthy) {
+            true => vec![input.clone()],
+            false => Vec::new(),
+        },
+        ("map", [filter]) => {
+            let mut values = Vec::new();
+            for value in Filter::Iterate(Box::new(Filter::Identity)).apply(input)? {
+                values.extend(filter.apply(&value)?);
+            }
+            vec![Json::List(values)]
+        }
+        ("has", [filter]) => {
+            let mut results = Vec::new();
+            for key in filter.apply(input)? {
+                results.push(Json::Bool(match (input, &key) {
+                    (Json::Object(items), Json::String(key)) => {
+                        items.iter().any(|(name, _)| name == key)
+                    }
+                    (Json::List(values), Json::Number(index)) => {
+                        *index >= 0.0 && (*index as usize) < values.len()
+                    }
+                    _ => return Err(format!("cannot check {} for keys", type_name(input))),
+                }));
+            }
+            results
+        }
+        _ => return Err(format!("unknown function {name}/{}", arguments.len())),
+    })
+}
+
+impl Filter {
+    /// Parses a filter expression
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut tokens = lex(source)?;
+        tokens.reverse();
+        let mut parser = Parser { tokens };
+        let filter = parser.pipe()?;
+        match parser.peek() {
+            None => Ok(filter),
+            Some(token) => Err(format!("unexpected {token:?}")),
+        }
+    }
+
+    /// Applies the filter to a value, returning all of its outputs
+    pub fn apply(&self, input: &Json) -> Result<Vec<Json>, String> {
+        Ok(match self {
+            Filter::Identity => vec![input.clone()],
+            Filter::Literal(value) => vec![value.clone()],
+
+            // Look up properties and elements of every output
+            Filter::Field(filter, name) => filter
+                .apply(input)?
+                .iter()
+                .map(|value| index(value, &Json::String(name.clone())))
+                .collect::<Result<_, _>>()?,
+            Filter::Index(filter, key) => {
+                let mut results = Vec::new();
+                for value in filter.apply(input)? {
+                    for key in key.apply(input)? {
+                        results.push(index(&value, &key)?);
+                    }
+                }
+                results
+            }
+            Filter::Iterate(filter) => {
+                let mut results = Vec::new();
+                for value in filter.apply(input)? {
+                    match value {
+                        Json::List(values) => results.extend(values),
+                        Json::Object(items) => results.extend(items.into_iter().map(|(_, v)| v)),
+                        value => return Err(format!("cannot iterate over {}", type_name(&value))),
+                    }
+                }
+                results
+            }
+            Filter::Try(filter) => filter.apply(input).unwrap_or_default(),
+
+            // Combine filters
+            Filter::Pipe(first, second) => {
+                let mut results = Vec::new();
+                for value in first.apply(input)? {
+                    results.extend(second.apply(&value)?);
+                }
+                results
+            }
+            Filter::Comma(first, second) => {
+                let mut results = first.apply(input)?;
+                results.extend(second.apply(input)?);
+                results
+            }
+
+            // Compare and combine every pair of outputs
+            Filter::Compare(left, operator, right) => {
+                let mut results = Vec::new();
+                for right in right.apply(input)? {
+                    for left in left.apply(input)? {
+                        let ordering = left.compare(&right);
+                        results.push(Json::Bool(match *operator {
+                            "==" => left.equivalent(&right),
+                            "!=" => !left.equivalent(&right),
+                            "<" => ordering.is_lt(),
+                            "<=" => ordering.is_le(),
+                            ">" => ordering.is_gt(),
+                            _ => ordering.is_ge(),
+                        }));
+                    }
+                }
+                results
+            }
+            Filter::And(left, right) | Filter::Or(left, right) => {
+                let or = matches!(self, Filter::Or(..));
+                let mut results = Vec::new();
+                for left in left.apply(input)? {
+                    if truthy(&left) == or {
+                        results.push(Json::Bool(or));
+                        continue;
+                    }
+                    for right in right.apply(input)? {
+                        results.push(Json::Bool(truthy(&right)));
+                    }
+                }
+                results
+            }
+
+            // Build lists and objects
+            Filter::Collect(filter) => vec![Json::List(match filter {
+                Some(filter) => filter.apply(input)?,
+                None => Vec::new(),
+            })],
+            Filter::Object(items) => {
+                let mut objects = vec![Vec::new()];
+                for (key, value) in items {
+                    let mut next = Vec::new();
+                    for key in key.apply(input)? {
+                        let Json::String(key) = key else {
+                            return Err(format!(
+                                "object keys must be strings, not {}",
+                                type_name(&key)
+                            ));
+                        };
+                        for value in value.apply(input)? {
+                            for object in &objects {
+                                let mut object: Vec<(String, Json)> = object.clone();
+                                object.push((key.clone(), value.clone()));
+                                next.push(object);
+                            }
+                        }
+                    }
+                    objects = next;
+                }
+                objects.into_iter().map(Json::Object).collect()
+            }
+            Filter::Call(name, arguments) => call(name, arguments, input)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use json_parser::Json;
+
+    use super::Filter;
+
+    /// Applies a filter to a document, returning the outputs as compact JSON
+    fn run(filter: &str, document: &str) -> Result<Vec<String>, String> {
+        let document = document.parse::<Json>().unwrap();
+        let outputs = Filter::parse(filter)?.apply(&document)?;
+        Ok(outputs.iter().map(Json::to_string).collect())
+    }
+
+    #[test]
+    fn paths() {
+        let document = r#"{"items": [{"name": "a", "n": 1}, {"name": "b", "n": 2}], "x y": null}"#;
+        assert_eq!(run(".", "1").unwrap(), ["1"]);
+        assert_eq!(run(".items[1].name", document).unwrap(), ["\"b\""]);
+        assert_eq!(run(".items[-1].n", document).unwrap(), ["2"]);
+        assert_eq!(run(".items[].name", document).unwrap(), ["\"a\"", "\"b\""]);
+        assert_eq!(
+            run(".\"x y\", .missing", document).unwrap(),
+            ["null", "null"]
+        );
+        assert!(run(".items.name", document).is_err());
+        assert_eq!(run(".items.name?", document).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn functions() {
+        let document = r#"{"items": [{"name": "a", "active": true, "n": 3}, {"name": "b", "active": false, "n": 1}]}"#;
+        assert_eq!(
+            run(".items[] | select(.active) | .name", document).unwrap(),
+            ["\"a\""]
+        );
+        assert_eq!(
+            run(
+                "[.items[] | select(.n > 1 and .name != \"b\")] | length",
+                document
+            )
+            .unwrap(),
+            ["1"]
+        );
+        assert_eq!(run(".items | map(.n) | add", document).unwrap(), ["4"]);
+        assert_eq!(
+            run(".items[0] | keys", document).unwrap(),
+            ["[\"active\",\"n\",\"name\"]"]
+        );
+        assert_eq!(
+            run(".items[] | {name, big: (.n >= 3)}", document).unwrap(),
+            [
+                "{\"name\":\"a\",\"big\":true}",
+                "{\"name\":\"b\",\"big\":false}"
+            ]
+        );
+        assert_eq!(
+            run(".items[0] | has(\"n\"), (.active | not)", document).unwrap(),
+            ["true", "false"]
+        );
+        assert!(Filter::parse(".items[").is_err());
+        assert!(run("frobnicate", "1").is_err());
+    }
+
+    #[test]
+    fn equality() {
+        let document = r#"{"a": {"x": 1, "y": [2]}, "b": {"y": [2.0], "x": 1}, "c": {"x": 2}}"#;
+        assert_eq!(
+            run(".a == .b, .a != .b", document).unwrap(),
+            ["true", "false"]
+        );
+        assert_eq!(
+            run(".a == .c, .a != .c", document).unwrap(),
+            ["false", "true"]
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! Applying a JSON Patch or a JSON Merge Patch to a document
+
+use std::process::ExitCode;
+
+use json_parser::{Format, Json};
+
+use super::{Failure, input_name, input_path, read_document};
+
+/// Applies the patch to the document and prints the result
+pub fn run<I: Iterator<Item = String>>(args: I) -> Result<ExitCode, Failure> {
+    // Parse the arguments
+    let mut merge = false;
+    let mut invert = false;
+    let mut format = Format::compact();
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--merge" => merge = true,
+            "--invert" => invert = true,
+            "--pretty" => format = Format::pretty(),
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {arg}")));
+            }
+            _ => paths.push(input_path(arg)),
+        }
+    }
+    let [document, patch] =
+        <[_; 2]>::try_from(paths).map_err(|_| "patch expects a document and a patch")?;
+
+    // A list is a JSON Patch, anything else a JSON Merge Patch, which can't be inverted
+    let name = input_name(patch.as_deref()).to_owned();
+    let mut document = read_document(document.as_deref())?;
+    let patch = read_document(patch.as_deref())?;
+    match patch {
+        Json::List(_) if !merge => {
+            let mut operations = patch
+                .to_patch()
+                .map_err(|error| Failure::Message(format!("{name}: {error}")))?;
+            if invert {
+                operations = Json::invert_patch(&operations).map_err(|_| {
+                    Failure::Message(format!("{name} doesn't record the values it changes"))
+                })?;
+            }
+            document
+                .apply_patch(&operations)
+                .map_err(|error| Failure::Message(format!("can't apply {name}: {error}")))?;
+        }
+        _ if invert => return Err("--invert needs a JSON Patch".into()),
+        _ => document.merge_patch(&patch),
+    }
+
+    // Print the patched document
+    println!("{}", document.display(&format));
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,282 @@
+//! Printing a document or a value in it
+
+use std::{
+    io::{BufRead, stdout},
+    process::ExitCode,
+};
+
+use json_parser::{Error, Format, Indent, Json};
+
+use super::{
+    Failure,
+    color::{When, colorize},
+    filter::Filter,
+    input_name, input_path, open_input, parse, read_input, report,
+    stream::{self, Selection},
+    watch,
+};
+
+/// How the document is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Indented, with every value on its own line
+    Pretty,
+
+    /// On a single line without whitespace
+    Minify,
+
+    /// In the canonical form of RFC 8785, for signing and byte-for-byte comparison
+    Canonical,
+}
+
+/// The parsed command line arguments
+struct Options {
+    /// How the document is printed
+    mode: Mode,
+
+    /// The indentation of pretty output
+    indent: Indent,
+
+    /// Whether the properties of objects are printed sorted by their keys
+    sort_keys: bool,
+
+    /// The keys whose properties are printed first, in this order
+    key_order: Vec<String>,
+
+    /// The JSON pointer of the value to print instead of the whole document
+    get: Option<String>,
+
+    /// The filter the value is passed through before printing
+    filter: Option<Filter>,
+
+    /// Whether every line of the input is a separate document
+    ndjson: bool,
+
+    /// Which items of the list are printed instead of all of them
+    selection: Option<Selection>,
+
+    /// Whether the output is colored
+    color: bool,
+
+    /// Whether the file is printed again whenever it changes
+    watch: bool,
+
+    /// The path of the file to read, `None` to read from standard input
+    path: Option<String>,
+}
+
+impl Options {
+    /// Parses the command line arguments
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, Failure> {
+        let mut mode = Mode::Minify;
+        let mut indent = Indent::Spaces(2);
+        let mut sort_keys = false;
+        let mut key_order = Vec::new();
+        let mut get = None;
+        let mut filter = None;
+        let mut ndjson = false;
+        let mut selection = None;
+        let mut color = When::Auto;
+        let mut watch = false;
+        let mut path = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--pretty" => mode = Mode::Pretty,
+                "--minify" => mode = Mode::Minify,
+                "--canonical" => mode = Mode::Canonical,
+                "--indent" => {
+                    let width = args.next().ok_or("--indent expects a number of spaces")?;
+                    let width = width
+                        .parse()
+                        .map_err(|_| Failure::Usage(format!("invalid indent width {width}")))?;
+                    indent = Indent::Spaces(width);
+                    mode = Mode::Pretty;
+                }
+                "--tab" => {
+                    indent = Indent::Tab;
+                    mode = Mode::Pretty;
+                }
+                "--sort-keys" => sort_keys = true,
+                "--key-order" => {
+                    let keys = args
+                        .next()
+                        .ok_or("--key-order expects comma-separated keys")?;
+                    key_order = keys.split(',').map(str::to_owned).collect();
+                }
+                "--filter" => {
+                    let source = args.next().ok_or("--filter expects a filter expression")?;
+                    filter = Some(
+                        Filter::parse(&source)
+                            .map_err(|error| Failure::Usage(format!("invalid filter: {error}")))?,
+                    );
+                }
+                "--ndjson" => ndjson = true,
+                "--watch" => watch = true,
+                "--head" | "--sample" => {
+                    let count = args.next().unwrap_or_default();
+                    let count = count
+                        .parse()
+                        .map_err(|_| Failure::Usage(format!("{arg} expects a number of items")))?;
+                    selection = Some(match arg.as_str() {
+                        "--head" => Selection::Head(count),
+                        _ => Selection::Sample(count),
+                    });
+                }
+                "--get" => get = Some(args.next().ok_or("--get expects a JSON pointer")?),
+                _ if arg.starts_with("--color=") => {
+                    color = When::parse(&arg["--color=".len()..])
+                        .ok_or("--color expects never, always or auto")?;
+                }
+                _ if arg.starts_with("--") => {
+                    return Err(Failure::Usage(format!("unknown option {arg}")));
+                }
+                _ if path.is_none() => path = Some(input_path(arg)),
+                _ => return Err(Failure::Usage(format!("unexpected argument {arg}"))),
+            }
+        }
+        if ndjson && selection.is_some() {
+            return Err("--head and --sample don't work with --ndjson".into());
+        }
+        Ok(Self {
+            mode,
+            indent,
+            sort_keys,
+            key_order,
+            get,
+            filter,
+            ndjson,
+            selection,
+            color: color.enabled(),
+            watch,
+            path: path.flatten(),
+        })
+    }
+
+    /// Returns the serializer options for the chosen style
+    fn format(&self) -> Format {
+        Format {
+            indent: (self.mode == Mode::Pretty).then_some(self.indent),
+            sort_keys: self.sort_keys,
+            key_order: self.key_order.clone(),
+            ..Format::default()
+        }
+    }
+}
+
+/// Prints the requested part of a document, using the name of the input in messages
+fn print(value: &Json, options: &Options, name: &str) -> Result<(), Failure> {
+    // Look up the requested value
+    let value = match &options.get {
+        Some(pointer) => value
+            .pointer(pointer)
+            .ok_or_else(|| Failure::Message(format!("no value at {pointer} in {name}")))?,
+        None => value,
+    };
+    output(value, options, name)
+}
+
+/// Prints the outputs of the filter for a value, or the value itself without a filter
+fn output(value: &Json, options: &Options, name: &str) -> Result<(), Failure> {
+    match &options.filter {
+        Some(filter) => filter
+            .apply(value)
+            .map_err(|error| Failure::Message(format!("can't filter {name}: {error}")))?
+            .iter()
+            .for_each(|value| print_value(value, options)),
+        None => print_value(value, options),
+    }
+    Ok(())
+}
+
+/// Prints a value in the chosen style
+fn print_value(value: &Json, options: &Options) {
+    // Serialize the value in the chosen style
+    let format = options.format();
+    let text = match options.mode {
+        Mode::Canonical => value.to_canonical_string(),
+        _ => value.display(&format).to_string(),
+    };
+
+    // Print it, highlighted if requested
+    if options.color {
+        println!("{}", colorize(&text));
+    } else {
+        println!("{text}");
+    }
+}
+
+/// Reads, parses and prints the document, or every line of it in NDJSON mode
+fn print_input(options: &Options) -> Result<(), Failure> {
+    let name = input_name(options.path.as_deref());
+
+    // Handle one line at a time, so the input never has to fit in memory
+    if options.ndjson {
+        for (index, line) in open_input(options.path.as_deref())
+            .map_err(Failure::Message)?
+            .lines()
+            .enumerate()
+        {
+            let line =
+                line.map_err(|error| Failure::Message(format!("can't read {name}: {error}")))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value = parse(&line).map_err(|error| match error {
+                Error::At { column, error, .. } => {
+                    Failure::Message(format!("{name}:{}:{column}: {error}", index + 1))
+                }
+                error => Failure::Message(format!("{name}:{}: {error}", index + 1)),
+            })?;
+            print(&value, options, &format!("{name}:{}", index + 1))?;
+        }
+        return Ok(());
+    }
+
+    // Keep only the selected items of the list while reading it
+    if let Some(selection) = options.selection {
+        let value = stream::read_selection(
+            open_input(options.path.as_deref()).map_err(Failure::Message)?,
+            options.get.as_deref().unwrap_or(""),
+            selection,
+            name,
+        )?;
+        return output(&value, options, name);
+    }
+
+    // Stream the events unless an option needs the whole document, so huge files never become a
+    // tree in memory
+    if options.filter.is_none()
+        && !options.sort_keys
+        && options.key_order.is_empty()
+        && options.mode != Mode::Canonical
+    {
+        return stream::print(
+            open_input(options.path.as_deref()).map_err(Failure::Message)?,
+            stdout().lock(),
+            options.get.as_deref().unwrap_or(""),
+            options.format(),
+            options.color,
+            name,
+        );
+    }
+
+    // Read and parse the whole input
+    let text = read_input(options.path.as_deref()).map_err(Failure::Message)?;
+    let value =
+        parse(&text).map_err(|error| Failure::Message(format!("can't parse {name}: {error}")))?;
+    print(&value, options, name)
+}
+
+/// Prints the input once, or again whenever it changes in watch mode
+pub fn run<I: Iterator<Item = String>>(args: I) -> Result<ExitCode, Failure> {
+    let options = Options::parse(args)?;
+    if options.watch {
+        let path = options
+            .path
+            .as_deref()
+            .ok_or("--watch can't watch standard input")?;
+        watch(&[path], || report(print_input(&options)));
+    }
+    print_input(&options)?;
+    Ok(ExitCode::SUCCESS)
+}
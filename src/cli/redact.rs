@@ -0,0 +1,97 @@
+//! Masking sensitive values before sharing a document
+
+use std::process::ExitCode;
+
+use json_parser::{Format, Json};
+
+use super::{Failure, input_path, pointer_tokens, read_document};
+
+/// The text replacing redacted values by default
+const MASK: &str = "[REDACTED]";
+
+/// Replaces every value matching the tokens with the mask, returning how many were replaced
+///
+/// A `*` token matches every property of an object and every item of a list.
+fn redact(value: &mut Json, tokens: &[String], mask: &str) -> usize {
+    let Some((token, rest)) = tokens.split_first() else {
+        *value = Json::String(mask.into());
+        return 1;
+    };
+    match value {
+        Json::Object(items) => items
+            .iter_mut()
+            .filter(|(key, _)| token == "*" || key == token)
+            .map(|(_, value)| redact(value, rest, mask))
+            .sum(),
+        Json::List(values) => values
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| token == "*" || index.to_string() == *token)
+            .map(|(_, value)| redact(value, rest, mask))
+            .sum(),
+        _ => 0,
+    }
+}
+
+/// Prints the document with the values at the patterns masked
+pub fn run<I: Iterator<Item = String>>(mut args: I) -> Result<ExitCode, Failure> {
+    // Parse the arguments
+    let mut patterns = Vec::new();
+    let mut mask = MASK.to_owned();
+    let mut format = Format::compact();
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--path" => {
+                let pattern = args.next().ok_or("--path expects a JSON pointer")?;
+                let tokens = pointer_tokens(&pattern)
+                    .ok_or_else(|| Failure::Usage(format!("invalid JSON pointer {pattern}")))?;
+                patterns.push((pattern, tokens));
+            }
+            "--mask" => mask = args.next().ok_or("--mask expects a replacement text")?,
+            "--pretty" => format = Format::pretty(),
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {arg}")));
+            }
+            _ if path.is_none() => path = Some(input_path(arg)),
+            _ => return Err(Failure::Usage(format!("unexpected argument {arg}"))),
+        }
+    }
+    if patterns.is_empty() {
+        return Err("redact expects at least one --path".into());
+    }
+
+    // Mask the values, warning about patterns that match nothing as they're likely typos
+    let mut document = read_document(path.flatten().as_deref())?;
+    for (pattern, tokens) in &patterns {
+        if redact(&mut document, tokens, &mask) == 0 {
+            eprintln!("json_parser: nothing matches {pattern}");
+        }
+    }
+    println!("{}", document.display(&format));
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use json_parser::Json;
+
+    use super::redact;
+
+    #[test]
+    fn wildcards() {
+        let mut document = "{\"users\": [{\"token\": 1}, {\"token\": [2]}, {}], \"token\": 3}"
+            .parse::<Json>()
+            .unwrap();
+        let tokens = ["users".to_owned(), "*".to_owned(), "token".to_owned()];
+        assert_eq!(redact(&mut document, &tokens, "x"), 2);
+        assert_eq!(
+            document.to_string(),
+            "{\"users\":[{\"token\":\"x\"},{\"token\":\"x\"},{}],\"token\":3}"
+        );
+        assert_eq!(
+            redact(&mut document, &["users".to_owned(), "3".to_owned()], "x"),
+            0
+        );
+    }
+}
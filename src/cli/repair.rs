@@ -0,0 +1,47 @@
+//! Repairing almost-JSON documents
+
+use std::process::ExitCode;
+
+use json_parser::{Format, Json};
+
+use super::{Failure, input_name, input_path, read_input};
+
+/// Prints the repaired document, and the corrections made to standard error
+pub fn run<I: Iterator<Item = String>>(args: I) -> Result<ExitCode, Failure> {
+    // Parse the arguments
+    let mut format = Format::compact();
+    let mut quiet = false;
+    let mut path = None;
+    for arg in args {
+        match arg.as_str() {
+            "--pretty" => format = Format::pretty(),
+            "--quiet" => quiet = true,
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {arg}")));
+            }
+            _ if path.is_none() => path = Some(input_path(arg)),
+            _ => return Err(Failure::Usage(format!("unexpected argument {arg}"))),
+        }
+    }
+    let path = path.flatten();
+    let name = input_name(path.as_deref());
+
+    // Repair the document
+    let text = read_input(path.as_deref()).map_err(Failure::Message)?;
+    let (value, repairs) = Json::repair(&text)
+        .map_err(|error| Failure::Message(format!("can't repair {name}: {error}")))?;
+    println!("{}", value.display(&format));
+
+    // Summarize the corrections, keeping standard output valid JSON
+    if !quiet {
+        for repair in &repairs {
+            eprintln!("{name}:{}:{}: {}", repair.line, repair.column, repair.kind);
+        }
+        match repairs.len() {
+            0 => eprintln!("{name}: no corrections needed"),
+            1 => eprintln!("{name}: 1 correction"),
+            count => eprintln!("{name}: {count} corrections"),
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
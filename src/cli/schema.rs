@@ -0,0 +1,64 @@
+//! Validating documents against a JSON Schema
+
+use std::process::ExitCode;
+
+use json_parser::Json;
+
+use super::{Failure, input_name, input_path, parse_options, read_document, read_input};
+
+/// Validates the documents against the schema, returning failure if any of them doesn't match
+pub fn run<I: Iterator<Item = String>>(mut args: I) -> Result<ExitCode, Failure> {
+    // Only validation is supported so far
+    match args.next().as_deref() {
+        Some("validate") => {}
+        Some(command) => return Err(Failure::Usage(format!("unknown schema command {command}"))),
+        None => return Err("schema expects a command".into()),
+    }
+
+    // Parse the arguments
+    let mut schema = None;
+    let mut paths = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--schema" => schema = Some(args.next().ok_or("--schema expects a file")?),
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {arg}")));
+            }
+            _ => paths.push(input_path(arg)),
+        }
+    }
+    let schema = read_document(Some(&schema.ok_or("schema validate needs --schema")?))?;
+    if paths.is_empty() {
+        paths.push(None);
+    }
+
+    // Print every violation with the position and the pointer of the value
+    let mut valid = true;
+    for path in &paths {
+        let name = input_name(path.as_deref());
+        let text = read_input(path.as_deref()).map_err(Failure::Message)?;
+        let document = Json::parse_spanned_with(&text, &parse_options())
+            .map_err(|error| Failure::Message(format!("can't parse {name}: {error}")))?;
+        if let Err(violations) = document.to_json().validate_schema(&schema) {
+            for violation in violations {
+                let span = document
+                    .pointer(&violation.pointer)
+                    .map_or(document.span, |value| value.span);
+                let pointer = match violation.pointer.as_str() {
+                    "" => "(root)",
+                    pointer => pointer,
+                };
+                println!(
+                    "{name}:{}:{}: {pointer}: {}",
+                    span.line, span.column, violation.message
+                );
+            }
+            valid = false;
+        }
+    }
+    Ok(if valid {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
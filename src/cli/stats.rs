@@ -0,0 +1,117 @@
+//! Summarizing the shape of a document
+
+use std::{collections::HashMap, process::ExitCode};
+
+use json_parser::{Json, Metrics};
+
+use super::{Failure, input_path, read_document};
+
+/// How many of the largest lists and most frequent keys are listed
+const TOP: usize = 10;
+
+/// The longest bar of the key histogram
+const BAR_WIDTH: usize = 40;
+
+/// The statistics gathered from a document, besides its metrics
+#[derive(Default)]
+struct Stats {
+    /// The pointers to all lists with their lengths
+    lists: Vec<(String, usize)>,
+
+    /// How often each key occurs in objects
+    keys: HashMap<String, usize>,
+}
+
+impl Stats {
+    /// Adds a value at the given pointer, and everything in it
+    fn visit(&mut self, value: &Json, pointer: &mut String) {
+        let length = pointer.len();
+        match value {
+            Json::Object(items) => {
+                for (key, value) in items {
+                    *self.keys.entry(key.clone()).or_default() += 1;
+                    pointer.push('/');
+                    pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                    self.visit(value, pointer);
+                    pointer.truncate(length);
+                }
+            }
+            Json::List(values) => {
+                self.lists.push((pointer.clone(), values.len()));
+                for (index, value) in values.iter().enumerate() {
+                    pointer.push_str(&format!("/{index}"));
+                    self.visit(value, pointer);
+                    pointer.truncate(length);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Prints the report
+    fn print(mut self, metrics: &Metrics) {
+        // Print the counts and sizes
+        println!("values:");
+        for (name, count) in [
+            ("objects", metrics.objects),
+            ("lists", metrics.lists),
+            ("strings", metrics.strings),
+            ("numbers", metrics.numbers),
+            ("booleans", metrics.booleans),
+            ("nulls", metrics.nulls),
+            ("total", metrics.values()),
+        ] {
+            println!("  {name:<10}{count}");
+        }
+        println!("max depth: {}", metrics.max_depth);
+        println!("string bytes: {}", metrics.string_bytes);
+
+        // Print the largest lists
+        if !self.lists.is_empty() {
+            self.lists
+                .sort_by(|(a, x), (b, y)| y.cmp(x).then_with(|| a.cmp(b)));
+            println!("largest lists:");
+            for (pointer, length) in self.lists.iter().take(TOP) {
+                let pointer = if pointer.is_empty() {
+                    "(root)"
+                } else {
+                    pointer
+                };
+                println!("  {length:>8}  {pointer}");
+            }
+        }
+
+        // Print a histogram of the most frequent keys
+        let mut keys = self.keys.into_iter().collect::<Vec<_>>();
+        keys.sort_by(|(a, x), (b, y)| y.cmp(x).then_with(|| a.cmp(b)));
+        if let Some(&(_, most)) = keys.first() {
+            println!("most frequent keys:");
+            for (key, count) in keys.iter().take(TOP) {
+                let bar = "#".repeat((count * BAR_WIDTH).div_ceil(most));
+                println!("  {count:>8}  {key:<20} {bar}");
+            }
+        }
+    }
+}
+
+/// Prints statistics about the shape of a document
+pub fn run<I: Iterator<Item = String>>(args: I) -> Result<ExitCode, Failure> {
+    // Parse the arguments
+    let mut path = None;
+    for arg in args {
+        match arg.as_str() {
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {arg}")));
+            }
+            _ if path.is_none() => path = Some(input_path(arg)),
+            _ => return Err(Failure::Usage(format!("unexpected argument {arg}"))),
+        }
+    }
+
+    // Gather and print the statistics
+    let document = read_document(path.flatten().as_deref())?;
+    let mut stats = Stats::default();
+    stats.visit(&document, &mut String::new());
+    stats.print(&document.metrics());
+    Ok(ExitCode::SUCCESS)
+}
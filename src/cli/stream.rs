@@ -0,0 +1,516 @@
+//! Printing documents larger than the available memory from a stream of events
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io::{self, BufRead, BufWriter, Write},
+};
+
+use json_parser::{Error, Event, Events, Format, Indent, Json};
+
+use super::{Failure, color, pointer_tokens};
+
+/// An iterator over the characters of UTF-8 input, remembering the first error
+struct CharReader<R> {
+    /// The input to decode
+    input: R,
+
+    /// The error that ended the characters early, if any
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> CharReader<R> {
+    /// Reads the next byte, recording errors
+    fn byte(&mut self) -> Option<u8> {
+        loop {
+            match self.input.fill_buf() {
+                Ok(buffer) => {
+                    let byte = *buffer.first()?;
+                    self.input.consume(1);
+                    return Some(byte);
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => {
+                    self.error = Some(error);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Records that the input isn't valid UTF-8
+    fn invalid(&mut self) -> Option<char> {
+        self.error = Some(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        ));
+        None
+    }
+}
+
+impl<R: BufRead> Iterator for CharReader<R> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+
+        // Find the length of the character from its first byte
+        let first = self.byte()?;
+        let width = match first {
+            0x00..=0x7f => return Some(char::from(first)),
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return self.invalid(),
+        };
+
+        // Decode the whole character
+        let mut bytes = [first, 0, 0, 0];
+        for byte in &mut bytes[1..width] {
+            match self.byte() {
+                Some(next) => *byte = next,
+                None if self.error.is_some() => return None,
+                None => return self.invalid(),
+            }
+        }
+        match std::str::from_utf8(&bytes[..width]) {
+            Ok(text) => text.chars().next(),
+            Err(_) => self.invalid(),
+        }
+    }
+}
+
+/// Writes events as JSON text in the chosen style
+struct EventWriter<W> {
+    /// Where the text goes
+    out: W,
+
+    /// The style of the output
+    format: Format,
+
+    /// Whether the output is colored
+    color: bool,
+
+    /// Whether each open container is still empty, innermost last
+    containers: Vec<bool>,
+
+    /// Whether a key has been written and its value is next
+    after_key: bool,
+
+    /// Whether anything has been written yet
+    written: bool,
+}
+
+impl<W: Write> EventWriter<W> {
+    /// Starts a new line indented to the given depth, if the output is indented
+    fn newline(&mut self, depth: usize) -> io::Result<()> {
+        match self.format.indent {
+            Some(Indent::Spaces(width)) => {
+                let line_ending = self.format.line_ending.as_str();
+                write!(self.out, "{line_ending}{:1$}", "", width * depth)
+            }
+            Some(Indent::Tab) => {
+                let line_ending = self.format.line_ending.as_str();
+                write!(self.out, "{line_ending}{}", "\t".repeat(depth))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Writes a token, highlighted if requested
+    fn token(&mut self, color: &str, token: &str) -> io::Result<()> {
+        match self.color {
+            true => write!(self.out, "{color}{token}{}", color::RESET),
+            false => self.out.write_all(token.as_bytes()),
+        }
+    }
+
+    /// Closes the innermost container, leaving empty ones on one line unless they're expanded
+    fn close(&mut self, bracket: &[u8]) -> io::Result<()> {
+        if self.containers.pop() == Some(false) || self.format.expand_empty {
+            self.newline(self.containers.len())?;
+        }
+        self.out.write_all(bracket)
+    }
+
+    /// Writes the next event
+    fn write(&mut self, event: Event) -> io::Result<()> {
+        self.written = true;
+
+        // Separate the values in containers, except the values of properties
+        let starts_value = !matches!(event, Event::EndObject | Event::EndList);
+        if starts_value && !std::mem::take(&mut self.after_key) {
+            let depth = self.containers.len();
+            if let Some(empty) = self.containers.last_mut() {
+                if !std::mem::replace(empty, false) {
+                    self.out.write_all(b",")?;
+                }
+                self.newline(depth)?;
+            }
+        }
+
+        // Write the event itself
+        match event {
+            Event::StartObject => {
+                self.containers.push(true);
+                self.out.write_all(b"{")
+            }
+            Event::StartList => {
+                self.containers.push(true);
+                self.out.write_all(b"[")
+            }
+            Event::EndObject => self.close(b"}"),
+            Event::EndList => self.close(b"]"),
+            Event::Key(key) => {
+                self.after_key = true;
+                let key = Json::String(key).display(&self.format).to_string();
+                self.token(color::KEY, &key)?;
+                let space = self.format.indent.is_some();
+                match self.format.space_after_colon.unwrap_or(space) {
+                    true => self.out.write_all(b": "),
+                    false => self.out.write_all(b":"),
+                }
+            }
+            Event::String(string) => {
+                let string = Json::String(string).display(&self.format).to_string();
+                self.token(color::STRING, &string)
+            }
+            Event::Number(number) => {
+                let number = Json::Number(number).display(&self.format).to_string();
+                self.token(color::NUMBER, &number)
+            }
+            Event::Bool(value) => self.token(color::BOOL, if value { "true" } else { "false" }),
+            Event::Null => self.token(color::NULL, "null"),
+        }
+    }
+}
+
+/// Which items of a list are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    /// The first items
+    Head(usize),
+
+    /// Items chosen at random, in their original order
+    Sample(usize),
+}
+
+/// Why streaming the value failed
+enum Problem {
+    /// The input isn't valid JSON
+    Parse(Error),
+
+    /// The output couldn't be written
+    Write(io::Error),
+
+    /// The value isn't a list
+    NotList,
+}
+
+impl From<Error> for Problem {
+    fn from(error: Error) -> Self {
+        Problem::Parse(error)
+    }
+}
+
+/// Reads the next event of a value that isn't complete yet
+fn next_event<I: Iterator<Item = char>>(events: &mut Events<I>) -> Result<Event, Error> {
+    events.next().unwrap_or(Err(Error::UnexpectedEndOfFile))
+}
+
+/// Reads events until the value at the path of tokens starts, returning its first event
+fn find<I: Iterator<Item = char>>(
+    events: &mut Events<I>,
+    target: &[String],
+) -> Result<Option<Event>, Error> {
+    // The token of the current value and the next list index of every container around it
+    let mut path: Vec<(Option<usize>, String)> = Vec::new();
+    for event in events.by_ref() {
+        let event = event?;
+        match &event {
+            Event::Key(key) => {
+                if let Some((_, token)) = path.last_mut() {
+                    token.clone_from(key);
+                }
+                continue;
+            }
+            Event::EndObject | Event::EndList => {
+                path.pop();
+                continue;
+            }
+            _ => {}
+        }
+
+        // Compare the path of every value that starts with the pointer
+        if let Some((Some(index), token)) = path.last_mut() {
+            *token = index.to_string();
+            *index += 1;
+        }
+        if path.iter().map(|(_, token)| token).eq(target) {
+            return Ok(Some(event));
+        }
+        match event {
+            Event::StartObject => path.push((None, String::new())),
+            Event::StartList => path.push((Some(0), String::new())),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the depth of the reader once the value starting with the event is complete
+fn value_depth<I: Iterator<Item = char>>(event: &Event, events: &Events<I>) -> usize {
+    match event {
+        Event::StartObject | Event::StartList => events.depth() - 1,
+        _ => events.depth(),
+    }
+}
+
+/// Reads past the value starting with the event without keeping it
+fn skip<I: Iterator<Item = char>>(event: &Event, events: &mut Events<I>) -> Result<(), Error> {
+    let depth = value_depth(event, events);
+    while events.depth() > depth {
+        next_event(events)?;
+    }
+    Ok(())
+}
+
+/// Returns the next number of a xorshift generator
+fn random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+/// Writes the value at the path of tokens, returning whether it was found
+///
+/// Reading stops as soon as the value has been written. The whole document is checked for
+/// trailing characters when it is the value.
+fn copy<I: Iterator<Item = char>, W: Write>(
+    chars: I,
+    writer: &mut EventWriter<W>,
+    target: &[String],
+) -> Result<bool, Problem> {
+    let mut events = Json::events(chars);
+    let Some(mut event) = find(&mut events, target)? else {
+        return Ok(false);
+    };
+
+    // Write the value, and stop once it's complete
+    let depth = value_depth(&event, &events);
+    loop {
+        writer.write(event).map_err(Problem::Write)?;
+        if events.depth() == depth {
+            break;
+        }
+        event = next_event(&mut events)?;
+    }
+    if target.is_empty() {
+        events.finish()?;
+    }
+    Ok(true)
+}
+
+/// Selects items of the list at the path of tokens, returning `None` if it wasn't found
+///
+/// Only the selected items are kept in memory, and reading stops after the head of the list.
+fn select<I: Iterator<Item = char>>(
+    chars: I,
+    target: &[String],
+    selection: Selection,
+) -> Result<Option<Vec<Json>>, Problem> {
+    let mut events = Json::events(chars);
+    match find(&mut events, target)? {
+        Some(Event::StartList) => {}
+        Some(_) => return Err(Problem::NotList),
+        None => return Ok(None),
+    }
+
+    // Keep a reservoir of items, replacing them with decreasing probability when sampling
+    let mut state = RandomState::new().build_hasher().finish() | 1;
+    let mut kept = Vec::<(usize, Json)>::new();
+    for index in 0.. {
+        if matches!(selection, Selection::Head(count) if kept.len() >= count) {
+            break;
+        }
+        let event = match next_event(&mut events)? {
+            Event::EndList => break,
+            event => event,
+        };
+        match selection {
+            Selection::Sample(count) if kept.len() >= count => {
+                let slot = (random(&mut state) % (index as u64 + 1)) as usize;
+                match kept.get_mut(slot) {
+                    Some(item) => *item = (index, events.build(event)?),
+                    None => skip(&event, &mut events)?,
+                }
+            }
+            _ => kept.push((index, events.build(event)?)),
+        }
+    }
+    kept.sort_by_key(|(index, _)| *index);
+    Ok(Some(kept.into_iter().map(|(_, value)| value).collect()))
+}
+
+/// Prints the value at the pointer while reading the input, without keeping the document in
+/// memory
+///
+/// Output is written as it's read, so invalid input leaves the part before the error, ended by
+/// a newline so the error message follows on its own line.
+pub fn print<R: BufRead, W: Write>(
+    input: R,
+    out: W,
+    pointer: &str,
+    format: Format,
+    color: bool,
+    name: &str,
+) -> Result<(), Failure> {
+    let not_found = || Failure::Message(format!("no value at {pointer} in {name}"));
+    let target = pointer_tokens(pointer).ok_or_else(not_found)?;
+    let mut writer = EventWriter {
+        out: BufWriter::new(out),
+        format,
+        color,
+        containers: Vec::new(),
+        after_key: false,
+        written: false,
+    };
+    let mut chars = CharReader { input, error: None };
+    let result = copy(&mut chars, &mut writer, &target);
+
+    // End the output, also when only part of it was written before an error
+    let newline: &[u8] = if writer.written { b"\n" } else { b"" };
+    let written = writer
+        .out
+        .write_all(newline)
+        .and_then(|()| writer.out.flush())
+        .map_err(|error| Failure::Message(format!("can't write output: {error}")));
+    match report(result, chars.error, pointer, name)? {
+        true => written,
+        false => Err(not_found()),
+    }
+}
+
+/// Reads the items of the list at the pointer selected from the input, without keeping the rest
+/// of the document in memory
+pub fn read_selection<R: BufRead>(
+    input: R,
+    pointer: &str,
+    selection: Selection,
+    name: &str,
+) -> Result<Json, Failure> {
+    let not_found = || Failure::Message(format!("no value at {pointer} in {name}"));
+    let target = pointer_tokens(pointer).ok_or_else(not_found)?;
+    let mut chars = CharReader { input, error: None };
+    let result = select(&mut chars, &target, selection);
+    report(result, chars.error, pointer, name)?
+        .map(Json::List)
+        .ok_or_else(not_found)
+}
+
+/// Turns the result of streaming into a failure message, preferring read errors as they cause
+/// parse errors
+fn report<T>(
+    result: Result<T, Problem>,
+    read_error: Option<io::Error>,
+    pointer: &str,
+    name: &str,
+) -> Result<T, Failure> {
+    if let Some(error) = read_error {
+        return Err(Failure::Message(format!("can't read {name}: {error}")));
+    }
+    result.map_err(|problem| {
+        Failure::Message(match problem {
+            Problem::Parse(error) => format!("can't parse {name}: {error}"),
+            Problem::Write(error) => format!("can't write output: {error}"),
+            Problem::NotList => format!("the value at {pointer:?} in {name} isn't a list"),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use json_parser::Format;
+
+    use super::Selection;
+
+    /// Prints a document through the event stream
+    fn print(text: &str, pointer: &str, format: Format) -> Result<String, String> {
+        let mut out = Vec::new();
+        super::print(text.as_bytes(), &mut out, pointer, format, false, "test").map_err(
+            |failure| match failure {
+                super::Failure::Usage(message) | super::Failure::Message(message) => message,
+            },
+        )?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn matches_the_serializer() {
+        let text = "{\"a\": [1, {}, [], {\"b\": null}], \"é\": \"x\\ny\", \"c\": true}";
+        let value = text.parse::<json_parser::Json>().unwrap();
+        for format in [Format::compact(), Format::pretty()] {
+            let expected = format!("{}\n", value.display(&format));
+            assert_eq!(print(text, "", format).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn pointers() {
+        let text = "{\"a\": [1, {\"b/c\": [true]}], \"d\": 2} trailing";
+        assert_eq!(
+            print(text, "/a/1/b~1c", Format::compact()).unwrap(),
+            "[true]\n"
+        );
+        assert_eq!(print(text, "/d", Format::compact()).unwrap(), "2\n");
+        assert!(print(text, "/a/2", Format::compact()).is_err());
+        assert!(print(text, "", Format::compact()).is_err());
+        assert!(print("[1, \u{0}", "", Format::compact()).is_err());
+    }
+
+    #[test]
+    fn invalid_input() {
+        // The output stops where the input turns out to be invalid, on a line of its own
+        let mut out = Vec::new();
+        let result = super::print(
+            b"[1,2".as_slice(),
+            &mut out,
+            "",
+            Format::compact(),
+            false,
+            "test",
+        );
+        assert!(result.is_err());
+        assert_eq!(out, b"[1,2\n");
+    }
+
+    #[test]
+    fn selections() {
+        let read = |text: &str, selection| {
+            super::read_selection(text.as_bytes(), "/a", selection, "test")
+                .ok()
+                .map(|value| value.to_string())
+        };
+        let text = "{\"a\": [1, [2], {\"b\": 3}, 4, oops";
+        assert_eq!(read(text, Selection::Head(2)).unwrap(), "[1,[2]]");
+        assert_eq!(read(text, Selection::Head(0)).unwrap(), "[]");
+        assert!(read(text, Selection::Sample(2)).is_none());
+        assert!(read("{\"a\": 1}", Selection::Head(1)).is_none());
+
+        // Samples keep the original order of the items
+        let sample = read("{\"a\": [1, 2, 3, 4, 5]}", Selection::Sample(3)).unwrap();
+        let sample = sample.parse::<json_parser::Json>().unwrap();
+        let json_parser::Json::List(values) = sample else {
+            panic!("sample isn't a list");
+        };
+        assert_eq!(values.len(), 3);
+        assert!(
+            values
+                .windows(2)
+                .all(|pair| pair[0].to_string() < pair[1].to_string())
+        );
+    }
+}
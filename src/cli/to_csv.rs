@@ -0,0 +1,59 @@
+//! Exporting a list of objects as a CSV table
+
+use std::{
+    io::{Write, stdout},
+    process::ExitCode,
+};
+
+use json_parser::{CsvOptions, Json};
+
+use super::{Failure, input_name, input_path, read_document};
+
+/// Writes the list of objects at a pointer of the document as CSV to standard output
+pub fn run<I: Iterator<Item = String>>(mut args: I) -> Result<ExitCode, Failure> {
+    // Parse the arguments
+    let mut pointer = String::new();
+    let mut delimiter = ',';
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--path" => pointer = args.next().ok_or("--path expects a JSON pointer")?,
+            "--delimiter" => {
+                let mut chars = args.next().unwrap_or_default().chars().collect::<Vec<_>>();
+                delimiter = match chars.pop() {
+                    Some(ch) if chars.is_empty() => ch,
+                    _ => return Err("--delimiter expects a single character".into()),
+                };
+            }
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {arg}")));
+            }
+            _ if path.is_none() => path = Some(input_path(arg)),
+            _ => return Err(Failure::Usage(format!("unexpected argument {arg}"))),
+        }
+    }
+    let path = path.flatten();
+    let name = input_name(path.as_deref());
+
+    // Find the list and flatten its objects into rows
+    let document = read_document(path.as_deref())?;
+    let value = document
+        .pointer(&pointer)
+        .ok_or_else(|| Failure::Message(format!("no value at {pointer} in {name}")))?;
+    if !matches!(value, Json::List(values) if values.iter().all(|value| matches!(value, Json::Object(_))))
+    {
+        return Err(Failure::Message(format!(
+            "the value at {pointer:?} in {name} isn't a list of objects"
+        )));
+    }
+    let csv = value
+        .to_csv(&CsvOptions {
+            delimiter,
+            flatten: true,
+        })
+        .map_err(|error| Failure::Message(format!("can't convert {name} to CSV: {error}")))?;
+    stdout()
+        .write_all(csv.as_bytes())
+        .map_err(|error| Failure::Message(format!("can't write output: {error}")))?;
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,272 @@
+//! Checking documents without printing them
+
+use std::{io::BufRead, process::ExitCode};
+
+use json_parser::{Error, Json, Warning};
+
+use super::{Failure, input_name, input_path, open_input, parse, parse_options, read_input, watch};
+
+/// How diagnostics are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorFormat {
+    /// As `file:line:column: message` lines on standard error
+    Human,
+
+    /// As one JSON object per line on standard output
+    Json,
+}
+
+/// A problem found in an input
+struct Diagnostic<'a> {
+    /// The name of the input
+    file: &'a str,
+
+    /// The line and column of the problem, if it's known
+    position: Option<(usize, usize)>,
+
+    /// The description of the problem
+    message: String,
+
+    /// Whether the problem is only a warning, which doesn't make the input invalid
+    warning: bool,
+}
+
+impl<'a> Diagnostic<'a> {
+    /// Describes a parse error, moving its position down by a number of lines
+    fn from_error(file: &'a str, error: Error, lines: usize) -> Self {
+        match error {
+            Error::At {
+                line,
+                column,
+                error,
+            } => Self {
+                file,
+                position: Some((line + lines, column)),
+                message: error.to_string(),
+                warning: false,
+            },
+            error => Self {
+                file,
+                position: None,
+                message: error.to_string(),
+                warning: false,
+            },
+        }
+    }
+
+    /// Describes a warning about a valid document, moving its position down by a number of lines
+    fn from_warning(file: &'a str, warning: &Warning, lines: usize) -> Self {
+        let (position, message) = match warning {
+            Warning::DuplicateKey {
+                pointer,
+                first,
+                duplicate,
+            } => (
+                (duplicate.line + lines, duplicate.column),
+                format!(
+                    "duplicate key {pointer}, first defined at line {}, column {}",
+                    first.line + lines,
+                    first.column
+                ),
+            ),
+        };
+        Self {
+            file,
+            position: Some(position),
+            message,
+            warning: true,
+        }
+    }
+
+    /// Prints the diagnostic in the given format
+    fn print(&self, format: ErrorFormat) {
+        match format {
+            ErrorFormat::Human => {
+                let prefix = if self.warning { "warning: " } else { "" };
+                match self.position {
+                    Some((line, column)) => {
+                        eprintln!("{}:{line}:{column}: {prefix}{}", self.file, self.message);
+                    }
+                    None => eprintln!("{}: {prefix}{}", self.file, self.message),
+                }
+            }
+            ErrorFormat::Json => {
+                let mut items = vec![("file".into(), Json::String(self.file.into()))];
+                if let Some((line, column)) = self.position {
+                    items.push(("line".into(), Json::Number(line as f64)));
+                    items.push(("column".into(), Json::Number(column as f64)));
+                }
+                items.push(("message".into(), Json::String(self.message.clone())));
+                if self.warning {
+                    items.push(("warning".into(), Json::Bool(true)));
+                }
+                println!("{}", Json::Object(items));
+            }
+        }
+    }
+}
+
+/// Checks a document, reporting a parse error or the warnings if they're requested
+fn check<F: FnMut(Diagnostic)>(
+    text: &str,
+    file: &str,
+    lines: usize,
+    warnings: bool,
+    report: &mut F,
+) {
+    match parse(text) {
+        Err(error) => report(Diagnostic::from_error(file, error, lines)),
+        Ok(_) if warnings => {
+            for warning in Json::parse_spanned_with(text, &parse_options())
+                .map(|root| root.warnings())
+                .unwrap_or_default()
+            {
+                report(Diagnostic::from_warning(file, &warning, lines));
+            }
+        }
+        Ok(_) => {}
+    }
+}
+
+/// Validates every line of an input as a separate document, in constant memory
+fn validate_lines<F: FnMut(Diagnostic)>(
+    path: Option<&str>,
+    file: &str,
+    warnings: bool,
+    report: &mut F,
+) {
+    let input = match open_input(path) {
+        Ok(input) => input,
+        Err(message) => {
+            return report(Diagnostic {
+                file,
+                position: None,
+                message,
+                warning: false,
+            });
+        }
+    };
+    for (index, line) in input.lines().enumerate() {
+        match line {
+            Err(error) => {
+                return report(Diagnostic {
+                    file,
+                    position: None,
+                    message: format!("can't read {file}: {error}"),
+                    warning: false,
+                });
+            }
+            Ok(line) if line.trim().is_empty() => {}
+            Ok(line) => check(&line, file, index, warnings, report),
+        }
+    }
+}
+
+/// Validates the inputs, reporting every problem, and returns whether all of them are valid
+///
+/// With `warnings`, suspicious but valid documents are reported too, without making them invalid.
+fn validate(paths: &[Option<String>], format: ErrorFormat, ndjson: bool, warnings: bool) -> bool {
+    let mut valid = true;
+    for path in paths {
+        let file = input_name(path.as_deref());
+        let mut report = |diagnostic: Diagnostic| {
+            diagnostic.print(format);
+            valid &= diagnostic.warning;
+        };
+        if ndjson {
+            validate_lines(path.as_deref(), file, warnings, &mut report);
+        } else {
+            match read_input(path.as_deref()) {
+                Err(message) => report(Diagnostic {
+                    file,
+                    position: None,
+                    message,
+                    warning: false,
+                }),
+                Ok(text) => check(&text, file, 0, warnings, &mut report),
+            }
+        }
+    }
+    valid
+}
+
+/// Validates the inputs, returning failure if any of them is invalid
+pub fn run<I: Iterator<Item = String>>(mut args: I) -> Result<ExitCode, Failure> {
+    // Parse the arguments
+    let mut format = ErrorFormat::Human;
+    let mut ndjson = false;
+    let mut watching = false;
+    let mut warnings = false;
+    let mut paths = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--error-format" => {
+                format = match args.next().as_deref() {
+                    Some("human") => ErrorFormat::Human,
+                    Some("json") => ErrorFormat::Json,
+                    _ => return Err("--error-format expects human or json".into()),
+                }
+            }
+            "--ndjson" => ndjson = true,
+            "--watch" => watching = true,
+            "--warnings" => warnings = true,
+            _ if arg.starts_with("--") => {
+                return Err(Failure::Usage(format!("unknown option {arg}")));
+            }
+            _ => paths.push(input_path(arg)),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(None);
+    }
+
+    // Validate again whenever a file changes, confirming when everything is fixed
+    if watching {
+        let files = paths
+            .iter()
+            .map(|path| path.as_deref().ok_or("--watch can't watch standard input"))
+            .collect::<Result<Vec<_>, _>>()?;
+        watch(&files, || {
+            if validate(&paths, format, ndjson, warnings) && format == ErrorFormat::Human {
+                eprintln!("json_parser: all inputs are valid");
+            }
+        });
+    }
+
+    // Validate every input once
+    Ok(if validate(&paths, format, ndjson, warnings) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+
+    /// Returns the error messages for a document
+    fn errors(text: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        check(text, "test.json", 0, false, &mut |diagnostic| {
+            errors.push(diagnostic.message);
+        });
+        errors
+    }
+
+    #[test]
+    fn strictness() {
+        for invalid in ["[1,]", "{\"a\":1,}", "+1", "01", ".5", "1.", "\"a\tb\""] {
+            assert_eq!(errors(invalid).len(), 1, "{invalid}");
+        }
+        assert!(errors("{\"a\": [1, -0.5e3, \"\\t\"]}").is_empty());
+    }
+
+    #[test]
+    fn deep_documents() {
+        let deep = "[".repeat(500) + &"]".repeat(500);
+        assert!(errors(&deep).is_empty());
+        let deeper = "[".repeat(600) + &"]".repeat(600);
+        assert_eq!(errors(&deeper), ["values are nested too deeply"]);
+    }
+}
@@ -0,0 +1,220 @@
+//! Editing a document while keeping its comments, whitespace and key order
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Display, Formatter},
+    ops::Range,
+    str::FromStr,
+};
+
+use crate::{
+    Error, Json, MAX_DEPTH, PatchError,
+    pointer::split_last,
+    spanned::{self, Spanned, SpannedJson},
+};
+
+/// A document that keeps its exact source text, so edits leave everything else byte-identical
+///
+/// Unlike [`Json`], this accepts `//` and `/* */` comments, as configuration files often have them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cst {
+    /// The source text with all edits applied
+    text: String,
+
+    /// The parsed text, with the span of every value and key
+    root: Spanned<SpannedJson>,
+}
+
+impl Cst {
+    /// Returns the source text with all edits applied
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the source text with all edits applied
+    pub fn into_string(self) -> String {
+        self.text
+    }
+
+    /// Returns the document without comments and formatting
+    pub fn to_json(&self) -> Json {
+        self.root.to_json()
+    }
+
+    /// Returns the value referenced by a JSON pointer
+    pub fn get(&self, pointer: &str) -> Option<Json> {
+        self.root.pointer(pointer).map(Spanned::to_json)
+    }
+
+    /// Sets the value at a JSON pointer, adding it to its object or appending it to its list
+    ///
+    /// Like with [`PatchOperation::Add`](crate::PatchOperation::Add), `-` and the length of the
+    /// list append to it.
+    ///
+    /// A new property or item is written after the last one with the same indentation.
+    pub fn set(&mut self, pointer: &str, value: &Json) -> Result<(), PatchError> {
+        let not_found = || PatchError::NotFound(pointer.to_owned());
+
+        // Replace the text of an existing value
+        if let Some(node) = self.root.pointer(pointer) {
+            let range = node.span.start..node.span.end;
+            self.splice(range, &value.to_string());
+            return Ok(());
+        }
+
+        // Otherwise add it to its parent
+        let (parent, token) = split_last(pointer).ok_or_else(not_found)?;
+        let parent = self.root.pointer(parent).ok_or_else(not_found)?;
+        let (last, item) = match &parent.value {
+            SpannedJson::Object(items) => {
+                // Separate the key from the value the same way as the last property does
+                let colon = items.last().map_or(": ", |(key, value)| {
+                    &self.text[key.span.end..value.span.start]
+                });
+                let item = alloc::format!("{}{colon}{value}", Json::String(token.into_owned()));
+                let last = items
+                    .last()
+                    .map(|(key, value)| key.span.start..value.span.end);
+                (last, item)
+            }
+            SpannedJson::List(values)
+                if token == "-" || crate::pointer::index(&token) == Some(values.len()) =>
+            {
+                (
+                    values.last().map(|value| value.span.start..value.span.end),
+                    value.to_string(),
+                )
+            }
+            _ => return Err(not_found()),
+        };
+
+        // Write the new item after the last one, or right after the opening bracket
+        match last {
+            Some(last) => {
+                let indent = self.text[..last.start].len()
+                    - self.text[..last.start]
+                        .trim_end_matches(char::is_whitespace)
+                        .len();
+                let indent = &self.text[last.start - indent..last.start];
+                let item = alloc::format!(",{indent}{item}");
+                self.splice(last.end..last.end, &item);
+            }
+            None => self.splice(parent.span.start + 1..parent.span.start + 1, &item),
+        }
+        Ok(())
+    }
+
+    /// Removes the value at a JSON pointer together with its key and separator, returning it
+    pub fn remove(&mut self, pointer: &str) -> Result<Json, PatchError> {
+        let not_found = || PatchError::NotFound(pointer.to_owned());
+
+        // Find the spans of the items of the parent and the item to remove
+        let (parent, token) = split_last(pointer).ok_or_else(not_found)?;
+        let parent = self.root.pointer(parent).ok_or_else(not_found)?;
+        let (spans, index): (Vec<Range<usize>>, _) = match &parent.value {
+            SpannedJson::Object(items) => (
+                items
+                    .iter()
+                    .map(|(key, value)| key.span.start..value.span.end)
+                    .collect(),
+                items.iter().rposition(|(key, _)| key.value == token),
+            ),
+            SpannedJson::List(values) => (
+                values
+                    .iter()
+                    .map(|value| value.span.start..value.span.end)
+                    .collect(),
+                crate::pointer::index(&token).filter(|&index| index < values.len()),
+            ),
+            _ => return Err(not_found()),
+        };
+        let index = index.ok_or_else(not_found)?;
+        let removed = self.root.pointer(pointer).ok_or_else(not_found)?.to_json();
+
+        // Remove the separator after the item, or before it if it's the last one
+        let range = if index + 1 < spans.len() {
+            spans[index].start..spans[index + 1].start
+        } else if index > 0 {
+            spans[index - 1].end..spans[index].end
+        } else {
+            spans[index].start..parent.span.end - 1
+        };
+        self.splice(range, "");
+        Ok(removed)
+    }
+
+    /// Replaces a range of the text and parses it again
+    ///
+    /// The nesting isn't limited, since edits only add values that are already in memory.
+    fn splice(&mut self, range: Range<usize>, replacement: &str) {
+        self.text.replace_range(range, replacement);
+        self.root = spanned::parse(&self.text, true, None).expect("edits keep the document valid");
+    }
+}
+
+impl FromStr for Cst {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            root: spanned::parse(s, true, Some(MAX_DEPTH))?,
+            text: s.to_owned(),
+        })
+    }
+}
+
+impl Display for Cst {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cst;
+    use crate::{Error, Json};
+
+    #[test]
+    fn edits() {
+        let mut cst: Cst = "{\n  // The port\n  \"port\": 80, /* old */\n  \"hosts\": [\"a\"]\n}\n"
+            .parse()
+            .unwrap();
+        cst.set("/port", &Json::Number(8080.0)).unwrap();
+        cst.set("/hosts/-", &Json::String("b".into())).unwrap();
+        cst.set("/debug", &Json::Bool(true)).unwrap();
+        assert_eq!(
+            cst.as_str(),
+            "{\n  // The port\n  \"port\": 8080, /* old */\n  \"hosts\": [\"a\",\"b\"],\n  \"debug\": true\n}\n"
+        );
+        assert_eq!(cst.get("/hosts/1"), Some(Json::String("b".into())));
+
+        // Removing takes the separator with it
+        assert_eq!(cst.remove("/hosts/0"), Ok(Json::String("a".into())));
+        assert_eq!(cst.remove("/debug"), Ok(Json::Bool(true)));
+        assert_eq!(
+            cst.as_str(),
+            "{\n  // The port\n  \"port\": 8080, /* old */\n  \"hosts\": [\"b\"]\n}\n"
+        );
+        assert!(cst.remove("/missing").is_err());
+
+        // Nesting is limited like when parsing
+        assert!(matches!(
+            "[".repeat(1_000_000).parse::<Cst>(),
+            Err(Error::At { error, .. }) if matches!(*error, Error::NestingTooDeep)
+        ));
+        assert!(cst.set("/a/b", &Json::Null).is_err());
+
+        // Empty containers get their first item right after the bracket
+        let mut cst: Cst = "[ ]".parse().unwrap();
+        cst.set("/-", &Json::Null).unwrap();
+        cst.set("/1", &Json::Null).unwrap();
+        assert_eq!(cst.as_str(), "[null,null ]");
+        cst.remove("/1").unwrap();
+        cst.remove("/0").unwrap();
+        assert_eq!(cst.as_str(), "[]");
+    }
+}
@@ -0,0 +1,280 @@
+//! Conversion between CSV tables and lists of objects
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{Error, Json};
+
+/// Options for reading and writing CSV
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    /// The character separating the fields of a record
+    pub delimiter: char,
+
+    /// Whether nested objects and lists are flattened into columns like `a.b` and `a.0` when
+    /// writing, instead of being rejected
+    pub flatten: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            flatten: false,
+        }
+    }
+}
+
+/// Reads the records of a CSV table
+fn read_records(csv: &str, delimiter: char) -> Result<Vec<Vec<String>>, Error> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut chars = csv.chars().peekable();
+    let mut quoted = false;
+    while let Some(ch) = chars.next() {
+        match ch {
+            // Handle escaped quotes and the end of quoted fields
+            '"' if quoted => {
+                if chars.next_if_eq(&'"').is_some() {
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+
+            // Keep everything else in quoted fields
+            _ if quoted => field.push(ch),
+
+            // Start a quoted field
+            '"' if field.is_empty() => quoted = true,
+
+            // End the field at a delimiter
+            _ if ch == delimiter => record.push(core::mem::take(&mut field)),
+
+            // End the record at a line break
+            '\r' | '\n' => {
+                if ch == '\r' {
+                    chars.next_if_eq(&'\n');
+                }
+                record.push(core::mem::take(&mut field));
+                records.push(core::mem::take(&mut record));
+            }
+
+            // Add other characters to the field
+            _ => field.push(ch),
+        }
+    }
+
+    // Make sure the last quoted field was closed
+    if quoted {
+        return Err(Error::UnclosedString);
+    }
+
+    // Add the last record if the table doesn't end with a line break
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Adds the value to the row, flattening it into multiple columns if needed
+fn flatten_into(
+    row: &mut Vec<(String, String)>,
+    key: String,
+    value: &Json,
+    flatten: bool,
+) -> Result<(), Error> {
+    match value {
+        // Flatten objects and lists, using the property names and indices as suffixes
+        Json::Object(items) if flatten => {
+            for (name, value) in items {
+                flatten_into(row, format!("{key}.{name}"), value, flatten)?;
+            }
+        }
+        Json::List(values) if flatten => {
+            for (index, value) in values.iter().enumerate() {
+                flatten_into(row, format!("{key}.{index}"), value, flatten)?;
+            }
+        }
+
+        // Nested values are rejected when not flattening
+        Json::Object(_) | Json::List(_) => return Err(Error::InvalidValue),
+
+        // Convert scalars to their text, leaving null empty
+        Json::String(string) => row.push((key, string.clone())),
+        Json::Number(number) => row.push((key, number.to_string())),
+        Json::NumberRaw(text) => row.push((key, text.clone())),
+        Json::Bool(value) => row.push((key, value.to_string())),
+        Json::Null => row.push((key, String::new())),
+    }
+    Ok(())
+}
+
+/// Writes a field, quoting it if needed
+fn write_field(out: &mut String, field: &str, delimiter: char) {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        out.push('"');
+        out.push_str(&field.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(field);
+    }
+}
+
+impl Json {
+    /// Reads a CSV table as a list of objects, using the first record as the property names
+    ///
+    /// All fields are read as strings.
+    pub fn from_csv(csv: &str, options: &CsvOptions) -> Result<Self, Error> {
+        let mut records = read_records(csv, options.delimiter)?.into_iter();
+
+        // Read the header, an empty table has no rows
+        let Some(header) = records.next() else {
+            return Ok(Json::List(Vec::new()));
+        };
+
+        // Convert the rows to objects, making sure they are as long as the header
+        records
+            .map(|record| {
+                if record.len() != header.len() {
+                    return Err(Error::MissingSeparator);
+                }
+                Ok(Json::Object(
+                    header
+                        .iter()
+                        .cloned()
+                        .zip(record.into_iter().map(Json::String))
+                        .collect(),
+                ))
+            })
+            .collect::<Result<_, _>>()
+            .map(Json::List)
+    }
+
+    /// Writes a list of objects as a CSV table
+    ///
+    /// The header contains every property name in the order they first appear, properties
+    /// missing from an object are left empty.
+    pub fn to_csv(&self, options: &CsvOptions) -> Result<String, Error> {
+        let Json::List(values) = self else {
+            return Err(Error::InvalidValue);
+        };
+
+        // Convert the objects to rows of fields
+        let rows = values
+            .iter()
+            .map(|value| {
+                let Json::Object(items) = value else {
+                    return Err(Error::InvalidValue);
+                };
+                let mut row = Vec::new();
+                for (key, value) in items {
+                    flatten_into(&mut row, key.clone(), value, options.flatten)?;
+                }
+                Ok(row)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Merge the columns of all rows into the header
+        let mut header = Vec::<&str>::new();
+        for (key, _) in rows.iter().flatten() {
+            if !header.contains(&key.as_str()) {
+                header.push(key);
+            }
+        }
+
+        // Write the header and the rows
+        let mut out = String::new();
+        for (index, key) in header.iter().enumerate() {
+            if index > 0 {
+                out.push(options.delimiter);
+            }
+            write_field(&mut out, key, options.delimiter);
+        }
+        out.push('\n');
+        for row in &rows {
+            for (index, key) in header.iter().enumerate() {
+                if index > 0 {
+                    out.push(options.delimiter);
+                }
+                if let Some((_, field)) = row.iter().find(|(name, _)| name == key) {
+                    write_field(&mut out, field, options.delimiter);
+                }
+            }
+            out.push('\n');
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, vec::Vec};
+
+    use crate::{CsvOptions, Json};
+
+    #[test]
+    fn csv_reading() {
+        assert_eq!(
+            Json::from_csv(
+                "name,note\r\nalice,\"says \"\"hi\"\", twice\"\nbob,\"multi\nline\"",
+                &CsvOptions::default()
+            )
+            .unwrap(),
+            Json::List(Vec::from([
+                Json::Object(Vec::from([
+                    ("name".to_owned(), Json::String("alice".to_owned())),
+                    (
+                        "note".to_owned(),
+                        Json::String("says \"hi\", twice".to_owned())
+                    )
+                ])),
+                Json::Object(Vec::from([
+                    ("name".to_owned(), Json::String("bob".to_owned())),
+                    ("note".to_owned(), Json::String("multi\nline".to_owned()))
+                ]))
+            ]))
+        );
+        assert_eq!(
+            Json::from_csv("", &CsvOptions::default()).unwrap(),
+            Json::List(Vec::new())
+        );
+        assert!(Json::from_csv("a,b\n1\n", &CsvOptions::default()).is_err());
+        assert!(Json::from_csv("a\n\"1\n", &CsvOptions::default()).is_err());
+    }
+
+    #[test]
+    fn csv_writing() {
+        let value = "[{\"a\":1,\"b\":\"x,y\"},{\"c\":null,\"a\":true}]"
+            .parse::<Json>()
+            .unwrap();
+        assert_eq!(
+            value.to_csv(&CsvOptions::default()).unwrap(),
+            "a,b,c\n1,\"x,y\",\ntrue,,\n"
+        );
+        assert!(Json::Null.to_csv(&CsvOptions::default()).is_err());
+    }
+
+    #[test]
+    fn csv_flattening() {
+        let value = Json::List(Vec::from([Json::Object(Vec::from([(
+            "a".to_owned(),
+            "{\"b\":1,\"c\":[2,3]}".parse::<Json>().unwrap(),
+        )]))]));
+        assert!(value.to_csv(&CsvOptions::default()).is_err());
+        assert_eq!(
+            value
+                .to_csv(&CsvOptions {
+                    delimiter: ';',
+                    flatten: true
+                })
+                .unwrap(),
+            "a.b;a.c.0;a.c.1\n1;2;3\n"
+        );
+    }
+}
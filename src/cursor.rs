@@ -0,0 +1,199 @@
+//! Navigating and editing a document from a moving focus
+
+use alloc::string::ToString;
+use core::mem;
+
+use crate::{Json, Path, Segment};
+
+/// A position in a document that can move to children, parents and siblings, with in-place
+/// edits of the value it's on, see [`Json::cursor`]
+#[derive(Debug)]
+pub struct Cursor<'a> {
+    /// The top-level value
+    root: &'a mut Json,
+
+    /// The path to the value in focus, which always exists
+    path: Path,
+}
+
+impl<'a> Cursor<'a> {
+    /// Starts at the top-level value
+    pub fn new(root: &'a mut Json) -> Self {
+        Self {
+            root,
+            path: Path::new(),
+        }
+    }
+
+    /// Returns the path to the value in focus
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the value in focus
+    pub fn focus(&self) -> &Json {
+        self.root
+            .get_path(&self.path)
+            .expect("the focus always exists")
+    }
+
+    /// Returns the value in focus for editing
+    pub fn focus_mut(&mut self) -> &mut Json {
+        self.root
+            .get_path_mut(&self.path)
+            .expect("the focus always exists")
+    }
+
+    /// Replaces the value in focus, returning the old one
+    pub fn replace(&mut self, value: Json) -> Json {
+        mem::replace(self.focus_mut(), value)
+    }
+
+    /// Moves to the property with a key or the element at an index of the value in focus,
+    /// returning whether it exists
+    pub fn down<S: Into<Segment>>(&mut self, segment: S) -> bool {
+        // Indices into objects refer to keys, like they do in JSON pointers
+        let segment = match (self.focus(), segment.into()) {
+            (Json::Object(_), Segment::Index(index)) => Segment::Key(index.to_string()),
+            (_, segment) => segment,
+        };
+        self.path.push(segment);
+        if self.root.get_path(&self.path).is_none() {
+            self.path.pop();
+            return false;
+        }
+        true
+    }
+
+    /// Moves to the first property or element of the value in focus, returning whether it has
+    /// one
+    pub fn first_child(&mut self) -> bool {
+        let segment = match self.focus() {
+            Json::List(values) if !values.is_empty() => Segment::Index(0),
+            Json::Object(items) => match items.first() {
+                Some((key, _)) => Segment::Key(key.clone()),
+                None => return false,
+            },
+            _ => return false,
+        };
+        self.path.push(segment);
+        true
+    }
+
+    /// Moves to the parent of the value in focus, returning whether it has one
+    pub fn up(&mut self) -> bool {
+        self.path.pop().is_some()
+    }
+
+    /// Moves to the next property or element in the parent, returning whether there is one
+    pub fn next_sibling(&mut self) -> bool {
+        self.sibling(1)
+    }
+
+    /// Moves to the previous property or element in the parent, returning whether there is
+    /// one
+    pub fn previous_sibling(&mut self) -> bool {
+        self.sibling(-1)
+    }
+
+    /// Moves to the sibling at an offset from the value in focus, returning whether it exists
+    fn sibling(&mut self, offset: isize) -> bool {
+        let Some(segment) = self.path.pop() else {
+            return false;
+        };
+        let parent = self.root.get_path(&self.path);
+        let sibling = match (parent, &segment) {
+            (Some(Json::List(values)), Segment::Index(index)) => index
+                .checked_add_signed(offset)
+                .filter(|&index| index < values.len())
+                .map(Segment::Index),
+            (Some(Json::Object(items)), Segment::Key(key)) => items
+                .iter()
+                .rposition(|(name, _)| name == key)
+                .and_then(|position| position.checked_add_signed(offset))
+                .and_then(|position| items.get(position))
+                .map(|(key, _)| Segment::Key(key.clone())),
+            _ => None,
+        };
+        match sibling {
+            Some(sibling) => {
+                self.path.push(sibling);
+                true
+            }
+            None => {
+                self.path.push(segment);
+                false
+            }
+        }
+    }
+
+    /// Removes the value in focus from its parent and moves to the parent, returning the value,
+    /// or `None` at the top-level value
+    pub fn remove(&mut self) -> Option<Json> {
+        let segment = self.path.pop()?;
+        match (self.focus_mut(), segment) {
+            (Json::List(values), Segment::Index(index)) => Some(values.remove(index)),
+            (Json::Object(items), Segment::Key(key)) => {
+                let position = items.iter().rposition(|(name, _)| *name == key)?;
+                Some(items.remove(position).1)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Json {
+    /// Returns a cursor starting at this value, for moving through and editing the document
+    pub fn cursor(&mut self) -> Cursor<'_> {
+        Cursor::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec::Vec};
+
+    use crate::Json;
+
+    #[test]
+    fn navigation() {
+        let mut document = "{\"a\":[1,2,3],\"b\":{\"c\":true}}"
+            .parse::<Json>()
+            .unwrap();
+        let mut cursor = document.cursor();
+        assert!(cursor.down("a") && cursor.down(1));
+        assert_eq!(cursor.focus(), &Json::Number(2.0));
+        assert!(cursor.next_sibling() && !cursor.next_sibling());
+        assert_eq!(cursor.path().to_string(), "/a/2");
+        assert!(cursor.previous_sibling());
+        assert!(!cursor.down(0));
+
+        // Move across the properties of the top-level object and into the next one
+        assert!(cursor.up() && cursor.next_sibling() && cursor.first_child());
+        assert_eq!(cursor.path().to_string(), "/b/c");
+        assert!(!cursor.previous_sibling());
+        assert!(cursor.up() && cursor.up() && !cursor.up());
+    }
+
+    #[test]
+    fn editing() {
+        let mut document = "{\"a\":[1,2,3],\"b\":{\"c\":true}}"
+            .parse::<Json>()
+            .unwrap();
+        let mut cursor = document.cursor();
+        cursor.down("b");
+        cursor.down("c");
+        assert_eq!(cursor.replace(Json::Null), Json::Bool(true));
+        cursor.up();
+        cursor.up();
+        cursor.down("a");
+        cursor.down(0);
+        assert_eq!(cursor.remove(), Some(Json::Number(1.0)));
+        assert_eq!(cursor.path().to_string(), "/a");
+        *cursor.focus_mut() = Json::List(Vec::new());
+        assert_eq!(
+            document,
+            "{\"a\":[],\"b\":{\"c\":null}}".parse::<Json>().unwrap()
+        );
+    }
+}
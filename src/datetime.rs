@@ -0,0 +1,87 @@
+//! Reading timestamps with `chrono`, from RFC 3339 strings or seconds since the Unix epoch
+
+use chrono::{DateTime, FixedOffset, Utc};
+
+use crate::Json;
+
+impl Json {
+    /// Returns an RFC 3339 string like `"2024-05-01T12:00:00+02:00"` as a date and time, keeping
+    /// its offset
+    pub fn as_datetime(&self) -> Option<DateTime<FixedOffset>> {
+        match self {
+            Json::String(text) => DateTime::parse_from_rfc3339(text).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns a timestamp in UTC, from an RFC 3339 string or a number of seconds since the
+    /// Unix epoch, which may have a fraction
+    pub fn as_timestamp(&self) -> Option<DateTime<Utc>> {
+        // Convert strings with an offset to UTC
+        if let Some(datetime) = self.as_datetime() {
+            return Some(datetime.to_utc());
+        }
+
+        // Split numbers into whole seconds and nanoseconds, rounding to the closest nanosecond
+        let Json::Number(seconds) = *self.plain() else {
+            return None;
+        };
+        if !seconds.is_finite() || seconds.abs() >= i64::MAX as f64 {
+            return None;
+        }
+        let whole = seconds as i64;
+        let fraction = (seconds - whole as f64) * 1e9;
+        let nanoseconds = i128::from(whole) * 1_000_000_000
+            + (fraction + if fraction < 0.0 { -0.5 } else { 0.5 }) as i128;
+        DateTime::from_timestamp(
+            nanoseconds.div_euclid(1_000_000_000) as i64,
+            nanoseconds.rem_euclid(1_000_000_000) as u32,
+        )
+    }
+}
+
+impl<Tz: chrono::TimeZone> From<DateTime<Tz>> for Json
+where
+    Tz::Offset: core::fmt::Display,
+{
+    /// Writes the date and time as an RFC 3339 string
+    fn from(datetime: DateTime<Tz>) -> Self {
+        Json::String(datetime.to_rfc3339())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::ToOwned;
+
+    use chrono::{DateTime, Timelike};
+
+    use crate::Json;
+
+    #[test]
+    fn timestamps() {
+        let text = Json::String("2024-05-01T12:00:00.500+02:00".to_owned());
+        let datetime = text.as_datetime().unwrap();
+        assert_eq!(datetime.offset().local_minus_utc(), 7200);
+        assert_eq!(Json::from(datetime), text);
+        assert_eq!(
+            text.as_timestamp().unwrap().timestamp_millis(),
+            1_714_557_600_500
+        );
+
+        // Epoch seconds may have a fraction and be negative
+        assert_eq!(
+            Json::Number(1_714_557_600.25).as_timestamp(),
+            DateTime::from_timestamp(1_714_557_600, 250_000_000)
+        );
+        assert_eq!(
+            Json::Number(-1.5).as_timestamp().unwrap().nanosecond(),
+            500_000_000
+        );
+
+        // Other values aren't dates
+        assert_eq!(Json::String("yesterday".to_owned()).as_datetime(), None);
+        assert_eq!(Json::Number(f64::INFINITY).as_timestamp(), None);
+        assert_eq!(Json::Bool(true).as_timestamp(), None);
+    }
+}
@@ -0,0 +1,76 @@
+//! Exact decimal numbers with `bigdecimal`, for values like amounts of money that can't be rounded
+//!
+//! Numbers only stay exact when they're parsed with
+//! [`ParseOptions::raw_numbers`](crate::ParseOptions::raw_numbers), which keeps their text.
+//! Decimals converted back into values are stored the same way, so the results of arithmetic
+//! are written with every digit.
+
+use alloc::string::ToString;
+
+use bigdecimal::BigDecimal;
+
+use crate::Json;
+
+impl Json {
+    /// Returns a number as a decimal, or `None` for other values and numbers that aren't finite
+    ///
+    /// Raw numbers are converted exactly, and other numbers from their shortest digits, so `0.1`
+    /// becomes exactly 0.1 rather than the binary fraction closest to it.
+    pub fn as_decimal(&self) -> Option<BigDecimal> {
+        match self {
+            Json::Number(number) if number.is_finite() => number.to_string().parse().ok(),
+            Json::NumberRaw(text) => text.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl From<&BigDecimal> for Json {
+    fn from(decimal: &BigDecimal) -> Self {
+        Json::NumberRaw(decimal.to_string())
+    }
+}
+
+impl From<BigDecimal> for Json {
+    fn from(decimal: BigDecimal) -> Self {
+        Json::from(&decimal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use bigdecimal::BigDecimal;
+
+    use crate::{Json, ParseOptions};
+
+    #[test]
+    fn exact_arithmetic() {
+        let options = ParseOptions {
+            raw_numbers: true,
+            ..ParseOptions::default()
+        };
+        let prices = Json::from_chars_with(
+            "[0.100000000000000000000000000001, 12345678901234567890.25]".chars(),
+            &options,
+        )
+        .unwrap();
+        let Json::List(prices) = prices else {
+            panic!("not a list");
+        };
+        let total = prices
+            .iter()
+            .map(|price| price.as_decimal().unwrap())
+            .sum::<BigDecimal>();
+        assert_eq!(
+            Json::from(total).to_string(),
+            "12345678901234567890.350000000000000000000000000001"
+        );
+
+        // Plain numbers convert from their shortest digits
+        assert_eq!(Json::Number(0.1).as_decimal(), Some("0.1".parse().unwrap()));
+        assert_eq!(Json::Number(f64::NAN).as_decimal(), None);
+        assert_eq!(Json::Null.as_decimal(), None);
+    }
+}
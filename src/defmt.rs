@@ -0,0 +1,52 @@
+//! `defmt` formatting for logging on embedded devices
+
+use defmt::{Format, Formatter, write};
+
+use crate::Error;
+#[cfg(feature = "alloc")]
+use crate::Json;
+
+impl Format for Error {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            Error::InvalidValue => write!(f, "InvalidValue"),
+            Error::UnclosedString => write!(f, "UnclosedString"),
+            Error::UnclosedList => write!(f, "UnclosedList"),
+            Error::MissingSeparator => write!(f, "MissingSeparator"),
+            Error::UnexpectedEndOfFile => write!(f, "UnexpectedEndOfFile"),
+            Error::EmptyInput => write!(f, "EmptyInput"),
+            Error::UnclosedObject => write!(f, "UnclosedObject"),
+            Error::TrailingCharacters => write!(f, "TrailingCharacters"),
+            Error::NestingTooDeep => write!(f, "NestingTooDeep"),
+            Error::IntegerOverflow => write!(f, "IntegerOverflow"),
+            Error::NumberOutOfRange => write!(f, "NumberOutOfRange"),
+            Error::TooLarge => write!(f, "TooLarge"),
+            Error::MisspelledLiteral(literal) => write!(f, "MisspelledLiteral({=str})", literal),
+            Error::Aborted(reason) => write!(f, "Aborted({=str})", reason),
+            #[cfg(feature = "alloc")]
+            Error::At {
+                line,
+                column,
+                error,
+            } => write!(f, "{}:{}: {}", line, column, **error),
+            #[cfg(feature = "alloc")]
+            Error::Document { index, error } => write!(f, "#{}: {}", index, **error),
+        }
+    }
+}
+
+/// Formats scalars in full, but only the length of lists and objects
+#[cfg(feature = "alloc")]
+impl Format for Json {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            Json::List(values) => write!(f, "[..{=usize}]", values.len()),
+            Json::Object(items) => write!(f, "{{..{=usize}}}", items.len()),
+            Json::String(string) => write!(f, "{=str:?}", string.as_str()),
+            Json::Number(number) => write!(f, "{=f64}", number),
+            Json::NumberRaw(text) => write!(f, "{=str}", text.as_str()),
+            Json::Bool(value) => write!(f, "{=bool}", value),
+            Json::Null => write!(f, "null"),
+        }
+    }
+}
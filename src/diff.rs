@@ -0,0 +1,184 @@
+//! Structural comparison of JSON values
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{Json, PatchOperation, pointer::push};
+
+/// Adds the operations turning `source` into `target` at the given location, preceding removals
+/// and replacements by a `test` of the previous value if `previous` is set
+fn diff_into(
+    operations: &mut Vec<PatchOperation>,
+    path: &mut String,
+    source: &Json,
+    target: &Json,
+    previous: bool,
+) {
+    // Records the previous value at the path
+    let test = |operations: &mut Vec<PatchOperation>, path: &String, value: &Json| {
+        if previous {
+            operations.push(PatchOperation::Test {
+                path: path.clone(),
+                value: value.clone(),
+            });
+        }
+    };
+
+    match (source, target) {
+        // Compare objects property by property
+        (Json::Object(source), Json::Object(target)) => {
+            let length = path.len();
+            for (key, value) in source {
+                push(path, key);
+                match target.iter().find(|(name, _)| name == key) {
+                    Some((_, target)) => diff_into(operations, path, value, target, previous),
+                    None => {
+                        test(operations, path, value);
+                        operations.push(PatchOperation::Remove { path: path.clone() });
+                    }
+                }
+                path.truncate(length);
+            }
+            for (key, value) in target {
+                if !source.iter().any(|(name, _)| name == key) {
+                    push(path, key);
+                    operations.push(PatchOperation::Add {
+                        path: path.clone(),
+                        value: value.clone(),
+                    });
+                    path.truncate(length);
+                }
+            }
+        }
+
+        // Compare lists index by index, adding or removing values at the end
+        (Json::List(source), Json::List(target)) => {
+            let length = path.len();
+            for (index, (source, target)) in source.iter().zip(target).enumerate() {
+                push(path, itoa(index).as_str());
+                diff_into(operations, path, source, target, previous);
+                path.truncate(length);
+            }
+            for index in (target.len()..source.len()).rev() {
+                push(path, itoa(index).as_str());
+                test(operations, path, &source[index]);
+                operations.push(PatchOperation::Remove { path: path.clone() });
+                path.truncate(length);
+            }
+            for (index, value) in target.iter().enumerate().skip(source.len()) {
+                push(path, itoa(index).as_str());
+                operations.push(PatchOperation::Add {
+                    path: path.clone(),
+                    value: value.clone(),
+                });
+                path.truncate(length);
+            }
+        }
+
+        // Replace other values that differ
+        _ if source != target => {
+            test(operations, path, source);
+            operations.push(PatchOperation::Replace {
+                path: path.clone(),
+                value: target.clone(),
+            });
+        }
+        _ => {}
+    }
+}
+
+/// Converts an index to a reference token
+fn itoa(index: usize) -> String {
+    use core::fmt::Write as _;
+
+    let mut token = String::new();
+    write!(token, "{index}").unwrap();
+    token
+}
+
+impl Json {
+    /// Returns the JSON Patch operations turning this value into the target
+    ///
+    /// Lists are compared index by index, so inserting a value at the start of a list replaces
+    /// every value after it.
+    pub fn diff(&self, target: &Json) -> Vec<PatchOperation> {
+        let mut operations = Vec::new();
+        diff_into(&mut operations, &mut String::new(), self, target, false);
+        operations
+    }
+
+    /// Returns the JSON Patch operations turning this value into the target, with a `test` of
+    /// the previous value before every removal and replacement
+    ///
+    /// The tests make sure the patch only applies to an unchanged value, and let
+    /// [`Json::invert_patch`] turn it into a patch undoing the change.
+    pub fn diff_invertible(&self, target: &Json) -> Vec<PatchOperation> {
+        let mut operations = Vec::new();
+        diff_into(&mut operations, &mut String::new(), self, target, true);
+        operations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, vec::Vec};
+
+    use crate::{Json, PatchOperation};
+
+    #[test]
+    fn diffing() {
+        let source = "{\"a\":1,\"b\":[1,2,3],\"c/d\":true,\"e\":{}}"
+            .parse::<Json>()
+            .unwrap();
+        let target = "{\"a\":2,\"b\":[1],\"e\":{\"f\":null},\"g\":[]}"
+            .parse::<Json>()
+            .unwrap();
+        assert_eq!(
+            source.diff(&target),
+            [
+                PatchOperation::Replace {
+                    path: "/a".to_owned(),
+                    value: Json::Number(2.0)
+                },
+                PatchOperation::Remove {
+                    path: "/b/2".to_owned()
+                },
+                PatchOperation::Remove {
+                    path: "/b/1".to_owned()
+                },
+                PatchOperation::Remove {
+                    path: "/c~1d".to_owned()
+                },
+                PatchOperation::Add {
+                    path: "/e/f".to_owned(),
+                    value: Json::Null
+                },
+                PatchOperation::Add {
+                    path: "/g".to_owned(),
+                    value: Json::List(Vec::new())
+                },
+            ]
+        );
+        assert!(source.diff(&source).is_empty());
+        assert_eq!(
+            Json::Null.diff(&Json::Bool(true)),
+            [PatchOperation::Replace {
+                path: "".to_owned(),
+                value: Json::Bool(true)
+            }]
+        );
+    }
+
+    #[test]
+    fn patch_document() {
+        let operations = "[1]"
+            .parse::<Json>()
+            .unwrap()
+            .diff(&"[1,2]".parse().unwrap());
+        assert_eq!(
+            Json::from_patch(&operations),
+            "[{\"op\":\"add\",\"path\":\"/1\",\"value\":2}]"
+                .parse::<Json>()
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,278 @@
+//! Parsing from `embedded-io` readers
+
+use alloc::vec::Vec;
+
+use embedded_io::Read;
+
+use crate::{Error, Json, ParseOptions};
+
+/// An error that occured while reading or parsing a JSON value
+#[derive(Debug)]
+pub enum ReadError<E> {
+    /// The reader returned an error
+    Io(E),
+
+    /// The input isn't valid JSON
+    Parse(Error),
+}
+
+/// Iterates over the bytes of a reader, keeping the error that stopped it
+struct Bytes<R: Read> {
+    /// The reader the bytes are read from
+    reader: R,
+
+    /// The bytes read, but not yet returned
+    buffer: [u8; 64],
+
+    /// The index of the next byte to return
+    start: usize,
+
+    /// The number of bytes in the buffer
+    end: usize,
+
+    /// The error returned by the reader, if any
+    error: Option<R::Error>,
+}
+
+impl<R: Read> Iterator for Bytes<R> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Refill the buffer once it's empty, stopping at the end of the input or an error
+        if self.start == self.end {
+            if self.error.is_some() {
+                return None;
+            }
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(count) => (self.start, self.end) = (0, count),
+                Err(error) => {
+                    self.error = Some(error);
+                    return None;
+                }
+            }
+        }
+
+        // Return the next byte from the buffer
+        self.start += 1;
+        Some(self.buffer[self.start - 1])
+    }
+}
+
+impl Json {
+    /// Parses a JSON value from a reader, stopping at the end of the value
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, ReadError<R::Error>> {
+        let mut bytes = Bytes {
+            reader,
+            buffer: [0; 64],
+            start: 0,
+            end: 0,
+            error: None,
+        };
+        let result = Self::from_bytes(&mut bytes);
+
+        // Report reading errors before the parse errors they caused
+        match bytes.error {
+            Some(error) => Err(ReadError::Io(error)),
+            None => result.map_err(ReadError::Parse),
+        }
+    }
+
+    /// Parses a JSON value from an asynchronous reader, stopping at the end of the value
+    ///
+    /// The input is read until the value is complete, so a connection that stays open after it
+    /// is fine. Bytes after the value that arrived in the same read are dropped, and the input
+    /// is limited to the size allowed by the default [`ParseOptions`](crate::ParseOptions).
+    pub async fn from_async_reader<R: embedded_io_async::Read>(
+        mut reader: R,
+    ) -> Result<Self, ReadError<R::Error>> {
+        let max_size = ParseOptions::default().max_size.unwrap_or(usize::MAX);
+        let mut input = Vec::new();
+        let mut end = ValueEnd::default();
+        let mut buffer = [0; 64];
+        'read: loop {
+            let count = reader.read(&mut buffer).await.map_err(ReadError::Io)?;
+            if count == 0 {
+                break;
+            }
+
+            // Keep the bytes up to the end of the value
+            for &byte in &buffer[..count] {
+                if input.len() == max_size {
+                    return Err(ReadError::Parse(Error::TooLarge));
+                }
+                input.push(byte);
+                if end.reached(byte) {
+                    break 'read;
+                }
+            }
+        }
+
+        // Parse the value, which stops before anything ending a number or literal
+        Self::from_bytes(input.into_iter()).map_err(ReadError::Parse)
+    }
+}
+
+/// Finds the end of a value in bytes arriving piece by piece, without checking that it's valid
+#[derive(Default)]
+struct ValueEnd {
+    /// The number of lists and objects that are open
+    depth: usize,
+
+    /// Whether the bytes are in a string
+    string: bool,
+
+    /// Whether the previous byte in a string started an escape
+    escape: bool,
+
+    /// Whether a number or literal outside of containers was started
+    scalar: bool,
+}
+
+impl ValueEnd {
+    /// Reads the next byte, returning whether the value is complete with it
+    fn reached(&mut self, byte: u8) -> bool {
+        // Skip the contents of strings, ending the value with a string on its own
+        if self.string {
+            match byte {
+                _ if self.escape => self.escape = false,
+                b'\\' => self.escape = true,
+                b'"' => {
+                    self.string = false;
+                    return self.depth == 0;
+                }
+                _ => {}
+            }
+            return false;
+        }
+
+        // A number or literal on its own ends at the first byte that can't be part of it
+        if self.scalar {
+            return !matches!(byte, b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z' | b'.' | b'+' | b'-');
+        }
+
+        // Follow the nesting, ending the value when the outermost container closes
+        match byte {
+            b'"' => self.string = true,
+            b'[' | b'{' => self.depth += 1,
+            b']' | b'}' => {
+                self.depth = self.depth.saturating_sub(1);
+                return self.depth == 0;
+            }
+            b' ' | b'\t' | b'\n' | b'\r' | b',' | b':' => {}
+            _ => self.scalar = self.depth == 0,
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::ToOwned;
+    use core::{
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use embedded_io::{ErrorKind, ErrorType};
+
+    use super::ReadError;
+    use crate::Json;
+
+    /// A reader returning its bytes and then failing
+    struct Failing<'a>(&'a [u8]);
+
+    impl ErrorType for Failing<'_> {
+        type Error = ErrorKind;
+    }
+
+    impl embedded_io::Read for Failing<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Self::Error> {
+            embedded_io::Read::read(&mut self.0, buffer)
+                .ok()
+                .filter(|&count| count > 0)
+                .ok_or(ErrorKind::BrokenPipe)
+        }
+    }
+
+    #[test]
+    fn reader_parsing() {
+        let input =
+            "{\"list\": [1, 2, 3], \"text\": \"a long enough string to refill the buffer\"}";
+        assert_eq!(
+            Json::from_reader(input.as_bytes()).unwrap(),
+            input.parse::<Json>().unwrap()
+        );
+        assert!(matches!(
+            Json::from_reader(&b"[1,"[..]),
+            Err(ReadError::Parse(_))
+        ));
+        assert!(matches!(
+            Json::from_reader(Failing(b"[1,")),
+            Err(ReadError::Io(ErrorKind::BrokenPipe))
+        ));
+
+        // Characters of several bytes are decoded, even across refills of the buffer
+        let input = "[".to_owned() + &"\"é☃🦀\",".repeat(20) + "null]";
+        assert_eq!(
+            Json::from_reader(input.as_bytes()).unwrap(),
+            input.parse::<Json>().unwrap()
+        );
+    }
+
+    #[test]
+    fn async_reader_parsing() {
+        assert_eq!(
+            read_async("[true, \"é🦀\"]".as_bytes()).unwrap(),
+            "[true,\"é🦀\"]".parse::<Json>().unwrap()
+        );
+    }
+
+    /// Parses a value from an asynchronous reader that never blocks
+    fn read_async<R: embedded_io_async::Read>(reader: R) -> Result<Json, ReadError<R::Error>> {
+        let future = pin!(Json::from_async_reader(reader));
+        match future.poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(result) => result,
+            Poll::Pending => panic!("the reader never blocks"),
+        }
+    }
+
+    /// A reader like an open connection, blocking once its bytes are read
+    struct Open<'a>(&'a [u8]);
+
+    impl ErrorType for Open<'_> {
+        type Error = ErrorKind;
+    }
+
+    impl embedded_io_async::Read for Open<'_> {
+        async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Self::Error> {
+            if self.0.is_empty() {
+                core::future::pending::<()>().await;
+            }
+            embedded_io::Read::read(&mut self.0, buffer).map_err(|_| ErrorKind::Other)
+        }
+    }
+
+    #[test]
+    fn async_reader_stops_after_the_value() {
+        for (input, expected) in [
+            ("{\"a\": [\"]}\\\"\", {}]}", "{\"a\":[\"]}\\\"\",{}]}"),
+            ("  \"text\"", "\"text\""),
+            ("-1.5e3 ", "-1500"),
+            ("null\n", "null"),
+        ] {
+            assert_eq!(
+                read_async(Open(input.as_bytes())).unwrap(),
+                expected.parse::<Json>().unwrap(),
+                "{input}"
+            );
+        }
+
+        // Values ending with the input, or not at all, are still read
+        assert_eq!(read_async("12".as_bytes()).unwrap(), Json::Number(12.0));
+        assert!(matches!(
+            read_async("[1, 2".as_bytes()),
+            Err(ReadError::Parse(_))
+        ));
+    }
+}
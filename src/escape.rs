@@ -0,0 +1,70 @@
+//! Decoding and encoding the escape sequences of JSON strings on their own
+
+use alloc::{borrow::Cow, string::String};
+use core::fmt::{self, Write};
+
+use crate::{Error, Json, ser::write_escaped};
+
+/// Decodes the escape sequences in the text between the quotes of a JSON string, borrowing the
+/// text if it has none
+///
+/// Surrogate pairs like `\ud83d\ude00` are combined, while unknown escapes and lone surrogates
+/// are rejected.
+pub fn unescape_json_string(text: &str) -> Result<Cow<'_, str>, Error> {
+    if !text.contains('\\') {
+        return Ok(Cow::Borrowed(text));
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut iter = text.chars();
+    while let Some(ch) = iter.next() {
+        match ch {
+            '\\' => result.push(Json::read_escape(&mut iter)?),
+            ch => result.push(ch),
+        }
+    }
+    Ok(Cow::Owned(result))
+}
+
+/// Writes a string as a JSON string with quotes, escaping what JSON requires, and every
+/// non-ASCII character as well if `ascii_only` is set
+pub fn escape_json_string<W: Write>(out: &mut W, text: &str, ascii_only: bool) -> fmt::Result {
+    write_escaped(out, text, false, ascii_only)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::Cow, string::String};
+
+    use super::{escape_json_string, unescape_json_string};
+
+    #[test]
+    fn escaping() {
+        let mut out = String::new();
+        escape_json_string(&mut out, "say \"hé\"\n😀", false).unwrap();
+        assert_eq!(out, "\"say \\\"hé\\\"\\n😀\"");
+
+        // Everything beyond ASCII can be escaped too, as surrogate pairs beyond U+FFFF
+        out.clear();
+        escape_json_string(&mut out, "hé😀", true).unwrap();
+        assert_eq!(out, "\"h\\u00e9\\ud83d\\ude00\"");
+        assert_eq!(
+            unescape_json_string(&out[1..out.len() - 1]).unwrap(),
+            "hé😀"
+        );
+    }
+
+    #[test]
+    fn unescaping() {
+        assert!(matches!(
+            unescape_json_string("plain"),
+            Ok(Cow::Borrowed("plain"))
+        ));
+        assert_eq!(
+            unescape_json_string("a\\\"b\\\\c\\/\\n\\u00e9\\ud83d\\ude00").unwrap(),
+            "a\"b\\c/\né😀"
+        );
+        assert!(unescape_json_string("\\x").is_err());
+        assert!(unescape_json_string("\\ud83d").is_err());
+        assert!(unescape_json_string("trailing\\").is_err());
+    }
+}
@@ -0,0 +1,408 @@
+//! Reading JSON as a stream of events, without building the value in memory
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{self, Display, Formatter},
+    iter::Peekable,
+};
+
+use crate::{Error, Json, MAX_DEPTH};
+
+/// A piece of a JSON document, in the order it appears
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The start of an object
+    StartObject,
+
+    /// The end of an object
+    EndObject,
+
+    /// The start of a list
+    StartList,
+
+    /// The end of a list
+    EndList,
+
+    /// The key of the next property of an object
+    Key(String),
+
+    /// A string value
+    String(String),
+
+    /// A number
+    Number(f64),
+
+    /// A boolean
+    Bool(bool),
+
+    /// The null value
+    Null,
+}
+
+/// Why transcoding events failed
+#[derive(Debug)]
+pub enum TranscodeError<E> {
+    /// The events couldn't be read
+    Parse(Error),
+
+    /// The sink didn't accept an event
+    Sink(E),
+}
+
+impl<E: Display> Display for TranscodeError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscodeError::Parse(error) => write!(f, "can't read the events: {error}"),
+            TranscodeError::Sink(error) => write!(f, "can't write the events: {error}"),
+        }
+    }
+}
+
+impl<E: core::error::Error> core::error::Error for TranscodeError<E> {}
+
+/// A destination for events, like a writer of some format
+///
+/// Formats that need the length of lists and objects before their items, like MessagePack, can't
+/// be written from events without buffering, so they have no sink.
+pub trait EventSink {
+    /// The error returned when an event isn't accepted
+    type Error;
+
+    /// Takes the next event
+    fn event(&mut self, event: Event) -> Result<(), Self::Error>;
+
+    /// Passes every event to the sink, stopping at the first error
+    ///
+    /// Any iterator over events works, so events can be filtered or changed on the way.
+    fn transcode<I: IntoIterator<Item = Result<Event, Error>>>(
+        &mut self,
+        events: I,
+    ) -> Result<(), TranscodeError<Self::Error>> {
+        for event in events {
+            self.event(event.map_err(TranscodeError::Parse)?)
+                .map_err(TranscodeError::Sink)?;
+        }
+        Ok(())
+    }
+}
+
+/// A list or object the reader is inside of
+struct Container {
+    /// Whether the container is an object
+    object: bool,
+
+    /// Whether nothing has been read from the container yet
+    first: bool,
+}
+
+/// An iterator over the events of a JSON document
+///
+/// Only the nesting of the containers around the current value is kept in memory, so documents
+/// much larger than the available memory can be processed. Like [`Json::from_chars`], reading
+/// stops after the first value. Iteration ends after the first error.
+pub struct Events<I: Iterator<Item = char>> {
+    /// The characters of the document
+    iter: Peekable<I>,
+
+    /// The containers around the current position, innermost last
+    stack: Vec<Container>,
+
+    /// Whether a key has been read and its value is next
+    after_key: bool,
+
+    /// Whether the first value has been started
+    started: bool,
+
+    /// Whether the document has been read completely or an error occured
+    done: bool,
+}
+
+impl<I: Iterator<Item = char>> Events<I> {
+    /// Creates an event reader for the characters of a document
+    pub fn new(iter: I) -> Self {
+        Self {
+            iter: iter.peekable(),
+            stack: Vec::new(),
+            after_key: false,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Returns how many lists and objects the reader is inside of
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Checks that only whitespace follows the value, after all events have been read
+    pub fn finish(mut self) -> Result<(), Error> {
+        Json::skip_whitespace(&mut self.iter);
+        match self.iter.peek() {
+            Some(_) => Err(Error::TrailingCharacters),
+            None => Ok(()),
+        }
+    }
+
+    /// Reads an event of a value that isn't complete yet
+    fn next_inside(&mut self) -> Result<Event, Error> {
+        self.next().unwrap_or(Err(Error::UnexpectedEndOfFile))
+    }
+
+    /// Builds the value starting with an event that was just read, reading the rest of its
+    /// events
+    ///
+    /// Lists and objects nested deeper than 128 levels fail with [`Error::NestingTooDeep`], like
+    /// when parsing with the default options.
+    pub fn build(&mut self, first: Event) -> Result<Json, Error> {
+        // Refuse to build values nested deeper than parsing does by default
+        if self.stack.len() > MAX_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
+
+        Ok(match first {
+            Event::StartList => {
+                let mut values = Vec::new();
+                loop {
+                    match self.next_inside()? {
+                        Event::EndList => break Json::List(values),
+                        event => values.push(self.build(event)?),
+                    }
+                }
+            }
+            Event::StartObject => {
+                let mut items = Vec::new();
+                loop {
+                    match self.next_inside()? {
+                        Event::EndObject => break Json::Object(items),
+                        Event::Key(key) => {
+                            let event = self.next_inside()?;
+                            items.push((key, self.build(event)?));
+                        }
+                        _ => return Err(Error::InvalidValue),
+                    }
+                }
+            }
+            Event::String(string) => Json::String(string),
+            Event::Number(number) => Json::Number(number),
+            Event::Bool(value) => Json::Bool(value),
+            Event::Null => Json::Null,
+            Event::EndObject | Event::EndList | Event::Key(_) => return Err(Error::InvalidValue),
+        })
+    }
+
+    /// Reads the start of a container or a whole value
+    fn value(&mut self) -> Result<Event, Error> {
+        Ok(match self.iter.peek() {
+            // Start containers
+            Some('{') => {
+                self.iter.next();
+                self.stack.push(Container {
+                    object: true,
+                    first: true,
+                });
+                Event::StartObject
+            }
+            Some('[') => {
+                self.iter.next();
+                self.stack.push(Container {
+                    object: false,
+                    first: true,
+                });
+                Event::StartList
+            }
+
+            // Read other values completely
+            Some('"') => Event::String(Json::read_string(&mut self.iter)?),
+            Some(ch) if ch.is_alphabetic() => {
+                Json::read_literal(&mut self.iter)?.map_or(Event::Null, Event::Bool)
+            }
+            Some('0'..='9' | '.' | '-' | '+') => Event::Number(Json::read_number(&mut self.iter)?),
+            Some(_) => return Err(Error::InvalidValue),
+            None if self.stack.is_empty() => return Err(Error::EmptyInput),
+            None => return Err(Error::UnexpectedEndOfFile),
+        })
+    }
+
+    /// Reads the next event
+    fn read(&mut self) -> Result<Option<Event>, Error> {
+        Json::skip_whitespace(&mut self.iter);
+
+        // Read the value of a property after its key
+        if self.after_key {
+            self.after_key = false;
+            return self.value().map(Some);
+        }
+
+        // Read the first value, and stop after it
+        let Some(container) = self.stack.last_mut() else {
+            if self.started {
+                return Ok(None);
+            }
+            self.started = true;
+            return self.value().map(Some);
+        };
+
+        // Find the separator or the end of the container
+        let (close, unclosed) = match container.object {
+            true => ('}', Error::UnclosedObject),
+            false => (']', Error::UnclosedList),
+        };
+        let end = if container.first {
+            container.first = false;
+            match self.iter.peek() {
+                None => return Err(unclosed),
+                ch => ch == Some(&close),
+            }
+        } else {
+            match self.iter.next() {
+                Some(',') => {
+                    Json::skip_whitespace(&mut self.iter);
+                    false
+                }
+                Some(ch) if ch == close => {
+                    let object = container.object;
+                    self.stack.pop();
+                    return Ok(Some(match object {
+                        true => Event::EndObject,
+                        false => Event::EndList,
+                    }));
+                }
+                Some(_) => return Err(Error::MissingSeparator),
+                None => return Err(unclosed),
+            }
+        };
+
+        // Close an empty container
+        if end {
+            self.iter.next();
+            let object = container.object;
+            self.stack.pop();
+            return Ok(Some(match object {
+                true => Event::EndObject,
+                false => Event::EndList,
+            }));
+        }
+
+        // Read the key of a property, or the next value of a list
+        if !container.object {
+            return self.value().map(Some);
+        }
+        let key = Json::read_string(&mut self.iter)?;
+        Json::skip_whitespace(&mut self.iter);
+        if self.iter.next() != Some(':') {
+            return Err(Error::MissingSeparator);
+        }
+        self.after_key = true;
+        Ok(Some(Event::Key(key)))
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for Events<I> {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read().transpose();
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
+
+impl Json {
+    /// Returns an iterator over the events of a document
+    pub fn events<I: Iterator<Item = char>>(iter: I) -> Events<I> {
+        Events::new(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, string::String, vec::Vec};
+
+    use super::{Event, EventSink, TranscodeError};
+    use crate::{Error, Format, Json, JsonWriter, WriterError};
+
+    #[test]
+    fn events() {
+        let events = Json::events(" {\"a\": [1, true, {}], \"b\": null, \"c\": \"d\"} ".chars())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            [
+                Event::StartObject,
+                Event::Key("a".to_owned()),
+                Event::StartList,
+                Event::Number(1.0),
+                Event::Bool(true),
+                Event::StartObject,
+                Event::EndObject,
+                Event::EndList,
+                Event::Key("b".to_owned()),
+                Event::Null,
+                Event::Key("c".to_owned()),
+                Event::String("d".to_owned()),
+                Event::EndObject,
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let last = |text: &str| Json::events(text.chars()).last().unwrap();
+        assert!(matches!(last("[1 2]"), Err(Error::MissingSeparator)));
+        assert!(matches!(last("[1, "), Err(Error::UnexpectedEndOfFile)));
+        assert!(matches!(last("{\"a\": 1"), Err(Error::UnclosedObject)));
+        assert!(matches!(last("{\"a\" 1}"), Err(Error::MissingSeparator)));
+        assert!(matches!(last("[[]"), Err(Error::UnclosedList)));
+        assert!(matches!(last("[] 1"), Ok(Event::EndList)));
+
+        // Trailing characters are only rejected when asked for
+        let mut events = Json::events("[] 1".chars());
+        events.by_ref().for_each(drop);
+        assert!(matches!(events.finish(), Err(Error::TrailingCharacters)));
+
+        // Building values nested too deeply fails instead of running out of stack
+        let build = |text: &str| {
+            let mut events = Json::events(text.chars());
+            let first = events.next().unwrap().unwrap();
+            events.build(first)
+        };
+        assert!(build(&("[".repeat(128) + &"]".repeat(128))).is_ok());
+        assert!(matches!(
+            build(&"[".repeat(1_000_000)),
+            Err(Error::NestingTooDeep)
+        ));
+    }
+
+    #[test]
+    fn transcoding() {
+        let mut writer = JsonWriter::new(String::new(), Format::pretty());
+        let events = Json::events("{\"a\": [1, 2], \"b\": {}}".chars()).map(|event| {
+            event.map(|event| match event {
+                Event::Number(number) => Event::Number(number * 10.0),
+                event => event,
+            })
+        });
+        writer.transcode(events).unwrap();
+        assert_eq!(
+            writer.finish().unwrap(),
+            "{\n  \"a\": [\n    10,\n    20\n  ],\n  \"b\": {}\n}"
+        );
+
+        // Errors of the reader and the writer are kept apart
+        let mut writer = JsonWriter::new(String::new(), Format::compact());
+        let result = writer.transcode(Json::events("[1, }".chars()));
+        assert!(matches!(
+            result,
+            Err(TranscodeError::Parse(Error::InvalidValue))
+        ));
+        let result = writer.transcode([Ok(Event::EndObject), Ok(Event::EndObject)]);
+        assert!(matches!(
+            result,
+            Err(TranscodeError::Sink(WriterError::NothingToEnd))
+        ));
+    }
+}
@@ -0,0 +1,204 @@
+//! A C interface for parsing, querying and serializing JSON values
+//!
+//! Parsed values are returned as opaque `Json` handles, which have to be released with
+//! [`json_free`]. Handles returned by [`json_pointer`] borrow from their root value and are
+//! valid until the root is freed. Strings returned by [`json_stringify`] have to be released
+//! with [`json_string_free`].
+//!
+//! To link the functions into C firmware, build a `staticlib` crate that depends on this crate
+//! with the `ffi` feature and provides a panic handler and global allocator.
+
+use alloc::{boxed::Box, ffi::CString, string::ToString};
+use core::{ffi::c_char, ptr, slice, str};
+
+use crate::Json;
+
+/// The type of a JSON value
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonType {
+    /// A list of data
+    List,
+
+    /// An object
+    Object,
+
+    /// A string
+    String,
+
+    /// A number
+    Number,
+
+    /// A boolean
+    Bool,
+
+    /// A null value
+    Null,
+}
+
+/// Reads a UTF-8 string from a pointer and length
+///
+/// # Safety
+/// `text` has to point to `length` readable bytes
+unsafe fn read_str<'a>(text: *const c_char, length: usize) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    // SAFETY: the caller guarantees that `text` points to `length` readable bytes
+    str::from_utf8(unsafe { slice::from_raw_parts(text.cast::<u8>(), length) }).ok()
+}
+
+/// Parses `length` bytes of UTF-8 text, returning null if it's not valid JSON
+///
+/// # Safety
+/// `text` has to point to `length` readable bytes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_parse(text: *const c_char, length: usize) -> *mut Json {
+    // SAFETY: the caller guarantees that `text` points to `length` readable bytes
+    match unsafe { read_str(text, length) }.and_then(|text| text.parse().ok()) {
+        Some(value) => Box::into_raw(Box::new(value)),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Frees a value returned by `json_parse`, doing nothing for null
+///
+/// # Safety
+/// `value` has to be null or returned by `json_parse` and not freed before
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_free(value: *mut Json) {
+    if !value.is_null() {
+        // SAFETY: the caller guarantees the value was allocated by `json_parse`
+        drop(unsafe { Box::from_raw(value) });
+    }
+}
+
+/// Looks up a value by a JSON pointer of `length` bytes, returning null if it doesn't exist
+///
+/// # Safety
+/// `value` has to be a valid handle and `pointer` has to point to `length` readable bytes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_pointer(
+    value: *const Json,
+    pointer: *const c_char,
+    length: usize,
+) -> *const Json {
+    // SAFETY: the caller guarantees the handle and pointer are valid
+    let (value, pointer) = unsafe { (value.as_ref(), read_str(pointer, length)) };
+    match value
+        .zip(pointer)
+        .and_then(|(value, pointer)| value.pointer(pointer))
+    {
+        Some(found) => found,
+        None => ptr::null(),
+    }
+}
+
+/// Returns the type of a value
+///
+/// # Safety
+/// `value` has to be a valid, non-null handle
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_type(value: *const Json) -> JsonType {
+    // SAFETY: the caller guarantees the handle is valid
+    match unsafe { &*value } {
+        Json::List(_) => JsonType::List,
+        Json::Object(_) => JsonType::Object,
+        Json::String(_) => JsonType::String,
+        Json::Number(_) | Json::NumberRaw(_) => JsonType::Number,
+        Json::Bool(_) => JsonType::Bool,
+        Json::Null => JsonType::Null,
+    }
+}
+
+/// Writes the number of a value to `out`, returning whether the value is a number
+///
+/// # Safety
+/// `value` has to be a valid handle and `out` has to be writable
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_as_number(value: *const Json, out: *mut f64) -> bool {
+    // SAFETY: the caller guarantees the handle and output are valid
+    match unsafe { value.as_ref() }.map(Json::plain).as_deref() {
+        Some(Json::Number(number)) => {
+            unsafe { out.write(*number) };
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Writes the boolean of a value to `out`, returning whether the value is a boolean
+///
+/// # Safety
+/// `value` has to be a valid handle and `out` has to be writable
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_as_bool(value: *const Json, out: *mut bool) -> bool {
+    // SAFETY: the caller guarantees the handle and output are valid
+    match unsafe { value.as_ref() } {
+        Some(Json::Bool(flag)) => {
+            unsafe { out.write(*flag) };
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Serializes a value to a null terminated string, returning null if that's not possible
+///
+/// # Safety
+/// `value` has to be a valid handle
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_stringify(value: *const Json) -> *mut c_char {
+    // SAFETY: the caller guarantees the handle is valid
+    unsafe { value.as_ref() }
+        .and_then(|value| CString::new(value.to_string()).ok())
+        .map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Frees a string returned by `json_stringify`, doing nothing for null
+///
+/// # Safety
+/// `string` has to be null or returned by `json_stringify` and not freed before
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn json_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        // SAFETY: the caller guarantees the string was allocated by `json_stringify`
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ffi::CStr;
+
+    use super::*;
+
+    #[test]
+    fn ffi_round_trip() {
+        let text = "{\"a\":[1,true]}";
+        unsafe {
+            let value = json_parse(text.as_ptr().cast(), text.len());
+            assert!(!value.is_null());
+            assert_eq!(json_type(value), JsonType::Object);
+
+            // Look up values by pointer
+            let pointer = "/a/0";
+            let number = json_pointer(value, pointer.as_ptr().cast(), pointer.len());
+            let mut out = 0.0;
+            assert!(json_as_number(number, &mut out));
+            assert_eq!(out, 1.0);
+            let mut flag = false;
+            assert!(!json_as_bool(number, &mut flag));
+            assert!(json_pointer(value, "/b".as_ptr().cast(), 2).is_null());
+
+            // Serialize the value
+            let string = json_stringify(value);
+            assert_eq!(CStr::from_ptr(string).to_str().unwrap(), text);
+            json_string_free(string);
+            json_free(value);
+
+            // Invalid input returns null
+            assert!(json_parse("[1,".as_ptr().cast(), 3).is_null());
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Conversion between `application/x-www-form-urlencoded` data and objects
+//!
+//! Bracketed keys describe nesting: `a[b]=1` becomes `{"a":{"b":"1"}}` and `a[]=1&a[]=2`
+//! becomes `{"a":["1","2"]}`. All values are read as strings.
+
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::fmt::Write as _;
+
+use crate::{Error, Json, MAX_DEPTH};
+
+/// Decodes a percent-encoded component, where `+` is a space
+fn decode(text: &str) -> Result<String, Error> {
+    let mut bytes = Vec::new();
+    let mut iter = text.bytes();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let byte = match [iter.next(), iter.next()] {
+                    [Some(high), Some(low)] => core::str::from_utf8(&[high, low])
+                        .ok()
+                        .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+                    _ => None,
+                };
+                bytes.push(byte.ok_or(Error::InvalidValue)?);
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| Error::InvalidValue)
+}
+
+/// Percent-encodes a component, writing spaces as `+`
+fn encode(out: &mut String, text: &str) {
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(char::from(byte));
+            }
+            b' ' => out.push('+'),
+            _ => write!(out, "%{byte:02X}").unwrap(),
+        }
+    }
+}
+
+/// Splits a key like `a[b][]` into its segments, `None` being an append to a list
+///
+/// Keys with more segments than values can be nested are rejected.
+fn split_key(key: &str) -> Result<(&str, Vec<Option<&str>>), Error> {
+    let Some(start) = key.find('[') else {
+        return Ok((key, Vec::new()));
+    };
+    let (name, mut rest) = key.split_at(start);
+    let mut segments = Vec::new();
+    while let Some(inner) = rest.strip_prefix('[') {
+        if segments.len() >= MAX_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
+        let end = inner.find(']').ok_or(Error::InvalidValue)?;
+        segments.push(Some(&inner[..end]).filter(|segment| !segment.is_empty()));
+        rest = &inner[end + 1..];
+    }
+    if !rest.is_empty() {
+        return Err(Error::InvalidValue);
+    }
+    Ok((name, segments))
+}
+
+/// Inserts a value at the path described by the segments
+fn insert(target: &mut Json, segments: &[Option<&str>], value: String) -> Result<(), Error> {
+    let Some((segment, rest)) = segments.split_first() else {
+        *target = Json::String(value);
+        return Ok(());
+    };
+    match (segment, target) {
+        // Append to a list
+        (None, Json::List(values)) => {
+            values.push(Json::Null);
+            insert(values.last_mut().unwrap(), rest, value)
+        }
+
+        // Set a property of an object
+        (Some(key), Json::Object(items)) => {
+            let index = match items.iter().position(|(name, _)| name == key) {
+                Some(index) => index,
+                None => {
+                    let child = match rest.first() {
+                        Some(None) => Json::List(Vec::new()),
+                        Some(Some(_)) => Json::Object(Vec::new()),
+                        None => Json::Null,
+                    };
+                    items.push(((*key).to_owned(), child));
+                    items.len() - 1
+                }
+            };
+            insert(&mut items[index].1, rest, value)
+        }
+
+        // The key conflicts with an earlier one
+        _ => Err(Error::InvalidValue),
+    }
+}
+
+/// Writes the pairs describing a value at the given key
+fn write_pairs(out: &mut String, key: &str, value: &Json) -> Result<(), Error> {
+    match value {
+        // Write the properties of objects with bracketed keys
+        Json::Object(items) => {
+            for (name, value) in items {
+                let mut nested = String::from(key);
+                nested.push('[');
+                encode(&mut nested, name);
+                nested.push(']');
+                write_pairs(out, &nested, value)?;
+            }
+            return Ok(());
+        }
+
+        // Write lists of scalars as appended values
+        Json::List(values) => {
+            let mut nested = String::from(key);
+            nested.push_str("[]");
+            for value in values {
+                if matches!(value, Json::Object(_) | Json::List(_)) {
+                    return Err(Error::InvalidValue);
+                }
+                write_pairs(out, &nested, value)?;
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    // Write a single pair, null being an empty value
+    if !out.is_empty() {
+        out.push('&');
+    }
+    out.push_str(key);
+    out.push('=');
+    match value {
+        Json::String(string) => encode(out, string),
+        Json::Number(number) => write!(out, "{number}").unwrap(),
+        Json::NumberRaw(text) => out.push_str(text),
+        Json::Bool(value) => write!(out, "{value}").unwrap(),
+        _ => {}
+    }
+    Ok(())
+}
+
+impl Json {
+    /// Reads form data as an object, nesting values with bracketed keys
+    pub fn from_form_urlencoded(form: &str) -> Result<Self, Error> {
+        let mut result = Json::Object(Vec::new());
+        for pair in form.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let key = decode(key)?;
+            let (name, segments) = split_key(&key)?;
+
+            // Later values replace earlier ones with the same key, except when appending
+            let mut path = Vec::from([Some(name)]);
+            path.extend(segments);
+            if let (Json::Object(items), [Some(name)]) = (&mut result, path.as_slice()) {
+                items.retain(|(key, _)| key != name);
+            }
+            insert(&mut result, &path, decode(value)?)?;
+        }
+        Ok(result)
+    }
+
+    /// Writes an object as form data, using bracketed keys for nested values
+    ///
+    /// Lists can only contain scalars, because objects in lists can't be told apart.
+    pub fn to_form_urlencoded(&self) -> Result<String, Error> {
+        let Json::Object(items) = self else {
+            return Err(Error::InvalidValue);
+        };
+        let mut out = String::new();
+        for (key, value) in items {
+            let mut encoded = String::new();
+            encode(&mut encoded, key);
+            write_pairs(&mut out, &encoded, value)?;
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, string::String, vec::Vec};
+
+    use crate::{Error, Json, MAX_DEPTH};
+
+    #[test]
+    fn form_reading() {
+        assert_eq!(
+            Json::from_form_urlencoded(
+                "name=J%C3%BCrgen+X&a=1&a=2&tags[]=x&tags%5B%5D=y&user[address][city]=Berlin&flag"
+            )
+            .unwrap(),
+            Json::Object(Vec::from([
+                ("name".to_owned(), Json::String("Jürgen X".to_owned())),
+                ("a".to_owned(), Json::String("2".to_owned())),
+                (
+                    "tags".to_owned(),
+                    Json::List(Vec::from([
+                        Json::String("x".to_owned()),
+                        Json::String("y".to_owned())
+                    ]))
+                ),
+                (
+                    "user".to_owned(),
+                    Json::Object(Vec::from([(
+                        "address".to_owned(),
+                        Json::Object(Vec::from([(
+                            "city".to_owned(),
+                            Json::String("Berlin".to_owned())
+                        )]))
+                    )]))
+                ),
+                ("flag".to_owned(), Json::String(String::new())),
+            ]))
+        );
+        assert!(Json::from_form_urlencoded("a=%zz").is_err());
+        assert!(Json::from_form_urlencoded("a[=1").is_err());
+        assert!(Json::from_form_urlencoded("a[]=1&a[b]=2").is_err());
+    }
+
+    #[test]
+    fn deep_keys() {
+        let key = String::from("a") + &"[x]".repeat(200_000);
+        assert!(matches!(
+            Json::from_form_urlencoded(&(key + "=1")),
+            Err(Error::NestingTooDeep)
+        ));
+        let key = String::from("a") + &"[x]".repeat(MAX_DEPTH);
+        assert!(Json::from_form_urlencoded(&(key + "=1")).is_ok());
+    }
+
+    #[test]
+    fn form_writing() {
+        let value = Json::Object(Vec::from([
+            ("q".to_owned(), Json::String("a b&c".to_owned())),
+            ("n".to_owned(), Json::Number(1.5)),
+            (
+                "list".to_owned(),
+                Json::List(Vec::from([Json::Bool(true), Json::Null])),
+            ),
+            (
+                "o".to_owned(),
+                Json::Object(Vec::from([("k".to_owned(), Json::String("v".to_owned()))])),
+            ),
+        ]));
+        assert_eq!(
+            value.to_form_urlencoded().unwrap(),
+            "q=a+b%26c&n=1.5&list[]=true&list[]=&o[k]=v"
+        );
+        assert!(Json::Null.to_form_urlencoded().is_err());
+    }
+}
@@ -0,0 +1,338 @@
+//! Typed lookup of values by JSON pointers, and comparisons with plain values
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+};
+use core::{
+    fmt::{self, Display, Formatter},
+    ops::Index,
+};
+
+use crate::{Json, Path, schema::type_name};
+
+/// A type that values can be converted to by [`Json::get_as`]
+pub trait FromJson<'a>: Sized {
+    /// The name of the type, used in errors
+    const NAME: &'static str;
+
+    /// Converts a value, returning `None` if it doesn't hold this type
+    fn from_json(value: &'a Json) -> Option<Self>;
+}
+
+impl<'a> FromJson<'a> for &'a Json {
+    const NAME: &'static str = "value";
+
+    fn from_json(value: &'a Json) -> Option<Self> {
+        Some(value)
+    }
+}
+
+impl<'a> FromJson<'a> for &'a str {
+    const NAME: &'static str = "string";
+
+    fn from_json(value: &'a Json) -> Option<Self> {
+        match value {
+            Json::String(string) => Some(string),
+            _ => None,
+        }
+    }
+}
+
+impl FromJson<'_> for String {
+    const NAME: &'static str = "string";
+
+    fn from_json(value: &Json) -> Option<Self> {
+        <&str>::from_json(value).map(ToOwned::to_owned)
+    }
+}
+
+impl FromJson<'_> for bool {
+    const NAME: &'static str = "boolean";
+
+    fn from_json(value: &Json) -> Option<Self> {
+        match value {
+            Json::Bool(boolean) => Some(*boolean),
+            _ => None,
+        }
+    }
+}
+
+impl FromJson<'_> for f64 {
+    const NAME: &'static str = "number";
+
+    fn from_json(value: &Json) -> Option<Self> {
+        match *value.plain() {
+            Json::Number(number) => Some(number),
+            _ => None,
+        }
+    }
+}
+
+/// Implements [`FromJson`] for integer types, which only accept whole numbers in their range
+macro_rules! from_json_integer {
+    ($($integer:ty),*) => {$(
+        impl FromJson<'_> for $integer {
+            const NAME: &'static str = stringify!($integer);
+
+            fn from_json(value: &Json) -> Option<Self> {
+                match value {
+                    // Raw integers are read exactly, even beyond 2^53
+                    Json::NumberRaw(text) => text
+                        .parse()
+                        .ok()
+                        .or_else(|| Self::from_json(&value.plain())),
+                    // Casts saturate, so numbers out of range are refused before casting
+                    Json::Number(number)
+                        if (<$integer>::MIN as f64..<$integer>::MAX as f64 + 1.0)
+                            .contains(number) =>
+                    {
+                        let integer = *number as $integer;
+                        (integer as f64 == *number).then_some(integer)
+                    }
+                    _ => None,
+                }
+            }
+        }
+    )*};
+}
+
+from_json_integer!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/// Implements comparisons of values with plain values, which are equal if the value converts
+/// to them with [`FromJson`]
+macro_rules! partial_eq {
+    ($($plain:ty),*) => {$(
+        impl PartialEq<$plain> for Json {
+            fn eq(&self, other: &$plain) -> bool {
+                <$plain>::from_json(self).is_some_and(|value| value == *other)
+            }
+        }
+    )*};
+}
+
+partial_eq!(&str, f64, bool, i64);
+
+/// The value indexing returns for missing properties and items
+static NULL: Json = Json::Null;
+
+impl Index<&str> for Json {
+    type Output = Json;
+
+    /// Returns the last property with a key, or null if it's missing or this isn't an object
+    fn index(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(items) => items
+                .iter()
+                .rev()
+                .find(|(name, _)| name == key)
+                .map_or(&NULL, |(_, value)| value),
+            _ => &NULL,
+        }
+    }
+}
+
+impl Index<usize> for Json {
+    type Output = Json;
+
+    /// Returns the item at an index, or null if it's missing or this isn't a list
+    fn index(&self, index: usize) -> &Json {
+        match self {
+            Json::List(values) => values.get(index).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+/// Why [`Json::get_as`] failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GetError {
+    /// There's no value at the pointer
+    NotFound(String),
+
+    /// The value at the pointer doesn't hold the requested type
+    Mismatch {
+        /// The pointer to the value
+        pointer: String,
+
+        /// The name of the requested type
+        expected: &'static str,
+
+        /// The JSON Schema type of the value
+        found: &'static str,
+    },
+}
+
+impl Display for GetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GetError::NotFound(pointer) => write!(f, "no value at \"{pointer}\""),
+            GetError::Mismatch {
+                pointer,
+                expected,
+                found,
+            } => write!(f, "expected {expected} at \"{pointer}\", found {found}"),
+        }
+    }
+}
+
+impl GetError {
+    /// Returns the location of the value as a typed path, or `None` if the pointer is invalid
+    pub fn to_path(&self) -> Option<Path> {
+        match self {
+            GetError::NotFound(pointer) | GetError::Mismatch { pointer, .. } => {
+                Path::parse(pointer)
+            }
+        }
+    }
+}
+
+impl core::error::Error for GetError {}
+
+impl Json {
+    /// Looks up the value at a JSON pointer and converts it, like
+    /// `json.get_as::<u16>("/server/port")`
+    pub fn get_as<'a, T: FromJson<'a>>(&'a self, pointer: &str) -> Result<T, GetError> {
+        let value = self
+            .pointer(pointer)
+            .ok_or_else(|| GetError::NotFound(pointer.to_string()))?;
+        T::from_json(value).ok_or_else(|| GetError::Mismatch {
+            pointer: pointer.to_string(),
+            expected: T::NAME,
+            found: type_name(value),
+        })
+    }
+
+    /// Returns whether two values mean the same, unlike `==` ignoring the order of properties and
+    /// comparing raw numbers by their value
+    ///
+    /// Objects with duplicate keys are compared by the last property with each key, the one
+    /// indexing returns.
+    pub fn equivalent(&self, other: &Json) -> bool {
+        match (&*self.plain(), &*other.plain()) {
+            (Json::List(values), Json::List(others)) => {
+                values.len() == others.len()
+                    && values.iter().zip(others).all(|(a, b)| a.equivalent(b))
+            }
+            (Json::Object(items), Json::Object(others)) => {
+                // Every key of each object must be in the other one with an equivalent value
+                let has =
+                    |items: &[(String, Json)], key: &str| items.iter().any(|(name, _)| name == key);
+                items.iter().all(|(key, _)| has(others, key))
+                    && others.iter().all(|(key, _)| has(items, key))
+                    && items
+                        .iter()
+                        .all(|(key, _)| self[key.as_str()].equivalent(&other[key.as_str()]))
+            }
+            (a, b) => a == b,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+
+    use super::{FromJson, GetError};
+    use crate::{IntegerOverflow, Json, ParseOptions};
+
+    #[test]
+    fn get_as() {
+        let config = "{\"server\":{\"host\":\"localhost\",\"port\":8080,\"debug\":true}}"
+            .parse::<Json>()
+            .unwrap();
+        assert_eq!(config.get_as::<u16>("/server/port"), Ok(8080));
+        assert_eq!(config.get_as::<f64>("/server/port"), Ok(8080.0));
+        assert_eq!(config.get_as::<&str>("/server/host"), Ok("localhost"));
+        assert_eq!(config.get_as::<bool>("/server/debug"), Ok(true));
+
+        // Errors name the pointer and the types
+        assert_eq!(
+            config.get_as::<u8>("/server/port").unwrap_err().to_string(),
+            "expected u8 at \"/server/port\", found number"
+        );
+        assert_eq!(
+            config
+                .get_as::<String>("/server/debug")
+                .unwrap_err()
+                .to_string(),
+            "expected string at \"/server/debug\", found boolean"
+        );
+        assert!(matches!(
+            config.get_as::<u16>("/server/timeout"),
+            Err(GetError::NotFound(_))
+        ));
+
+        // Raw integers too large for a float are read exactly
+        let options = ParseOptions {
+            integer_overflow: IntegerOverflow::Raw,
+            ..ParseOptions::default()
+        };
+        let id = Json::from_chars_with("18446744073709551615".chars(), &options).unwrap();
+        assert_eq!(id.get_as::<u64>(""), Ok(u64::MAX));
+        assert!(Json::Number(1.5).get_as::<i32>("").is_err());
+    }
+
+    #[test]
+    fn integer_bounds() {
+        // Numbers just past the range of a type are refused rather than saturated
+        let two_63 = 9_223_372_036_854_775_808.0;
+        let two_64 = 18_446_744_073_709_551_616.0;
+        assert_eq!(i64::from_json(&Json::Number(-two_63)), Some(i64::MIN));
+        assert_eq!(i64::from_json(&Json::Number(two_63)), None);
+        assert_eq!(i64::from_json(&Json::Number(-two_63 * 2.0)), None);
+        assert_eq!(u64::from_json(&Json::Number(two_64)), None);
+        assert_eq!(u64::from_json(&Json::Number(-1.0)), None);
+        assert_eq!(u8::from_json(&Json::Number(255.0)), Some(255));
+        assert_eq!(u8::from_json(&Json::Number(256.0)), None);
+        assert_eq!(i8::from_json(&Json::Number(-128.0)), Some(-128));
+        assert_eq!(i8::from_json(&Json::Number(-129.0)), None);
+        assert_eq!(i32::from_json(&Json::Number(f64::NAN)), None);
+        assert_ne!(Json::Number(two_63), i64::MAX);
+    }
+
+    #[test]
+    fn equivalence() {
+        let parse = |text: &str| text.parse::<Json>().unwrap();
+        assert!(
+            parse("{\"a\":1,\"b\":[{\"c\":null,\"d\":2}]}")
+                .equivalent(&parse("{\"b\":[{\"d\":2,\"c\":null}],\"a\":1}"))
+        );
+        assert!(Json::NumberRaw("1.0".into()).equivalent(&Json::Number(1.0)));
+        assert!(parse("{\"a\":1,\"a\":2}").equivalent(&parse("{\"a\":2}")));
+
+        // Order still matters in lists, and missing properties differ from null ones
+        assert!(!parse("[1,2]").equivalent(&parse("[2,1]")));
+        assert!(!parse("{\"a\":null}").equivalent(&parse("{}")));
+        assert!(!parse("{}").equivalent(&parse("{\"a\":null}")));
+        assert!(!parse("{\"a\":1}").equivalent(&parse("{\"a\":\"1\"}")));
+    }
+
+    #[test]
+    fn comparing() {
+        let user = "{\"name\":\"alice\",\"age\":30,\"admin\":false,\"tags\":[\"a\",1.5]}"
+            .parse::<Json>()
+            .unwrap();
+        assert_eq!(user["name"], "alice");
+        assert_eq!(user["age"], 30);
+        assert_eq!(user["age"], 30.0);
+        assert_eq!(user["admin"], false);
+        assert_eq!(user["tags"][1], 1.5);
+        assert_ne!(user["tags"][1], 1);
+        assert_ne!(user["age"], "30");
+
+        // Missing values are null
+        assert_eq!(user["email"], Json::Null);
+        assert_eq!(user["tags"][5], Json::Null);
+        assert_eq!(user["name"]["first"], Json::Null);
+
+        // Raw integers are compared exactly
+        let options = ParseOptions {
+            integer_overflow: IntegerOverflow::Raw,
+            ..ParseOptions::default()
+        };
+        let id = Json::from_chars_with("9223372036854775807".chars(), &options).unwrap();
+        assert_eq!(id, i64::MAX);
+        assert_ne!(id, i64::MAX - 1);
+    }
+}
@@ -0,0 +1,197 @@
+//! Undoing and redoing edits of a document
+
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::{Json, PatchError, PatchOperation};
+
+/// A point in the edits of a [`History`] to roll back to
+///
+/// It refers to a version of the document by the edit that made it, so it stays distinct from
+/// versions made by other edits after undoing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+/// A document that remembers its earlier versions, so edits can be undone and redone
+///
+/// Every edit keeps a copy of the document as it was before, so undoing restores it exactly,
+/// including the order of keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct History {
+    /// The current version of the document
+    document: Json,
+
+    /// The number of the edit that made the current version, zero for the original
+    version: usize,
+
+    /// The number of edits recorded so far, to number the next one
+    edits: usize,
+
+    /// The versions before the current one with their numbers, the latest last
+    undone: Vec<(usize, Json)>,
+
+    /// The versions that were undone with their numbers, the latest undone last
+    redone: Vec<(usize, Json)>,
+}
+
+impl History {
+    /// Starts the history of a document
+    pub fn new(document: Json) -> Self {
+        Self {
+            document,
+            version: 0,
+            edits: 0,
+            undone: Vec::new(),
+            redone: Vec::new(),
+        }
+    }
+
+    /// Returns the current version of the document
+    pub fn document(&self) -> &Json {
+        &self.document
+    }
+
+    /// Returns the current version of the document, forgetting the history
+    pub fn into_document(self) -> Json {
+        self.document
+    }
+
+    /// Edits the document with a function, recording the change if there is one
+    pub fn edit<R, F: FnOnce(&mut Json) -> R>(&mut self, edit: F) -> R {
+        let previous = self.document.clone();
+        let result = edit(&mut self.document);
+        self.record(previous);
+        result
+    }
+
+    /// Applies a JSON Patch to the document, recording the change if it succeeds
+    pub fn apply_patch(&mut self, operations: &[PatchOperation]) -> Result<(), PatchError> {
+        let previous = self.document.clone();
+        self.document.apply_patch(operations)?;
+        self.record(previous);
+        Ok(())
+    }
+
+    /// Remembers the version before an edit, unless the edit changed nothing
+    fn record(&mut self, previous: Json) {
+        if previous != self.document {
+            self.undone.push((self.version, previous));
+            self.redone.clear();
+            self.edits += 1;
+            self.version = self.edits;
+        }
+    }
+
+    /// Returns whether there's an edit to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Returns whether there's an undone edit to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redone.is_empty()
+    }
+
+    /// Undoes the last edit, returning whether there was one
+    pub fn undo(&mut self) -> bool {
+        let Some((version, previous)) = self.undone.pop() else {
+            return false;
+        };
+        let current = mem::replace(&mut self.document, previous);
+        self.redone
+            .push((mem::replace(&mut self.version, version), current));
+        true
+    }
+
+    /// Redoes the last undone edit, returning whether there was one
+    pub fn redo(&mut self) -> bool {
+        let Some((version, next)) = self.redone.pop() else {
+            return false;
+        };
+        let current = mem::replace(&mut self.document, next);
+        self.undone
+            .push((mem::replace(&mut self.version, version), current));
+        true
+    }
+
+    /// Returns the current point in the history, to roll back to later
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.version)
+    }
+
+    /// Undoes every edit since a checkpoint, returning `false` without changing anything if
+    /// the version at the checkpoint was undone since
+    pub fn rollback(&mut self, checkpoint: Checkpoint) -> bool {
+        if self.version != checkpoint.0
+            && !self
+                .undone
+                .iter()
+                .any(|(version, _)| *version == checkpoint.0)
+        {
+            return false;
+        }
+        while self.version != checkpoint.0 {
+            self.undo();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, vec::Vec};
+
+    use super::History;
+    use crate::{Json, PatchOperation};
+
+    #[test]
+    fn undo_and_redo() {
+        let original = "{\"a\":1,\"b\":2}".parse::<Json>().unwrap();
+        let mut history = History::new(original.clone());
+        history.edit(|document| *document.pointer_mut("/a").unwrap() = Json::Null);
+        history
+            .apply_patch(&[PatchOperation::Remove {
+                path: "/a".to_owned(),
+            }])
+            .unwrap();
+        assert_eq!(history.document(), &"{\"b\":2}".parse::<Json>().unwrap());
+
+        // Undoing restores the exact earlier versions, key order included
+        assert!(history.undo() && history.undo() && !history.undo());
+        assert_eq!(history.document(), &original);
+        assert!(history.redo());
+        assert_eq!(
+            history.document(),
+            &"{\"a\":null,\"b\":2}".parse::<Json>().unwrap()
+        );
+
+        // A new edit drops what was undone, and edits changing nothing aren't recorded
+        history.edit(|document| document.merge_patch(&"{\"c\":3}".parse().unwrap()));
+        history.edit(|_| {});
+        assert!(!history.can_redo());
+        assert!(history.undo() && history.undo() && !history.can_undo());
+    }
+
+    #[test]
+    fn rollback() {
+        let mut history = History::new(Json::List(Vec::new()));
+        history.edit(|document| *document = Json::Number(1.0));
+        let checkpoint = history.checkpoint();
+        history.edit(|document| *document = Json::Number(2.0));
+        history.edit(|document| *document = Json::Number(3.0));
+        assert!(history.rollback(checkpoint));
+        assert_eq!(history.document(), &Json::Number(1.0));
+
+        // A checkpoint can't be reached once edits before it were undone
+        history.undo();
+        assert!(!history.rollback(checkpoint));
+        assert_eq!(history.document(), &Json::List(Vec::new()));
+
+        // Redoing makes it reachable again, but a different edit in its place doesn't
+        assert!(history.redo() && history.rollback(checkpoint));
+        history.undo();
+        history.edit(|document| *document = Json::Number(2.0));
+        assert!(!history.rollback(checkpoint));
+        assert_eq!(history.document(), &Json::Number(2.0));
+    }
+}
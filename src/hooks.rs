@@ -0,0 +1,86 @@
+//! Callbacks on the keys and values read while parsing
+
+use crate::{Error, Json, Path};
+
+/// Callbacks run while parsing, see [`Json::from_source_with_hooks`]
+///
+/// Returning an error from a callback stops parsing with it, like [`Error::Aborted`] for a
+/// policy that the input breaks.
+pub trait ParseHooks {
+    /// Called with the path to an object and each of its keys, before the value is read
+    fn on_key(&mut self, path: &Path, key: &str) -> Result<(), Error> {
+        let _ = (path, key);
+        Ok(())
+    }
+
+    /// Called with each value and its path once it's read, so the values in lists and objects
+    /// come before the lists and objects themselves
+    fn on_value(&mut self, path: &Path, value: &Json) -> Result<(), Error> {
+        let _ = (path, value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        string::{String, ToString},
+        vec::Vec,
+    };
+
+    use super::ParseHooks;
+    use crate::{Error, Json, ParseOptions, Path, StrSource};
+
+    /// Records the keys and the paths of the values, refusing keys starting with `$`
+    #[derive(Default)]
+    struct Recorder {
+        keys: Vec<String>,
+        values: Vec<String>,
+    }
+
+    impl ParseHooks for Recorder {
+        fn on_key(&mut self, path: &Path, key: &str) -> Result<(), Error> {
+            if key.starts_with('$') {
+                return Err(Error::Aborted("keys can't start with $"));
+            }
+            self.keys.push(path.to_string() + "/" + key);
+            Ok(())
+        }
+
+        fn on_value(&mut self, path: &Path, _: &Json) -> Result<(), Error> {
+            self.values.push(path.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn hooks() {
+        let mut recorder = Recorder::default();
+        let value = Json::from_source_with_hooks(
+            StrSource::new("{\"a\":[1,{\"b\":null}],\"c\":true}"),
+            &ParseOptions::default(),
+            &mut recorder,
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            "{\"a\":[1,{\"b\":null}],\"c\":true}"
+                .parse::<Json>()
+                .unwrap()
+        );
+        assert_eq!(recorder.keys, ["/a", "/a/1/b", "/c"]);
+        assert_eq!(recorder.values, ["/a/0", "/a/1/b", "/a/1", "/a", "/c", ""]);
+
+        // Hooks can stop parsing early
+        let error = Json::from_source_with_hooks(
+            StrSource::new("[{\"$where\": 1}]"),
+            &ParseOptions::default(),
+            &mut Recorder::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "parsing was aborted: keys can't start with $ at line 1, column 10"
+        );
+    }
+}
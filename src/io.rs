@@ -0,0 +1,90 @@
+//! Serialization into `std::io` writers
+
+use core::fmt;
+use std::io::{self, BufWriter, Write};
+
+use crate::{Format, Json};
+
+/// Passes formatted text on to a writer, keeping the error that stopped it
+struct Adapter<W: Write> {
+    /// The buffered writer the text goes to
+    writer: BufWriter<W>,
+
+    /// The error returned by the writer, if any
+    error: Option<io::Error>,
+}
+
+impl<W: Write> fmt::Write for Adapter<W> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.writer.write_all(text.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+impl Json {
+    /// Writes the value as compact JSON into a writer, buffering the small writes
+    pub fn to_writer<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.to_writer_formatted(writer, &Format::compact())
+    }
+
+    /// Writes the value as JSON indented by two spaces into a writer
+    pub fn to_writer_pretty<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.to_writer_formatted(writer, &Format::pretty())
+    }
+
+    /// Writes the value with the given formatting options into a writer
+    pub fn to_writer_formatted<W: Write>(&self, writer: W, format: &Format) -> io::Result<()> {
+        let mut adapter = Adapter {
+            writer: BufWriter::new(writer),
+            error: None,
+        };
+        if self.write_formatted(&mut adapter, format).is_err() {
+            return Err(adapter
+                .error
+                .unwrap_or_else(|| io::Error::other("formatting failed")));
+        }
+        adapter.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, vec::Vec};
+
+    use crate::Json;
+
+    /// A writer that fails after accepting a number of bytes
+    struct Full(usize);
+
+    impl io::Write for Full {
+        fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            match self.0 {
+                0 => Err(io::Error::new(io::ErrorKind::StorageFull, "full")),
+                space => {
+                    let count = space.min(buffer.len());
+                    self.0 -= count;
+                    Ok(count)
+                }
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writers() {
+        let value = "{\"a\": [1, \"b\"]}".parse::<Json>().unwrap();
+        let mut out = Vec::new();
+        value.to_writer(&mut out).unwrap();
+        assert_eq!(out, b"{\"a\":[1,\"b\"]}");
+        out.clear();
+        value.to_writer_pretty(&mut out).unwrap();
+        assert_eq!(out, value.to_string_pretty().as_bytes());
+        let error = value.to_writer(Full(4)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+    }
+}
@@ -0,0 +1,1050 @@
+//! Querying values with JMESPath expressions
+//!
+//! Supports the whole expression grammar (projections, filters, slices, multi-selects, pipes
+//! and expression references) and the built-in functions of the specification.
+
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::Json;
+
+/// Why an expression couldn't be parsed or evaluated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JmesPathError {
+    /// The expression is invalid from the byte at the offset on
+    Syntax(usize),
+
+    /// The expression calls a function that doesn't exist
+    UnknownFunction(String),
+
+    /// A function was called with the wrong number or types of arguments
+    InvalidArguments(String),
+}
+
+impl Display for JmesPathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            JmesPathError::Syntax(offset) => write!(f, "invalid expression at offset {offset}"),
+            JmesPathError::UnknownFunction(name) => write!(f, "unknown function {name}()"),
+            JmesPathError::InvalidArguments(name) => write!(f, "invalid arguments for {name}()"),
+        }
+    }
+}
+
+impl core::error::Error for JmesPathError {}
+
+/// A token of an expression
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// An unquoted name like `foo`
+    Name(String),
+
+    /// A quoted name like `"foo bar"`
+    QuotedName(String),
+
+    /// A literal like `` `[1]` `` or a raw string like `'text'`
+    Literal(Json),
+
+    /// An integer in an index or slice
+    Number(isize),
+
+    /// An operator or punctuation
+    Symbol(&'static str),
+
+    /// The end of the expression
+    End,
+}
+
+/// The symbols, longest first so they're matched before their prefixes
+const SYMBOLS: [&str; 22] = [
+    "[]", "[?", "||", "&&", "==", "!=", "<=", ">=", ".", "*", "[", "]", "{", "}", "(", ")", ",",
+    ":", "|", "&", "!", "@",
+];
+
+/// Returns how strongly a token binds to the expression before it
+fn binding_power(token: &Token) -> u8 {
+    match token {
+        Token::Symbol("|") => 1,
+        Token::Symbol("||") => 2,
+        Token::Symbol("&&") => 3,
+        Token::Symbol("==" | "!=" | "<" | "<=" | ">" | ">=") => 5,
+        Token::Symbol("[]") => 9,
+        Token::Symbol("*") => 20,
+        Token::Symbol("[?") => 21,
+        Token::Symbol(".") => 40,
+        Token::Symbol("!") => 45,
+        Token::Symbol("{") => 50,
+        Token::Symbol("[") => 55,
+        Token::Symbol("(") => 60,
+        _ => 0,
+    }
+}
+
+/// Splits an expression into tokens with their byte offsets, ending with [`Token::End`]
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, JmesPathError> {
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+    while let Some(ch) = text[offset..].chars().next() {
+        let rest = &text[offset..];
+        let start = offset;
+        let token = match ch {
+            // Skip whitespace
+            _ if ch.is_ascii_whitespace() => {
+                offset += 1;
+                continue;
+            }
+
+            // Read names and numbers
+            'a'..='z' | 'A'..='Z' | '_' => {
+                let length = rest
+                    .find(|ch: char| !ch.is_ascii_alphanumeric() && ch != '_')
+                    .unwrap_or(rest.len());
+                offset += length;
+                Token::Name(rest[..length].to_owned())
+            }
+            '-' | '0'..='9' => {
+                let length = rest[1..]
+                    .find(|ch: char| !ch.is_ascii_digit())
+                    .map_or(rest.len(), |length| length + 1);
+                offset += length;
+                Token::Number(
+                    rest[..length]
+                        .parse()
+                        .map_err(|_| JmesPathError::Syntax(start))?,
+                )
+            }
+
+            // Read quoted names as JSON strings
+            '"' => {
+                let mut chars = rest.chars();
+                let name =
+                    Json::read_string(&mut chars).map_err(|_| JmesPathError::Syntax(start))?;
+                offset = text.len() - chars.as_str().len();
+                Token::QuotedName(name)
+            }
+
+            // Read raw strings and literals up to their unescaped closing quote
+            '\'' | '`' => {
+                let mut end = 1;
+                loop {
+                    match rest[end..].find(['\\', ch]) {
+                        None => return Err(JmesPathError::Syntax(start)),
+                        Some(found) if rest[end + found..].starts_with('\\') => end += found + 2,
+                        Some(found) => {
+                            end += found;
+                            break;
+                        }
+                    }
+                }
+                offset += end + 1;
+                let quote = match ch {
+                    '\'' => "\\'",
+                    _ => "\\`",
+                };
+                let content = rest[1..end].replace(quote, &quote[1..]);
+                Token::Literal(match ch {
+                    '\'' => Json::String(content),
+                    _ => content
+                        .trim()
+                        .parse()
+                        .map_err(|_| JmesPathError::Syntax(start))?,
+                })
+            }
+
+            // Read symbols, the comparisons last because of their prefixes
+            _ => {
+                let symbol = SYMBOLS
+                    .into_iter()
+                    .chain(["<", ">"])
+                    .find(|symbol| rest.starts_with(symbol))
+                    .ok_or(JmesPathError::Syntax(start))?;
+                offset += symbol.len();
+                Token::Symbol(symbol)
+            }
+        };
+        tokens.push((token, start));
+    }
+    tokens.push((Token::End, text.len()));
+    Ok(tokens)
+}
+
+/// A node of a parsed expression
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// The current value, `@`
+    Current,
+
+    /// A property of an object
+    Field(String),
+
+    /// A literal value
+    Literal(Json),
+
+    /// An item of a list, counting from the end if it's negative
+    Index(isize),
+
+    /// Items of a list from a start, to an end, by a step
+    Slice([Option<isize>; 3]),
+
+    /// The right expression applied to the result of the left one, for `a.b` and `a | b`
+    Chain(Box<Node>, Box<Node>),
+
+    /// The right expression applied to every item of the list on the left
+    Projection(Box<Node>, Box<Node>),
+
+    /// The right expression applied to every value of the object on the left
+    ValueProjection(Box<Node>, Box<Node>),
+
+    /// The right expression applied to the items on the left matching a condition
+    FilterProjection(Box<Node>, Box<Node>, Box<Node>),
+
+    /// A list with the lists in it flattened
+    Flatten(Box<Node>),
+
+    /// The left value if it's truthy and the right one otherwise
+    Or(Box<Node>, Box<Node>),
+
+    /// The left value if it's falsy and the right one otherwise
+    And(Box<Node>, Box<Node>),
+
+    /// Whether the value is falsy
+    Not(Box<Node>),
+
+    /// A comparison
+    Compare(&'static str, Box<Node>, Box<Node>),
+
+    /// A list of the results of expressions
+    MultiList(Vec<Node>),
+
+    /// An object of the results of expressions
+    MultiHash(Vec<(String, Node)>),
+
+    /// A call of a function
+    Function(String, Vec<Node>),
+
+    /// An expression passed to a function to be evaluated by it, `&expression`
+    Reference(Box<Node>),
+}
+
+/// Parses tokens into nodes with top down operator precedence
+struct Parser {
+    /// The tokens with their offsets
+    tokens: Vec<(Token, usize)>,
+
+    /// The index of the next token
+    position: usize,
+}
+
+impl Parser {
+    /// Returns a token ahead without consuming it
+    fn peek(&self, ahead: usize) -> &Token {
+        let index = (self.position + ahead).min(self.tokens.len() - 1);
+        &self.tokens[index].0
+    }
+
+    /// Returns whether the next token is a symbol
+    fn is(&self, symbol: &str) -> bool {
+        matches!(self.peek(0), Token::Symbol(next) if *next == symbol)
+    }
+
+    /// Returns a syntax error at the next token
+    fn error(&self) -> JmesPathError {
+        JmesPathError::Syntax(self.tokens[self.position.min(self.tokens.len() - 1)].1)
+    }
+
+    /// Consumes the next token
+    fn advance(&mut self) -> Token {
+        let token = self.peek(0).clone();
+        self.position = (self.position + 1).min(self.tokens.len());
+        token
+    }
+
+    /// Consumes the symbol, failing if it isn't next
+    fn expect(&mut self, symbol: &str) -> Result<(), JmesPathError> {
+        match self.is(symbol) {
+            true => {
+                self.advance();
+                Ok(())
+            }
+            false => Err(self.error()),
+        }
+    }
+
+    /// Parses an expression, until a token that binds less strongly than the given power
+    fn expression(&mut self, power: u8) -> Result<Node, JmesPathError> {
+        let mut left = self.prefix()?;
+        while power < binding_power(self.peek(0)) {
+            left = self.infix(left)?;
+        }
+        Ok(left)
+    }
+
+    /// Parses an expression starting with the next token
+    fn prefix(&mut self) -> Result<Node, JmesPathError> {
+        let error = self.error();
+        Ok(match self.advance() {
+            Token::Literal(value) => Node::Literal(value),
+            Token::Name(name) => Node::Field(name),
+            Token::QuotedName(_) if self.is("(") => return Err(self.error()),
+            Token::QuotedName(name) => Node::Field(name),
+            Token::Symbol("@") => Node::Current,
+            Token::Symbol("&") => Node::Reference(Box::new(self.expression(0)?)),
+            Token::Symbol("!") => Node::Not(Box::new(self.expression(45)?)),
+            Token::Symbol("(") => {
+                let expression = self.expression(0)?;
+                self.expect(")")?;
+                expression
+            }
+            Token::Symbol("{") => self.multi_hash()?,
+            Token::Symbol("*") => {
+                let right = match self.is("]") {
+                    true => Node::Current,
+                    false => self.projection_rhs(20)?,
+                };
+                Node::ValueProjection(Box::new(Node::Current), Box::new(right))
+            }
+            Token::Symbol("[?") => self.filter(Node::Current)?,
+            Token::Symbol("[]") => Node::Projection(
+                Box::new(Node::Flatten(Box::new(Node::Current))),
+                Box::new(self.projection_rhs(9)?),
+            ),
+            Token::Symbol("[") => match (self.peek(0), self.peek(1)) {
+                (Token::Number(_) | Token::Symbol(":"), _) => {
+                    let index = self.index()?;
+                    self.project_if_slice(Node::Current, index)?
+                }
+                (Token::Symbol("*"), Token::Symbol("]")) => {
+                    self.advance();
+                    self.advance();
+                    Node::Projection(Box::new(Node::Current), Box::new(self.projection_rhs(20)?))
+                }
+                _ => self.multi_list()?,
+            },
+            _ => return Err(error),
+        })
+    }
+
+    /// Parses the rest of an expression continuing the left one with the next token
+    fn infix(&mut self, left: Node) -> Result<Node, JmesPathError> {
+        let left = Box::new(left);
+        Ok(match self.advance() {
+            Token::Symbol(".") if self.is("*") => {
+                self.advance();
+                Node::ValueProjection(left, Box::new(self.projection_rhs(40)?))
+            }
+            Token::Symbol(".") => Node::Chain(left, Box::new(self.dot_rhs(40)?)),
+            Token::Symbol("|") => Node::Chain(left, Box::new(self.expression(1)?)),
+            Token::Symbol("||") => Node::Or(left, Box::new(self.expression(2)?)),
+            Token::Symbol("&&") => Node::And(left, Box::new(self.expression(3)?)),
+            Token::Symbol(symbol @ ("==" | "!=" | "<" | "<=" | ">" | ">=")) => {
+                Node::Compare(symbol, left, Box::new(self.expression(5)?))
+            }
+            Token::Symbol("(") => {
+                let Node::Field(name) = *left else {
+                    return Err(self.error());
+                };
+                let mut arguments = Vec::new();
+                while !self.is(")") {
+                    arguments.push(self.expression(0)?);
+                    if !self.is(")") {
+                        self.expect(",")?;
+                    }
+                }
+                self.advance();
+                Node::Function(name, arguments)
+            }
+            Token::Symbol("[?") => self.filter(*left)?,
+            Token::Symbol("[]") => Node::Projection(
+                Box::new(Node::Flatten(left)),
+                Box::new(self.projection_rhs(9)?),
+            ),
+            Token::Symbol("[") if matches!(self.peek(0), Token::Number(_) | Token::Symbol(":")) => {
+                let index = self.index()?;
+                self.project_if_slice(*left, index)?
+            }
+            Token::Symbol("[") => {
+                self.expect("*")?;
+                self.expect("]")?;
+                Node::Projection(left, Box::new(self.projection_rhs(20)?))
+            }
+            _ => return Err(self.error()),
+        })
+    }
+
+    /// Parses what's applied to every item of a projection, stopping at weakly binding tokens
+    fn projection_rhs(&mut self, power: u8) -> Result<Node, JmesPathError> {
+        if binding_power(self.peek(0)) < 10 {
+            Ok(Node::Current)
+        } else if self.is("[") || self.is("[?") {
+            self.expression(power)
+        } else if self.is(".") {
+            self.advance();
+            self.dot_rhs(power)
+        } else {
+            Err(self.error())
+        }
+    }
+
+    /// Parses what follows a dot
+    fn dot_rhs(&mut self, power: u8) -> Result<Node, JmesPathError> {
+        match self.peek(0) {
+            Token::Name(_) | Token::QuotedName(_) | Token::Symbol("*") => self.expression(power),
+            Token::Symbol("[") => {
+                self.advance();
+                self.multi_list()
+            }
+            Token::Symbol("{") => {
+                self.advance();
+                self.multi_hash()
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    /// Parses a filter after the `[?`, applied to the left expression
+    fn filter(&mut self, left: Node) -> Result<Node, JmesPathError> {
+        let condition = self.expression(0)?;
+        self.expect("]")?;
+        let right = match self.is("[]") {
+            true => Node::Current,
+            false => self.projection_rhs(21)?,
+        };
+        Ok(Node::FilterProjection(
+            Box::new(left),
+            Box::new(right),
+            Box::new(condition),
+        ))
+    }
+
+    /// Parses an index or slice after the `[`
+    fn index(&mut self) -> Result<Node, JmesPathError> {
+        // Read an index
+        if let (Token::Number(index), Token::Symbol("]")) = (self.peek(0), self.peek(1)) {
+            let index = *index;
+            self.advance();
+            self.advance();
+            return Ok(Node::Index(index));
+        }
+
+        // Read the parts of a slice separated by colons
+        let mut parts = [None; 3];
+        let mut part = 0;
+        loop {
+            match self.advance() {
+                Token::Symbol("]") => break,
+                Token::Symbol(":") if part < 2 => part += 1,
+                Token::Number(number) if parts[part].is_none() => parts[part] = Some(number),
+                _ => return Err(self.error()),
+            }
+        }
+        match parts[2] {
+            Some(0) => Err(self.error()),
+            _ => Ok(Node::Slice(parts)),
+        }
+    }
+
+    /// Indexes the left expression, projecting the rest of the expression over slices
+    fn project_if_slice(&mut self, left: Node, index: Node) -> Result<Node, JmesPathError> {
+        let slice = matches!(index, Node::Slice(_));
+        let indexed = Node::Chain(Box::new(left), Box::new(index));
+        Ok(match slice {
+            true => Node::Projection(Box::new(indexed), Box::new(self.projection_rhs(20)?)),
+            false => indexed,
+        })
+    }
+
+    /// Parses a list of expressions after the `[`
+    fn multi_list(&mut self) -> Result<Node, JmesPathError> {
+        let mut expressions = Vec::new();
+        loop {
+            expressions.push(self.expression(0)?);
+            match self.advance() {
+                Token::Symbol(",") => {}
+                Token::Symbol("]") => return Ok(Node::MultiList(expressions)),
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    /// Parses an object of named expressions after the `{`
+    fn multi_hash(&mut self) -> Result<Node, JmesPathError> {
+        let mut items = Vec::new();
+        loop {
+            let (Token::Name(key) | Token::QuotedName(key)) = self.advance() else {
+                return Err(self.error());
+            };
+            self.expect(":")?;
+            items.push((key, self.expression(0)?));
+            match self.advance() {
+                Token::Symbol(",") => {}
+                Token::Symbol("}") => return Ok(Node::MultiHash(items)),
+                _ => return Err(self.error()),
+            }
+        }
+    }
+}
+
+/// Returns whether a value counts as true, which is anything but `false`, `null` and empty
+/// strings, lists and objects
+fn truthy(value: &Json) -> bool {
+    match value {
+        Json::Bool(boolean) => *boolean,
+        Json::Null => false,
+        Json::String(string) => !string.is_empty(),
+        Json::List(values) => !values.is_empty(),
+        Json::Object(items) => !items.is_empty(),
+        Json::Number(_) | Json::NumberRaw(_) => true,
+    }
+}
+
+/// Resolves an index counting from the end if it's negative, clamping it to a range
+fn resolve(index: isize, length: usize, min: isize, max: isize) -> isize {
+    let index = match index {
+        ..0 => index + length as isize,
+        _ => index,
+    };
+    index.clamp(min, max)
+}
+
+/// Selects items of a list like slices in Python
+fn slice(values: &[Json], [start, stop, step]: [Option<isize>; 3]) -> Vec<Json> {
+    let length = values.len() as isize;
+    let step = step.unwrap_or(1);
+    let (mut index, stop) = match step {
+        1.. => (
+            start.map_or(0, |start| resolve(start, values.len(), 0, length)),
+            stop.map_or(length, |stop| resolve(stop, values.len(), 0, length)),
+        ),
+        _ => (
+            start.map_or(length - 1, |start| {
+                resolve(start, values.len(), -1, length - 1)
+            }),
+            stop.map_or(-1, |stop| resolve(stop, values.len(), -1, length - 1)),
+        ),
+    };
+    let mut out = Vec::new();
+    while (step > 0 && index < stop) || (step < 0 && index > stop) {
+        out.push(values[index as usize].clone());
+
+        // Steps beyond the end of the range can overflow
+        match index.checked_add(step) {
+            Some(next) => index = next,
+            None => break,
+        }
+    }
+    out
+}
+
+/// Returns whether two values are equal, comparing numbers by value
+fn equal(a: &Json, b: &Json) -> bool {
+    match (a, b) {
+        (Json::List(a), Json::List(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equal(a, b))
+        }
+        (Json::Object(a), Json::Object(b)) => {
+            a.len() == b.len()
+                && a.iter().all(|(key, a)| {
+                    b.iter()
+                        .rev()
+                        .find(|(name, _)| name == key)
+                        .is_some_and(|(_, b)| equal(a, b))
+                })
+        }
+        _ => *a.plain() == *b.plain(),
+    }
+}
+
+/// Orders two numbers or two strings, returning `None` for anything else
+fn order(a: &Json, b: &Json) -> Option<Ordering> {
+    match (&*a.plain(), &*b.plain()) {
+        (Json::Number(a), Json::Number(b)) => a.partial_cmp(b),
+        (Json::String(a), Json::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// Rounds a number down, without the standard library
+fn floor(number: f64) -> f64 {
+    // Numbers this large are already whole
+    if number.abs() >= 4_503_599_627_370_496.0 || !number.is_finite() {
+        return number;
+    }
+    let truncated = number as i64 as f64;
+    match truncated > number {
+        true => truncated - 1.0,
+        false => truncated,
+    }
+}
+
+/// A function argument, which is either a value or an expression reference
+enum Argument<'a> {
+    /// An evaluated argument
+    Value(Json),
+
+    /// An expression reference, evaluated by the function
+    Reference(&'a Node),
+}
+
+/// Sorts values by keys which all have to be numbers or all strings, returning `None` otherwise
+fn sort_by_keys(values: Vec<Json>, keys: Vec<Json>) -> Option<Vec<Json>> {
+    let same_type = keys
+        .windows(2)
+        .all(|pair| order(&pair[0], &pair[1]).is_some());
+    if !same_type || keys.first().is_some_and(|key| order(key, key).is_none()) {
+        return None;
+    }
+    let mut pairs = keys.into_iter().zip(values).collect::<Vec<_>>();
+    pairs.sort_by(|(a, _), (b, _)| order(a, b).unwrap_or(Ordering::Equal));
+    Some(pairs.into_iter().map(|(_, value)| value).collect())
+}
+
+/// Calls a built-in function
+fn call(name: &str, arguments: &[Node], current: &Json) -> Result<Json, JmesPathError> {
+    // Evaluate the arguments that aren't expression references
+    let arguments = arguments
+        .iter()
+        .map(|argument| match argument {
+            Node::Reference(node) => Ok(Argument::Reference(node)),
+            node => {
+                evaluate(node, current).map(|value| Argument::Value(value.plain().into_owned()))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let invalid = || JmesPathError::InvalidArguments(name.to_owned());
+
+    // Returns the numbers in a list, failing if there's anything else
+    let numbers = |values: &[Json]| {
+        values
+            .iter()
+            .map(|value| match *value.plain() {
+                Json::Number(number) => Ok(number),
+                _ => Err(invalid()),
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    // Evaluates an expression reference for every item of a list
+    let keys = |node: &Node, values: &[Json]| {
+        values
+            .iter()
+            .map(|value| evaluate(node, value))
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    use Argument::{Reference, Value};
+    Ok(match (name, arguments.as_slice()) {
+        // Numbers
+        ("abs", [Value(Json::Number(number))]) => Json::Number(number.abs()),
+        ("ceil", [Value(Json::Number(number))]) => Json::Number(-floor(-number)),
+        ("floor", [Value(Json::Number(number))]) => Json::Number(floor(*number)),
+        ("sum", [Value(Json::List(values))]) => Json::Number(numbers(values)?.iter().sum()),
+        ("avg", [Value(Json::List(values))]) => match numbers(values)? {
+            numbers if numbers.is_empty() => Json::Null,
+            numbers => Json::Number(numbers.iter().sum::<f64>() / numbers.len() as f64),
+        },
+
+        // Strings
+        ("starts_with", [Value(Json::String(string)), Value(Json::String(prefix))]) => {
+            Json::Bool(string.starts_with(prefix.as_str()))
+        }
+        ("ends_with", [Value(Json::String(string)), Value(Json::String(suffix))]) => {
+            Json::Bool(string.ends_with(suffix.as_str()))
+        }
+        ("join", [Value(Json::String(glue)), Value(Json::List(values))]) => {
+            let strings = values
+                .iter()
+                .map(|value| match value {
+                    Json::String(string) => Ok(string.as_str()),
+                    _ => Err(invalid()),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Json::String(strings.join(glue))
+        }
+
+        // Lists, objects and strings
+        ("length", [Value(Json::String(string))]) => Json::Number(string.chars().count() as f64),
+        ("length", [Value(Json::List(values))]) => Json::Number(values.len() as f64),
+        ("length", [Value(Json::Object(items))]) => Json::Number(items.len() as f64),
+        ("contains", [Value(Json::List(values)), Value(search)]) => {
+            Json::Bool(values.iter().any(|value| equal(value, search)))
+        }
+        ("contains", [Value(Json::String(string)), Value(Json::String(search))]) => {
+            Json::Bool(string.contains(search.as_str()))
+        }
+        ("reverse", [Value(Json::List(values))]) => {
+            Json::List(values.iter().rev().cloned().collect())
+        }
+        ("reverse", [Value(Json::String(string))]) => Json::String(string.chars().rev().collect()),
+        ("keys", [Value(Json::Object(items))]) => Json::List(
+            items
+                .iter()
+                .map(|(key, _)| Json::String(key.clone()))
+                .collect(),
+        ),
+        ("values", [Value(Json::Object(items))]) => {
+            Json::List(items.iter().map(|(_, value)| value.clone()).collect())
+        }
+        ("merge", objects) => {
+            let mut merged = Vec::<(String, Json)>::new();
+            for object in objects {
+                let Value(Json::Object(items)) = object else {
+                    return Err(invalid());
+                };
+                for (key, value) in items {
+                    merged.retain(|(name, _)| name != key);
+                    merged.push((key.clone(), value.clone()));
+                }
+            }
+            Json::Object(merged)
+        }
+
+        // Sorting and extremes
+        ("sort", [Value(Json::List(values))]) => {
+            Json::List(sort_by_keys(values.clone(), values.clone()).ok_or_else(invalid)?)
+        }
+        ("sort_by", [Value(Json::List(values)), Reference(node)]) => {
+            let keys = keys(node, values)?;
+            Json::List(sort_by_keys(values.clone(), keys).ok_or_else(invalid)?)
+        }
+        ("max" | "min", [Value(Json::List(values))]) => {
+            let sorted = sort_by_keys(values.clone(), values.clone()).ok_or_else(invalid)?;
+            let extreme = if name == "max" {
+                sorted.last()
+            } else {
+                sorted.first()
+            };
+            extreme.cloned().unwrap_or(Json::Null)
+        }
+        ("max_by" | "min_by", [Value(Json::List(values)), Reference(node)]) => {
+            let keys = keys(node, values)?;
+            let sorted = sort_by_keys(values.clone(), keys).ok_or_else(invalid)?;
+            let extreme = if name == "max_by" {
+                sorted.last()
+            } else {
+                sorted.first()
+            };
+            extreme.cloned().unwrap_or(Json::Null)
+        }
+
+        // Expressions over lists
+        ("map", [Reference(node), Value(Json::List(values))]) => Json::List(keys(node, values)?),
+
+        // Types and conversions
+        ("type", [Value(value)]) => Json::String(
+            match value {
+                Json::Number(_) | Json::NumberRaw(_) => "number",
+                Json::String(_) => "string",
+                Json::Bool(_) => "boolean",
+                Json::List(_) => "array",
+                Json::Object(_) => "object",
+                Json::Null => "null",
+            }
+            .to_owned(),
+        ),
+        ("not_null", values) if !values.is_empty() => values
+            .iter()
+            .find_map(|value| match value {
+                Value(Json::Null) | Reference(_) => None,
+                Value(value) => Some(value.clone()),
+            })
+            .unwrap_or(Json::Null),
+        ("to_array", [Value(value @ Json::List(_))]) => value.clone(),
+        ("to_array", [Value(value)]) => Json::List(Vec::from([value.clone()])),
+        ("to_string", [Value(Json::String(string))]) => Json::String(string.clone()),
+        ("to_string", [Value(value)]) => Json::String(value.to_string()),
+        ("to_number", [Value(Json::Number(number))]) => Json::Number(*number),
+        ("to_number", [Value(Json::String(string))]) => string
+            .parse::<Json>()
+            .ok()
+            .map(|number| number.plain().into_owned())
+            .filter(|number| matches!(number, Json::Number(_)))
+            .unwrap_or(Json::Null),
+        ("to_number", [Value(_)]) => Json::Null,
+
+        // Known functions with the wrong arguments, and unknown functions
+        (
+            "abs" | "avg" | "ceil" | "contains" | "ends_with" | "floor" | "join" | "keys"
+            | "length" | "map" | "max" | "max_by" | "min" | "min_by" | "not_null" | "reverse"
+            | "sort" | "sort_by" | "starts_with" | "sum" | "to_array" | "to_number" | "to_string"
+            | "type" | "values",
+            _,
+        ) => return Err(invalid()),
+        _ => return Err(JmesPathError::UnknownFunction(name.to_owned())),
+    })
+}
+
+/// Evaluates a node against the current value
+fn evaluate(node: &Node, current: &Json) -> Result<Json, JmesPathError> {
+    // Evaluates the right node for every value, leaving out null results
+    let project = |values: &mut dyn Iterator<Item = &Json>, right: &Node| {
+        let mut out = Vec::new();
+        for value in values {
+            match evaluate(right, value)? {
+                Json::Null => {}
+                result => out.push(result),
+            }
+        }
+        Ok(Json::List(out))
+    };
+
+    match node {
+        Node::Current => Ok(current.clone()),
+        Node::Literal(value) => Ok(value.clone()),
+        Node::Field(name) => Ok(match current {
+            Json::Object(items) => items
+                .iter()
+                .rev()
+                .find(|(key, _)| key == name)
+                .map_or(Json::Null, |(_, value)| value.clone()),
+            _ => Json::Null,
+        }),
+        Node::Index(index) => Ok(match current {
+            Json::List(values) => {
+                let index = resolve(*index, values.len(), -1, values.len() as isize);
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|index| values.get(index))
+                    .cloned()
+                    .unwrap_or(Json::Null)
+            }
+            _ => Json::Null,
+        }),
+        Node::Slice(parts) => Ok(match current {
+            Json::List(values) => Json::List(slice(values, *parts)),
+            _ => Json::Null,
+        }),
+        Node::Chain(left, right) => evaluate(right, &evaluate(left, current)?),
+        Node::Projection(left, right) => match evaluate(left, current)? {
+            Json::List(values) => project(&mut values.iter(), right),
+            _ => Ok(Json::Null),
+        },
+        Node::ValueProjection(left, right) => match evaluate(left, current)? {
+            Json::Object(items) => project(&mut items.iter().map(|(_, value)| value), right),
+            _ => Ok(Json::Null),
+        },
+        Node::FilterProjection(left, right, condition) => match evaluate(left, current)? {
+            Json::List(values) => {
+                let mut matching = Vec::new();
+                for value in &values {
+                    if truthy(&evaluate(condition, value)?) {
+                        matching.push(value);
+                    }
+                }
+                project(&mut matching.into_iter(), right)
+            }
+            _ => Ok(Json::Null),
+        },
+        Node::Flatten(node) => Ok(match evaluate(node, current)? {
+            Json::List(values) => Json::List(
+                values
+                    .into_iter()
+                    .flat_map(|value| match value {
+                        Json::List(values) => values,
+                        value => Vec::from([value]),
+                    })
+                    .collect(),
+            ),
+            _ => Json::Null,
+        }),
+        Node::Or(left, right) => match evaluate(left, current)? {
+            left if truthy(&left) => Ok(left),
+            _ => evaluate(right, current),
+        },
+        Node::And(left, right) => match evaluate(left, current)? {
+            left if !truthy(&left) => Ok(left),
+            _ => evaluate(right, current),
+        },
+        Node::Not(node) => Ok(Json::Bool(!truthy(&evaluate(node, current)?))),
+        Node::Compare(operator, left, right) => {
+            let (left, right) = (evaluate(left, current)?, evaluate(right, current)?);
+            Ok(match (*operator, order(&left, &right)) {
+                ("==", _) => Json::Bool(equal(&left, &right)),
+                ("!=", _) => Json::Bool(!equal(&left, &right)),
+                // Only numbers can be ordered
+                (_, Some(ordering)) if matches!(*left.plain(), Json::Number(_)) => {
+                    Json::Bool(match *operator {
+                        "<" => ordering.is_lt(),
+                        "<=" => ordering.is_le(),
+                        ">" => ordering.is_gt(),
+                        _ => ordering.is_ge(),
+                    })
+                }
+                _ => Json::Null,
+            })
+        }
+        Node::MultiList(nodes) => match current {
+            Json::Null => Ok(Json::Null),
+            _ => nodes
+                .iter()
+                .map(|node| evaluate(node, current))
+                .collect::<Result<_, _>>()
+                .map(Json::List),
+        },
+        Node::MultiHash(items) => match current {
+            Json::Null => Ok(Json::Null),
+            _ => items
+                .iter()
+                .map(|(key, node)| Ok((key.clone(), evaluate(node, current)?)))
+                .collect::<Result<_, _>>()
+                .map(Json::Object),
+        },
+        Node::Function(name, arguments) => call(name, arguments, current),
+        // Expression references only mean something as function arguments
+        Node::Reference(_) => Ok(Json::Null),
+    }
+}
+
+/// A parsed JMESPath expression, for searching many documents
+#[derive(Debug, Clone, PartialEq)]
+pub struct JmesPath {
+    /// The root of the expression
+    root: Node,
+}
+
+impl JmesPath {
+    /// Evaluates the expression against a document, returning `null` if nothing matches
+    pub fn search(&self, document: &Json) -> Result<Json, JmesPathError> {
+        evaluate(&self.root, document)
+    }
+}
+
+impl FromStr for JmesPath {
+    type Err = JmesPathError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+        };
+        let root = parser.expression(0)?;
+        match parser.peek(0) {
+            Token::End => Ok(JmesPath { root }),
+            _ => Err(parser.error()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JmesPath, JmesPathError};
+    use crate::Json;
+
+    /// Evaluates an expression against a document, both given as text
+    fn search(expression: &str, document: &str) -> Result<Json, JmesPathError> {
+        expression
+            .parse::<JmesPath>()?
+            .search(&document.parse().unwrap())
+    }
+
+    #[test]
+    fn expressions() {
+        let people = "{\"people\":[{\"name\":\"a\",\"age\":30,\"tags\":[\"x\",\"y\"]},\
+            {\"name\":\"b\",\"age\":20,\"tags\":[\"z\"]},{\"name\":\"c\"}],\
+            \"ops\":{\"f\":{\"n\":1},\"g\":{\"n\":2}}}";
+        for (expression, expected) in [
+            ("people[0].name", "\"a\""),
+            ("people[-1].name", "\"c\""),
+            ("people[*].age", "[30,20]"),
+            ("people[1:].name", "[\"b\",\"c\"]"),
+            ("people[::-1].name", "[\"c\",\"b\",\"a\"]"),
+            ("people[].tags[]", "[\"x\",\"y\",\"z\"]"),
+            ("people[?age > `25`].name", "[\"a\"]"),
+            ("people[?name == 'b'] | [0].age", "20"),
+            ("people[?!age].name", "[\"c\"]"),
+            ("ops.*.n", "[1,2]"),
+            ("people[0].[name, age]", "[\"a\",30]"),
+            (
+                "people[1].{n: name, \"first tag\": tags[0]}",
+                "{\"n\":\"b\",\"first tag\":\"z\"}",
+            ),
+            ("missing || people[2].name", "\"c\""),
+            ("people[0].age && people[1].age", "20"),
+            ("missing.deeper[0]", "null"),
+            ("`[1, 2]`", "[1,2]"),
+        ] {
+            assert_eq!(
+                search(expression, people),
+                Ok(expected.parse().unwrap()),
+                "{expression}"
+            );
+        }
+    }
+
+    #[test]
+    fn extreme_slices() {
+        let list = "[1,2,3,4,5]";
+        for (expression, expected) in [
+            ("[1:10:9223372036854775807]", "[2]"),
+            ("[::9223372036854775807]", "[1]"),
+            ("[::-9223372036854775807]", "[5]"),
+            ("[::-9223372036854775808]", "[5]"),
+            ("[-9223372036854775808:9223372036854775807:2]", "[1,3,5]"),
+            ("[9223372036854775807:-9223372036854775808:-2]", "[5,3,1]"),
+        ] {
+            assert_eq!(
+                search(expression, list),
+                Ok(expected.parse().unwrap()),
+                "{expression}"
+            );
+        }
+    }
+
+    #[test]
+    fn functions() {
+        let data = "{\"numbers\":[3,1.5,-2],\"words\":[\"pear\",\"fig\"],\
+            \"items\":[{\"n\":\"x\",\"p\":2},{\"n\":\"y\",\"p\":1}]}";
+        for (expression, expected) in [
+            ("length(words)", "2"),
+            ("sum(numbers)", "2.5"),
+            ("max(numbers)", "3"),
+            ("avg(`[]`)", "null"),
+            ("floor(numbers[1])", "1"),
+            ("ceil(numbers[1])", "2"),
+            ("abs(numbers[2])", "2"),
+            ("sort(words)", "[\"fig\",\"pear\"]"),
+            ("sort_by(items, &p)[*].n", "[\"y\",\"x\"]"),
+            ("max_by(items, &p).n", "\"x\""),
+            ("map(&p, items)", "[2,1]"),
+            ("join(', ', words)", "\"pear, fig\""),
+            ("contains(words, 'fig')", "true"),
+            ("starts_with(words[0], 'pe')", "true"),
+            ("keys(items[0])", "[\"n\",\"p\"]"),
+            ("merge(items[0], `{\"p\": 5}`)", "{\"n\":\"x\",\"p\":5}"),
+            ("not_null(missing, words[1])", "\"fig\""),
+            ("type(items)", "\"array\""),
+            ("to_string(numbers[0])", "\"3\""),
+            ("to_number('4.5')", "4.5"),
+            ("reverse(words)", "[\"fig\",\"pear\"]"),
+        ] {
+            assert_eq!(
+                search(expression, data),
+                Ok(expected.parse().unwrap()),
+                "{expression}"
+            );
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(search("a.", "{}"), Err(JmesPathError::Syntax(2)));
+        assert_eq!(search("a[?b", "{}"), Err(JmesPathError::Syntax(4)));
+        assert_eq!(search("'open", "{}"), Err(JmesPathError::Syntax(0)));
+        assert_eq!(
+            search("shout(a)", "{}"),
+            Err(JmesPathError::UnknownFunction("shout".into()))
+        );
+        assert_eq!(
+            search("length(`1`)", "{}"),
+            Err(JmesPathError::InvalidArguments("length".into()))
+        );
+    }
+}
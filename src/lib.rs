@@ -1,15 +1,180 @@
 #![no_std]
 
+#[cfg(feature = "alloc")]
 extern crate alloc;
-
-use alloc::{fmt, string::String, vec::Vec};
-use core::{
-    fmt::{Display, Formatter},
-    iter::Peekable,
-    str::FromStr,
-};
-
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(any(feature = "base64", feature = "bson"))]
+mod base64;
+#[cfg(feature = "bson")]
+mod bson;
+#[cfg(feature = "alloc")]
+mod canonical;
+#[cfg(feature = "cbor")]
+mod cbor;
+#[cfg(feature = "alloc")]
+mod changes;
+#[cfg(feature = "alloc")]
+mod cst;
+#[cfg(feature = "csv")]
+mod csv;
+#[cfg(feature = "alloc")]
+mod cursor;
+#[cfg(feature = "chrono")]
+mod datetime;
+#[cfg(feature = "bigdecimal")]
+mod decimal;
+#[cfg(feature = "defmt")]
+mod defmt;
+#[cfg(feature = "alloc")]
+mod diff;
+#[cfg(feature = "embedded-io")]
+mod embedded_io;
+#[cfg(feature = "alloc")]
+mod escape;
+#[cfg(feature = "alloc")]
+mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "form")]
+mod form;
+#[cfg(feature = "alloc")]
+mod get;
+#[cfg(feature = "alloc")]
+mod history;
+#[cfg(feature = "alloc")]
+mod hooks;
+#[cfg(feature = "std")]
+mod io;
+#[cfg(feature = "jmespath")]
+mod jmespath;
+#[cfg(feature = "alloc")]
+mod memory;
+#[cfg(feature = "alloc")]
+mod metrics;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+#[cfg(feature = "alloc")]
+mod ndjson;
+#[cfg(feature = "alloc")]
+mod parser;
+#[cfg(feature = "alloc")]
+mod patch;
+#[cfg(feature = "alloc")]
+mod path;
+#[cfg(feature = "alloc")]
+mod pointer;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "alloc")]
+mod query;
+#[cfg(feature = "alloc")]
+mod repair;
+mod scan;
+#[cfg(feature = "alloc")]
+mod schema;
+#[cfg(feature = "alloc")]
+mod seq;
+#[cfg(feature = "alloc")]
+mod ser;
+#[cfg(feature = "serde_json")]
+mod serde_json;
+#[cfg(feature = "shared")]
+mod shared;
+#[cfg(feature = "alloc")]
+mod sort;
+#[cfg(feature = "alloc")]
+mod source;
+#[cfg(feature = "alloc")]
+mod spanned;
+#[cfg(feature = "alloc")]
+mod stream;
+#[cfg(feature = "alloc")]
+mod template;
+mod token;
+#[cfg(feature = "toml")]
+mod toml;
+#[cfg(feature = "uuid")]
+mod uuid;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "alloc")]
+mod writer;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, boxed::Box, fmt, string::String, vec::Vec};
+use core::fmt::Display;
+#[cfg(feature = "alloc")]
+use core::{fmt::Formatter, str::FromStr};
+
+#[cfg(feature = "alloc")]
 use itertools::{Itertools as _, PeekingNext};
+#[cfg(feature = "alloc")]
+use parser::{Items, Owned, ParseState, Storage};
+#[cfg(feature = "alloc")]
+use source::{Limited, SourceChars};
+
+#[cfg(feature = "cbor")]
+pub use cbor::CborEncoder;
+#[cfg(feature = "alloc")]
+pub use changes::ChangeTracker;
+#[cfg(feature = "alloc")]
+pub use cst::Cst;
+#[cfg(feature = "csv")]
+pub use csv::CsvOptions;
+#[cfg(feature = "alloc")]
+pub use cursor::Cursor;
+#[cfg(feature = "embedded-io")]
+pub use embedded_io::ReadError;
+#[cfg(feature = "alloc")]
+pub use escape::{escape_json_string, unescape_json_string};
+#[cfg(feature = "alloc")]
+pub use events::{Event, EventSink, Events, TranscodeError};
+#[cfg(feature = "alloc")]
+pub use get::{FromJson, GetError};
+#[cfg(feature = "alloc")]
+pub use history::{Checkpoint, History};
+#[cfg(feature = "alloc")]
+pub use hooks::ParseHooks;
+#[cfg(feature = "jmespath")]
+pub use jmespath::{JmesPath, JmesPathError};
+#[cfg(feature = "alloc")]
+pub use metrics::Metrics;
+#[cfg(feature = "alloc")]
+pub use ndjson::JsonLines;
+#[cfg(feature = "alloc")]
+pub use parser::{Capacity, Parser};
+#[cfg(feature = "alloc")]
+pub use patch::{PatchError, PatchOperation};
+#[cfg(feature = "alloc")]
+pub use path::{Path, Segment};
+#[cfg(feature = "alloc")]
+pub use query::{Query, QueryError, StreamMatches};
+#[cfg(feature = "alloc")]
+pub use repair::{Repair, RepairKind};
+pub use scan::{Boundary, Scanner, ValueKind, validate, validate_bytes, validate_slice};
+#[cfg(feature = "alloc")]
+pub use schema::Violation;
+#[cfg(feature = "alloc")]
+pub use seq::JsonSeq;
+#[cfg(feature = "alloc")]
+pub use ser::{Format, Formatted, Indent, LineEnding, NumberFormat, RawJson};
+#[cfg(feature = "shared")]
+pub use shared::{KeyInterner, SharedJson};
+#[cfg(feature = "std")]
+pub use source::ReaderSource;
+#[cfg(feature = "alloc")]
+pub use source::{IterSource, SliceSource, Source, StrSource};
+#[cfg(feature = "alloc")]
+pub use spanned::{Spanned, SpannedJson, SpannedProperty, Warning};
+#[cfg(feature = "alloc")]
+pub use stream::{JsonStream, JsonValues};
+pub use token::{Span, Token, TokenKind, Tokens};
+#[cfg(feature = "alloc")]
+pub use writer::{JsonWriter, WriterError};
 
 /// An error occured while trying to parse the json file
 #[derive(Debug)]
@@ -29,12 +194,60 @@ pub enum Error {
     /// The byte stream ended unexpectedly
     UnexpectedEndOfFile,
 
+    /// There's nothing but whitespace, so there's no value at all
+    EmptyInput,
+
     /// An object wasn't closed
     UnclosedObject,
+
+    /// There are characters left after the value
+    TrailingCharacters,
+
+    /// The values are nested deeper than allowed
+    NestingTooDeep,
+
+    /// An integer is too large to be held exactly, see [`IntegerOverflow::Error`]
+    IntegerOverflow,
+
+    /// A number is too large to be held as an `f64`, like `1e400`
+    NumberOutOfRange,
+
+    /// The input is longer than allowed, see [`ParseOptions::max_size`]
+    TooLarge,
+
+    /// A word was found that's likely a misspelling of the literal `true`, `false` or `null`
+    MisspelledLiteral(&'static str),
+
+    /// A [`ParseHooks`] callback stopped parsing, for the given reason
+    Aborted(&'static str),
+
+    /// An error occured at a specific position in the input
+    #[cfg(feature = "alloc")]
+    At {
+        /// The line on which the error occured, starting at 1
+        line: usize,
+
+        /// The column at which the error occured, starting at 1
+        column: usize,
+
+        /// The error that occured
+        error: Box<Error>,
+    },
+
+    /// An error occured in one of several values, see [`Json::iter_values`]
+    #[cfg(feature = "alloc")]
+    Document {
+        /// The index of the value, starting at 0
+        index: usize,
+
+        /// The error that occured
+        error: Box<Error>,
+    },
 }
 
+#[cfg(feature = "alloc")]
 /// A JSON value
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub enum Json {
     /// A list of data
     List(Vec<Json>),
@@ -48,185 +261,377 @@ pub enum Json {
     /// A number
     Number(f64),
 
+    /// A number kept as its source text, see [`ParseOptions::raw_numbers`]
+    NumberRaw(String),
+
     /// A boolean
     Bool(bool),
 
     /// A null value
+    #[default]
     Null,
 }
 
+/// Options for parsing JSON
+///
+/// The defaults limit the nesting to 128 levels and the input to 64 MiB, so crafted input can't
+/// overflow the stack or exhaust the memory. Use [`ParseOptions::unlimited`] for trusted input.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Whether numbers are kept as their source text, so they can be forwarded without rounding
+    pub raw_numbers: bool,
+
+    /// What happens to integers that are too large to be held exactly
+    pub integer_overflow: IntegerOverflow,
+
+    /// How deeply lists and objects may be nested, or `None` for no limit
+    pub max_depth: Option<usize>,
+
+    /// How many characters may be read, or `None` for no limit
+    pub max_size: Option<usize>,
+}
+
+#[cfg(feature = "alloc")]
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            raw_numbers: false,
+            integer_overflow: IntegerOverflow::default(),
+            max_depth: Some(MAX_DEPTH),
+            max_size: Some(64 << 20),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl ParseOptions {
+    /// Returns the default options without limits on the nesting and the size of the input
+    pub fn unlimited() -> Self {
+        Self {
+            max_depth: None,
+            max_size: None,
+            ..Self::default()
+        }
+    }
+}
+
+/// What happens to integers beyond ±(2^53 - 1), which numbers can't all hold exactly
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntegerOverflow {
+    /// Round them to the nearest number, like JavaScript does
+    #[default]
+    Round,
+
+    /// Fail with [`Error::IntegerOverflow`]
+    Error,
+
+    /// Replace them by the largest or smallest integer that's held exactly
+    Saturate,
+
+    /// Keep them as their source text in a [`Json::NumberRaw`]
+    Raw,
+}
+
+/// How deeply lists and objects may be nested by default, and in formats read without options
+#[cfg(feature = "alloc")]
+const MAX_DEPTH: usize = 128;
+
+/// The largest integer up to which all integers can be held exactly, 2^53 - 1
+#[cfg(feature = "alloc")]
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+#[cfg(feature = "alloc")]
 impl Json {
+    /// Creates an empty object
+    pub fn object() -> Self {
+        Json::Object(Vec::new())
+    }
+
+    /// Creates an empty list
+    pub fn array() -> Self {
+        Json::List(Vec::new())
+    }
+
+    /// Creates a number, or returns `None` for NaN and infinities, which JSON can't hold
+    pub fn number(number: f64) -> Option<Self> {
+        number.is_finite().then_some(Json::Number(number))
+    }
+
+    /// Creates a string value
+    pub fn string(text: impl Into<String>) -> Self {
+        Json::String(text.into())
+    }
+
+    /// Returns a raw number as a plain number and any other value as it is, for code that
+    /// only handles plain numbers
+    pub(crate) fn plain(&self) -> Cow<'_, Json> {
+        match self {
+            Json::NumberRaw(text) => Cow::Owned(Json::Number(text.parse().unwrap_or(f64::NAN))),
+            value => Cow::Borrowed(value),
+        }
+    }
+
     /// Tries to read a string value
-    fn read_string<I: PeekingNext<Item = char>>(mut iter: I) -> Result<String, Error> {
+    fn read_string<I: Iterator<Item = char>>(iter: I) -> Result<String, Error> {
+        let mut result = String::new();
+        Self::read_string_into(iter, &mut result)?;
+        Ok(result)
+    }
+
+    /// Tries to read a string, adding its characters to a buffer
+    fn read_string_into<I: Iterator<Item = char>>(
+        mut iter: I,
+        result: &mut String,
+    ) -> Result<(), Error> {
         // Make sure the value started with "
         if iter.next() != Some('"') {
             return Err(Error::InvalidValue);
         }
 
-        // Read the string
-        let mut escaped = false;
-        let result = iter
-            .peeking_take_while(|&c| {
-                let keep_reading = escaped || c != '"';
-                escaped = !escaped && c == '\\';
-                keep_reading
-            })
-            .collect();
-
-        // Make sure the string actually ended
-        if iter.next() != Some('"') || escaped {
-            return Err(Error::UnclosedString);
+        // Read the string up to the closing ", decoding escape sequences
+        loop {
+            match iter.next() {
+                Some('"') => return Ok(()),
+                Some('\\') => result.push(Self::read_escape(&mut iter)?),
+                Some('\0'..='\u{1f}') => return Err(Error::InvalidValue),
+                Some(ch) => result.push(ch),
+                None => return Err(Error::UnclosedString),
+            }
         }
+    }
 
-        Ok(result)
+    /// Tries to read a string into the scratch buffer, so it's stored once at its size
+    fn read_scratch_string<'s, S: Source>(
+        iter: &mut SourceChars<S>,
+        state: &'s mut ParseState<'_>,
+    ) -> Result<&'s str, Error> {
+        state.scratch.text.clear();
+        Self::read_string_into(iter, &mut state.scratch.text)?;
+        Ok(&state.scratch.text)
     }
 
-    /// Tries to read a boolean
-    fn read_bool<I: Iterator<Item = char>>(mut iter: I) -> Result<bool, Error> {
-        // Read the first character of the boolean
-        match iter.next() {
-            // If it's an f, make sure the value is false.
-            // Return an error otherwise.
-            Some('f') => {
-                if iter
-                    .zip("alse".chars())
-                    .filter(|(found, expected)| found == expected)
-                    .take(4)
-                    .count()
-                    == 4
-                {
-                    Ok(false)
-                } else {
-                    Err(Error::InvalidValue)
-                }
+    /// Tries to read the character of an escape sequence after its backslash
+    fn read_escape<I: Iterator<Item = char>>(mut iter: I) -> Result<char, Error> {
+        Ok(match iter.next() {
+            // Decode the single character escapes
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+
+            // Decode unicode escapes, combining surrogate pairs
+            Some('u') => {
+                let high = Self::read_hex(&mut iter)?;
+                let code = match high {
+                    0xd800..=0xdbff => {
+                        if iter.next() != Some('\\') || iter.next() != Some('u') {
+                            return Err(Error::InvalidValue);
+                        }
+                        match Self::read_hex(&mut iter)? {
+                            low @ 0xdc00..=0xdfff => {
+                                0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                            }
+                            _ => return Err(Error::InvalidValue),
+                        }
+                    }
+                    _ => high,
+                };
+                char::from_u32(code).ok_or(Error::InvalidValue)?
             }
 
-            // If the first character is a t, make sure the value is true.
-            // Return an error otherwise.
-            Some('t') => {
-                if iter
-                    .zip("rue".chars())
-                    .filter(|(found, expected)| found == expected)
-                    .take(3)
-                    .count()
-                    == 3
-                {
-                    Ok(true)
-                } else {
-                    Err(Error::InvalidValue)
-                }
-            }
+            // Return an error if the string ended or the escape is unknown
+            None => return Err(Error::UnclosedString),
+            Some(_) => return Err(Error::InvalidValue),
+        })
+    }
 
-            // Return an error if the value isn't a boolean
-            None | Some(_) => Err(Error::InvalidValue),
+    /// Tries to read the four hexadecimal digits of a unicode escape
+    fn read_hex<I: Iterator<Item = char>>(iter: I) -> Result<u32, Error> {
+        let mut digits = 0;
+        let code = iter.take(4).try_fold(0, |code, ch| {
+            digits += 1;
+            ch.to_digit(16).map(|digit| (code << 4) | digit)
+        });
+        match code {
+            Some(code) if digits == 4 => Ok(code),
+            Some(_) => Err(Error::UnclosedString),
+            None => Err(Error::InvalidValue),
         }
     }
 
-    /// Tries to read a null value
-    fn read_null<I: Iterator<Item = char>>(iter: I) -> Result<(), Error> {
-        // Make sure the value is null, return an error otherwise.
-        if iter
-            .zip("null".chars())
-            .filter(|(found, expected)| found == expected)
-            .take(4)
-            .count()
-            == 4
-        {
-            Ok(())
-        } else {
-            Err(Error::InvalidValue)
+    /// Tries to read `true`, `false` or `null`, returning `None` for null
+    ///
+    /// Other words are an error naming the literal they're likely a misspelling of.
+    fn read_literal<I: PeekingNext<Item = char>>(mut iter: I) -> Result<Option<bool>, Error> {
+        let word = iter
+            .peeking_take_while(|ch| ch.is_alphanumeric())
+            .collect::<String>();
+        match word.as_str() {
+            "true" => Ok(Some(true)),
+            "false" => Ok(Some(false)),
+            "null" => Ok(None),
+            _ => Err(["true", "false", "null"]
+                .into_iter()
+                .find(|literal| is_one_edit_away(word.as_bytes(), literal.as_bytes()))
+                .map_or(Error::InvalidValue, Error::MisspelledLiteral)),
         }
     }
 
     /// Tries to read a numeric value
-    fn read_number<I: PeekingNext<Item = char>>(mut iter: I) -> Result<f64, Error> {
+    fn read_number<I: PeekingNext<Item = char>>(iter: I) -> Result<f64, Error> {
+        // Try to parse the number, return an error on failure or when it doesn't fit
+        match Self::read_number_text(iter)?.parse::<f64>() {
+            Err(_) => Err(Error::InvalidValue),
+            Ok(number) if number.is_infinite() => Err(Error::NumberOutOfRange),
+            Ok(number) => Ok(number),
+        }
+    }
+
+    /// Tries to read a numeric value, keeping its text or handling large integers as the options ask
+    fn read_numeric<I: PeekingNext<Item = char>>(
+        iter: I,
+        options: &ParseOptions,
+    ) -> Result<Self, Error> {
+        let text = Self::read_number_text(iter)?;
+        if options.raw_numbers {
+            return Ok(Self::NumberRaw(text));
+        }
+        let number = text.parse::<f64>().map_err(|_| Error::InvalidValue)?;
+
+        // Handle integers that don't fit as requested, and refuse other numbers that don't fit
+        let overflows = !text.contains(['.', 'e', 'E'])
+            && text
+                .parse::<i64>()
+                .map_or(true, |integer| integer.unsigned_abs() > MAX_SAFE_INTEGER);
+        match options.integer_overflow {
+            _ if !overflows && number.is_infinite() => Err(Error::NumberOutOfRange),
+            _ if !overflows => Ok(Self::Number(number)),
+            IntegerOverflow::Round if number.is_infinite() => Err(Error::NumberOutOfRange),
+            IntegerOverflow::Round => Ok(Self::Number(number)),
+            IntegerOverflow::Error => Err(Error::IntegerOverflow),
+            IntegerOverflow::Saturate => {
+                Ok(Self::Number((MAX_SAFE_INTEGER as f64).copysign(number)))
+            }
+            IntegerOverflow::Raw => Ok(Self::NumberRaw(text)),
+        }
+    }
+
+    /// Tries to read the text of a numeric value
+    fn read_number_text<I: PeekingNext<Item = char>>(mut iter: I) -> Result<String, Error> {
         // Read the characters of the number into a string
         let result = iter
-            .peeking_take_while(|&ch| matches!(ch, '0'..='9' | '.' | '+' | '-'))
+            .peeking_take_while(|&ch| matches!(ch, '0'..='9' | '.' | '+' | '-' | 'e' | 'E'))
             .collect::<String>();
 
-        // Return an error if the string is empty
-        if result.is_empty() {
+        // Return an error unless it's a number as JSON writes them
+        if !token::is_number(&result) {
             return Err(Error::InvalidValue);
         }
-
-        // Try to parse an error, return an error on failure
-        match result.parse::<f64>() {
-            Err(_) => Err(Error::InvalidValue),
-            Ok(number) => Ok(number),
-        }
+        Ok(result)
     }
 
-    /// Skips whitespace without wasting characters
+    /// Skips whitespace as JSON defines it without wasting characters
     fn skip_whitespace<I: PeekingNext<Item = char>>(mut iter: I) {
-        iter.peeking_take_while(|&ch| ch.is_whitespace())
+        iter.peeking_take_while(|&ch| is_whitespace(ch))
             .for_each(|_| {});
     }
 
-    /// Tries to parse a json value
-    fn parse_value<I: Iterator<Item = char>>(mut iter: &mut Peekable<I>) -> Result<Self, Error> {
-        Ok(
+    /// Tries to parse a json value, storing it the way the storage does
+    fn parse_value<S: Source, T: Storage>(
+        mut iter: &mut SourceChars<S>,
+        state: &mut ParseState<'_>,
+        storage: &mut T,
+        depth: usize,
+    ) -> Result<T::Value, Error> {
+        // Refuse to open another list or object beyond the deepest nesting allowed
+        if matches!(iter.peek(), Some('[' | '{'))
+            && state.options.max_depth.is_some_and(|max| depth >= max)
+        {
+            return Err(Error::NestingTooDeep);
+        }
+
+        let value =
             // Read the first character
             match iter.peek() {
                 // If it's a ", try to read and return the string
-                Some('"') => Self::String(Self::read_string(&mut iter)?),
+                Some('"') => storage.string(Self::read_scratch_string(iter, state)?),
 
-                // If it's a t or an f, try to read and the bool
-                Some('t' | 'f') => Self::Bool(Json::read_bool(&mut iter)?),
-
-                // If it's an n, make sure it's null and return it
-                Some('n') => {
-                    Self::read_null(&mut iter)?;
-                    Self::Null
+                // If it's numeric, try to parse and return the number
+                Some('0'..='9' | '.' | '-' | '+') => {
+                    storage.scalar(Self::read_numeric(&mut iter, state.options)?)
                 }
 
-                // If it's numeric, try to parse and return the number
-                Some('0'..='9' | '.' | '-' | '+') => Self::Number(Self::read_number(&mut iter)?),
+                // If it's a word, make sure it's a bool or null and return it
+                Some(ch) if ch.is_alphabetic() => {
+                    storage.scalar(Self::read_literal(&mut iter)?.map_or(Self::Null, Self::Bool))
+                }
 
                 // If it's [, try to parse and return the list
-                Some('[') => Self::List(Self::read_list(iter)?),
+                Some('[') => {
+                    let values = Self::read_list(iter, state, storage, depth + 1)?;
+                    storage.list(values)
+                }
 
                 // If it's {, try to parse and return the object
-                Some('{') => Self::Object(Self::read_object(iter)?),
+                Some('{') => {
+                    let items = Self::read_object(iter, state, storage, depth + 1)?;
+                    storage.object(items)
+                }
 
                 // If it is a different value, return it
                 Some(_) => return Err(Error::InvalidValue),
 
-                // If there is no value, return an error
+                // If there is no value, return an error telling whether there's any value at all
+                None if depth == 0 => return Err(Error::EmptyInput),
                 None => return Err(Error::UnexpectedEndOfFile),
-            },
-        )
+            };
+
+        // Let the callbacks see the value
+        state.read::<T>(&value)?;
+        Ok(value)
     }
 
     /// Tries to parse a list of data
-    fn read_list<I: Iterator<Item = char>>(mut iter: &mut Peekable<I>) -> Result<Vec<Self>, Error> {
+    fn read_list<S: Source, T: Storage>(
+        mut iter: &mut SourceChars<S>,
+        state: &mut ParseState<'_>,
+        storage: &mut T,
+        depth: usize,
+    ) -> Result<Vec<T::Value>, Error> {
         // Make sure the first character is a [
         if iter.next() != Some('[') {
             return Err(Error::InvalidValue);
         }
 
-        // Read the list
-        let mut result = Vec::new();
-        loop {
-            // Find the value or closing character
-            Self::skip_whitespace(&mut iter);
-
-            // Stop if the closing character has been found
-            if iter.peek() == Some(&']') {
-                iter.next().unwrap();
-                break;
-            }
+        // Stop if the list is empty
+        Self::skip_whitespace(&mut iter);
+        if iter.peeking_next(|&ch| ch == ']').is_some() {
+            return Ok(Vec::new());
+        }
 
-            // Add the value to the list
-            result.push(Self::parse_value(iter)?);
+        // Read the list onto the scratch stack of elements
+        let start = storage.values(&mut state.scratch).len();
+        loop {
+            // Add the value to the list, which has to follow a separator
+            let index = storage.values(&mut state.scratch).len() - start;
+            state.enter_index(index);
+            let value = Self::parse_value(iter, state, storage, depth)?;
+            storage.values(&mut state.scratch).push(value);
+            state.leave();
 
             // Find the seperator or closing character
-            match iter.find(|&ch| !ch.is_whitespace()) {
+            match iter.find(|&ch| !is_whitespace(ch)) {
                 // Stop if the closing character has been found
                 Some(']') => break,
 
                 // Skip the value separator
-                Some(',') => {}
+                Some(',') => Self::skip_whitespace(&mut iter),
 
                 // Return an error if neither was found
                 Some(_) => return Err(Error::MissingSeparator),
@@ -235,32 +640,33 @@ impl Json {
                 None => return Err(Error::UnclosedList),
             }
         }
-        Ok(result)
+        Ok(storage.values(&mut state.scratch).drain(start..).collect())
     }
 
     /// Tries to read an object
-    fn read_object<I: Iterator<Item = char>>(
-        mut iter: &mut Peekable<I>,
-    ) -> Result<Vec<(String, Self)>, Error> {
+    fn read_object<S: Source, T: Storage>(
+        mut iter: &mut SourceChars<S>,
+        state: &mut ParseState<'_>,
+        storage: &mut T,
+        depth: usize,
+    ) -> Result<Items<T>, Error> {
         // Return an error if the object isn't an object
         if iter.next() != Some('{') {
             return Err(Error::InvalidValue);
         }
 
-        // Read the object
-        let mut result = Vec::new();
-        loop {
-            // Skip whitespace
-            Self::skip_whitespace(&mut iter);
-
-            // Stop if the end of the object has been found
-            if iter.peek() == Some(&'}') {
-                iter.next().unwrap();
-                break;
-            }
+        // Stop if the object is empty
+        Self::skip_whitespace(&mut iter);
+        if iter.peeking_next(|&ch| ch == '}').is_some() {
+            return Ok(Vec::new());
+        }
 
-            // Read the name of the property
-            let name = Self::read_string(&mut iter)?;
+        // Read the object onto the scratch stack of properties
+        let start = storage.items(&mut state.scratch).len();
+        loop {
+            // Read the name of the property, which has to follow a separator
+            let name = storage.key(Self::read_scratch_string(iter, state)?);
+            state.enter_key(name.as_ref())?;
 
             // Skip whitespace
             Self::skip_whitespace(&mut iter);
@@ -274,10 +680,11 @@ impl Json {
             Self::skip_whitespace(&mut iter);
 
             // Try to parse the found value
-            let value = Self::parse_value(iter)?;
+            let value = Self::parse_value(iter, state, storage, depth)?;
+            state.leave();
 
             // Insert the property with name and value
-            result.push((name, value));
+            storage.items(&mut state.scratch).push((name, value));
 
             // Skip the whitespace
             Self::skip_whitespace(&mut iter);
@@ -288,7 +695,7 @@ impl Json {
                 Some('}') => break,
 
                 // Skip the value separator
-                Some(',') => {}
+                Some(',') => Self::skip_whitespace(&mut iter),
 
                 // Return an error if an other character was found
                 Some(_) => return Err(Error::MissingSeparator),
@@ -297,109 +704,398 @@ impl Json {
                 None => return Err(Error::UnclosedObject),
             }
         }
-        Ok(result)
+        Ok(storage.items(&mut state.scratch).drain(start..).collect())
     }
 
     /// Parses a JSON value from characters
     pub fn from_chars<I: Iterator<Item = char>>(iter: I) -> Result<Self, Error> {
-        Self::parse_value(&mut iter.skip_while(|ch| ch.is_whitespace()).peekable())
+        Self::parse_source(IterSource::new(iter)).map(|(value, _)| value)
+    }
+
+    /// Parses the JSON value at the start of a string, returning it with the rest of the string,
+    /// for JSON embedded in a larger format
+    ///
+    /// Whitespace before the value is skipped, but the rest starts right after it.
+    pub fn parse_prefix(text: &str) -> Result<(Self, &str), Error> {
+        let (value, end) = Self::parse_source(StrSource::new(text))?;
+        Ok((value, &text[end..]))
+    }
+
+    /// Parses the JSON value at the start of a source, after any whitespace, returning it with
+    /// the position after it
+    fn parse_source<S: Source>(source: S) -> Result<(Self, usize), Error> {
+        let options = ParseOptions::default();
+        let mut iter = SourceChars(Limited::new(source, options.max_size));
+        Self::skip_whitespace(&mut iter);
+        let result = Self::parse_value(&mut iter, &mut ParseState::new(&options), &mut Owned, 0);
+        let value = iter.0.check(result)?;
+        Ok((value, iter.0.position()))
     }
 
-    /// Parses a JSON value from bytes (if the byte to char conversion works well enough)
+    /// Parses a JSON value from UTF-8 bytes, replacing invalid bytes by U+FFFD
     pub fn from_bytes<I: Iterator<Item = u8>>(iter: I) -> Result<Self, Error> {
-        Self::from_chars(Chars(iter))
+        Self::from_chars(Chars::new(iter))
+    }
+
+    /// Parses a complete JSON value from pieces of text, like the slices of a ring buffer, as if
+    /// they were one string, adding the line and column to errors
+    pub fn from_str_chunks<'a, I: IntoIterator<Item = &'a str>>(chunks: I) -> Result<Self, Error> {
+        Self::from_chars_located(chunks.into_iter().flat_map(str::chars))
+    }
+
+    /// Parses a complete JSON value, adding the line and column to errors
+    ///
+    /// Unlike [`Json::from_chars`], this fails if anything but whitespace follows the value.
+    pub fn from_chars_located<I: Iterator<Item = char>>(iter: I) -> Result<Self, Error> {
+        Self::from_chars_with(iter, &ParseOptions::default())
+    }
+
+    /// Parses a complete JSON value with options, adding the line and column to errors
+    pub fn from_chars_with<I: Iterator<Item = char>>(
+        iter: I,
+        options: &ParseOptions,
+    ) -> Result<Self, Error> {
+        Self::from_source_with(IterSource::new(iter), options)
+    }
+
+    /// Parses a complete JSON value from a source, like a [`SliceSource`] or a [`ReaderSource`],
+    /// adding the line and column to errors
+    pub fn from_source<S: Source>(source: S) -> Result<Self, Error> {
+        Self::from_source_with(source, &ParseOptions::default())
+    }
+
+    /// Parses a complete JSON value from a source with options, adding the line and column to
+    /// errors
+    pub fn from_source_with<S: Source>(source: S, options: &ParseOptions) -> Result<Self, Error> {
+        Self::from_source_located(source, &mut ParseState::new(options))
+    }
+
+    /// Parses a complete JSON value from a source with options, running callbacks on the keys
+    /// and values read and adding the line and column to errors
+    pub fn from_source_with_hooks<S: Source>(
+        source: S,
+        options: &ParseOptions,
+        hooks: &mut dyn ParseHooks,
+    ) -> Result<Self, Error> {
+        Self::from_source_located(source, &mut ParseState::with_hooks(options, hooks))
+    }
+
+    /// Parses a complete JSON value, adding the line and column to errors
+    pub(crate) fn from_source_located<S: Source>(
+        source: S,
+        state: &mut ParseState<'_>,
+    ) -> Result<Self, Error> {
+        Self::from_source_tracked(source, state).map_err(|(error, line, column)| Error::At {
+            line,
+            column,
+            error: Box::new(error),
+        })
+    }
+
+    /// Parses a complete JSON value, returning the line and column at which parsing failed
+    fn from_source_tracked<S: Source>(
+        source: S,
+        state: &mut ParseState<'_>,
+    ) -> Result<Self, (Error, usize, usize)> {
+        read_tracked(source, state, |iter, state| {
+            Self::parse_value(iter, state, &mut Owned, 0)
+        })
     }
 }
 
-impl FromStr for Json {
-    type Err = Error;
+/// Reads a complete value with a function and makes sure nothing but whitespace follows it,
+/// returning the line and column at which reading failed
+#[cfg(feature = "alloc")]
+fn read_tracked<'a, S: Source, T>(
+    source: S,
+    state: &mut ParseState<'a>,
+    read: impl FnOnce(&mut SourceChars<Tracked<Limited<S>>>, &mut ParseState<'a>) -> Result<T, Error>,
+) -> Result<T, (Error, usize, usize)> {
+    let mut iter = SourceChars(Tracked {
+        source: Limited::new(source, state.options.max_size),
+        line: 1,
+        column: 0,
+    });
+
+    // Read the value and make sure nothing but whitespace follows it
+    Json::skip_whitespace(&mut iter);
+    let result = read(&mut iter, state).and_then(|value| {
+        Json::skip_whitespace(&mut iter);
+        match iter.peek() {
+            Some(_) => Err(Error::TrailingCharacters),
+            None => Ok(value),
+        }
+    });
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Self::from_chars(s.chars())
+    // Add the position of the last character read to the error
+    let SourceChars(tracked) = iter;
+    let result = tracked.source.check(result);
+    result.map_err(|error| (error, tracked.line, tracked.column))
+}
+
+/// Reads a complete value with a function, adding the line and column to errors
+#[cfg(feature = "shared")]
+fn read_located<'a, S: Source, T>(
+    source: S,
+    state: &mut ParseState<'a>,
+    read: impl FnOnce(&mut SourceChars<Tracked<Limited<S>>>, &mut ParseState<'a>) -> Result<T, Error>,
+) -> Result<T, Error> {
+    read_tracked(source, state, read).map_err(|(error, line, column)| Error::At {
+        line,
+        column,
+        error: Box::new(error),
+    })
+}
+
+/// Returns whether a character is whitespace as JSON defines it
+#[cfg(feature = "alloc")]
+fn is_whitespace(ch: char) -> bool {
+    matches!(ch, ' ' | '\t' | '\n' | '\r')
+}
+
+/// Returns whether a word becomes a literal by changing the case, or by inserting, removing,
+/// replacing or swapping a single character
+#[cfg(feature = "alloc")]
+fn is_one_edit_away(word: &[u8], literal: &[u8]) -> bool {
+    // Strip the common start and end, leaving only what differs
+    let same = |(a, b): &(&u8, &u8)| a.eq_ignore_ascii_case(b);
+    let start = word.iter().zip(literal).take_while(same).count();
+    let (word, literal) = (&word[start..], &literal[start..]);
+    let end = word
+        .iter()
+        .rev()
+        .zip(literal.iter().rev())
+        .take_while(same)
+        .count();
+    match (&word[..word.len() - end], &literal[..literal.len() - end]) {
+        (word, literal) if word.len() + literal.len() <= 1 => true,
+        ([_], [_]) => true,
+        ([a, b], [c, d]) => a.eq_ignore_ascii_case(d) && b.eq_ignore_ascii_case(c),
+        _ => false,
     }
 }
 
-impl Display for Json {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
-            // Display a list
-            Json::List(values) => {
-                // Start displaying the list
-                write!(f, "[")?;
-
-                // Display the content of the list, if there is any
-                if !values.is_empty() {
-                    write!(f, "{}", values[0])?;
-                    for value in values.iter().skip(1) {
-                        write!(f, ",{value}")?;
-                    }
-                }
-
-                // Close the list
-                write!(f, "]")
+            Error::InvalidValue => write!(f, "invalid value"),
+            Error::UnclosedString => write!(f, "unclosed string"),
+            Error::UnclosedList => write!(f, "unclosed list"),
+            Error::MissingSeparator => write!(f, "missing separator"),
+            Error::UnexpectedEndOfFile => write!(f, "unexpected end of file"),
+            Error::EmptyInput => write!(f, "the input is empty"),
+            Error::UnclosedObject => write!(f, "unclosed object"),
+            Error::TrailingCharacters => write!(f, "unexpected characters after the value"),
+            Error::NestingTooDeep => write!(f, "values are nested too deeply"),
+            Error::IntegerOverflow => write!(f, "integer is too large to be held exactly"),
+            Error::NumberOutOfRange => write!(f, "number is too large"),
+            Error::TooLarge => write!(f, "the input is too large"),
+            Error::MisspelledLiteral(literal) => {
+                write!(f, "invalid value, did you mean {literal}?")
             }
+            Error::Aborted(reason) => write!(f, "parsing was aborted: {reason}"),
+            #[cfg(feature = "alloc")]
+            Error::At {
+                line,
+                column,
+                error,
+            } => write!(f, "{error} at line {line}, column {column}"),
+            #[cfg(feature = "alloc")]
+            Error::Document { index, error } => write!(f, "{error} in document {index}"),
+        }
+    }
+}
 
-            // Display an object
-            Json::Object(items) => {
-                // Start displaying the object
-                write!(f, "{{")?;
+impl core::error::Error for Error {}
 
-                // Display the properties of the object, if there are any
-                if !items.is_empty() {
-                    write!(f, "{:?}:{}", items[0].0, items[0].1)?;
-                    for item in items.iter().skip(1) {
-                        write!(f, ",{:?}:{}", item.0, item.1)?;
-                    }
-                }
+impl Error {
+    /// Returns the error without its position
+    fn without_position(&self) -> &Error {
+        match self {
+            #[cfg(feature = "alloc")]
+            Error::At { error, .. } | Error::Document { error, .. } => error.without_position(),
+            error => error,
+        }
+    }
 
-                // Close the object
-                write!(f, "}}")
-            }
+    /// Returns a number identifying the kind of error, which stays the same between versions
+    pub fn code(&self) -> u16 {
+        match self.without_position() {
+            Error::InvalidValue => 1,
+            Error::UnclosedString => 2,
+            Error::UnclosedList => 3,
+            Error::MissingSeparator => 4,
+            Error::UnexpectedEndOfFile => 5,
+            Error::UnclosedObject => 6,
+            Error::TrailingCharacters => 7,
+            Error::NestingTooDeep => 8,
+            Error::IntegerOverflow => 9,
+            Error::MisspelledLiteral(_) => 10,
+            Error::TooLarge => 11,
+            Error::EmptyInput => 12,
+            Error::Aborted(_) => 13,
+            Error::NumberOutOfRange => 14,
+            #[cfg(feature = "alloc")]
+            Error::At { .. } | Error::Document { .. } => unreachable!(),
+        }
+    }
 
-            // Display a string
-            Json::String(string) => write!(f, "{string:?}"),
+    /// Returns whether the input ended before the value did, so it may parse once more of it
+    /// has arrived
+    ///
+    /// This includes [`Error::EmptyInput`], since nothing of the value may have arrived yet.
+    pub fn is_unexpected_eof(&self) -> bool {
+        matches!(
+            self.without_position(),
+            Error::UnexpectedEndOfFile
+                | Error::EmptyInput
+                | Error::UnclosedString
+                | Error::UnclosedList
+                | Error::UnclosedObject
+        )
+    }
 
-            // Display a number
-            Json::Number(number) => write!(f, "{number}"),
+    /// Returns whether the input isn't JSON, so more of it won't help
+    pub fn is_syntax(&self) -> bool {
+        matches!(
+            self.without_position(),
+            Error::InvalidValue
+                | Error::MissingSeparator
+                | Error::TrailingCharacters
+                | Error::MisspelledLiteral(_)
+        )
+    }
+}
 
-            // Display a boolean
-            Json::Bool(value) => write!(f, "{value}"),
+/// Parses a complete JSON value strictly following RFC 8259, so it accepts the same values as
+/// [`validate`] apart from numbers too large for an `f64`
+#[cfg(feature = "alloc")]
+impl FromStr for Json {
+    type Err = Error;
 
-            // Display the null value
-            Json::Null => write!(f, "null"),
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Make sure nothing but whitespace follows the value
+        match Self::parse_prefix(s)? {
+            (value, rest) if rest.chars().all(is_whitespace) => Ok(value),
+            _ => Err(Error::TrailingCharacters),
         }
     }
 }
 
-/// Converts the items from an iterator to characters
-struct Chars<I>(I);
+#[cfg(feature = "alloc")]
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // Pretty print with {:#}, write compact JSON otherwise
+        let format = if f.alternate() {
+            Format::pretty()
+        } else {
+            Format::compact()
+        };
+        ser::write_value(f, self, &format, 0)
+    }
+}
+
+/// Decodes the UTF-8 bytes from an iterator to characters, replacing invalid bytes by U+FFFD
+pub struct Chars<I> {
+    /// The iterator the bytes are taken from
+    iter: I,
+
+    /// A byte that was taken, but that starts the next character
+    pending: Option<u8>,
+}
+
+impl<I> Chars<I> {
+    /// Starts decoding at the next byte of an iterator
+    pub(crate) fn new(iter: I) -> Self {
+        Self {
+            iter,
+            pending: None,
+        }
+    }
+}
 
 impl<I: Iterator<Item = u8>> Iterator for Chars<I> {
     type Item = char;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Convert a series of bytes to a single char
-        let mut value = 0;
-        for byte in self.0.by_ref().take(4) {
-            // Add the byte to the number
-            value = (value << 8) | u32::from(byte);
-
-            // Try to convert the number to a char and return it on success
-            if let Some(ch) = char::from_u32(value) {
-                return Some(ch);
+        // Find the number of bytes that follow the first one, and its bits of the character
+        let first = self.pending.take().or_else(|| self.iter.next())?;
+        let (count, mut value) = match first {
+            0x00..=0x7f => return Some(char::from(first)),
+            0xc2..=0xdf => (1, u32::from(first & 0x1f)),
+            0xe0..=0xef => (2, u32::from(first & 0x0f)),
+            0xf0..=0xf4 => (3, u32::from(first & 0x07)),
+            _ => return Some(char::REPLACEMENT_CHARACTER),
+        };
+
+        // Add the bits of the following bytes, keeping a byte that doesn't belong to them
+        for _ in 0..count {
+            match self.iter.next() {
+                Some(byte @ 0x80..=0xbf) => value = (value << 6) | u32::from(byte & 0x3f),
+                byte => {
+                    self.pending = byte;
+                    return Some(char::REPLACEMENT_CHARACTER);
+                }
             }
         }
 
-        // Return None on failure
-        None
+        // Refuse surrogates and characters encoded with more bytes than they need
+        let smallest = [0x80, 0x800, 0x1_0000][count - 1];
+        Some(
+            char::from_u32(value)
+                .filter(|_| value >= smallest)
+                .unwrap_or(char::REPLACEMENT_CHARACTER),
+        )
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// Keeps track of the line and column of the characters taken from a source
+struct Tracked<S> {
+    /// The source the characters are taken from
+    source: S,
+
+    /// The line of the last character, starting at 1
+    line: usize,
+
+    /// The column of the last character, starting at 1
+    column: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<S: Source> Source for Tracked<S> {
+    fn peek(&mut self) -> Option<char> {
+        self.source.peek()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.source.next()?;
+
+        // Move to the start of the next line after a newline, to the next column otherwise
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+        Some(ch)
+    }
+
+    fn position(&self) -> usize {
+        self.source.position()
     }
 }
 
+#[cfg(feature = "alloc")]
 #[cfg(test)]
 mod tests {
-    use alloc::{borrow::ToOwned, vec::Vec};
+    use alloc::{borrow::ToOwned, string::ToString, vec::Vec};
 
-    use crate::Json;
+    use crate::{
+        Error, IntegerOverflow, Json, ParseOptions, StrSource,
+        parser::{Owned, ParseState},
+        source::SourceChars,
+    };
 
     #[test]
     fn string_parsing() {
@@ -409,35 +1105,263 @@ mod tests {
     }
 
     #[test]
-    fn bool_parsing() {
-        assert!(Json::read_bool("true".chars()).unwrap());
-        assert!(Json::read_bool("tru".chars()).is_err());
-        assert!(!Json::read_bool("false".chars()).unwrap());
-        assert!(Json::read_bool("fals".chars()).is_err());
-    }
-
-    #[test]
-    fn null_parsing() {
-        Json::read_null("null".chars()).unwrap();
-        assert!(Json::read_null("nu".chars()).is_err());
+    fn literal_parsing() {
+        assert_eq!(Json::read_literal("true".chars()).unwrap(), Some(true));
+        assert_eq!(Json::read_literal("false".chars()).unwrap(), Some(false));
+        assert_eq!(Json::read_literal("null".chars()).unwrap(), None);
+        assert!(Json::read_literal("nu".chars()).is_err());
+        assert!(Json::read_literal("yes".chars()).is_err());
+
+        // Near misses name the intended literal
+        for (word, literal) in [
+            ("True", "true"),
+            ("tru", "true"),
+            ("flase", "false"),
+            ("fals", "false"),
+            ("NULL", "null"),
+            ("nul", "null"),
+        ] {
+            assert!(matches!(
+                Json::read_literal(word.chars()),
+                Err(Error::MisspelledLiteral(found)) if found == literal
+            ));
+        }
     }
 
     #[test]
     fn number_parsing() {
         assert_eq!(Json::read_number("-123.456".chars()).unwrap(), -123.456);
         assert!(Json::read_number("hello".chars()).is_err());
+
+        // Exponents scale the number, but numbers beyond the range of f64 are refused
+        assert_eq!(Json::read_number("25e-2".chars()).unwrap(), 0.25);
+        assert_eq!(Json::read_number("1E+3".chars()).unwrap(), 1000.0);
+        assert!(matches!(
+            Json::read_number("1e400".chars()),
+            Err(Error::NumberOutOfRange)
+        ));
+        assert!(matches!(
+            Json::read_number("-1e400".chars()),
+            Err(Error::NumberOutOfRange)
+        ));
+        assert_eq!(Json::read_number("1e-400".chars()).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn byte_decoding() {
+        let text = "[\"é\",\"☃\",\"🦀\"]";
+        let expected = text.parse::<Json>().unwrap();
+        assert_eq!(Json::from_bytes(text.bytes()).unwrap(), expected);
+        let lines = Json::lines_from_bytes(text.bytes()).collect::<Result<Vec<_>, _>>();
+        assert_eq!(lines.unwrap(), core::slice::from_ref(&expected));
+        let stream = Json::stream_from_bytes(text.bytes()).collect::<Result<Vec<_>, _>>();
+        assert_eq!(stream.unwrap(), core::slice::from_ref(&expected));
+        let sequence = Json::seq_from_bytes("\u{1e}".bytes().chain(text.bytes()));
+        assert_eq!(sequence.collect::<Result<Vec<_>, _>>().unwrap(), [expected]);
+
+        // Invalid bytes, cut-off characters, surrogates and overlong encodings are replaced
+        assert_eq!(
+            Json::from_bytes(b"\"a\xffb\xed\xa0\x80c\xc0\xafd\xe2\x98\"".iter().copied()).unwrap(),
+            Json::String("a\u{fffd}b\u{fffd}c\u{fffd}\u{fffd}d\u{fffd}".into())
+        );
+    }
+
+    #[test]
+    fn chunked_parsing() {
+        let chunks = ["{\"na", "me\":\"\\u00", "e9\",\"n\":1", "2.5}", ""];
+        assert_eq!(
+            Json::from_str_chunks(chunks).unwrap(),
+            "{\"name\":\"\u{e9}\",\"n\":12.5}".parse::<Json>().unwrap()
+        );
+        assert!(matches!(
+            Json::from_str_chunks(["[1,", "\n2", "] x"]),
+            Err(Error::At { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn raw_numbers() {
+        let options = ParseOptions {
+            raw_numbers: true,
+            ..ParseOptions::default()
+        };
+        let value =
+            Json::from_chars_with("[0.10, 12345678901234567890, -1.5E+3]".chars(), &options)
+                .unwrap();
+        assert_eq!(
+            value,
+            Json::List(Vec::from([
+                Json::NumberRaw("0.10".to_owned()),
+                Json::NumberRaw("12345678901234567890".to_owned()),
+                Json::NumberRaw("-1.5E+3".to_owned()),
+            ]))
+        );
+        assert_eq!(value.to_string(), "[0.10,12345678901234567890,-1.5E+3]");
+
+        // Only numbers JSON allows are kept, as they're written back as they are
+        assert!(Json::from_chars_with("+1".chars(), &options).is_err());
+        assert!(Json::from_chars_with("01".chars(), &options).is_err());
+    }
+
+    #[test]
+    fn error_classification() {
+        // Truncated input may parse once more of it arrives, garbage never will
+        let truncated = Json::from_chars_located("{\"a\": [1, \"tw".chars()).unwrap_err();
+        assert!(truncated.is_unexpected_eof() && !truncated.is_syntax());
+        assert_eq!(truncated.code(), 2);
+        let garbage = Json::from_chars_located("[1 2]".chars()).unwrap_err();
+        assert!(garbage.is_syntax() && !garbage.is_unexpected_eof());
+        assert_eq!(garbage.code(), Error::MissingSeparator.code());
+
+        // Limits are neither
+        assert!(!Error::NestingTooDeep.is_syntax() && !Error::NestingTooDeep.is_unexpected_eof());
+    }
+
+    #[test]
+    fn prefix_parsing() {
+        assert!(matches!(
+            "{}garbage".parse::<Json>(),
+            Err(Error::TrailingCharacters)
+        ));
+        assert!(" [1] \n".parse::<Json>().is_ok());
+
+        // The rest starts right after the value
+        let (value, rest) = Json::parse_prefix(" {\"a\":[1, 2]}\r\nnext").unwrap();
+        assert_eq!(value, "{\"a\":[1,2]}".parse::<Json>().unwrap());
+        assert_eq!(rest, "\r\nnext");
+        let (value, rest) = Json::parse_prefix("12.5e1,\"é\"").unwrap();
+        assert_eq!((value, rest), (Json::Number(125.0), ",\"é\""));
+        assert!(Json::parse_prefix("").is_err());
+    }
+
+    #[test]
+    fn empty_input() {
+        assert!(matches!(" \n".parse::<Json>(), Err(Error::EmptyInput)));
+        assert!(matches!(
+            "[1,".parse::<Json>(),
+            Err(Error::UnexpectedEndOfFile)
+        ));
+        let error = Json::from_chars_located("".chars()).unwrap_err();
+        assert!(matches!(error, Error::At { error, .. } if matches!(*error, Error::EmptyInput)));
+        assert!(matches!(
+            Json::events("".chars()).next(),
+            Some(Err(Error::EmptyInput))
+        ));
+    }
+
+    #[test]
+    fn constructors() {
+        assert_eq!(Json::default(), Json::Null);
+        assert_eq!(Json::string("name"), Json::String("name".to_owned()));
+        assert_eq!(Json::number(-0.5), Some(Json::Number(-0.5)));
+        for number in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(Json::number(number), None);
+        }
+
+        // Empty containers can be filled through pointers
+        let mut document = Json::object();
+        *document.pointer_mut_or_insert("/tags").unwrap() = Json::array();
+        *document.pointer_mut_or_insert("/tags/-").unwrap() = Json::string("a");
+        assert_eq!(document, "{\"tags\":[\"a\"]}".parse::<Json>().unwrap());
+    }
+
+    #[test]
+    fn limits() {
+        // Nesting is limited by default, but can be allowed
+        let deep = "[".repeat(200) + &"]".repeat(200);
+        assert!(matches!(deep.parse::<Json>(), Err(Error::NestingTooDeep)));
+        assert!(Json::from_chars_with(deep.chars(), &ParseOptions::unlimited()).is_ok());
+        let fits = "[".repeat(128) + &"]".repeat(128);
+        assert!(fits.parse::<Json>().is_ok());
+
+        // So is the size of the input
+        let options = ParseOptions {
+            max_size: Some(8),
+            ..ParseOptions::default()
+        };
+        assert!(Json::from_chars_with("[1, 2]  ".chars(), &options).is_ok());
+        let error = Json::from_chars_with("[1, 2, 3]".chars(), &options).unwrap_err();
+        assert!(matches!(error, Error::At { error, .. } if matches!(*error, Error::TooLarge)));
+    }
+
+    #[test]
+    fn integer_overflow() {
+        let parse = |text: &str, integer_overflow| {
+            let options = ParseOptions {
+                integer_overflow,
+                ..ParseOptions::default()
+            };
+            Json::from_chars_with(text.chars(), &options)
+        };
+        let large = "-9007199254740993";
+        assert_eq!(
+            parse(large, IntegerOverflow::Round).unwrap(),
+            Json::Number(-9007199254740992.0)
+        );
+        assert!(matches!(
+            parse(large, IntegerOverflow::Error),
+            Err(Error::At { error, .. }) if matches!(*error, Error::IntegerOverflow)
+        ));
+        assert_eq!(
+            parse(large, IntegerOverflow::Saturate).unwrap(),
+            Json::Number(-9007199254740991.0)
+        );
+        assert_eq!(
+            parse(large, IntegerOverflow::Raw).unwrap(),
+            Json::NumberRaw(large.to_owned())
+        );
+
+        // Integers that fit and other numbers aren't affected
+        assert_eq!(
+            parse("9007199254740991", IntegerOverflow::Error).unwrap(),
+            Json::Number(9007199254740991.0)
+        );
+        assert_eq!(
+            parse("1e300", IntegerOverflow::Error).unwrap(),
+            Json::Number(1e300)
+        );
+
+        // Integers beyond the range of f64 are only kept as text
+        let huge = "1".repeat(400);
+        assert!(matches!(
+            parse(&huge, IntegerOverflow::Round),
+            Err(Error::At { error, .. }) if matches!(*error, Error::NumberOutOfRange)
+        ));
+        assert_eq!(
+            parse(&huge, IntegerOverflow::Raw).unwrap(),
+            Json::NumberRaw(huge)
+        );
     }
 
     #[test]
     fn list_parsing() {
-        assert!(Json::read_list(&mut "{}".chars().peekable()).is_err());
+        assert!(
+            Json::read_list(
+                &mut SourceChars(StrSource::new("{}")),
+                &mut ParseState::new(&ParseOptions::default()),
+                &mut Owned,
+                0
+            )
+            .is_err()
+        );
         assert_eq!(
-            Json::read_list(&mut "[]".chars().peekable()).unwrap(),
-            Vec::new()
+            Json::read_list(
+                &mut SourceChars(StrSource::new("[]")),
+                &mut ParseState::new(&ParseOptions::default()),
+                &mut Owned,
+                0
+            )
+            .unwrap(),
+            Vec::<Json>::new()
         );
         assert_eq!(
-            Json::read_list(&mut "[-654.321, {},[], \"Hello\",false,null]".chars().peekable())
-                .unwrap(),
+            Json::read_list(
+                &mut SourceChars(StrSource::new("[-654.321, {},[], \"Hello\",false,null]")),
+                &mut ParseState::new(&ParseOptions::default()),
+                &mut Owned,
+                0
+            )
+            .unwrap(),
             [
                 Json::Number(-654.321),
                 Json::Object(Vec::new()),
@@ -451,14 +1375,33 @@ mod tests {
 
     #[test]
     fn object_parsing() {
-        assert!(Json::read_object(&mut "[]".chars().peekable()).is_err());
+        assert!(
+            Json::read_object(
+                &mut SourceChars(StrSource::new("[]")),
+                &mut ParseState::new(&ParseOptions::default()),
+                &mut Owned,
+                0
+            )
+            .is_err()
+        );
         assert_eq!(
-            Json::read_object(&mut "{}".chars().peekable()).unwrap(),
+            Json::read_object(
+                &mut SourceChars(StrSource::new("{}")),
+                &mut ParseState::new(&ParseOptions::default()),
+                &mut Owned,
+                0
+            )
+            .unwrap(),
             Vec::new()
         );
         assert_eq!(
-            Json::read_object(&mut "{\"number\":-123.456,\"object\":{}}".chars().peekable())
-                .unwrap(),
+            Json::read_object(
+                &mut SourceChars(StrSource::new("{\"number\":-123.456,\"object\":{}}")),
+                &mut ParseState::new(&ParseOptions::default()),
+                &mut Owned,
+                0
+            )
+            .unwrap(),
             Vec::from([
                 ("number".to_owned(), Json::Number(-123.456)),
                 ("object".to_owned(), Json::Object(Vec::new()))
@@ -466,7 +1409,8 @@ mod tests {
         );
         assert_eq!(
             Json::read_object(
-                &mut "{\"number\":-123.456,\"object\":{},\"list\":[],\"string\": \"Hello\", \"bool\": true ,\"null\":null}".chars().peekable()
+                &mut SourceChars(StrSource::new("{\"number\":-123.456,\"object\":{},\"list\":[],\"string\": \"Hello\", \"bool\": true ,\"null\":null}")),
+                &mut ParseState::new(&ParseOptions::default()), &mut Owned, 0,
             ).unwrap(),
             Vec::from([
                 ("number".to_owned(), Json::Number(-123.456)),
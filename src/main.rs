@@ -1,21 +1,74 @@
-use std::{
-    env::args,
-    fs::File,
-    io::{BufReader, Read},
-};
+mod cli;
 
-use json_parser::Json;
+use std::{env::args, process::ExitCode};
 
-fn main() {
-    println!(
-        "{}",
-        Json::from_bytes(
-            BufReader::new(
-                File::open(args().nth(1).expect("Expected a filepath as argument")).unwrap(),
-            )
-            .bytes()
-            .map_while(Result::ok),
-        )
-        .unwrap()
-    );
+use cli::Failure;
+
+/// The usage message printed for invalid arguments
+const USAGE: &str = "\
+usage: json_parser [--max-depth <n>] <command> ...
+       json_parser [--pretty | --minify | --canonical] [--indent <n> | --tab] [--sort-keys]
+                   [--key-order <key>,...] [--get <pointer>] [--filter <filter>] [--ndjson] [--color=never|always|auto]
+                   [--head <n> | --sample <n>] [--watch] [<file> | -]
+       json_parser validate [--error-format human|json] [--ndjson] [--warnings] [--watch] [<file>...]
+       json_parser convert [--from <format>] [--to <format>] [<file> | -]
+       json_parser diff [--patch | --invertible] <file> <file>
+       json_parser explore [<file> | -]
+       json_parser patch [--merge | --invert] [--pretty] <file> <patch>
+       json_parser redact --path <pattern>... [--mask <text>] [--pretty] [<file> | -]
+       json_parser repair [--pretty] [--quiet] [<file> | -]
+       json_parser schema validate --schema <schema> [<file>...]
+       json_parser stats [<file> | -]
+       json_parser to-csv [--path <pointer>] [--delimiter <char>] [<file> | -]
+       json_parser set [--pretty | --minify] <file> <pointer> <value>
+       json_parser del [--pretty | --minify] <file> <pointer>";
+
+fn main() -> ExitCode {
+    // Take the limit on the nesting of documents, which applies to every subcommand
+    let mut args = args().skip(1).peekable();
+    if args.next_if(|arg| arg == "--max-depth").is_some() {
+        match args.next().and_then(|depth| depth.parse().ok()) {
+            Some(depth) => cli::set_max_depth(depth),
+            None => {
+                eprintln!("json_parser: --max-depth expects a number of levels\n{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    // Run the subcommand, printing the document by default
+    let result = match args.peek().map(String::as_str) {
+        Some("convert") => cli::convert::run(args.skip(1)),
+        Some("del") => cli::edit::run_delete(args.skip(1)),
+        Some("diff") => cli::diff::run(args.skip(1)),
+        #[cfg(feature = "explore")]
+        Some("explore") => cli::explore::run(args.skip(1)),
+        #[cfg(not(feature = "explore"))]
+        Some("explore") => Err(Failure::Message("explore needs the explore feature".into())),
+        Some("patch") => cli::patch::run(args.skip(1)),
+        Some("redact") => cli::redact::run(args.skip(1)),
+        Some("repair") => cli::repair::run(args.skip(1)),
+        Some("schema") => cli::schema::run(args.skip(1)),
+        Some("set") => cli::edit::run_set(args.skip(1)),
+        Some("stats") => cli::stats::run(args.skip(1)),
+        #[cfg(feature = "csv")]
+        Some("to-csv") => cli::to_csv::run(args.skip(1)),
+        #[cfg(not(feature = "csv"))]
+        Some("to-csv") => Err(Failure::Message("to-csv needs the csv feature".into())),
+        Some("validate") => cli::validate::run(args.skip(1)),
+        _ => cli::print::run(args),
+    };
+
+    // Print the reason the subcommand failed
+    match result {
+        Ok(code) => code,
+        Err(Failure::Usage(message)) => {
+            eprintln!("json_parser: {message}\n{USAGE}");
+            ExitCode::from(2)
+        }
+        Err(Failure::Message(message)) => {
+            eprintln!("json_parser: {message}");
+            ExitCode::FAILURE
+        }
+    }
 }
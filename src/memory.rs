@@ -0,0 +1,95 @@
+//! The memory used by values, and freeing what's unused
+
+use alloc::string::String;
+use core::mem::size_of;
+
+use crate::Json;
+
+impl Json {
+    /// Returns the approximate number of bytes this value uses on the heap, including unused
+    /// capacity but not the allocator's own overhead
+    pub fn estimated_size(&self) -> usize {
+        match self {
+            Json::List(values) => {
+                values.capacity() * size_of::<Json>()
+                    + values.iter().map(Json::estimated_size).sum::<usize>()
+            }
+            Json::Object(items) => {
+                items.capacity() * size_of::<(String, Json)>()
+                    + items
+                        .iter()
+                        .map(|(key, value)| key.capacity() + value.estimated_size())
+                        .sum::<usize>()
+            }
+            Json::String(text) | Json::NumberRaw(text) => text.capacity(),
+            Json::Number(_) | Json::Bool(_) | Json::Null => 0,
+        }
+    }
+
+    /// Frees the unused capacity of this value and every value in it, which parsing leaves
+    /// behind as lists, objects and strings grow
+    pub fn shrink_to_fit(&mut self) {
+        match self {
+            Json::List(values) => {
+                values.shrink_to_fit();
+                values.iter_mut().for_each(Json::shrink_to_fit);
+            }
+            Json::Object(items) => {
+                items.shrink_to_fit();
+                items.iter_mut().for_each(|(key, value)| {
+                    key.shrink_to_fit();
+                    value.shrink_to_fit();
+                });
+            }
+            Json::String(text) | Json::NumberRaw(text) => text.shrink_to_fit(),
+            Json::Number(_) | Json::Bool(_) | Json::Null => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+    use core::mem::size_of;
+
+    use crate::Json;
+
+    #[test]
+    fn estimated_size() {
+        assert_eq!(Json::Number(1.0).estimated_size(), 0);
+        assert_eq!(Json::String(String::with_capacity(10)).estimated_size(), 10);
+
+        // Lists count their capacity and what their elements hold
+        let list = Json::List(Vec::from([Json::String("abc".into()), Json::Null]));
+        assert_eq!(list.estimated_size(), 2 * size_of::<Json>() + 3);
+    }
+
+    #[test]
+    fn shrinking() {
+        // Values built up by pushing have spare capacity
+        let mut value = Json::Object(Vec::new());
+        if let Json::Object(items) = &mut value {
+            let mut name = String::from("a long");
+            name.push_str(" enough name");
+            items.push(("name".into(), Json::String(name)));
+            let list = (1..=5).map(|number| Json::Number(number.into())).collect();
+            items.push(("list".into(), Json::List(list)));
+        }
+        let before = value.estimated_size();
+        value.shrink_to_fit();
+        assert!(value.estimated_size() < before);
+
+        // Nothing is left to free
+        let Json::Object(items) = &value else {
+            unreachable!()
+        };
+        assert_eq!(items.capacity(), 2);
+        assert!(items.iter().all(|(key, _)| key.capacity() == key.len()));
+        assert_eq!(
+            value,
+            "{\"name\":\"a long enough name\",\"list\":[1,2,3,4,5]}"
+                .parse::<Json>()
+                .unwrap()
+        );
+    }
+}
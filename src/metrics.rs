@@ -0,0 +1,114 @@
+//! Summaries of the shape of documents
+
+use crate::Json;
+
+/// The shape of a document, see [`Json::metrics`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// The number of objects
+    pub objects: usize,
+
+    /// The number of lists
+    pub lists: usize,
+
+    /// The number of strings, not counting keys
+    pub strings: usize,
+
+    /// The number of numbers
+    pub numbers: usize,
+
+    /// The number of booleans
+    pub booleans: usize,
+
+    /// The number of nulls
+    pub nulls: usize,
+
+    /// The deepest nesting of any value, the top level value being at depth 0
+    pub max_depth: usize,
+
+    /// The total length of all strings in bytes, not counting keys
+    pub string_bytes: usize,
+
+    /// The most properties in any object
+    pub largest_object: usize,
+
+    /// The most elements in any list
+    pub largest_list: usize,
+}
+
+impl Metrics {
+    /// Returns the number of values of all types
+    pub fn values(&self) -> usize {
+        self.objects + self.lists + self.strings + self.numbers + self.booleans + self.nulls
+    }
+
+    /// Adds a value at the given depth, and everything in it
+    fn visit(&mut self, value: &Json, depth: usize) {
+        self.max_depth = self.max_depth.max(depth);
+        match value {
+            Json::Object(items) => {
+                self.objects += 1;
+                self.largest_object = self.largest_object.max(items.len());
+                for (_, value) in items {
+                    self.visit(value, depth + 1);
+                }
+            }
+            Json::List(values) => {
+                self.lists += 1;
+                self.largest_list = self.largest_list.max(values.len());
+                for value in values {
+                    self.visit(value, depth + 1);
+                }
+            }
+            Json::String(string) => {
+                self.strings += 1;
+                self.string_bytes += string.len();
+            }
+            Json::Number(_) | Json::NumberRaw(_) => self.numbers += 1,
+            Json::Bool(_) => self.booleans += 1,
+            Json::Null => self.nulls += 1,
+        }
+    }
+}
+
+impl Json {
+    /// Counts the values of each type and measures the depth and the largest strings, lists and
+    /// objects, in one pass without allocating
+    pub fn metrics(&self) -> Metrics {
+        let mut metrics = Metrics::default();
+        metrics.visit(self, 0);
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Metrics;
+    use crate::Json;
+
+    #[test]
+    fn metrics() {
+        let document =
+            "{\"users\":[{\"name\":\"ann\",\"admin\":true},{\"name\":\"bob\",\"age\":null}],\
+            \"count\":2}"
+                .parse::<Json>()
+                .unwrap();
+        let metrics = document.metrics();
+        assert_eq!(
+            metrics,
+            Metrics {
+                objects: 3,
+                lists: 1,
+                strings: 2,
+                numbers: 1,
+                booleans: 1,
+                nulls: 1,
+                max_depth: 3,
+                string_bytes: 6,
+                largest_object: 2,
+                largest_list: 2,
+            }
+        );
+        assert_eq!(metrics.values(), 9);
+    }
+}
@@ -0,0 +1,319 @@
+//! Conversion between JSON values and MessagePack
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{Error, Json, MAX_DEPTH};
+
+/// Writes the header of a string, array or map, using the short form if the length allows it
+fn write_length(out: &mut Vec<u8>, length: usize, fix: (u8, usize), markers: [u8; 3]) {
+    if length < fix.1 {
+        out.push(fix.0 | length as u8);
+    } else if markers[0] != 0 && length <= 0xff {
+        out.extend([markers[0], length as u8]);
+    } else if length <= 0xffff {
+        out.push(markers[1]);
+        out.extend((length as u16).to_be_bytes());
+    } else {
+        out.push(markers[2]);
+        out.extend((length as u32).to_be_bytes());
+    }
+}
+
+/// Writes a string
+fn write_string(out: &mut Vec<u8>, string: &str) {
+    write_length(out, string.len(), (0xa0, 32), [0xd9, 0xda, 0xdb]);
+    out.extend(string.as_bytes());
+}
+
+/// Writes a value as MessagePack
+fn write_value(out: &mut Vec<u8>, value: &Json) {
+    match value {
+        // Write lists as arrays
+        Json::List(values) => {
+            write_length(out, values.len(), (0x90, 16), [0, 0xdc, 0xdd]);
+            values.iter().for_each(|value| write_value(out, value));
+        }
+
+        // Write objects as maps with string keys
+        Json::Object(items) => {
+            write_length(out, items.len(), (0x80, 16), [0, 0xde, 0xdf]);
+            for (key, value) in items {
+                write_string(out, key);
+                write_value(out, value);
+            }
+        }
+
+        // Write strings
+        Json::String(string) => write_string(out, string),
+
+        // Write whole numbers as the smallest integer they fit in, as doubles otherwise
+        Json::NumberRaw(_) => write_value(out, &value.plain()),
+        Json::Number(number) => {
+            let integer = *number as i128;
+            match integer {
+                _ if (integer as f64).to_bits() != number.to_bits() => {
+                    out.push(0xcb);
+                    out.extend(number.to_be_bytes());
+                }
+                -32..=0x7f => out.push(integer as u8),
+                0x80..=0xff => out.extend([0xcc, integer as u8]),
+                0x100..=0xffff => {
+                    out.push(0xcd);
+                    out.extend((integer as u16).to_be_bytes());
+                }
+                0x1_0000..=0xffff_ffff => {
+                    out.push(0xce);
+                    out.extend((integer as u32).to_be_bytes());
+                }
+                0x1_0000_0000.. if integer <= i128::from(u64::MAX) => {
+                    out.push(0xcf);
+                    out.extend((integer as u64).to_be_bytes());
+                }
+                -0x80..=-33 => out.extend([0xd0, integer as u8]),
+                -0x8000..=-0x81 => {
+                    out.push(0xd1);
+                    out.extend((integer as i16).to_be_bytes());
+                }
+                -0x8000_0000..=-0x8001 => {
+                    out.push(0xd2);
+                    out.extend((integer as i32).to_be_bytes());
+                }
+                -0x8000_0000_0000_0000..=-0x8000_0001 => {
+                    out.push(0xd3);
+                    out.extend((integer as i64).to_be_bytes());
+                }
+                _ => {
+                    out.push(0xcb);
+                    out.extend(number.to_be_bytes());
+                }
+            }
+        }
+
+        // Write booleans and null
+        Json::Bool(false) => out.push(0xc2),
+        Json::Bool(true) => out.push(0xc3),
+        Json::Null => out.push(0xc0),
+    }
+}
+
+/// Reads MessagePack values from a byte slice
+struct Reader<'a> {
+    /// The bytes that haven't been read yet
+    bytes: &'a [u8],
+
+    /// The number of arrays and maps around the next value
+    depth: usize,
+}
+
+impl Reader<'_> {
+    /// Reads a fixed number of bytes
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let (taken, rest) = self
+            .bytes
+            .split_first_chunk()
+            .ok_or(Error::UnexpectedEndOfFile)?;
+        self.bytes = rest;
+        Ok(*taken)
+    }
+
+    /// Reads a big endian length of N bytes
+    fn length<const N: usize>(&mut self) -> Result<usize, Error> {
+        Ok(self
+            .take::<N>()?
+            .into_iter()
+            .fold(0, |length, byte| (length << 8) | usize::from(byte)))
+    }
+
+    /// Reads a string of the given length
+    fn string(&mut self, length: usize) -> Result<String, Error> {
+        if self.bytes.len() < length {
+            return Err(Error::UnexpectedEndOfFile);
+        }
+        let (string, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        String::from_utf8(string.to_vec()).map_err(|_| Error::InvalidValue)
+    }
+
+    /// Reads the items of an array or map, refusing to nest deeper than parsing does by default
+    fn nested(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<Json, Error>,
+    ) -> Result<Json, Error> {
+        if self.depth >= MAX_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
+        self.depth += 1;
+        let value = read(self);
+        self.depth -= 1;
+        value
+    }
+
+    /// Reads an array of the given length
+    fn array(&mut self, length: usize) -> Result<Json, Error> {
+        self.nested(|reader| {
+            (0..length)
+                .map(|_| reader.value())
+                .collect::<Result<_, _>>()
+                .map(Json::List)
+        })
+    }
+
+    /// Reads a map with string keys of the given length
+    fn map(&mut self, length: usize) -> Result<Json, Error> {
+        self.nested(|reader| {
+            (0..length)
+                .map(|_| match reader.value()? {
+                    Json::String(key) => Ok((key, reader.value()?)),
+                    _ => Err(Error::InvalidValue),
+                })
+                .collect::<Result<_, _>>()
+                .map(Json::Object)
+        })
+    }
+
+    /// Reads a value
+    fn value(&mut self) -> Result<Json, Error> {
+        let [marker] = self.take()?;
+        Ok(match marker {
+            // Read fixed size integers, strings, arrays and maps
+            0x00..=0x7f => Json::Number(f64::from(marker)),
+            0xe0..=0xff => Json::Number(f64::from(marker as i8)),
+            0xa0..=0xbf => Json::String(self.string(usize::from(marker & 0x1f))?),
+            0x90..=0x9f => self.array(usize::from(marker & 0x0f))?,
+            0x80..=0x8f => self.map(usize::from(marker & 0x0f))?,
+
+            // Read nil and booleans
+            0xc0 => Json::Null,
+            0xc2 => Json::Bool(false),
+            0xc3 => Json::Bool(true),
+
+            // Read floats
+            0xca => Json::Number(f64::from(f32::from_be_bytes(self.take()?))),
+            0xcb => Json::Number(f64::from_be_bytes(self.take()?)),
+
+            // Read unsigned integers
+            0xcc => Json::Number(f64::from(u8::from_be_bytes(self.take()?))),
+            0xcd => Json::Number(f64::from(u16::from_be_bytes(self.take()?))),
+            0xce => Json::Number(f64::from(u32::from_be_bytes(self.take()?))),
+            0xcf => Json::Number(u64::from_be_bytes(self.take()?) as f64),
+
+            // Read signed integers
+            0xd0 => Json::Number(f64::from(i8::from_be_bytes(self.take()?))),
+            0xd1 => Json::Number(f64::from(i16::from_be_bytes(self.take()?))),
+            0xd2 => Json::Number(f64::from(i32::from_be_bytes(self.take()?))),
+            0xd3 => Json::Number(i64::from_be_bytes(self.take()?) as f64),
+
+            // Read strings, arrays and maps with explicit lengths
+            0xd9..=0xdb => {
+                let length = match marker {
+                    0xd9 => self.length::<1>()?,
+                    0xda => self.length::<2>()?,
+                    _ => self.length::<4>()?,
+                };
+                Json::String(self.string(length)?)
+            }
+            0xdc | 0xdd => {
+                let length = match marker {
+                    0xdc => self.length::<2>()?,
+                    _ => self.length::<4>()?,
+                };
+                self.array(length)?
+            }
+            0xde | 0xdf => {
+                let length = match marker {
+                    0xde => self.length::<2>()?,
+                    _ => self.length::<4>()?,
+                };
+                self.map(length)?
+            }
+
+            // JSON has no binary data or extension types
+            _ => return Err(Error::InvalidValue),
+        })
+    }
+}
+
+impl Json {
+    /// Encodes the value as MessagePack
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_value(&mut out, self);
+        out
+    }
+
+    /// Decodes a value from MessagePack, failing if there are bytes left after it
+    ///
+    /// Arrays and maps nested deeper than 128 levels fail with [`Error::NestingTooDeep`], like
+    /// when parsing with the default options.
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader { bytes, depth: 0 };
+        let value = reader.value()?;
+        if !reader.bytes.is_empty() {
+            return Err(Error::TrailingCharacters);
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, vec::Vec};
+
+    use crate::{Error, Json};
+
+    #[test]
+    fn msgpack_encoding() {
+        assert_eq!(Json::Number(5.0).to_msgpack(), [0x05]);
+        assert_eq!(Json::Number(-5.0).to_msgpack(), [0xfb]);
+        assert_eq!(Json::Number(300.0).to_msgpack(), [0xcd, 0x01, 0x2c]);
+        assert_eq!(Json::Number(-200.0).to_msgpack(), [0xd1, 0xff, 0x38]);
+        assert_eq!(
+            Json::Object(Vec::from([(
+                "a".to_owned(),
+                Json::List(Vec::from([Json::Bool(true), Json::Null]))
+            )]))
+            .to_msgpack(),
+            [0x81, 0xa1, b'a', 0x92, 0xc3, 0xc0]
+        );
+    }
+
+    #[test]
+    fn msgpack_round_trip() {
+        let value = "{\"list\":[1,-2.25,\"text\",false,null,{}],\"big\":-12345678901}"
+            .parse::<Json>()
+            .unwrap();
+        assert_eq!(Json::from_msgpack(&value.to_msgpack()).unwrap(), value);
+        assert!(matches!(
+            Json::from_msgpack(&Json::Number(-0.0).to_msgpack()).unwrap(),
+            Json::Number(zero) if zero.is_sign_negative()
+        ));
+
+        let long = Json::String("x".repeat(300));
+        assert_eq!(Json::from_msgpack(&long.to_msgpack()).unwrap(), long);
+
+        // Whole numbers beyond 64 bits are written as floats
+        for number in [1e30, 18446744073709551616.0, -1e30] {
+            let value = Json::Number(number);
+            assert_eq!(Json::from_msgpack(&value.to_msgpack()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn msgpack_decoding() {
+        assert_eq!(
+            Json::from_msgpack(&[0xca, 0x3f, 0xc0, 0x00, 0x00]).unwrap(),
+            Json::Number(1.5)
+        );
+        assert!(Json::from_msgpack(&[0x92, 0x01]).is_err());
+        assert!(Json::from_msgpack(&[0x01, 0x01]).is_err());
+        assert!(Json::from_msgpack(&[0xc4, 0x00]).is_err());
+
+        // Arrays nested too deeply are refused instead of running out of stack
+        let deepest = [[0x91; 128].as_slice(), &[0xc0]].concat();
+        assert!(Json::from_msgpack(&deepest).is_ok());
+        assert!(matches!(
+            Json::from_msgpack(&[0x91; 1_000_000]),
+            Err(Error::NestingTooDeep)
+        ));
+    }
+}
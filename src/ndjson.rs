@@ -0,0 +1,118 @@
+//! Parsing of newline-delimited JSON (NDJSON / JSON Lines)
+
+use alloc::{boxed::Box, string::String};
+
+use crate::{Chars, Error, Json, ParseOptions, StrSource, parser::ParseState};
+
+/// An iterator parsing one JSON value per line, skipping empty lines
+pub struct JsonLines<I> {
+    /// The characters that haven't been read yet
+    iter: I,
+
+    /// The number of lines read so far
+    line: usize,
+}
+
+impl<I: Iterator<Item = char>> JsonLines<I> {
+    /// Creates an iterator parsing newline-delimited JSON from characters
+    pub fn new(iter: I) -> Self {
+        Self { iter, line: 0 }
+    }
+
+    /// Returns the number of lines read so far
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for JsonLines<I> {
+    type Item = Result<Json, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // Read the next line, stop if there are no chars left
+            let mut text = String::new();
+            let mut ended = true;
+            for ch in self.iter.by_ref() {
+                ended = false;
+                if ch == '\n' {
+                    break;
+                }
+                text.push(ch);
+            }
+            if ended {
+                return None;
+            }
+            self.line += 1;
+
+            // Skip empty lines
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            // Parse the line, adding the line number to errors
+            return Some(
+                Json::from_source_tracked(
+                    StrSource::new(&text),
+                    &mut ParseState::new(&ParseOptions::default()),
+                )
+                .map_err(|(error, _, column)| Error::At {
+                    line: self.line,
+                    column,
+                    error: Box::new(error),
+                }),
+            );
+        }
+    }
+}
+
+impl Json {
+    /// Parses newline-delimited JSON from characters, one value per line
+    pub fn lines<I: Iterator<Item = char>>(iter: I) -> JsonLines<I> {
+        JsonLines::new(iter)
+    }
+
+    /// Parses newline-delimited JSON from UTF-8 bytes, one value per line
+    pub fn lines_from_bytes<I: Iterator<Item = u8>>(iter: I) -> JsonLines<Chars<I>> {
+        JsonLines::new(Chars::new(iter))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{Error, Json};
+
+    #[test]
+    fn lines_parsing() {
+        let values = Json::lines("1\n\n  true \r\n[null]".chars())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            values,
+            [
+                Json::Number(1.0),
+                Json::Bool(true),
+                Json::List(Vec::from([Json::Null]))
+            ]
+        );
+        assert_eq!(Json::lines("".chars()).count(), 0);
+    }
+
+    #[test]
+    fn line_numbers_in_errors() {
+        let mut lines = Json::lines("{}\n\n{\"a\" 1}\n2 3\nnull".chars());
+        assert_eq!(lines.next().unwrap().unwrap(), Json::Object(Vec::new()));
+        assert!(matches!(
+            lines.next(),
+            Some(Err(Error::At { line: 3, column: 6, error })) if matches!(*error, Error::MissingSeparator)
+        ));
+        assert!(matches!(
+            lines.next(),
+            Some(Err(Error::At { line: 4, error, .. })) if matches!(*error, Error::TrailingCharacters)
+        ));
+        assert_eq!(lines.next().unwrap().unwrap(), Json::Null);
+        assert!(lines.next().is_none());
+    }
+}
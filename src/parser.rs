@@ -0,0 +1,386 @@
+//! Parsing many documents with the same reusable buffers
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::mem;
+
+use crate::{
+    Error, Json, ParseHooks, ParseOptions, Path, Scanner, Segment, Source, StrSource, ValueKind,
+};
+#[cfg(feature = "shared")]
+use crate::{KeyInterner, SharedJson, shared};
+
+/// The buffers values are read into before they're copied out at their final size
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Scratch {
+    /// The string being read
+    pub(crate) text: String,
+
+    /// The elements of the lists being read, the innermost list's last
+    pub(crate) values: Vec<Json>,
+
+    /// The properties of the objects being read, the innermost object's last
+    pub(crate) items: Vec<(String, Json)>,
+}
+
+impl Scratch {
+    /// Empties the buffers, keeping their capacity
+    fn clear(&mut self) {
+        self.text.clear();
+        self.values.clear();
+        self.items.clear();
+    }
+}
+
+/// How the reader stores the values it reads, so other representations than [`Json`] are read
+/// by the same code
+pub(crate) trait Storage {
+    /// A value
+    type Value;
+
+    /// A key of an object
+    type Key: AsRef<str>;
+
+    /// Stores a string
+    fn string(&mut self, text: &str) -> Self::Value;
+
+    /// Stores a key of an object
+    fn key(&mut self, text: &str) -> Self::Key;
+
+    /// Stores a number, boolean or null
+    fn scalar(&mut self, value: Json) -> Self::Value;
+
+    /// Stores a list
+    fn list(&mut self, values: Vec<Self::Value>) -> Self::Value;
+
+    /// Stores an object
+    fn object(&mut self, items: Vec<(Self::Key, Self::Value)>) -> Self::Value;
+
+    /// Returns the buffer the elements of lists are read into before they're stored
+    fn values<'s>(&'s mut self, scratch: &'s mut Scratch) -> &'s mut Vec<Self::Value>;
+
+    /// Returns the buffer the properties of objects are read into before they're stored
+    fn items<'s>(&'s mut self, scratch: &'s mut Scratch) -> &'s mut Items<Self>;
+
+    /// Returns a value as [`Json`] for the callbacks
+    fn to_json(value: &Self::Value) -> Cow<'_, Json>;
+}
+
+/// The properties of objects as a storage keeps them
+pub(crate) type Items<T> = Vec<(<T as Storage>::Key, <T as Storage>::Value)>;
+
+/// Stores values as [`Json`], reading lists and objects into the buffers of the parser
+pub(crate) struct Owned;
+
+impl Storage for Owned {
+    type Value = Json;
+    type Key = String;
+
+    fn string(&mut self, text: &str) -> Json {
+        Json::String(String::from(text))
+    }
+
+    fn key(&mut self, text: &str) -> String {
+        String::from(text)
+    }
+
+    fn scalar(&mut self, value: Json) -> Json {
+        value
+    }
+
+    fn list(&mut self, values: Vec<Json>) -> Json {
+        Json::List(values)
+    }
+
+    fn object(&mut self, items: Vec<(String, Json)>) -> Json {
+        Json::Object(items)
+    }
+
+    fn values<'s>(&'s mut self, scratch: &'s mut Scratch) -> &'s mut Vec<Json> {
+        &mut scratch.values
+    }
+
+    fn items<'s>(&'s mut self, scratch: &'s mut Scratch) -> &'s mut Items<Self> {
+        &mut scratch.items
+    }
+
+    fn to_json(value: &Json) -> Cow<'_, Json> {
+        Cow::Borrowed(value)
+    }
+}
+
+/// How much the buffers of a [`Parser`] hold before they have to grow
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capacity {
+    /// The length of the longest string in bytes
+    pub string: usize,
+
+    /// The number of elements of the largest list
+    pub elements: usize,
+
+    /// The number of properties of the largest object
+    pub properties: usize,
+}
+
+impl Capacity {
+    /// Measures the longest string and the largest list and object of a document by scanning
+    /// it without allocating, stopping at the first error
+    ///
+    /// Strings are measured in bytes once their escapes are decoded. Like the parser by default,
+    /// the scanner refuses nesting deeper than 128 levels, so deeper documents are only measured
+    /// up to there.
+    pub fn scan(text: &str) -> Self {
+        let mut capacity = Self::default();
+
+        // Follow the scanner through the characters to measure the strings it finds
+        let mut chars = text.chars();
+        let mut offset = 0;
+
+        // Count the values at each depth, which belong to the last list or object above them
+        let mut counts = [0; 129];
+        for boundary in Scanner::new(text.chars()).map_while(Result::ok) {
+            let children = counts.get_mut(boundary.depth + 1).map_or(0, mem::take);
+            match boundary.kind {
+                ValueKind::String => {
+                    // Measure the characters between the quotes, leaving the closing quote
+                    let string = chars
+                        .by_ref()
+                        .skip(boundary.start + 1 - offset)
+                        .take(boundary.end - boundary.start - 2);
+                    capacity.string = capacity.string.max(decoded_len(string));
+                    offset = boundary.end - 1;
+                }
+                ValueKind::List => capacity.elements = capacity.elements.max(children),
+                ValueKind::Object => capacity.properties = capacity.properties.max(children),
+                _ => {}
+            }
+            counts[boundary.depth] += 1;
+        }
+        capacity
+    }
+}
+
+/// Returns the length in bytes of the characters of a valid string once its escapes are decoded
+fn decoded_len<I: Iterator<Item = char>>(mut chars: I) -> usize {
+    let mut len = 0;
+    while let Some(ch) = chars.next() {
+        let ch = match ch {
+            '\\' => Json::read_escape(&mut chars).unwrap_or(char::REPLACEMENT_CHARACTER),
+            ch => ch,
+        };
+        len += ch.len_utf8();
+    }
+    len
+}
+
+/// A parser keeping its buffers between documents, so parsing many of them, like the lines of
+/// NDJSON, doesn't allocate and free the buffers each time
+///
+/// Strings, lists and objects are read into the buffers and copied out at their final size, so
+/// the values don't have spare capacity either.
+#[derive(Debug, Clone, Default)]
+pub struct Parser {
+    /// The options used for every document
+    pub options: ParseOptions,
+
+    /// The buffers, kept between documents
+    scratch: Scratch,
+}
+
+impl Parser {
+    /// Creates a parser with options
+    pub fn new(options: ParseOptions) -> Self {
+        Self {
+            options,
+            scratch: Scratch::default(),
+        }
+    }
+
+    /// Creates a parser with options, with buffers that hold a capacity before they grow
+    pub fn with_capacity(options: ParseOptions, capacity: Capacity) -> Self {
+        let mut parser = Self::new(options);
+        parser.reserve(capacity);
+        parser
+    }
+
+    /// Grows the buffers to hold at least a capacity, like one from [`Capacity::scan`] for the
+    /// next document
+    pub fn reserve(&mut self, capacity: Capacity) {
+        self.scratch.text.reserve(capacity.string);
+        self.scratch.values.reserve(capacity.elements);
+        self.scratch.items.reserve(capacity.properties);
+    }
+
+    /// Parses a complete JSON value from a string, adding the line and column to errors
+    pub fn parse(&mut self, text: &str) -> Result<Json, Error> {
+        self.parse_source(StrSource::new(text))
+    }
+
+    /// Parses a complete JSON value from a source, adding the line and column to errors
+    pub fn parse_source<S: Source>(&mut self, source: S) -> Result<Json, Error> {
+        self.with_state(|state| Json::from_source_located(source, state))
+    }
+
+    /// Parses a complete JSON value from a string as a [`SharedJson`], taking its keys from an
+    /// interner as they're read and adding the line and column to errors
+    #[cfg(feature = "shared")]
+    pub fn parse_interned(
+        &mut self,
+        text: &str,
+        interner: &mut KeyInterner,
+    ) -> Result<SharedJson, Error> {
+        self.with_state(|state| shared::parse_interned(StrSource::new(text), state, interner))
+    }
+
+    /// Parses with the options and the buffers of the parser
+    fn with_state<T>(
+        &mut self,
+        parse: impl FnOnce(&mut ParseState<'_>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut state = ParseState::new(&self.options);
+        state.scratch = mem::take(&mut self.scratch);
+        let result = parse(&mut state);
+
+        // Keep the buffers, which an error may have left values in
+        self.scratch = state.scratch;
+        self.scratch.clear();
+        result
+    }
+}
+
+/// What the parser keeps track of besides the characters
+pub(crate) struct ParseState<'a> {
+    /// The options of the parser
+    pub(crate) options: &'a ParseOptions,
+
+    /// The callbacks, if any
+    hooks: Option<&'a mut dyn ParseHooks>,
+
+    /// The path to the value being read, only kept up to date for the callbacks
+    path: Path,
+
+    /// The buffers strings, lists and objects are read into
+    pub(crate) scratch: Scratch,
+}
+
+impl<'a> ParseState<'a> {
+    /// Starts parsing with options and without callbacks
+    pub(crate) fn new(options: &'a ParseOptions) -> Self {
+        Self {
+            options,
+            hooks: None,
+            path: Path::new(),
+            scratch: Scratch::default(),
+        }
+    }
+
+    /// Starts parsing with options and callbacks
+    pub(crate) fn with_hooks(options: &'a ParseOptions, hooks: &'a mut dyn ParseHooks) -> Self {
+        Self {
+            options,
+            hooks: Some(hooks),
+            path: Path::new(),
+            scratch: Scratch::default(),
+        }
+    }
+
+    /// Enters the property with a key, telling the callbacks about it
+    pub(crate) fn enter_key(&mut self, key: &str) -> Result<(), Error> {
+        if let Some(hooks) = &mut self.hooks {
+            hooks.on_key(&self.path, key)?;
+            self.path.push(key);
+        }
+        Ok(())
+    }
+
+    /// Enters the element at an index
+    pub(crate) fn enter_index(&mut self, index: usize) {
+        if self.hooks.is_some() {
+            self.path.push(Segment::Index(index));
+        }
+    }
+
+    /// Leaves the property or element entered last
+    pub(crate) fn leave(&mut self) {
+        if self.hooks.is_some() {
+            self.path.pop();
+        }
+    }
+
+    /// Tells the callbacks about a value that was read
+    pub(crate) fn read<T: Storage>(&mut self, value: &T::Value) -> Result<(), Error> {
+        match &mut self.hooks {
+            Some(hooks) => hooks.on_value(&self.path, &T::to_json(value)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Capacity, Parser};
+    use crate::{Error, Json, ParseOptions};
+
+    #[test]
+    fn reusing() {
+        let mut parser = Parser::default();
+        for line in [
+            "{\"a\":[1,2,{\"b\":\"long enough text\"}]}",
+            "[]",
+            "\"x\"",
+            "[[1], 2",
+        ] {
+            match line.parse::<Json>() {
+                Ok(expected) => assert_eq!(parser.parse(line).unwrap(), expected),
+                Err(_) => assert!(parser.parse(line).is_err()),
+            }
+        }
+
+        // Values come out without spare capacity, and the buffers stay for the next document
+        let Json::List(values) = parser.parse("[1, 2, 3, 4, 5]").unwrap() else {
+            unreachable!()
+        };
+        assert_eq!(values.capacity(), 5);
+        assert!(parser.scratch.values.capacity() >= 5);
+        assert!(parser.scratch.values.is_empty());
+
+        // The options apply to every document
+        let mut parser = Parser::new(ParseOptions {
+            max_depth: Some(1),
+            ..ParseOptions::default()
+        });
+        assert!(parser.parse("[1]").is_ok());
+        assert!(matches!(
+            parser.parse("[[1]]"),
+            Err(Error::At { error, .. }) if matches!(*error, Error::NestingTooDeep)
+        ));
+    }
+
+    #[test]
+    fn capacity() {
+        let text = "{\"a\":[1,2,3,[4,5,6,7]],\"b\":\"hello\",\"c\":{\"d\":null}}";
+        let capacity = Capacity::scan(text);
+        assert_eq!(
+            capacity,
+            Capacity {
+                string: 5,
+                elements: 4,
+                properties: 3,
+            }
+        );
+
+        // The buffers start out large enough
+        let mut parser = Parser::with_capacity(ParseOptions::default(), capacity);
+        assert!(parser.scratch.values.capacity() >= 4 && parser.scratch.items.capacity() >= 3);
+        assert_eq!(parser.parse(text).unwrap(), text.parse::<Json>().unwrap());
+        assert_eq!(Capacity::scan("[1,"), Capacity::default());
+
+        // Strings are measured in bytes once decoded, which is what the buffer holds
+        let text = "[\"\\u00e9\\ud83e\\udd80\\n\", \"ab\", \"\\\"\", \"☃\"]";
+        assert_eq!(text.parse::<Json>().unwrap()[0], "é🦀\n");
+        assert_eq!(Capacity::scan(text).string, "é🦀\n".len());
+
+        // Scanning stops where the nesting gets deeper than the parser allows by default
+        let deep = "[".repeat(129) + "\"abc\"" + &"]".repeat(129);
+        assert_eq!(Capacity::scan(&deep).string, 0);
+    }
+}
@@ -0,0 +1,602 @@
+//! JSON Patch (RFC 6902) operations
+
+use alloc::{borrow::ToOwned, boxed::Box, string::String, vec::Vec};
+use core::fmt::{self, Display, Formatter};
+
+use crate::{
+    Json, Path,
+    pointer::{index, push, split_last},
+};
+
+/// A single operation of a JSON Patch document
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOperation {
+    /// Adds a value to an object or inserts it into a list
+    Add {
+        /// The location to add the value at
+        path: String,
+
+        /// The value to add
+        value: Json,
+    },
+
+    /// Removes the value at a location
+    Remove {
+        /// The location of the value to remove
+        path: String,
+    },
+
+    /// Replaces the value at a location
+    Replace {
+        /// The location of the value to replace
+        path: String,
+
+        /// The new value
+        value: Json,
+    },
+
+    /// Moves the value at one location to another
+    Move {
+        /// The location of the value to move
+        from: String,
+
+        /// The location to move the value to
+        path: String,
+    },
+
+    /// Copies the value at one location to another
+    Copy {
+        /// The location of the value to copy
+        from: String,
+
+        /// The location to copy the value to
+        path: String,
+    },
+
+    /// Checks that the value at a location equals the given value
+    Test {
+        /// The location of the value to check
+        path: String,
+
+        /// The expected value
+        value: Json,
+    },
+}
+
+impl PatchOperation {
+    /// Returns the name of the operation, as used in the `op` property
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatchOperation::Add { .. } => "add",
+            PatchOperation::Remove { .. } => "remove",
+            PatchOperation::Replace { .. } => "replace",
+            PatchOperation::Move { .. } => "move",
+            PatchOperation::Copy { .. } => "copy",
+            PatchOperation::Test { .. } => "test",
+        }
+    }
+
+    /// Returns the location the operation applies to
+    pub fn path(&self) -> &str {
+        match self {
+            PatchOperation::Add { path, .. }
+            | PatchOperation::Remove { path }
+            | PatchOperation::Replace { path, .. }
+            | PatchOperation::Move { path, .. }
+            | PatchOperation::Copy { path, .. }
+            | PatchOperation::Test { path, .. } => path,
+        }
+    }
+
+    /// Returns the location the operation applies to as a typed path, or `None` if it isn't a
+    /// valid JSON pointer
+    pub fn to_path(&self) -> Option<Path> {
+        Path::parse(self.path())
+    }
+
+    /// Returns the operation with its locations moved into the value at a JSON pointer
+    pub(crate) fn prefixed(mut self, prefix: &str) -> Self {
+        match &mut self {
+            PatchOperation::Move { from, path } | PatchOperation::Copy { from, path } => {
+                from.insert_str(0, prefix);
+                path.insert_str(0, prefix);
+            }
+            PatchOperation::Add { path, .. }
+            | PatchOperation::Remove { path }
+            | PatchOperation::Replace { path, .. }
+            | PatchOperation::Test { path, .. } => path.insert_str(0, prefix),
+        }
+        self
+    }
+}
+
+impl From<&PatchOperation> for Json {
+    fn from(operation: &PatchOperation) -> Self {
+        let mut items = Vec::from([
+            ("op".to_owned(), Json::String(operation.name().to_owned())),
+            ("path".to_owned(), Json::String(operation.path().to_owned())),
+        ]);
+        match operation {
+            PatchOperation::Add { value, .. }
+            | PatchOperation::Replace { value, .. }
+            | PatchOperation::Test { value, .. } => items.push(("value".to_owned(), value.clone())),
+            PatchOperation::Move { from, .. } | PatchOperation::Copy { from, .. } => {
+                items.push(("from".to_owned(), Json::String(from.clone())));
+            }
+            PatchOperation::Remove { .. } => {}
+        }
+        Json::Object(items)
+    }
+}
+
+/// An error that occured while applying a patch
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchError {
+    /// The patch document isn't a valid list of operations
+    InvalidPatch,
+
+    /// A location the patch refers to doesn't exist
+    NotFound(String),
+
+    /// A `test` operation found a different value
+    TestFailed(String),
+
+    /// An operation of a patch failed, leaving the document as it was
+    At {
+        /// The index of the operation in the patch
+        index: usize,
+
+        /// Why the operation failed
+        error: Box<PatchError>,
+    },
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::InvalidPatch => write!(f, "invalid patch document"),
+            PatchError::NotFound(path) => write!(f, "no value at \"{path}\""),
+            PatchError::TestFailed(path) => write!(f, "test failed at \"{path}\""),
+            PatchError::At { index, error } => write!(f, "{error} in operation {index}"),
+        }
+    }
+}
+
+impl core::error::Error for PatchError {}
+
+impl TryFrom<&Json> for PatchOperation {
+    type Error = PatchError;
+
+    fn try_from(value: &Json) -> Result<Self, Self::Error> {
+        // Look up a string or any other member of the operation object
+        let Json::Object(_) = value else {
+            return Err(PatchError::InvalidPatch);
+        };
+        let member = |name| value.pointer(name).ok_or(PatchError::InvalidPatch);
+        let string = |name| match member(name)? {
+            Json::String(string) => Ok(string.clone()),
+            _ => Err(PatchError::InvalidPatch),
+        };
+
+        // Read the members used by the operation
+        let path = string("/path")?;
+        Ok(match string("/op")?.as_str() {
+            "add" => PatchOperation::Add {
+                path,
+                value: member("/value")?.clone(),
+            },
+            "remove" => PatchOperation::Remove { path },
+            "replace" => PatchOperation::Replace {
+                path,
+                value: member("/value")?.clone(),
+            },
+            "move" => PatchOperation::Move {
+                from: string("/from")?,
+                path,
+            },
+            "copy" => PatchOperation::Copy {
+                from: string("/from")?,
+                path,
+            },
+            "test" => PatchOperation::Test {
+                path,
+                value: member("/value")?.clone(),
+            },
+            _ => return Err(PatchError::InvalidPatch),
+        })
+    }
+}
+
+/// Inserts a value at a location, replacing the value of an existing property
+fn add(document: &mut Json, path: &str, value: Json) -> Result<(), PatchError> {
+    // Adding to the root replaces the whole document
+    let not_found = || PatchError::NotFound(path.to_owned());
+    let Some((parent, token)) = split_last(path) else {
+        if !path.is_empty() {
+            return Err(not_found());
+        }
+        *document = value;
+        return Ok(());
+    };
+
+    // Set the property, or insert the value into the list
+    match document.pointer_mut(parent).ok_or_else(not_found)? {
+        Json::Object(items) => match items.iter_mut().rev().find(|(key, _)| *key == token) {
+            Some((_, old)) => *old = value,
+            None => items.push((token.into_owned(), value)),
+        },
+        Json::List(values) if token == "-" => values.push(value),
+        Json::List(values) => match index(&token).filter(|&index| index <= values.len()) {
+            Some(index) => values.insert(index, value),
+            None => return Err(not_found()),
+        },
+        _ => return Err(not_found()),
+    }
+    Ok(())
+}
+
+/// Removes the value at a location, returning it
+fn remove(document: &mut Json, path: &str) -> Result<Json, PatchError> {
+    let not_found = || PatchError::NotFound(path.to_owned());
+    let (parent, token) = split_last(path).ok_or_else(not_found)?;
+    match document.pointer_mut(parent).ok_or_else(not_found)? {
+        Json::Object(items) => {
+            let position = items.iter().rposition(|(key, _)| *key == token);
+            Ok(items.remove(position.ok_or_else(not_found)?).1)
+        }
+        Json::List(values) => match index(&token).filter(|&index| index < values.len()) {
+            Some(index) => Ok(values.remove(index)),
+            None => Err(not_found()),
+        },
+        _ => Err(not_found()),
+    }
+}
+
+/// Applies a single operation
+fn apply(document: &mut Json, operation: &PatchOperation) -> Result<(), PatchError> {
+    match operation {
+        PatchOperation::Add { path, value } => add(document, path, value.clone())?,
+        PatchOperation::Remove { path } => {
+            remove(document, path)?;
+        }
+        PatchOperation::Replace { path, value } => {
+            *document
+                .pointer_mut(path)
+                .ok_or_else(|| PatchError::NotFound(path.clone()))? = value.clone();
+        }
+        PatchOperation::Move { from, path } => {
+            // A value can't be moved into one of its own children
+            if path
+                .strip_prefix(from.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+            {
+                return Err(PatchError::InvalidPatch);
+            }
+            let value = remove(document, from)?;
+            add(document, path, value)?;
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = document
+                .pointer(from)
+                .ok_or_else(|| PatchError::NotFound(from.clone()))?
+                .clone();
+            add(document, path, value)?;
+        }
+        PatchOperation::Test { path, value } => {
+            if !document
+                .pointer(path)
+                .is_some_and(|found| found.equivalent(value))
+            {
+                return Err(PatchError::TestFailed(path.clone()));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Json {
+    /// Converts patch operations to a JSON Patch document
+    pub fn from_patch(operations: &[PatchOperation]) -> Self {
+        Json::List(operations.iter().map(Json::from).collect())
+    }
+
+    /// Converts a JSON Patch document to its operations
+    pub fn to_patch(&self) -> Result<Vec<PatchOperation>, PatchError> {
+        match self {
+            Json::List(operations) => operations.iter().map(PatchOperation::try_from).collect(),
+            _ => Err(PatchError::InvalidPatch),
+        }
+    }
+
+    /// Returns the operations undoing a patch, which has to record the value before every
+    /// removal and replacement with a `test` of the same path, like [`Json::diff_invertible`]
+    ///
+    /// Added and moved values are assumed not to replace anything. Fails with
+    /// [`PatchError::InvalidPatch`] if a previous value is missing, the patch copies values or
+    /// it appends to a list with `-`.
+    pub fn invert_patch(operations: &[PatchOperation]) -> Result<Vec<PatchOperation>, PatchError> {
+        let mut steps = Vec::new();
+        let mut operations = operations.iter().peekable();
+        while let Some(operation) = operations.next() {
+            let test = |path: &String, value: &Json| PatchOperation::Test {
+                path: path.clone(),
+                value: value.clone(),
+            };
+            steps.push(match operation {
+                // Restore removed and replaced values recorded by a test before them
+                PatchOperation::Test { path, value } => match operations.next_if(|next| {
+                    matches!(next, PatchOperation::Remove { path: next }
+                        | PatchOperation::Replace { path: next, .. } if next == path)
+                }) {
+                    Some(PatchOperation::Remove { .. }) => Vec::from([PatchOperation::Add {
+                        path: path.clone(),
+                        value: value.clone(),
+                    }]),
+                    Some(PatchOperation::Replace { value: new, .. }) => Vec::from([
+                        test(path, new),
+                        PatchOperation::Replace {
+                            path: path.clone(),
+                            value: value.clone(),
+                        },
+                    ]),
+                    _ => Vec::from([operation.clone()]),
+                },
+
+                // Remove added values, and move moved values back
+                PatchOperation::Add { path, value } if !path.ends_with("/-") => Vec::from([
+                    test(path, value),
+                    PatchOperation::Remove { path: path.clone() },
+                ]),
+                PatchOperation::Move { from, path } => Vec::from([PatchOperation::Move {
+                    from: path.clone(),
+                    path: from.clone(),
+                }]),
+                _ => return Err(PatchError::InvalidPatch),
+            });
+        }
+        Ok(steps.into_iter().rev().flatten().collect())
+    }
+
+    /// Applies JSON Patch operations in order, either all of them or none
+    ///
+    /// If an operation fails, including a `test`, the value is left as it was and the error says
+    /// which operation failed.
+    pub fn apply_patch(&mut self, operations: &[PatchOperation]) -> Result<(), PatchError> {
+        let mut patched = self.clone();
+        for (index, operation) in operations.iter().enumerate() {
+            apply(&mut patched, operation).map_err(|error| PatchError::At {
+                index,
+                error: Box::new(error),
+            })?;
+        }
+        *self = patched;
+        Ok(())
+    }
+
+    /// Applies a JSON Merge Patch (RFC 7396), where `null` removes a property
+    pub fn merge_patch(&mut self, patch: &Json) {
+        // Anything but an object replaces the value
+        let Json::Object(patch) = patch else {
+            *self = patch.clone();
+            return;
+        };
+        if !matches!(self, Json::Object(_)) {
+            *self = Json::Object(Vec::new());
+        }
+        let Json::Object(items) = self else {
+            unreachable!()
+        };
+
+        // Remove properties patched with null, and merge the others
+        for (key, value) in patch {
+            let position = items.iter().rposition(|(name, _)| name == key);
+            match (position, value) {
+                (Some(_), Json::Null) => items.retain(|(name, _)| name != key),
+                (None, Json::Null) => {}
+                (Some(position), value) => items[position].1.merge_patch(value),
+                (None, value) => {
+                    let mut merged = Json::Null;
+                    merged.merge_patch(value);
+                    items.push((key.clone(), merged));
+                }
+            }
+        }
+    }
+
+    /// Merges another value into this one, letting a callback resolve conflicts
+    ///
+    /// Objects are merged property by property and properties only the other value has are
+    /// added. Wherever both values have something else, `resolve` is called with the JSON
+    /// pointer to it, this value and the other one, and returns the merged value.
+    pub fn merge_with<F: FnMut(&str, &Json, &Json) -> Json>(
+        &mut self,
+        other: &Json,
+        mut resolve: F,
+    ) {
+        merge(self, other, &mut String::new(), &mut resolve);
+    }
+}
+
+/// Merges a value into another at a JSON pointer, which is restored afterwards
+fn merge<F: FnMut(&str, &Json, &Json) -> Json>(
+    value: &mut Json,
+    other: &Json,
+    pointer: &mut String,
+    resolve: &mut F,
+) {
+    // Let the callback decide unless both values are objects
+    let (Json::Object(items), Json::Object(others)) = (&mut *value, other) else {
+        *value = resolve(pointer, value, other);
+        return;
+    };
+
+    // Merge shared properties and add the others
+    for (key, other) in others {
+        match items.iter().rposition(|(name, _)| name == key) {
+            Some(position) => {
+                let length = pointer.len();
+                push(pointer, key);
+                merge(&mut items[position].1, other, pointer, resolve);
+                pointer.truncate(length);
+            }
+            None => items.push((key.clone(), other.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, boxed::Box, vec::Vec};
+
+    use crate::{Json, PatchError};
+
+    /// Parses a document, panicking if it's invalid
+    fn json(text: &str) -> Json {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn applying() {
+        let mut document = json("{\"a\":{\"b\":[1,2]},\"c\":\"d\"}");
+        let patch = json(
+            "[{\"op\":\"add\",\"path\":\"/a/b/1\",\"value\":3},\
+            {\"op\":\"add\",\"path\":\"/a/b/-\",\"value\":4},\
+            {\"op\":\"remove\",\"path\":\"/a/b/0\"},\
+            {\"op\":\"replace\",\"path\":\"/c\",\"value\":null},\
+            {\"op\":\"move\",\"from\":\"/c\",\"path\":\"/e\"},\
+            {\"op\":\"copy\",\"from\":\"/a/b\",\"path\":\"/f\"},\
+            {\"op\":\"test\",\"path\":\"/f/2\",\"value\":4}]",
+        );
+        document.apply_patch(&patch.to_patch().unwrap()).unwrap();
+        assert_eq!(
+            document,
+            json("{\"a\":{\"b\":[3,2,4]},\"e\":null,\"f\":[3,2,4]}")
+        );
+    }
+
+    #[test]
+    fn failing() {
+        let mut document = json("{\"a\":[1]}");
+        let patch = |text| json(text).to_patch();
+        let at = |index, error| {
+            Err(PatchError::At {
+                index,
+                error: Box::new(error),
+            })
+        };
+        assert_eq!(
+            patch("[{\"op\":\"jump\",\"path\":\"\"}]"),
+            Err(PatchError::InvalidPatch)
+        );
+        assert_eq!(
+            document.apply_patch(&patch("[{\"op\":\"remove\",\"path\":\"/a/1\"}]").unwrap()),
+            at(0, PatchError::NotFound("/a/1".to_owned()))
+        );
+        assert_eq!(
+            document
+                .apply_patch(&patch("[{\"op\":\"test\",\"path\":\"/a/0\",\"value\":2}]").unwrap()),
+            at(0, PatchError::TestFailed("/a/0".to_owned()))
+        );
+        assert_eq!(
+            document.apply_patch(
+                &patch("[{\"op\":\"move\",\"from\":\"/a\",\"path\":\"/a/0\"}]").unwrap()
+            ),
+            at(0, PatchError::InvalidPatch)
+        );
+
+        // A failing operation undoes the ones before it
+        assert_eq!(
+            document.apply_patch(
+                &patch(
+                    "[{\"op\":\"add\",\"path\":\"/b\",\"value\":1},\
+                    {\"op\":\"remove\",\"path\":\"/a/0\"},\
+                    {\"op\":\"test\",\"path\":\"/b\",\"value\":2}]"
+                )
+                .unwrap()
+            ),
+            at(2, PatchError::TestFailed("/b".to_owned()))
+        );
+        assert_eq!(document, json("{\"a\":[1]}"));
+    }
+
+    #[test]
+    fn testing_equivalent_values() {
+        // Tests ignore the order of properties and compare raw numbers by their value
+        let mut document = json("{\"a\":{\"b\":1,\"c\":2},\"d\":3}");
+        let test = json("[{\"op\":\"test\",\"path\":\"/a\",\"value\":{\"c\":2,\"b\":1}}]");
+        assert_eq!(document.apply_patch(&test.to_patch().unwrap()), Ok(()));
+        *document.pointer_mut("/d").unwrap() = Json::NumberRaw("3.0".to_owned());
+        let test = json("[{\"op\":\"test\",\"path\":\"/d\",\"value\":3}]");
+        assert_eq!(document.apply_patch(&test.to_patch().unwrap()), Ok(()));
+    }
+
+    #[test]
+    fn merging() {
+        let mut document = json("{\"a\":\"b\",\"c\":{\"d\":\"e\",\"f\":\"g\"}}");
+        document.merge_patch(&json("{\"a\":\"z\",\"c\":{\"f\":null},\"h\":{\"i\":null}}"));
+        assert_eq!(document, json("{\"a\":\"z\",\"c\":{\"d\":\"e\"},\"h\":{}}"));
+        document.merge_patch(&json("[1]"));
+        assert_eq!(document, json("[1]"));
+    }
+
+    #[test]
+    fn merging_with() {
+        let mut document = json("{\"name\":\"app\",\"tags\":[\"a\"],\"limits\":{\"cpu\":1}}");
+        let mut conflicts = Vec::new();
+        document.merge_with(
+            &json("{\"tags\":[\"b\"],\"limits\":{\"cpu\":2,\"memory\":3},\"debug\":true}"),
+            |pointer, left, right| {
+                conflicts.push(pointer.to_owned());
+                match (left, right) {
+                    (Json::List(left), Json::List(right)) => {
+                        Json::List(left.iter().chain(right).cloned().collect())
+                    }
+                    (Json::Number(left), Json::Number(right)) => Json::Number(left.max(*right)),
+                    _ => right.clone(),
+                }
+            },
+        );
+        assert_eq!(
+            document,
+            json(
+                "{\"name\":\"app\",\"tags\":[\"a\",\"b\"],\
+                \"limits\":{\"cpu\":2,\"memory\":3},\"debug\":true}"
+            )
+        );
+        assert_eq!(conflicts, ["/tags", "/limits/cpu"]);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let source = json("{\"a\":[1,2,3],\"b\":{\"c\":1}}");
+        let target = json("{\"a\":[1,4],\"b\":{\"d\":1},\"e\":true}");
+        let mut document = source.clone();
+        document.apply_patch(&source.diff(&target)).unwrap();
+        assert_eq!(document, target);
+    }
+
+    #[test]
+    fn inverting() {
+        let source = json("{\"a\":[1,2,3],\"b\":{\"c\":1},\"d\":\"x\"}");
+        let target = json("{\"a\":[1,4],\"b\":{\"d\":1},\"d\":\"y\",\"e\":true}");
+        let patch = source.diff_invertible(&target);
+        let mut document = source.clone();
+        document.apply_patch(&patch).unwrap();
+        assert_eq!(document, target);
+
+        // The inverse undoes the patch, and inverting it again redoes it
+        let inverse = Json::invert_patch(&patch).unwrap();
+        document.apply_patch(&inverse).unwrap();
+        assert_eq!(document, source);
+        document
+            .apply_patch(&Json::invert_patch(&inverse).unwrap())
+            .unwrap();
+        assert_eq!(document, target);
+
+        // Patches without the previous values can't be inverted
+        assert_eq!(
+            Json::invert_patch(&source.diff(&target)),
+            Err(PatchError::InvalidPatch)
+        );
+    }
+}
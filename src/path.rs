@@ -0,0 +1,256 @@
+//! Typed paths to values, made of keys and indices
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Display, Formatter};
+
+use crate::{
+    Json,
+    pointer::{index, push, tokens},
+};
+
+/// A step of a [`Path`] into a list or object
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Segment {
+    /// The property with a key
+    Key(String),
+
+    /// The element at an index
+    Index(usize),
+}
+
+impl From<&str> for Segment {
+    fn from(key: &str) -> Self {
+        Segment::Key(key.into())
+    }
+}
+
+impl From<String> for Segment {
+    fn from(key: String) -> Self {
+        Segment::Key(key)
+    }
+}
+
+impl From<usize> for Segment {
+    fn from(index: usize) -> Self {
+        Segment::Index(index)
+    }
+}
+
+/// The location of a value in a document, like `/items/0/name` as a JSON pointer
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Path {
+    /// The steps from the top-level value
+    segments: Vec<Segment>,
+}
+
+impl Path {
+    /// Returns the path to the top-level value
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a JSON pointer, returning `None` if it's invalid
+    ///
+    /// Tokens that are valid indices become [`Segment::Index`], which still match the property
+    /// with that key, since a pointer can't tell them apart.
+    pub fn parse(pointer: &str) -> Option<Self> {
+        let segments = tokens(pointer)?
+            .map(|token| match index(&token) {
+                Some(index) => Segment::Index(index),
+                None => Segment::Key(token.into_owned()),
+            })
+            .collect();
+        Some(Self { segments })
+    }
+
+    /// Returns the steps from the top-level value
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns whether this is the path to the top-level value
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Adds a step to the end of the path
+    pub fn push<S: Into<Segment>>(&mut self, segment: S) {
+        self.segments.push(segment.into());
+    }
+
+    /// Removes the last step, returning it
+    pub fn pop(&mut self) -> Option<Segment> {
+        self.segments.pop()
+    }
+
+    /// Returns the path with a step added, like `Path::new().join("items").join(0)`
+    pub fn join<S: Into<Segment>>(mut self, segment: S) -> Self {
+        self.push(segment);
+        self
+    }
+
+    /// Returns the path to the parent value, or `None` for the top-level value
+    pub fn parent(&self) -> Option<Path> {
+        let (_, parent) = self.segments.split_last()?;
+        Some(Self {
+            segments: parent.to_vec(),
+        })
+    }
+
+    /// Returns whether this path leads through or to the value at another path
+    pub fn starts_with(&self, prefix: &Path) -> bool {
+        self.segments.starts_with(&prefix.segments)
+    }
+}
+
+impl FromIterator<Segment> for Path {
+    fn from_iter<I: IntoIterator<Item = Segment>>(iter: I) -> Self {
+        Self {
+            segments: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Writes the path as a JSON pointer
+impl Display for Path {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut pointer = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Key(key) => push(&mut pointer, key),
+                Segment::Index(index) => push(&mut pointer, &index.to_string()),
+            }
+        }
+        f.write_str(&pointer)
+    }
+}
+
+/// Returns whether a key is the one a segment refers to
+fn matches(segment: &Segment, key: &str) -> bool {
+    match segment {
+        Segment::Key(name) => name == key,
+        Segment::Index(index) => self::index(key) == Some(*index),
+    }
+}
+
+impl Json {
+    /// Returns the value at a path
+    pub fn get_path(&self, path: &Path) -> Option<&Json> {
+        path.segments
+            .iter()
+            .try_fold(self, |value, segment| match (value, segment) {
+                (Json::Object(items), segment) => items
+                    .iter()
+                    .rev()
+                    .find(|(key, _)| matches(segment, key))
+                    .map(|(_, value)| value),
+                (Json::List(values), Segment::Index(index)) => values.get(*index),
+                _ => None,
+            })
+    }
+
+    /// Returns a mutable reference to the value at a path
+    pub fn get_path_mut(&mut self, path: &Path) -> Option<&mut Json> {
+        path.segments
+            .iter()
+            .try_fold(self, |value, segment| match (value, segment) {
+                (Json::Object(items), segment) => items
+                    .iter_mut()
+                    .rev()
+                    .find(|(key, _)| matches(segment, key))
+                    .map(|(_, value)| value),
+                (Json::List(values), Segment::Index(index)) => values.get_mut(*index),
+                _ => None,
+            })
+    }
+
+    /// Calls a function with every value and its path, parents before the values in them
+    pub fn walk<F: FnMut(&Path, &Json)>(&self, mut visit: F) {
+        self.walk_from(&mut Path::new(), &mut visit);
+    }
+
+    /// Visits this value at a path and the values in it
+    fn walk_from<F: FnMut(&Path, &Json)>(&self, path: &mut Path, visit: &mut F) {
+        visit(path, self);
+        match self {
+            Json::List(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    path.push(index);
+                    value.walk_from(path, visit);
+                    path.pop();
+                }
+            }
+            Json::Object(items) => {
+                for (key, value) in items {
+                    path.push(key.as_str());
+                    value.walk_from(path, visit);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec::Vec};
+
+    use super::{Path, Segment};
+    use crate::Json;
+
+    #[test]
+    fn paths() {
+        let path = Path::new().join("items").join(0).join("a/b~");
+        assert_eq!(path.to_string(), "/items/0/a~1b~0");
+        assert_eq!(Path::parse("/items/0/a~1b~0"), Some(path.clone()));
+        assert_eq!(path.parent(), Path::parse("/items/0"));
+        assert!(path.starts_with(&Path::parse("/items").unwrap()));
+        assert_eq!(Path::parse("items"), None);
+        assert_eq!(
+            Path::parse("/-").unwrap().segments(),
+            [Segment::Key("-".into())]
+        );
+
+        // Paths find the same values as pointers
+        let document = "{\"items\":[{\"a/b~\":1}],\"0\":2}"
+            .parse::<Json>()
+            .unwrap();
+        assert_eq!(document.get_path(&path), Some(&Json::Number(1.0)));
+        assert_eq!(
+            document.get_path(&Path::parse("/0").unwrap()),
+            Some(&Json::Number(2.0))
+        );
+        assert_eq!(
+            document.get_path(&Path::new().join("items").join("0")),
+            None
+        );
+
+        // Diffs and errors report typed paths too
+        let mut changed = document.clone();
+        *changed.get_path_mut(&path).unwrap() = Json::Null;
+        assert_eq!(document.diff(&changed)[0].to_path(), Some(path));
+        let error = document.get_as::<bool>("/0").unwrap_err();
+        assert_eq!(error.to_path(), Some(Path::new().join(0)));
+    }
+
+    #[test]
+    fn walking() {
+        let document = "{\"a\":[1,{\"b\":null}]}".parse::<Json>().unwrap();
+        let mut visited = Vec::new();
+        document.walk(|path, value| visited.push((path.to_string(), value.clone())));
+        let pointers = visited
+            .iter()
+            .map(|(pointer, _)| pointer.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(pointers, ["", "/a", "/a/0", "/a/1", "/a/1/b"]);
+        assert!(
+            visited
+                .iter()
+                .all(|(pointer, value)| document.pointer(pointer) == Some(value))
+        );
+    }
+}
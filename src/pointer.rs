@@ -0,0 +1,239 @@
+//! Lookup of values by JSON pointers (RFC 6901)
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+use crate::Json;
+
+/// Splits a JSON pointer into its unescaped reference tokens, returning `None` if it's invalid
+pub(crate) fn tokens(pointer: &str) -> Option<impl Iterator<Item = Cow<'_, str>>> {
+    // A non-empty pointer has to start with a slash
+    let rest = match pointer {
+        "" => None,
+        _ => Some(pointer.strip_prefix('/')?),
+    };
+
+    // Unescape ~1 to / and ~0 to ~
+    Some(
+        rest.into_iter()
+            .flat_map(|rest| rest.split('/'))
+            .map(|token| {
+                if token.contains('~') {
+                    Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+                } else {
+                    Cow::Borrowed(token)
+                }
+            }),
+    )
+}
+
+/// Parses an array index, which can't have leading zeros
+pub(crate) fn index(token: &str) -> Option<usize> {
+    match token.as_bytes() {
+        [b'0'] => Some(0),
+        [b'1'..=b'9', rest @ ..] if rest.iter().all(u8::is_ascii_digit) => token.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Appends a reference token to a JSON pointer, escaping ~ and /
+pub(crate) fn push(pointer: &mut String, token: &str) {
+    pointer.push('/');
+    for ch in token.chars() {
+        match ch {
+            '~' => pointer.push_str("~0"),
+            '/' => pointer.push_str("~1"),
+            _ => pointer.push(ch),
+        }
+    }
+}
+
+/// Splits a non-empty JSON pointer into the pointer to its parent and its unescaped last token
+pub(crate) fn split_last(pointer: &str) -> Option<(&str, Cow<'_, str>)> {
+    if !pointer.starts_with('/') {
+        return None;
+    }
+    let (parent, last) = pointer.rsplit_once('/')?;
+    let last = if last.contains('~') {
+        Cow::Owned(last.replace("~1", "/").replace("~0", "~"))
+    } else {
+        Cow::Borrowed(last)
+    };
+    Some((parent, last))
+}
+
+/// Returns whether `Json::pointer_mut_or_insert` can follow a pointer, without changing anything
+fn insertable(root: &Json, pointer: &str) -> bool {
+    let Some(tokens) = tokens(pointer) else {
+        return false;
+    };
+
+    // Follow existing values, `None` meaning the value would be added as `null`
+    let mut value = Some(root);
+    for token in tokens {
+        value = match value.filter(|value| **value != Json::Null) {
+            // Added values become lists that can only get their first item, or objects
+            None if index(&token).is_some_and(|position| position > 0) => return false,
+            None => None,
+            Some(Json::Object(items)) => items
+                .iter()
+                .rev()
+                .find(|(key, _)| *key == token)
+                .map(|(_, value)| value),
+            Some(Json::List(values)) => match &*token {
+                "-" => None,
+                _ => match index(&token) {
+                    Some(position) if position < values.len() => Some(&values[position]),
+                    Some(position) if position == values.len() => None,
+                    _ => return false,
+                },
+            },
+            Some(_) => return false,
+        };
+    }
+    true
+}
+
+impl Json {
+    /// Returns the value referenced by a JSON pointer like `/items/0/name`
+    pub fn pointer(&self, pointer: &str) -> Option<&Json> {
+        tokens(pointer)?.try_fold(self, |value, token| match value {
+            Json::Object(items) => items
+                .iter()
+                .rev()
+                .find(|(key, _)| *key == token)
+                .map(|(_, value)| value),
+            Json::List(values) => values.get(index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// Returns a mutable reference to the value referenced by a JSON pointer
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Json> {
+        tokens(pointer)?.try_fold(self, |value, token| match value {
+            Json::Object(items) => items
+                .iter_mut()
+                .rev()
+                .find(|(key, _)| *key == token)
+                .map(|(_, value)| value),
+            Json::List(values) => values.get_mut(index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// Returns a mutable reference to the value referenced by a JSON pointer, creating it if it
+    /// doesn't exist yet, like assignment in `jq`
+    ///
+    /// Missing properties are added as `null`, items are appended as `null` for `-` or the index
+    /// just past the end, and `null` on the way becomes a list if the next token is an index or
+    /// `-` and an object otherwise. Returns `None` if the pointer is invalid, passes through
+    /// another value or has an index further past the end of a list, leaving the value unchanged.
+    pub fn pointer_mut_or_insert(&mut self, pointer: &str) -> Option<&mut Json> {
+        // Check the whole pointer first, so nothing is added on the way to an error
+        if !insertable(self, pointer) {
+            return None;
+        }
+        tokens(pointer)?.try_fold(self, |value, token| {
+            // Create the container the token refers into
+            if *value == Json::Null {
+                *value = if token == "-" || index(&token).is_some() {
+                    Json::List(Vec::new())
+                } else {
+                    Json::Object(Vec::new())
+                };
+            }
+
+            // Find or add the property or element
+            match value {
+                Json::Object(items) => {
+                    let position = match items.iter().rposition(|(key, _)| *key == token) {
+                        Some(position) => position,
+                        None => {
+                            items.push((token.into_owned(), Json::Null));
+                            items.len() - 1
+                        }
+                    };
+                    Some(&mut items[position].1)
+                }
+                Json::List(values) => {
+                    let position = match &*token {
+                        "-" => values.len(),
+                        _ => index(&token).filter(|&position| position <= values.len())?,
+                    };
+                    if position == values.len() {
+                        values.push(Json::Null);
+                    }
+                    Some(&mut values[position])
+                }
+                _ => None,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::ToOwned;
+
+    use crate::Json;
+
+    #[test]
+    fn pointer_lookup() {
+        let value = "{\"a\":[1,{\"b/c\":true,\"d~e\":null}],\"\":2}"
+            .parse::<Json>()
+            .unwrap();
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(value.pointer("/a/0"), Some(&Json::Number(1.0)));
+        assert_eq!(value.pointer("/a/1/b~1c"), Some(&Json::Bool(true)));
+        assert_eq!(value.pointer("/a/1/d~0e"), Some(&Json::Null));
+        assert_eq!(value.pointer("/"), Some(&Json::Number(2.0)));
+        assert_eq!(value.pointer("a"), None);
+        assert_eq!(value.pointer("/a/01"), None);
+        assert_eq!(value.pointer("/a/2"), None);
+        assert_eq!(value.pointer("/a/0/x"), None);
+    }
+
+    #[test]
+    fn pointer_mutation() {
+        let mut value = "{\"a\":[1,2]}".parse::<Json>().unwrap();
+        *value.pointer_mut("/a/1").unwrap() = Json::String("x".to_owned());
+        assert_eq!(value, "{\"a\":[1,\"x\"]}".parse::<Json>().unwrap());
+        assert!(value.pointer_mut("/b").is_none());
+    }
+
+    #[test]
+    fn pointer_insertion() {
+        let mut value = Json::Null;
+        *value.pointer_mut_or_insert("/server/ports/0").unwrap() = Json::Number(80.0);
+        *value.pointer_mut_or_insert("/server/ports/1").unwrap() = Json::Number(443.0);
+        *value.pointer_mut_or_insert("/server/ports/-").unwrap() = Json::Number(8080.0);
+        *value.pointer_mut_or_insert("/server/name").unwrap() = Json::String("web".to_owned());
+        assert_eq!(
+            value,
+            "{\"server\":{\"ports\":[80,443,8080],\"name\":\"web\"}}"
+                .parse::<Json>()
+                .unwrap()
+        );
+
+        // Existing values are kept, and other values can't be passed through
+        assert_eq!(
+            value.pointer_mut_or_insert("/server/ports/2"),
+            Some(&mut Json::Number(8080.0))
+        );
+
+        // Items are only appended right at the end, and invalid pointers change nothing
+        let expected = value.clone();
+        for pointer in [
+            "/server/name/x",
+            "server",
+            "/server/ports/4",
+            "/list/1",
+            "/list/0/1",
+            "/server/new/ports/3",
+            "/server/ports/-/1",
+            "/server/ports/18446744073709551615",
+        ] {
+            assert!(value.pointer_mut_or_insert(pointer).is_none(), "{pointer}");
+            assert_eq!(value, expected, "{pointer}");
+        }
+    }
+}
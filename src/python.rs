@@ -0,0 +1,162 @@
+//! Python bindings through `pyo3`
+//!
+//! Lists become `list`, objects become `dict`, whole numbers become `int`, other numbers become
+//! `float` and null becomes `None`.
+//! The module is exported as `json_parser` with the functions `loads`, `dumps` and `pointer`.
+
+use alloc::{format, string::String, vec::Vec};
+
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple},
+};
+
+use crate::Json;
+
+/// Converts a JSON value to a Python object
+fn to_python<'py>(py: Python<'py>, value: &Json) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Json::List(values) => PyList::new(
+            py,
+            values
+                .iter()
+                .map(|value| to_python(py, value))
+                .collect::<PyResult<Vec<_>>>()?,
+        )?
+        .into_any(),
+        Json::Object(items) => {
+            let dict = PyDict::new(py);
+            for (key, value) in items {
+                dict.set_item(key, to_python(py, value)?)?;
+            }
+            dict.into_any()
+        }
+        Json::String(string) => PyString::new(py, string).into_any(),
+        Json::NumberRaw(_) => to_python(py, &value.plain())?,
+        Json::Number(number) => {
+            // Keep whole numbers integers, so they don't gain a trailing `.0` in Python
+            let integer = *number as i64;
+            if (integer as f64).to_bits() == number.to_bits() && integer != i64::MAX {
+                PyInt::new(py, integer).into_any()
+            } else {
+                PyFloat::new(py, *number).into_any()
+            }
+        }
+        Json::Bool(value) => PyBool::new(py, *value).to_owned().into_any(),
+        Json::Null => py.None().into_bound(py),
+    })
+}
+
+/// Converts a Python object to a JSON value
+fn from_python(object: &Bound<'_, PyAny>) -> PyResult<Json> {
+    // Convert None and the scalar types, checking booleans before their integer base class
+    if object.is_none() {
+        return Ok(Json::Null);
+    }
+    if let Ok(value) = object.cast::<PyBool>() {
+        return Ok(Json::Bool(value.is_true()));
+    }
+    if object.is_instance_of::<PyInt>() || object.is_instance_of::<PyFloat>() {
+        return Ok(Json::Number(object.extract()?));
+    }
+    if let Ok(string) = object.cast::<PyString>() {
+        return Ok(Json::String(string.to_str()?.into()));
+    }
+
+    // Convert lists and tuples to lists
+    if object.is_instance_of::<PyList>() || object.is_instance_of::<PyTuple>() {
+        return object
+            .try_iter()?
+            .map(|value| from_python(&value?))
+            .collect::<PyResult<_>>()
+            .map(Json::List);
+    }
+
+    // Convert dicts with string keys to objects
+    if let Ok(dict) = object.cast::<PyDict>() {
+        return dict
+            .iter()
+            .map(|(key, value)| {
+                let key = key
+                    .cast::<PyString>()
+                    .map_err(|_| PyTypeError::new_err("dict keys have to be strings"))?
+                    .to_str()?
+                    .into();
+                Ok((key, from_python(&value)?))
+            })
+            .collect::<PyResult<_>>()
+            .map(Json::Object);
+    }
+
+    // Other objects have no JSON equivalent
+    Err(PyTypeError::new_err(format!(
+        "{} can't be converted to JSON",
+        object.get_type().name()?
+    )))
+}
+
+/// Parses JSON text into Python objects
+#[pyfunction]
+fn loads<'py>(py: Python<'py>, text: &str) -> PyResult<Bound<'py, PyAny>> {
+    let value = text
+        .parse::<Json>()
+        .map_err(|error| PyValueError::new_err(format!("{error}")))?;
+    to_python(py, &value)
+}
+
+/// Serializes Python objects as JSON text
+#[pyfunction]
+fn dumps(object: &Bound<'_, PyAny>) -> PyResult<String> {
+    Ok(format!("{}", from_python(object)?))
+}
+
+/// Returns the value referenced by a JSON pointer, or `None` if it doesn't exist
+#[pyfunction]
+fn pointer<'py>(object: &Bound<'py, PyAny>, pointer: &str) -> PyResult<Bound<'py, PyAny>> {
+    let py = object.py();
+    match from_python(object)?.pointer(pointer) {
+        Some(value) => to_python(py, value),
+        None => Ok(py.None().into_bound(py)),
+    }
+}
+
+/// The `json_parser` Python module
+#[pymodule]
+fn json_parser(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(loads, module)?)?;
+    module.add_function(wrap_pyfunction!(dumps, module)?)?;
+    module.add_function(wrap_pyfunction!(pointer, module)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use pyo3::{prelude::*, types::PyDict};
+
+    use super::{from_python, loads, to_python};
+    use crate::Json;
+
+    #[test]
+    fn python_round_trip() {
+        Python::initialize();
+        Python::attach(|py| {
+            let value = "{\"a\":[1,-2.5,\"x\",true,null],\"b\":{}}"
+                .parse::<Json>()
+                .unwrap();
+            let object = to_python(py, &value).unwrap();
+            assert!(object.cast::<PyDict>().is_ok());
+            assert_eq!(from_python(&object).unwrap(), value);
+            assert!(from_python(&py.eval(c"{1: 2}", None, None).unwrap()).is_err());
+
+            // Parse errors carry their message
+            let error = loads(py, "[1,").unwrap_err();
+            assert_eq!(
+                error.value(py).to_string(),
+                "[1,".parse::<Json>().unwrap_err().to_string()
+            );
+        });
+    }
+}
@@ -0,0 +1,471 @@
+//! Path expressions that are parsed once and evaluated against many documents or streams
+
+use alloc::{string::String, vec::Vec};
+use core::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::{
+    Error, Event, Events, Json,
+    pointer::{index, tokens},
+};
+
+/// What a step of a query selects from a value
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    /// The property with a name, or the item at the index it spells
+    Key(String),
+
+    /// The item of a list at an index
+    Index(usize),
+
+    /// Every property or item
+    Wildcard,
+}
+
+impl Selector {
+    /// Adds the values the selector selects from a value
+    fn select<'a>(&self, value: &'a Json, out: &mut Vec<&'a Json>) {
+        match (self, value) {
+            (Selector::Key(key), Json::Object(items)) => out.extend(
+                items
+                    .iter()
+                    .rev()
+                    .find(|(name, _)| name == key)
+                    .map(|(_, value)| value),
+            ),
+            (Selector::Key(key), Json::List(values)) => {
+                out.extend(index(key).and_then(|index| values.get(index)));
+            }
+            (Selector::Index(index), Json::List(values)) => out.extend(values.get(*index)),
+            (Selector::Wildcard, Json::Object(items)) => {
+                out.extend(items.iter().map(|(_, value)| value));
+            }
+            (Selector::Wildcard, Json::List(values)) => out.extend(values),
+            _ => {}
+        }
+    }
+
+    /// Returns whether the selector selects the value at a step of a path
+    fn accepts(&self, step: &PathStep) -> bool {
+        match (self, step) {
+            (Selector::Key(key), PathStep::Key(name)) => key == name,
+            (Selector::Key(key), PathStep::Index(position)) => index(key) == Some(*position),
+            (Selector::Index(index), PathStep::Index(position)) => index == position,
+            (Selector::Index(_), PathStep::Key(_)) => false,
+            (Selector::Wildcard, _) => true,
+        }
+    }
+}
+
+/// A step of the path from the root of a document to a value being streamed
+#[derive(Debug, Clone, PartialEq)]
+enum PathStep {
+    /// The property with a key
+    Key(String),
+
+    /// The item at an index
+    Index(usize),
+}
+
+/// A step of a query
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    /// Selects from the current values
+    Child(Selector),
+
+    /// Selects from the current values and everything in them
+    Descendant(Selector),
+}
+
+/// Adds the values a selector selects from a value and everything in it, in document order
+fn descend<'a>(selector: &Selector, value: &'a Json, out: &mut Vec<&'a Json>) {
+    selector.select(value, out);
+    match value {
+        Json::List(values) => values
+            .iter()
+            .for_each(|value| descend(selector, value, out)),
+        Json::Object(items) => items
+            .iter()
+            .for_each(|(_, value)| descend(selector, value, out)),
+        _ => {}
+    }
+}
+
+/// A path expression that's parsed once, for looking up values in many documents
+///
+/// Queries are JSON pointers like `/items/0/name`, or JSONPath expressions starting with `$`
+/// made of `.name`, `['name']`, `[0]`, the wildcards `.*` and `[*]`, and `..` to select from
+/// everything below, like `$..items[*].name`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// The steps from the root to the selected values
+    steps: Vec<Step>,
+}
+
+impl Query {
+    /// Returns the values the query selects from a document, in document order
+    pub fn select<'a>(&self, document: &'a Json) -> Vec<&'a Json> {
+        let mut current = Vec::from([document]);
+        for step in &self.steps {
+            let mut next = Vec::new();
+            for value in current {
+                match step {
+                    Step::Child(selector) => selector.select(value, &mut next),
+                    Step::Descendant(selector) => descend(selector, value, &mut next),
+                }
+            }
+            current = next;
+        }
+        current
+    }
+
+    /// Returns the first value the query selects from a document
+    pub fn first<'a>(&self, document: &'a Json) -> Option<&'a Json> {
+        self.select(document).into_iter().next()
+    }
+
+    /// Returns an iterator over the values the query selects from the characters of a
+    /// document, building only those values and never the whole document
+    ///
+    /// Values are matched by their path, so every property with a duplicated key matches, and
+    /// values inside a matching value aren't matched again. Iteration ends after the first
+    /// error.
+    pub fn select_stream<I: Iterator<Item = char>>(&self, iter: I) -> StreamMatches<'_, I> {
+        StreamMatches {
+            query: self,
+            events: Events::new(iter),
+            path: Vec::new(),
+        }
+    }
+
+    /// Returns whether the query selects the value at a path
+    fn accepts(&self, path: &[(Option<usize>, PathStep)]) -> bool {
+        // Follow every way the steps can match the path, where `..` can skip any number of them
+        let mut states = Vec::from([0]);
+        for (_, step) in path {
+            let mut next = Vec::new();
+            for state in states {
+                match self.steps.get(state) {
+                    Some(Step::Child(selector)) if selector.accepts(step) => next.push(state + 1),
+                    Some(Step::Descendant(selector)) => {
+                        next.push(state);
+                        if selector.accepts(step) {
+                            next.push(state + 1);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            next.dedup();
+            states = next;
+        }
+        states.contains(&self.steps.len())
+    }
+}
+
+/// An iterator over the values a [`Query`] selects from a stream of characters
+pub struct StreamMatches<'a, I: Iterator<Item = char>> {
+    /// The query values are matched against
+    query: &'a Query,
+
+    /// The events of the document
+    events: Events<I>,
+
+    /// The next list index, if any, of every container around the current value, and the step
+    /// to the value in it
+    path: Vec<(Option<usize>, PathStep)>,
+}
+
+impl<I: Iterator<Item = char>> Iterator for StreamMatches<'_, I> {
+    type Item = Result<Json, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let event = match self.events.next()? {
+                Ok(event) => event,
+                Err(error) => return Some(Err(error)),
+            };
+
+            // Keep track of the path to the next value
+            match &event {
+                Event::Key(key) => {
+                    if let Some((_, step)) = self.path.last_mut() {
+                        *step = PathStep::Key(key.clone());
+                    }
+                    continue;
+                }
+                Event::EndObject | Event::EndList => {
+                    self.path.pop();
+                    continue;
+                }
+                _ => {}
+            }
+            if let Some((Some(index), step)) = self.path.last_mut() {
+                *step = PathStep::Index(*index);
+                *index += 1;
+            }
+
+            // Build matching values, and look inside other containers
+            if self.query.accepts(&self.path) {
+                return Some(self.events.build(event));
+            }
+            match event {
+                Event::StartObject => self.path.push((None, PathStep::Key(String::new()))),
+                Event::StartList => self.path.push((Some(0), PathStep::Index(0))),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Why a query couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    /// The byte offset in the query where it became invalid
+    pub offset: usize,
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid query at offset {}", self.offset)
+    }
+}
+
+impl core::error::Error for QueryError {}
+
+/// Parses JSONPath steps, after the `$`
+struct Parser<'a> {
+    /// The whole query
+    text: &'a str,
+
+    /// The byte offset of the next character
+    offset: usize,
+}
+
+impl Parser<'_> {
+    /// Returns an error at the current offset
+    fn error(&self) -> QueryError {
+        QueryError {
+            offset: self.offset,
+        }
+    }
+
+    /// Returns the next character without consuming it
+    fn peek(&self) -> Option<char> {
+        self.text[self.offset..].chars().next()
+    }
+
+    /// Consumes the character if it's next
+    fn eat(&mut self, ch: char) -> bool {
+        let next = self.peek() == Some(ch);
+        if next {
+            self.offset += ch.len_utf8();
+        }
+        next
+    }
+
+    /// Parses a name after a dot, or a wildcard
+    fn name(&mut self) -> Result<Selector, QueryError> {
+        if self.eat('*') {
+            return Ok(Selector::Wildcard);
+        }
+        let rest = &self.text[self.offset..];
+        let length = rest.find(['.', '[']).unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.error());
+        }
+        self.offset += length;
+        Ok(Selector::Key(rest[..length].into()))
+    }
+
+    /// Parses a selector in brackets, after the `[`
+    fn bracket(&mut self) -> Result<Selector, QueryError> {
+        let selector = match self.peek() {
+            Some('*') => {
+                self.offset += 1;
+                Selector::Wildcard
+            }
+            Some(quote @ ('\'' | '"')) => {
+                self.offset += 1;
+                Selector::Key(self.quoted(quote)?)
+            }
+            _ => {
+                let rest = &self.text[self.offset..];
+                let length = rest.find(']').unwrap_or(rest.len());
+                let index = index(&rest[..length]).ok_or_else(|| self.error())?;
+                self.offset += length;
+                Selector::Index(index)
+            }
+        };
+        match self.eat(']') {
+            true => Ok(selector),
+            false => Err(self.error()),
+        }
+    }
+
+    /// Parses a quoted name after its opening quote, where a backslash escapes any character
+    fn quoted(&mut self, quote: char) -> Result<String, QueryError> {
+        let mut name = String::new();
+        let mut chars = self.text[self.offset..].chars();
+        loop {
+            let ch = chars.next().ok_or(QueryError {
+                offset: self.text.len(),
+            })?;
+            self.offset += ch.len_utf8();
+            match ch {
+                _ if ch == quote => return Ok(name),
+                '\\' => {
+                    let escaped = chars.next().ok_or_else(|| self.error())?;
+                    self.offset += escaped.len_utf8();
+                    name.push(escaped);
+                }
+                _ => name.push(ch),
+            }
+        }
+    }
+
+    /// Parses every step
+    fn steps(&mut self) -> Result<Vec<Step>, QueryError> {
+        let mut steps = Vec::new();
+        while self.offset < self.text.len() {
+            let step = if self.eat('[') {
+                Step::Child(self.bracket()?)
+            } else if !self.eat('.') {
+                return Err(self.error());
+            } else if !self.eat('.') {
+                Step::Child(self.name()?)
+            } else if self.eat('[') {
+                Step::Descendant(self.bracket()?)
+            } else {
+                Step::Descendant(self.name()?)
+            };
+            steps.push(step);
+        }
+        Ok(steps)
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        // Parse JSON pointers token by token
+        if let Some(tokens) = tokens(text) {
+            return Ok(Query {
+                steps: tokens
+                    .map(|token| Step::Child(Selector::Key(token.into_owned())))
+                    .collect(),
+            });
+        }
+
+        // Parse JSONPath expressions after the root
+        let mut parser = Parser { text, offset: 0 };
+        if !parser.eat('$') {
+            return Err(parser.error());
+        }
+        Ok(Query {
+            steps: parser.steps()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::{Query, QueryError};
+    use crate::Json;
+
+    #[test]
+    fn selecting() {
+        let store = "{\"books\":[{\"title\":\"A\",\"tags\":[\"x\"]},{\"title\":\"B\",\"a.b\":1}],\
+            \"title\":\"Store\"}"
+            .parse::<Json>()
+            .unwrap();
+        let titles = |query: &str| {
+            query
+                .parse::<Query>()
+                .unwrap()
+                .select(&store)
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let strings = |strings: &[&str]| {
+            strings
+                .iter()
+                .map(|string| Json::String((*string).into()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(titles("/books/1/title"), strings(&["B"]));
+        assert_eq!(titles("$.books[0].title"), strings(&["A"]));
+        assert_eq!(titles("$['books'][*]['title']"), strings(&["A", "B"]));
+        assert_eq!(titles("$.books.*.tags[0]"), strings(&["x"]));
+        assert_eq!(titles("$..title"), strings(&["Store", "A", "B"]));
+        assert_eq!(titles("$.books[1][\"a.b\"]"), vec![Json::Number(1.0)]);
+        assert_eq!(titles("$"), vec![store.clone()]);
+        assert!(titles("$.books[5]").is_empty());
+
+        // A query can be evaluated against many documents
+        let query = "$.id".parse::<Query>().unwrap();
+        let ids = ["{\"id\":1}", "{\"id\":2}", "[]"]
+            .into_iter()
+            .map(|text| query.first(&text.parse().unwrap()).cloned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            [Some(Json::Number(1.0)), Some(Json::Number(2.0)), None]
+        );
+    }
+
+    #[test]
+    fn streaming() {
+        let text = "{\"records\":[{\"id\":1,\"tags\":[\"a\"]},{\"id\":2}],\"meta\":{\"id\":3}}";
+        let stream = |query: &str| {
+            query
+                .parse::<Query>()
+                .unwrap()
+                .select_stream(text.chars())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+        let document = text.parse::<Json>().unwrap();
+        for query in [
+            "$.records[*]",
+            "$..id",
+            "/records/1/id",
+            "$.records[0].tags",
+            "$",
+        ] {
+            let selected = query.parse::<Query>().unwrap().select(&document);
+            assert_eq!(
+                stream(query).iter().collect::<Vec<_>>(),
+                selected,
+                "{query}"
+            );
+        }
+
+        // Errors end the stream
+        let query = "$[*]".parse::<Query>().unwrap();
+        let mut results = query.select_stream("[1, 2, x]".chars());
+        assert_eq!(results.next().unwrap().unwrap(), Json::Number(1.0));
+        assert_eq!(results.next().unwrap().unwrap(), Json::Number(2.0));
+        assert!(results.next().unwrap().is_err());
+        assert!(results.next().is_none());
+    }
+
+    #[test]
+    fn errors() {
+        for (text, offset) in [
+            ("books", 0),
+            ("$.", 2),
+            ("$[01]", 2),
+            ("$['a'", 5),
+            ("$x", 1),
+        ] {
+            assert_eq!(text.parse::<Query>(), Err(QueryError { offset }), "{text}");
+        }
+    }
+}
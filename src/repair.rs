@@ -0,0 +1,584 @@
+//! Lenient parsing that repairs common mistakes in almost-JSON documents
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Display, Formatter};
+
+use crate::{Error, Json};
+
+/// How deeply values can be nested before repairing gives up
+const MAX_DEPTH: usize = 512;
+
+/// A kind of correction made while repairing a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairKind {
+    /// A `//`, `/* */` or `#` comment was removed
+    RemovedComment,
+
+    /// A comma before a closing bracket or brace, or an extra comma, was removed
+    RemovedComma,
+
+    /// A missing comma between two values was added
+    InsertedComma,
+
+    /// A missing colon between a key and its value was added
+    InsertedColon,
+
+    /// A missing value was replaced by `null`
+    InsertedValue,
+
+    /// An unquoted key or word was turned into a string
+    QuotedString,
+
+    /// A single quoted string was turned into a double quoted one
+    ConvertedQuotes,
+
+    /// A control character in a string was escaped
+    EscapedCharacter,
+
+    /// A string that wasn't closed before the end of the input was closed
+    ClosedString,
+
+    /// A list or object that wasn't closed before the end of the input was closed
+    ClosedContainer,
+
+    /// A literal from another language, like `True`, `None` or `NaN`, was replaced
+    ReplacedLiteral,
+
+    /// A number that isn't valid JSON, like `+1` or `.5`, was normalized
+    FixedNumber,
+
+    /// A character that can't start a value was removed
+    RemovedCharacter,
+
+    /// Characters after the value were removed
+    RemovedTrailingCharacters,
+}
+
+impl Display for RepairKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RepairKind::RemovedComment => "removed comment",
+            RepairKind::RemovedComma => "removed extra comma",
+            RepairKind::InsertedComma => "inserted missing comma",
+            RepairKind::InsertedColon => "inserted missing colon",
+            RepairKind::InsertedValue => "inserted null for a missing value",
+            RepairKind::QuotedString => "quoted unquoted string",
+            RepairKind::ConvertedQuotes => "converted single quotes to double quotes",
+            RepairKind::EscapedCharacter => "escaped control character in string",
+            RepairKind::ClosedString => "closed unterminated string",
+            RepairKind::ClosedContainer => "closed unterminated list or object",
+            RepairKind::ReplacedLiteral => "replaced non-JSON literal",
+            RepairKind::FixedNumber => "normalized invalid number",
+            RepairKind::RemovedCharacter => "removed unexpected character",
+            RepairKind::RemovedTrailingCharacters => "removed characters after the value",
+        })
+    }
+}
+
+/// A correction made while repairing a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repair {
+    /// The line the correction was made at, starting at 1
+    pub line: usize,
+
+    /// The column the correction was made at, starting at 1
+    pub column: usize,
+
+    /// What was corrected
+    pub kind: RepairKind,
+}
+
+/// Returns whether a character can start a value or an unquoted key
+fn starts_value(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, '"' | '\'' | '{' | '[' | '-' | '+' | '.' | '_' | '$')
+}
+
+/// A lenient parser recording the corrections it makes
+struct Repairer<'a> {
+    /// The characters of the input
+    chars: &'a [char],
+
+    /// The index of the next character
+    index: usize,
+
+    /// The line of the next character
+    line: usize,
+
+    /// The column of the next character
+    column: usize,
+
+    /// The corrections made so far
+    repairs: Vec<Repair>,
+}
+
+impl Repairer<'_> {
+    /// Returns the next character without consuming it
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    /// Consumes the next character
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.index += 1;
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(ch)
+    }
+
+    /// Records a correction at the next character
+    fn repair(&mut self, kind: RepairKind) {
+        self.repairs.push(Repair {
+            line: self.line,
+            column: self.column,
+            kind,
+        });
+    }
+
+    /// Skips whitespace and comments
+    fn skip(&mut self) {
+        loop {
+            match (self.peek(), self.chars.get(self.index + 1)) {
+                (Some(ch), _) if ch.is_whitespace() => {
+                    self.next();
+                }
+
+                // Skip line comments
+                (Some('#'), _) | (Some('/'), Some('/')) => {
+                    self.repair(RepairKind::RemovedComment);
+                    while self.next().is_some_and(|ch| ch != '\n') {}
+                }
+
+                // Skip block comments
+                (Some('/'), Some('*')) => {
+                    self.repair(RepairKind::RemovedComment);
+                    self.index += 2;
+                    self.column += 2;
+                    while self.peek().is_some()
+                        && !self.chars[self.index..].starts_with(&['*', '/'])
+                    {
+                        self.next();
+                    }
+                    self.next();
+                    self.next();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Reads the four hexadecimal digits of a `\u` escape, if they're there
+    fn hex(&mut self) -> Option<u32> {
+        let hex = self.chars.get(self.index..self.index + 4)?;
+        let code = hex
+            .iter()
+            .try_fold(0, |code, ch| Some(code << 4 | ch.to_digit(16)?))?;
+        for _ in 0..4 {
+            self.next();
+        }
+        Some(code)
+    }
+
+    /// Reads a string after its opening quote, until the closing quote
+    fn string(&mut self, quote: char) -> String {
+        if quote == '\'' {
+            self.repair(RepairKind::ConvertedQuotes);
+        }
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some(ch) if ch == quote => return string,
+                None => {
+                    self.repair(RepairKind::ClosedString);
+                    return string;
+                }
+
+                // Decode escapes, keeping unknown ones as they are
+                Some('\\') => match self.next() {
+                    Some('u') => match self.hex() {
+                        // Combine surrogate pairs
+                        Some(high @ 0xd800..=0xdbff)
+                            if self.chars[self.index..].starts_with(&['\\', 'u']) =>
+                        {
+                            self.next();
+                            self.next();
+                            let ch = match self.hex() {
+                                Some(low @ 0xdc00..=0xdfff) => char::from_u32(
+                                    0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00),
+                                ),
+                                _ => None,
+                            };
+                            string.push(ch.unwrap_or(char::REPLACEMENT_CHARACTER));
+                        }
+                        Some(code) => {
+                            string
+                                .push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                        }
+                        None => string.push_str("\\u"),
+                    },
+                    Some(ch) => string.push(match ch {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        ch => ch,
+                    }),
+                    None => {
+                        self.repair(RepairKind::ClosedString);
+                        return string;
+                    }
+                },
+
+                // Control characters have to be escaped in JSON
+                Some(ch) => {
+                    if ch.is_control() {
+                        self.repair(RepairKind::EscapedCharacter);
+                    }
+                    string.push(ch);
+                }
+            }
+        }
+    }
+
+    /// Reads an unquoted word, like a key or a literal
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(ch) = self
+            .peek()
+            .filter(|&ch| ch.is_alphanumeric() || matches!(ch, '_' | '$' | '-' | '.' | '+'))
+        {
+            word.push(ch);
+            self.next();
+        }
+        word
+    }
+
+    /// Reads a value, replacing it with `null` if it's missing
+    fn value(&mut self, depth: usize) -> Result<Json, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::NestingTooDeep);
+        }
+        self.skip();
+        Ok(match self.peek() {
+            Some('{') => self.object(depth)?,
+            Some('[') => self.list(depth)?,
+            Some(quote @ ('"' | '\'')) => {
+                self.next();
+                Json::String(self.string(quote))
+            }
+            Some(ch) if starts_value(ch) => self.literal(),
+            _ => {
+                self.repair(RepairKind::InsertedValue);
+                Json::Null
+            }
+        })
+    }
+
+    /// Reads a literal, number or unquoted string
+    fn literal(&mut self) -> Json {
+        let (line, column) = (self.line, self.column);
+        let word = self.word();
+        let (value, kind) = match word.as_str() {
+            "true" => return Json::Bool(true),
+            "false" => return Json::Bool(false),
+            "null" => return Json::Null,
+            "True" | "TRUE" => (Json::Bool(true), RepairKind::ReplacedLiteral),
+            "False" | "FALSE" => (Json::Bool(false), RepairKind::ReplacedLiteral),
+            "None" | "NULL" | "Null" | "nil" | "undefined" | "NaN" | "Infinity" | "-Infinity" => {
+                (Json::Null, RepairKind::ReplacedLiteral)
+            }
+
+            // Numbers that are valid JSON are kept, others normalized if Rust can read them
+            _ => match word.parse::<f64>() {
+                Ok(number) if number.is_finite() => {
+                    let digits = word.trim_start_matches('-');
+                    let invalid = word.starts_with('+')
+                        || digits.starts_with('.')
+                        || digits.contains(".e")
+                        || digits.ends_with('.')
+                        || digits.len() > 1
+                            && digits.starts_with('0')
+                            && !digits[1..].starts_with(['.', 'e', 'E']);
+                    if !invalid {
+                        return Json::Number(number);
+                    }
+                    (Json::Number(number), RepairKind::FixedNumber)
+                }
+                _ => (Json::String(word), RepairKind::QuotedString),
+            },
+        };
+        self.repairs.push(Repair { line, column, kind });
+        value
+    }
+
+    /// Handles what follows a list element or object property, returning `false` at the end
+    fn separator(&mut self, close: char) -> bool {
+        // Remove stray characters until a separator, value or end is found
+        self.skip();
+        while let Some(ch) = self.peek()
+            && ch != close
+            && ch != ','
+            && !starts_value(ch)
+        {
+            self.repair(RepairKind::RemovedCharacter);
+            self.next();
+            self.skip();
+        }
+        match self.peek() {
+            Some(ch) if ch == close => {
+                self.next();
+                false
+            }
+            Some(',') => {
+                // Remove repeated commas and commas before the closing bracket
+                self.next();
+                self.skip();
+                while self.peek() == Some(',') {
+                    self.repair(RepairKind::RemovedComma);
+                    self.next();
+                    self.skip();
+                }
+                if self.peek() == Some(close) {
+                    self.repair(RepairKind::RemovedComma);
+                    self.next();
+                    return false;
+                }
+                if self.peek().is_none() {
+                    self.repair(RepairKind::ClosedContainer);
+                    return false;
+                }
+                true
+            }
+            None => {
+                self.repair(RepairKind::ClosedContainer);
+                false
+            }
+            Some(_) => {
+                self.repair(RepairKind::InsertedComma);
+                true
+            }
+        }
+    }
+
+    /// Reads a list after checking its opening bracket
+    fn list(&mut self, depth: usize) -> Result<Json, Error> {
+        self.next();
+        self.skip();
+
+        // Handle empty lists and leading commas
+        while self.peek() == Some(',') {
+            self.repair(RepairKind::RemovedComma);
+            self.next();
+            self.skip();
+        }
+        let mut values = Vec::new();
+        match self.peek() {
+            Some(']') => {
+                self.next();
+                return Ok(Json::List(values));
+            }
+            None => {
+                self.repair(RepairKind::ClosedContainer);
+                return Ok(Json::List(values));
+            }
+            _ => {}
+        }
+
+        // Read values until the closing bracket
+        loop {
+            values.push(self.value(depth + 1)?);
+            if !self.separator(']') {
+                return Ok(Json::List(values));
+            }
+        }
+    }
+
+    /// Reads an object after checking its opening brace
+    fn object(&mut self, depth: usize) -> Result<Json, Error> {
+        self.next();
+        self.skip();
+
+        // Handle empty objects and leading commas
+        while self.peek() == Some(',') {
+            self.repair(RepairKind::RemovedComma);
+            self.next();
+            self.skip();
+        }
+        let mut items = Vec::new();
+        match self.peek() {
+            Some('}') => {
+                self.next();
+                return Ok(Json::Object(items));
+            }
+            None => {
+                self.repair(RepairKind::ClosedContainer);
+                return Ok(Json::Object(items));
+            }
+            _ => {}
+        }
+
+        // Read properties until the closing brace
+        loop {
+            // Read the key, quoting it if necessary
+            let key = match self.peek() {
+                Some(quote @ ('"' | '\'')) => {
+                    self.next();
+                    self.string(quote)
+                }
+                _ => {
+                    self.repair(RepairKind::QuotedString);
+                    self.word()
+                }
+            };
+
+            // Read the colon and the value
+            self.skip();
+            if self.peek() == Some(':') {
+                self.next();
+            } else {
+                self.repair(RepairKind::InsertedColon);
+            }
+            items.push((key, self.value(depth + 1)?));
+            if !self.separator('}') {
+                return Ok(Json::Object(items));
+            }
+            self.skip();
+        }
+    }
+}
+
+impl Json {
+    /// Parses an almost-JSON document, repairing the mistakes it finds
+    ///
+    /// This accepts comments, trailing commas, single quotes, unquoted keys, missing commas,
+    /// literals like `True` and `None`, and documents cut off before the end, returning the
+    /// value along with every correction made. Only nesting beyond 512 levels fails.
+    pub fn repair(input: &str) -> Result<(Self, Vec<Repair>), Error> {
+        let chars = input.chars().collect::<Vec<_>>();
+        let mut repairer = Repairer {
+            chars: &chars,
+            index: 0,
+            line: 1,
+            column: 1,
+            repairs: Vec::new(),
+        };
+
+        // Read the value and drop anything after it
+        let value = repairer.value(0)?;
+        repairer.skip();
+        if repairer.peek().is_some() {
+            repairer.repair(RepairKind::RemovedTrailingCharacters);
+        }
+        Ok((value, repairer.repairs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::RepairKind;
+    use crate::Json;
+
+    /// Repairs a document, returning the compact result and the kinds of corrections
+    fn repair(input: &str) -> (alloc::string::String, Vec<RepairKind>) {
+        let (value, repairs) = Json::repair(input).unwrap();
+        (
+            alloc::format!("{value}"),
+            repairs.into_iter().map(|repair| repair.kind).collect(),
+        )
+    }
+
+    #[test]
+    fn valid_input() {
+        let input = "{\"a\": [1, -2.5, \"x\\ny\"], \"b\": {\"c\": null, \"d\": true}}";
+        let (output, repairs) = repair(input);
+        assert_eq!(
+            output.parse::<Json>().unwrap(),
+            input.parse::<Json>().unwrap()
+        );
+        assert!(repairs.is_empty());
+    }
+
+    #[test]
+    fn repairs() {
+        assert_eq!(
+            repair("{a: 'b', // comment\n \"c\": [1, 2,],}"),
+            (
+                "{\"a\":\"b\",\"c\":[1,2]}".into(),
+                [
+                    RepairKind::QuotedString,
+                    RepairKind::ConvertedQuotes,
+                    RepairKind::RemovedComment,
+                    RepairKind::RemovedComma,
+                    RepairKind::RemovedComma,
+                ]
+                .into()
+            )
+        );
+        assert_eq!(
+            repair("[True None +1 .5]"),
+            (
+                "[true,null,1,0.5]".into(),
+                [
+                    RepairKind::ReplacedLiteral,
+                    RepairKind::InsertedComma,
+                    RepairKind::ReplacedLiteral,
+                    RepairKind::InsertedComma,
+                    RepairKind::FixedNumber,
+                    RepairKind::InsertedComma,
+                    RepairKind::FixedNumber,
+                ]
+                .into()
+            )
+        );
+        assert_eq!(
+            repair("{\"a\": [\"b"),
+            (
+                "{\"a\":[\"b\"]}".into(),
+                [
+                    RepairKind::ClosedString,
+                    RepairKind::ClosedContainer,
+                    RepairKind::ClosedContainer,
+                ]
+                .into()
+            )
+        );
+        assert_eq!(
+            repair("['\\ud83d\\ude00' @ 1]"),
+            (
+                "[\"😀\",1]".into(),
+                [
+                    RepairKind::ConvertedQuotes,
+                    RepairKind::RemovedCharacter,
+                    RepairKind::InsertedComma
+                ]
+                .into()
+            )
+        );
+        assert_eq!(
+            repair("{\"a\" 1} x"),
+            (
+                "{\"a\":1}".into(),
+                [
+                    RepairKind::InsertedColon,
+                    RepairKind::RemovedTrailingCharacters
+                ]
+                .into()
+            )
+        );
+    }
+    #[test]
+    fn stray_characters() {
+        // Many stray characters are removed without running out of stack
+        let input = alloc::format!("[1 {}]", ")".repeat(1_000_000));
+        let (output, repairs) = repair(&input);
+        assert_eq!(output, "[1]");
+        assert_eq!(repairs.len(), 1_000_000);
+    }
+}
@@ -0,0 +1,545 @@
+//! Validation of JSON without allocating
+//!
+//! The scanner checks the syntax strictly following RFC 8259, keeping track of the open lists
+//! and objects in a fixed-size stack, so it works without an allocator.
+
+use core::iter::Peekable;
+
+use crate::{Chars, Error};
+
+/// The kind of a scanned value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// A list of data
+    List,
+
+    /// An object
+    Object,
+
+    /// A string
+    String,
+
+    /// A number
+    Number,
+
+    /// A boolean
+    Bool,
+
+    /// A null value
+    Null,
+}
+
+/// The position of a complete value in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Boundary {
+    /// The kind of the value
+    pub kind: ValueKind,
+
+    /// The number of lists and objects containing the value
+    pub depth: usize,
+
+    /// The offset in characters of the first character of the value
+    pub start: usize,
+
+    /// The offset in characters after the last character of the value
+    pub end: usize,
+}
+
+/// What the scanner expects next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    /// A value or the end of an empty list
+    ValueOrEnd,
+
+    /// A value
+    Value,
+
+    /// A key or the end of an empty object
+    KeyOrEnd,
+
+    /// A key
+    Key,
+
+    /// A key-value separator
+    Colon,
+
+    /// A value separator or the end of the list or object
+    CommaOrEnd,
+
+    /// Nothing, the top-level value is complete
+    Done,
+}
+
+/// An open list or object
+#[derive(Debug, Clone, Copy)]
+struct Container {
+    /// Whether the container is an object
+    object: bool,
+
+    /// The offset of the opening character
+    start: usize,
+}
+
+/// Scans JSON without allocating, yielding the boundaries of every complete value
+///
+/// Values are yielded when they end, so nested values come before the lists and objects
+/// containing them and the top-level value comes last. `DEPTH` is the maximum nesting depth.
+pub struct Scanner<I: Iterator<Item = char>, const DEPTH: usize = 128> {
+    /// The characters that haven't been read yet
+    iter: Peekable<I>,
+
+    /// The number of characters read so far
+    offset: usize,
+
+    /// The open lists and objects
+    stack: [Container; DEPTH],
+
+    /// The number of open lists and objects
+    depth: usize,
+
+    /// What is expected next
+    expect: Expect,
+
+    /// Whether scanning stopped, because of an error or the end of the input
+    finished: bool,
+}
+
+impl<I: Iterator<Item = char>> Scanner<I> {
+    /// Creates a scanner allowing values to be nested 128 levels deep, like the parser does by
+    /// default
+    pub fn new(iter: I) -> Self {
+        Self::with_depth(iter)
+    }
+}
+
+impl<I: Iterator<Item = char>, const DEPTH: usize> Scanner<I, DEPTH> {
+    /// Creates a scanner allowing values to be nested `DEPTH` levels deep
+    pub fn with_depth(iter: I) -> Self {
+        Self {
+            iter: iter.peekable(),
+            offset: 0,
+            stack: [Container {
+                object: false,
+                start: 0,
+            }; DEPTH],
+            depth: 0,
+            expect: Expect::Value,
+            finished: false,
+        }
+    }
+
+    /// Reads the next character
+    fn next_char(&mut self) -> Option<char> {
+        let ch = self.iter.next()?;
+        self.offset += 1;
+        Some(ch)
+    }
+
+    /// Reads the next character, returning an error at the end of the input
+    fn expect_char(&mut self, error: Error) -> Result<char, Error> {
+        self.next_char().ok_or(error)
+    }
+
+    /// Skips whitespace as defined by JSON
+    fn skip_whitespace(&mut self) {
+        while self
+            .iter
+            .next_if(|ch| matches!(ch, ' ' | '\t' | '\n' | '\r'))
+            .is_some()
+        {
+            self.offset += 1;
+        }
+    }
+
+    /// Returns the error for the input ending in the current container
+    fn unclosed(&self) -> Error {
+        match self.depth {
+            0 => Error::EmptyInput,
+            depth if self.stack[depth - 1].object => Error::UnclosedObject,
+            _ => Error::UnclosedList,
+        }
+    }
+
+    /// Scans a string after its opening quote
+    fn scan_string(&mut self) -> Result<(), Error> {
+        loop {
+            match self.expect_char(Error::UnclosedString)? {
+                '"' => return Ok(()),
+                '\\' => match self.expect_char(Error::UnclosedString)? {
+                    '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' => {}
+                    // Surrogates are only valid as a high one followed by a low one
+                    'u' => {
+                        let valid = match self.scan_hex()? {
+                            0xd800..=0xdbff => {
+                                self.expect_char(Error::UnclosedString)? == '\\'
+                                    && self.expect_char(Error::UnclosedString)? == 'u'
+                                    && (0xdc00..=0xdfff).contains(&self.scan_hex()?)
+                            }
+                            0xdc00..=0xdfff => false,
+                            _ => true,
+                        };
+                        if !valid {
+                            return Err(Error::InvalidValue);
+                        }
+                    }
+                    _ => return Err(Error::InvalidValue),
+                },
+                '\0'..='\u{1f}' => return Err(Error::InvalidValue),
+                _ => {}
+            }
+        }
+    }
+
+    /// Scans the four hexadecimal digits of a unicode escape
+    fn scan_hex(&mut self) -> Result<u32, Error> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.expect_char(Error::UnclosedString)?.to_digit(16);
+            code = code * 16 + digit.ok_or(Error::InvalidValue)?;
+        }
+        Ok(code)
+    }
+
+    /// Scans the digits of a number, returning an error if there are none
+    fn scan_digits(&mut self) -> Result<(), Error> {
+        if self.iter.next_if(char::is_ascii_digit).is_none() {
+            return Err(Error::InvalidValue);
+        }
+        self.offset += 1;
+        while self.iter.next_if(char::is_ascii_digit).is_some() {
+            self.offset += 1;
+        }
+        Ok(())
+    }
+
+    /// Scans a number after its first character
+    fn scan_number(&mut self, first: char) -> Result<(), Error> {
+        // Scan the integer part, which can't have leading zeros
+        let first = match first {
+            '-' => self.expect_char(Error::InvalidValue)?,
+            _ => first,
+        };
+        match first {
+            '0' => {}
+            '1'..='9' => {
+                while self.iter.next_if(char::is_ascii_digit).is_some() {
+                    self.offset += 1;
+                }
+            }
+            _ => return Err(Error::InvalidValue),
+        }
+
+        // Scan the fraction
+        if self.iter.next_if_eq(&'.').is_some() {
+            self.offset += 1;
+            self.scan_digits()?;
+        }
+
+        // Scan the exponent
+        if self.iter.next_if(|&ch| matches!(ch, 'e' | 'E')).is_some() {
+            self.offset += 1;
+            if self.iter.next_if(|&ch| matches!(ch, '+' | '-')).is_some() {
+                self.offset += 1;
+            }
+            self.scan_digits()?;
+        }
+        Ok(())
+    }
+
+    /// Scans the rest of a literal after its first character
+    fn scan_literal(&mut self, rest: &str) -> Result<(), Error> {
+        for expected in rest.chars() {
+            if self.next_char() != Some(expected) {
+                return Err(Error::InvalidValue);
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks the end of a value in the current container
+    fn end_value(&mut self, kind: ValueKind, start: usize) -> Boundary {
+        self.expect = match self.depth {
+            0 => Expect::Done,
+            _ => Expect::CommaOrEnd,
+        };
+        Boundary {
+            kind,
+            depth: self.depth,
+            start,
+            end: self.offset,
+        }
+    }
+
+    /// Scans until the next value ends
+    fn scan(&mut self) -> Result<Option<Boundary>, Error> {
+        loop {
+            self.skip_whitespace();
+            let start = self.offset;
+
+            // Make sure there is nothing but whitespace after the top-level value
+            if self.expect == Expect::Done {
+                return match self.next_char() {
+                    Some(_) => Err(Error::TrailingCharacters),
+                    None => Ok(None),
+                };
+            }
+
+            let ch = self.next_char().ok_or_else(|| self.unclosed())?;
+            match (self.expect, ch) {
+                // Close lists and objects
+                (Expect::ValueOrEnd | Expect::CommaOrEnd, ']')
+                    if !self.stack[self.depth - 1].object =>
+                {
+                    self.depth -= 1;
+                    let start = self.stack[self.depth].start;
+                    return Ok(Some(self.end_value(ValueKind::List, start)));
+                }
+                (Expect::KeyOrEnd | Expect::CommaOrEnd, '}')
+                    if self.stack[self.depth - 1].object =>
+                {
+                    self.depth -= 1;
+                    let start = self.stack[self.depth].start;
+                    return Ok(Some(self.end_value(ValueKind::Object, start)));
+                }
+
+                // Read separators
+                (Expect::CommaOrEnd, ',') => {
+                    self.expect = match self.stack[self.depth - 1].object {
+                        true => Expect::Key,
+                        false => Expect::Value,
+                    };
+                }
+                (Expect::CommaOrEnd, _) => return Err(Error::MissingSeparator),
+                (Expect::Colon, ':') => self.expect = Expect::Value,
+                (Expect::Colon, _) => return Err(Error::MissingSeparator),
+
+                // Read keys
+                (Expect::KeyOrEnd | Expect::Key, '"') => {
+                    self.scan_string()?;
+                    self.expect = Expect::Colon;
+                }
+                (Expect::KeyOrEnd | Expect::Key, _) => return Err(Error::InvalidValue),
+
+                // Open lists and objects
+                (_, '[' | '{') => {
+                    if self.depth == DEPTH {
+                        return Err(Error::NestingTooDeep);
+                    }
+                    self.stack[self.depth] = Container {
+                        object: ch == '{',
+                        start,
+                    };
+                    self.depth += 1;
+                    self.expect = match ch {
+                        '{' => Expect::KeyOrEnd,
+                        _ => Expect::ValueOrEnd,
+                    };
+                }
+
+                // Read scalars
+                (_, '"') => {
+                    self.scan_string()?;
+                    return Ok(Some(self.end_value(ValueKind::String, start)));
+                }
+                (_, '-' | '0'..='9') => {
+                    self.scan_number(ch)?;
+                    return Ok(Some(self.end_value(ValueKind::Number, start)));
+                }
+                (_, 't') => {
+                    self.scan_literal("rue")?;
+                    return Ok(Some(self.end_value(ValueKind::Bool, start)));
+                }
+                (_, 'f') => {
+                    self.scan_literal("alse")?;
+                    return Ok(Some(self.end_value(ValueKind::Bool, start)));
+                }
+                (_, 'n') => {
+                    self.scan_literal("ull")?;
+                    return Ok(Some(self.end_value(ValueKind::Null, start)));
+                }
+                (_, _) => return Err(Error::InvalidValue),
+            }
+        }
+    }
+
+    /// Returns the offset in characters of the next character to read
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<I: Iterator<Item = char>, const DEPTH: usize> Iterator for Scanner<I, DEPTH> {
+    type Item = Result<Boundary, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = self.scan().transpose();
+        self.finished = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
+
+/// Checks whether characters form a single valid JSON value without allocating
+///
+/// This accepts the same values as parsing with the default [`ParseOptions`](crate::ParseOptions),
+/// except for the limit on the size of the input and numbers too large for an `f64`, like `1e400`,
+/// which parsing refuses.
+pub fn validate<I: Iterator<Item = char>>(iter: I) -> Result<(), Error> {
+    Scanner::new(iter).try_for_each(|boundary| boundary.map(|_| ()))
+}
+
+/// Checks whether UTF-8 bytes form a single valid JSON value without allocating, rejecting
+/// invalid UTF-8 as well
+///
+/// Unlike [`validate_bytes`], which replaces invalid UTF-8 by U+FFFD, this refuses it, so it's
+/// the one to use for input from the network or from files.
+pub fn validate_slice(bytes: &[u8]) -> Result<(), Error> {
+    let text = core::str::from_utf8(bytes).map_err(|_| Error::InvalidValue)?;
+    validate(text.chars())
+}
+
+/// Checks whether UTF-8 bytes form a single valid JSON value without allocating, replacing
+/// invalid bytes by U+FFFD
+pub fn validate_bytes<I: Iterator<Item = u8>>(iter: I) -> Result<(), Error> {
+    validate(Chars::new(iter))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Boundary, Scanner, ValueKind, validate, validate_slice};
+    use crate::Error;
+
+    #[test]
+    fn validation() {
+        for valid in [
+            "{}",
+            " [1, -0.5e+3, \"a\\\"\\u00e9\", true, false, null, {\"k\": []}] ",
+            "\"\"",
+            "0",
+        ] {
+            assert!(validate(valid.chars()).is_ok(), "{valid}");
+        }
+        for (invalid, error) in [
+            ("", Error::EmptyInput),
+            (" \n", Error::EmptyInput),
+            ("[1 2]", Error::MissingSeparator),
+            ("{\"a\" 1}", Error::MissingSeparator),
+            ("[1,", Error::UnclosedList),
+            ("{\"a\":1", Error::UnclosedObject),
+            ("\"abc", Error::UnclosedString),
+            ("01", Error::TrailingCharacters),
+            ("[1,]", Error::InvalidValue),
+            ("{1:2}", Error::InvalidValue),
+            ("+1", Error::InvalidValue),
+            ("1.", Error::InvalidValue),
+            ("tru", Error::InvalidValue),
+            ("\"\\x\"", Error::InvalidValue),
+            ("[}", Error::InvalidValue),
+            ("{} {}", Error::TrailingCharacters),
+        ] {
+            let found = validate(invalid.chars()).unwrap_err();
+            assert_eq!(
+                core::mem::discriminant(&found),
+                core::mem::discriminant(&error),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn slice_validation() {
+        assert!(validate_slice("{\"caf\u{e9}\": [1, 2]}".as_bytes()).is_ok());
+        assert!(validate_slice(b"[1,").unwrap_err().is_unexpected_eof());
+        assert!(validate_slice(b"\"\xff\"").unwrap_err().is_syntax());
+    }
+
+    #[test]
+    fn boundaries() {
+        let mut scanner = Scanner::new("[1, {\"a\": null}]".chars());
+        assert_eq!(
+            scanner.next().unwrap().unwrap(),
+            Boundary {
+                kind: ValueKind::Number,
+                depth: 1,
+                start: 1,
+                end: 2
+            }
+        );
+        assert_eq!(
+            scanner.next().unwrap().unwrap(),
+            Boundary {
+                kind: ValueKind::Null,
+                depth: 2,
+                start: 10,
+                end: 14
+            }
+        );
+        assert_eq!(
+            scanner.next().unwrap().unwrap(),
+            Boundary {
+                kind: ValueKind::Object,
+                depth: 1,
+                start: 4,
+                end: 15
+            }
+        );
+        assert_eq!(
+            scanner.next().unwrap().unwrap(),
+            Boundary {
+                kind: ValueKind::List,
+                depth: 0,
+                start: 0,
+                end: 16
+            }
+        );
+        assert!(scanner.next().is_none());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn agrees_with_parsing() {
+        use crate::Json;
+
+        let deepest = "[".repeat(128) + &"]".repeat(128);
+        let deeper = "[".repeat(129) + &"]".repeat(129);
+        for text in [
+            "[1,]",
+            "{\"a\":1,}",
+            "[,1]",
+            "+1",
+            "01",
+            ".5",
+            "1.",
+            "-",
+            "1e",
+            "\"a\tb\"",
+            "[1]\u{a0}",
+            "[1, -0.5e+3, \"\\u00e9\"]",
+            "\"\\ud800\"",
+            "\"\\udc00\"",
+            "\"\\ud800\\u0041\"",
+            "\"\\ud83d\\ude00\"",
+            "{\"a\": [true, null]}",
+            &deepest,
+            &deeper,
+        ] {
+            assert_eq!(
+                validate(text.chars()).is_ok(),
+                text.parse::<Json>().is_ok(),
+                "{text}"
+            );
+        }
+        assert!(validate(deepest.chars()).is_ok() && validate(deeper.chars()).is_err());
+    }
+
+    #[test]
+    fn depth_limit() {
+        assert!(Scanner::<_, 2>::with_depth("[[1]]".chars()).all(|result| result.is_ok()));
+        assert!(matches!(
+            Scanner::<_, 2>::with_depth("[[[1]]]".chars()).last(),
+            Some(Err(Error::NestingTooDeep))
+        ));
+    }
+}
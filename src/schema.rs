@@ -0,0 +1,426 @@
+//! Validation against a subset of JSON Schema
+
+use alloc::{borrow::ToOwned, collections::BTreeMap, format, string::String, vec::Vec};
+use core::mem;
+
+use crate::{Json, Path, pointer::push, ser};
+
+/// A place where a value doesn't match its schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The JSON pointer to the value that doesn't match
+    pub pointer: String,
+
+    /// A description of the problem
+    pub message: String,
+}
+
+impl Violation {
+    /// Returns the location of the value that doesn't match as a typed path
+    pub fn to_path(&self) -> Option<Path> {
+        Path::parse(&self.pointer)
+    }
+}
+
+/// Returns the name of the JSON Schema type of a value
+pub(crate) fn type_name(value: &Json) -> &'static str {
+    match value {
+        Json::List(_) => "array",
+        Json::Object(_) => "object",
+        Json::String(_) => "string",
+        Json::Number(_) | Json::NumberRaw(_) => "number",
+        Json::Bool(_) => "boolean",
+        Json::Null => "null",
+    }
+}
+
+/// Returns whether a value has a JSON Schema type
+fn has_type(value: &Json, name: &str) -> bool {
+    match (&*value.plain(), name) {
+        (Json::Number(number), "integer") => number.is_finite() && ser::is_whole(*number),
+        _ => type_name(value) == name,
+    }
+}
+
+/// Returns whether a quotient is a whole number, allowing for the rounding of the division
+fn is_whole(quotient: f64) -> bool {
+    // Round to the nearest whole number, away from zero for halves
+    let nearest = match quotient.abs() >= 4_503_599_627_370_496.0 {
+        true => quotient,
+        false => (quotient + 0.5f64.copysign(quotient)) as i64 as f64,
+    };
+    quotient.is_finite()
+        && (quotient - nearest).abs() <= 4.0 * f64::EPSILON * quotient.abs().max(1.0)
+}
+
+/// Validates values against the schemas of a root schema
+struct Validator<'a> {
+    /// The root schema, which `$ref` pointers are resolved against
+    root: &'a Json,
+
+    /// The problems found so far
+    violations: Vec<Violation>,
+
+    /// Whether each value matched each schema a reference led to, so references are checked
+    /// once per value, and cycles count as matching while they're being checked
+    references: BTreeMap<(*const Json, *const Json), bool>,
+}
+
+impl Validator<'_> {
+    /// Records a problem with the value at the pointer
+    fn report(&mut self, pointer: &str, message: String) {
+        self.violations.push(Violation {
+            pointer: pointer.to_owned(),
+            message,
+        });
+    }
+
+    /// Returns whether the value matches the schema, without recording problems
+    fn matches(&mut self, value: &Json, schema: &Json) -> bool {
+        let mut validator = Validator {
+            root: self.root,
+            violations: Vec::new(),
+            references: mem::take(&mut self.references),
+        };
+        validator.validate(value, schema, &mut String::new(), 0);
+        self.references = validator.references;
+        validator.violations.is_empty()
+    }
+
+    /// Records every problem of the value at the pointer with the schema
+    fn validate(&mut self, value: &Json, schema: &Json, pointer: &mut String, depth: usize) {
+        // Boolean schemas accept everything or nothing
+        let items = match schema {
+            Json::Bool(true) => return,
+            Json::Bool(false) => return self.report(pointer, "no value is allowed here".into()),
+            Json::Object(items) => items,
+            _ => return,
+        };
+
+        // Resolve references within the document, guarding against cycles
+        if let Some(Json::String(reference)) = schema.pointer("/$ref") {
+            let target = reference
+                .strip_prefix('#')
+                .and_then(|target| self.root.pointer(target));
+            match target {
+                Some(_) if depth > 64 => self.report(pointer, "references nest too deeply".into()),
+                Some(target) => match self.references.get(&(target, value)) {
+                    Some(true) => {}
+                    Some(false) => self.report(pointer, format!("value doesn't match {reference}")),
+                    None => {
+                        self.references.insert((target, value), true);
+                        let count = self.violations.len();
+                        self.validate(value, target, pointer, depth + 1);
+                        let matched = self.violations.len() == count;
+                        self.references.insert((target, value), matched);
+                    }
+                },
+                None => self.report(pointer, format!("can't resolve reference {reference}")),
+            }
+        }
+
+        // Check every other keyword
+        for (keyword, argument) in items {
+            self.keyword(value, keyword, argument, schema, pointer, depth);
+        }
+    }
+
+    /// Records the problems of the value with one keyword of its schema
+    fn keyword(
+        &mut self,
+        value: &Json,
+        keyword: &str,
+        argument: &Json,
+        schema: &Json,
+        pointer: &mut String,
+        depth: usize,
+    ) {
+        // Compare raw numbers by their value
+        let length = pointer.len();
+        let (argument, value) = (&*argument.plain(), &*value.plain());
+        match (keyword, argument, value) {
+            // Types, constants and enumerations
+            ("type", Json::String(name), _) if !has_type(value, name) => {
+                self.report(
+                    pointer,
+                    format!("expected {name}, found {}", type_name(value)),
+                );
+            }
+            ("type", Json::List(names), _)
+                if !names
+                    .iter()
+                    .any(|name| matches!(name, Json::String(name) if has_type(value, name))) =>
+            {
+                self.report(
+                    pointer,
+                    format!("{} isn't an allowed type", type_name(value)),
+                );
+            }
+            ("const", expected, _) if !value.equivalent(expected) => {
+                self.report(pointer, format!("expected {expected}"));
+            }
+            ("enum", Json::List(allowed), _)
+                if !allowed.iter().any(|allowed| value.equivalent(allowed)) =>
+            {
+                self.report(pointer, "value isn't one of the allowed values".into());
+            }
+
+            // Numbers
+            ("minimum", Json::Number(limit), Json::Number(number)) if number < limit => {
+                self.report(pointer, format!("{number} is less than {limit}"));
+            }
+            ("maximum", Json::Number(limit), Json::Number(number)) if number > limit => {
+                self.report(pointer, format!("{number} is greater than {limit}"));
+            }
+            ("exclusiveMinimum", Json::Number(limit), Json::Number(number)) if number <= limit => {
+                self.report(pointer, format!("{number} isn't greater than {limit}"));
+            }
+            ("exclusiveMaximum", Json::Number(limit), Json::Number(number)) if number >= limit => {
+                self.report(pointer, format!("{number} isn't less than {limit}"));
+            }
+            ("multipleOf", Json::Number(factor), Json::Number(number))
+                if *factor > 0.0 && !is_whole(number / factor) =>
+            {
+                self.report(pointer, format!("{number} isn't a multiple of {factor}"));
+            }
+
+            // Strings
+            ("minLength", Json::Number(limit), Json::String(string))
+                if (string.chars().count() as f64) < *limit =>
+            {
+                self.report(
+                    pointer,
+                    format!("string is shorter than {limit} characters"),
+                );
+            }
+            ("maxLength", Json::Number(limit), Json::String(string))
+                if string.chars().count() as f64 > *limit =>
+            {
+                self.report(pointer, format!("string is longer than {limit} characters"));
+            }
+
+            // Lists
+            ("minItems", Json::Number(limit), Json::List(values))
+                if (values.len() as f64) < *limit =>
+            {
+                self.report(pointer, format!("list has fewer than {limit} items"));
+            }
+            ("maxItems", Json::Number(limit), Json::List(values))
+                if values.len() as f64 > *limit =>
+            {
+                self.report(pointer, format!("list has more than {limit} items"));
+            }
+            ("uniqueItems", Json::Bool(true), Json::List(values))
+                if values.iter().enumerate().any(|(index, value)| {
+                    values[..index].iter().any(|other| value.equivalent(other))
+                }) =>
+            {
+                self.report(pointer, "list has duplicate items".into());
+            }
+            ("items", items, Json::List(values)) => {
+                for (index, value) in values.iter().enumerate() {
+                    push(pointer, &format!("{index}"));
+                    self.validate(value, items, pointer, depth);
+                    pointer.truncate(length);
+                }
+            }
+
+            // Objects
+            ("required", Json::List(names), Json::Object(items)) => {
+                for name in names {
+                    if let Json::String(name) = name
+                        && !items.iter().any(|(key, _)| key == name)
+                    {
+                        self.report(pointer, format!("missing property \"{name}\""));
+                    }
+                }
+            }
+            ("minProperties", Json::Number(limit), Json::Object(items))
+                if (items.len() as f64) < *limit =>
+            {
+                self.report(pointer, format!("object has fewer than {limit} properties"));
+            }
+            ("maxProperties", Json::Number(limit), Json::Object(items))
+                if items.len() as f64 > *limit =>
+            {
+                self.report(pointer, format!("object has more than {limit} properties"));
+            }
+            ("properties", Json::Object(schemas), Json::Object(items)) => {
+                for (key, value) in items {
+                    if let Some((_, schema)) = schemas.iter().find(|(name, _)| name == key) {
+                        push(pointer, key);
+                        self.validate(value, schema, pointer, depth);
+                        pointer.truncate(length);
+                    }
+                }
+            }
+            ("additionalProperties", additional, Json::Object(items)) => {
+                let known = match schema.pointer("/properties") {
+                    Some(Json::Object(schemas)) => schemas.as_slice(),
+                    _ => &[],
+                };
+                for (key, value) in items {
+                    if !known.iter().any(|(name, _)| name == key) {
+                        push(pointer, key);
+                        match additional {
+                            Json::Bool(false) => {
+                                self.report(pointer, format!("unexpected property \"{key}\""));
+                            }
+                            additional => self.validate(value, additional, pointer, depth),
+                        }
+                        pointer.truncate(length);
+                    }
+                }
+            }
+
+            // Combinations of schemas
+            ("allOf", Json::List(schemas), _) => {
+                for schema in schemas {
+                    self.validate(value, schema, pointer, depth);
+                }
+            }
+            ("anyOf", Json::List(schemas), _)
+                if !schemas.iter().any(|schema| self.matches(value, schema)) =>
+            {
+                self.report(pointer, "value matches none of the schemas in anyOf".into());
+            }
+            ("oneOf", Json::List(schemas), _) => {
+                let count = schemas
+                    .iter()
+                    .filter(|schema| self.matches(value, schema))
+                    .count();
+                if count != 1 {
+                    self.report(
+                        pointer,
+                        format!("value matches {count} schemas in oneOf instead of 1"),
+                    );
+                }
+            }
+            ("not", schema, _) if self.matches(value, schema) => {
+                self.report(pointer, "value matches the schema in not".into());
+            }
+
+            // Anything else is either satisfied or not supported, like pattern and format
+            _ => {}
+        }
+    }
+}
+
+impl Json {
+    /// Validates the value against a JSON Schema, returning every violation
+    ///
+    /// This supports the common validation keywords of draft 2020-12 and local `$ref` pointers
+    /// like `#/$defs/item`. Keywords needing regular expressions, like `pattern`, are ignored.
+    pub fn validate_schema(&self, schema: &Json) -> Result<(), Vec<Violation>> {
+        let mut validator = Validator {
+            root: schema,
+            violations: Vec::new(),
+            references: BTreeMap::new(),
+        };
+        validator.validate(self, schema, &mut String::new(), 0);
+        match validator.violations.is_empty() {
+            true => Ok(()),
+            false => Err(validator.violations),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, vec::Vec};
+
+    use crate::Json;
+
+    /// Returns the pointers of the violations of a document with a schema
+    fn violations(schema: &str, document: &str) -> Vec<alloc::string::String> {
+        let schema = schema.parse::<Json>().unwrap();
+        match document.parse::<Json>().unwrap().validate_schema(&schema) {
+            Ok(()) => Vec::new(),
+            Err(violations) => violations
+                .into_iter()
+                .map(|violation| violation.pointer)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn objects_and_lists() {
+        let schema = "{\"type\": \"object\", \"required\": [\"id\"], \
+            \"properties\": {\"id\": {\"type\": \"integer\", \"minimum\": 1}, \
+            \"tags\": {\"type\": \"array\", \"items\": {\"$ref\": \"#/$defs/tag\"}}}, \
+            \"additionalProperties\": false, \
+            \"$defs\": {\"tag\": {\"type\": \"string\", \"minLength\": 1}}}";
+        assert!(violations(schema, "{\"id\": 3, \"tags\": [\"a\"]}").is_empty());
+        assert_eq!(
+            violations(
+                schema,
+                "{\"id\": 0.5, \"tags\": [\"a\", \"\", 1], \"x\": 1}"
+            ),
+            ["/id", "/id", "/tags/1", "/tags/2", "/x"]
+        );
+        assert_eq!(violations(schema, "{}"), [""]);
+        assert_eq!(violations(schema, "[]"), [""]);
+    }
+
+    #[test]
+    fn combinations() {
+        let schema = "{\"oneOf\": [{\"type\": \"number\"}, {\"enum\": [1, \"a\"]}]}";
+        assert!(violations(schema, "2").is_empty());
+        assert_eq!(violations(schema, "1"), [""]);
+        assert!(violations(schema, "\"a\"").is_empty());
+        assert_eq!(violations("{\"not\": {\"const\": null}}", "null"), [""]);
+        assert_eq!(violations("false", "1"), [""]);
+        assert!(violations("{\"anyOf\": [{\"type\": \"null\"}, true]}", "1").is_empty());
+    }
+
+    #[test]
+    fn references() {
+        // References to the same schema are checked once per value, also through cycles
+        let fanning = "{\"allOf\": [{\"$ref\": \"#\"}, {\"$ref\": \"#\"}]}";
+        assert!(violations(fanning, "null").is_empty());
+        let doubling = (0..64)
+            .map(|level| {
+                let next = format!("{{\"$ref\": \"#/$defs/a{}\"}}", level + 1);
+                format!("\"a{level}\": {{\"allOf\": [{next}, {next}]}}")
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let doubling = format!(
+            "{{\"$ref\": \"#/$defs/a0\", \"$defs\": {{{doubling}, \"a64\": {{\"type\": \"string\"}}}}}}"
+        );
+        // The type is reported once, and the second reference of each level refers to it
+        assert_eq!(violations(&doubling, "1").len(), 65);
+        assert!(violations(&doubling, "\"a\"").is_empty());
+
+        // Recursive schemas still check every level of the value
+        let tree = "{\"type\": \"object\", \"properties\": {\"child\": {\"$ref\": \"#\"}}}";
+        assert!(violations(tree, "{\"child\": {\"child\": {}}}").is_empty());
+        assert_eq!(
+            violations(tree, "{\"child\": {\"child\": 1}}"),
+            ["/child/child"]
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        assert!(violations("{\"multipleOf\": 0.1}", "0.3").is_empty());
+        assert!(violations("{\"multipleOf\": 0.01}", "19.99").is_empty());
+        assert_eq!(violations("{\"multipleOf\": 0.1}", "0.35"), [""]);
+        assert!(violations("{\"type\": \"integer\"}", "1e20").is_empty());
+        assert_eq!(violations("{\"type\": \"integer\"}", "1.5"), [""]);
+    }
+
+    #[test]
+    fn equivalent_values() {
+        // The order of properties doesn't matter when comparing values
+        let schema = "{\"const\": {\"a\": 1, \"b\": 2}}";
+        assert!(violations(schema, "{\"b\": 2, \"a\": 1}").is_empty());
+        let schema = "{\"enum\": [{\"a\": 1, \"b\": 2}]}";
+        assert!(violations(schema, "{\"b\": 2, \"a\": 1}").is_empty());
+        let schema = "{\"uniqueItems\": true}";
+        assert_eq!(
+            violations(schema, "[{\"a\": 1, \"b\": 2}, {\"b\": 2, \"a\": 1}]"),
+            [""]
+        );
+    }
+}
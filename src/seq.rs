@@ -0,0 +1,142 @@
+//! Parsing and serialization of JSON text sequences (RFC 7464)
+
+use alloc::{format, string::String};
+use core::fmt::{self, Write};
+
+use crate::{Chars, Error, Json, ParseOptions, StrSource, parser::ParseState};
+
+/// The record separator starting every JSON text in a sequence
+const RECORD_SEPARATOR: char = '\u{1e}';
+
+/// An iterator parsing the records of a JSON text sequence
+///
+/// Parsing continues after an invalid record, because the next record separator marks the
+/// start of the next value.
+pub struct JsonSeq<I> {
+    /// The characters that haven't been read yet
+    iter: I,
+
+    /// Whether the first record separator has been found
+    started: bool,
+}
+
+impl<I: Iterator<Item = char>> JsonSeq<I> {
+    /// Creates an iterator parsing a JSON text sequence from characters
+    pub fn new(iter: I) -> Self {
+        Self {
+            iter,
+            started: false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for JsonSeq<I> {
+    type Item = Result<Json, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Skip everything before the first record separator
+        if !self.started {
+            self.iter.find(|&ch| ch == RECORD_SEPARATOR)?;
+            self.started = true;
+        }
+
+        loop {
+            // Read the record up to the next separator, stop if there are no chars left
+            let mut record = String::new();
+            let mut ended = true;
+            for ch in self.iter.by_ref() {
+                ended = false;
+                if ch == RECORD_SEPARATOR {
+                    break;
+                }
+                record.push(ch);
+            }
+            if ended && record.is_empty() {
+                return None;
+            }
+
+            // Skip empty records
+            if record.trim().is_empty() {
+                continue;
+            }
+
+            // Parse the record
+            let value = match Json::from_source_tracked(
+                StrSource::new(&record),
+                &mut ParseState::new(&ParseOptions::default()),
+            ) {
+                Ok(value) => value,
+                Err((error, ..)) => return Some(Err(error)),
+            };
+
+            // A number without whitespace after it may have been truncated
+            if matches!(value, Json::Number(_) | Json::NumberRaw(_))
+                && !record.ends_with(char::is_whitespace)
+            {
+                return Some(Err(Error::UnexpectedEndOfFile));
+            }
+            return Some(Ok(value));
+        }
+    }
+}
+
+impl Json {
+    /// Parses a JSON text sequence from characters
+    pub fn seq<I: Iterator<Item = char>>(iter: I) -> JsonSeq<I> {
+        JsonSeq::new(iter)
+    }
+
+    /// Parses a JSON text sequence from UTF-8 bytes
+    pub fn seq_from_bytes<I: Iterator<Item = u8>>(iter: I) -> JsonSeq<Chars<I>> {
+        JsonSeq::new(Chars::new(iter))
+    }
+
+    /// Writes the value as a record of a JSON text sequence
+    pub fn write_seq_record<W: Write>(&self, out: &mut W) -> fmt::Result {
+        writeln!(out, "{RECORD_SEPARATOR}{self}")
+    }
+
+    /// Returns the value as a record of a JSON text sequence
+    pub fn to_seq_record(&self) -> String {
+        format!("{RECORD_SEPARATOR}{self}\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+
+    use crate::{Error, Json};
+
+    #[test]
+    fn seq_parsing() {
+        let mut values =
+            Json::seq("\u{1e}{}\n\u{1e}\u{1e}[1]\n\u{1e}tru\n\u{1e}12\u{1e}null\n".chars());
+        assert_eq!(values.next().unwrap().unwrap(), Json::Object(Vec::new()));
+        assert_eq!(
+            values.next().unwrap().unwrap(),
+            Json::List(Vec::from([Json::Number(1.0)]))
+        );
+        assert!(values.next().unwrap().is_err());
+        assert!(matches!(
+            values.next(),
+            Some(Err(Error::UnexpectedEndOfFile))
+        ));
+        assert_eq!(values.next().unwrap().unwrap(), Json::Null);
+        assert!(values.next().is_none());
+    }
+
+    #[test]
+    fn seq_serialization() {
+        let mut out = String::new();
+        Json::Number(1.0).write_seq_record(&mut out).unwrap();
+        out.push_str(&Json::List(Vec::new()).to_seq_record());
+        assert_eq!(out, "\u{1e}1\n\u{1e}[]\n");
+        assert_eq!(
+            Json::seq(out.chars())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            [Json::Number(1.0), Json::List(Vec::new())]
+        );
+    }
+}
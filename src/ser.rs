@@ -0,0 +1,675 @@
+//! Serialization of JSON values with configurable formatting
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter, Write},
+};
+
+use crate::{Error, Json, pointer::push, validate};
+
+/// The indentation of nested values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    /// Indent every level by a number of spaces
+    Spaces(usize),
+
+    /// Indent every level by a tab
+    Tab,
+}
+
+/// The characters ending the lines of indented output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// A line feed, as on Unix
+    #[default]
+    Lf,
+
+    /// A carriage return and a line feed, as on Windows
+    CrLf,
+}
+
+impl LineEnding {
+    /// Returns the characters of the line ending
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// How numbers are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberFormat {
+    /// The shortest digits that read back as exactly the same number, without an exponent
+    #[default]
+    Shortest,
+
+    /// A fixed number of digits after the decimal point, except for whole numbers
+    Fixed(usize),
+
+    /// The shortest digits, with an exponent if the decimal exponent is below `min` or at least
+    /// `max`, like JavaScript does with -6 and 21
+    Scientific {
+        /// The smallest exponent written without an exponent
+        min: i32,
+
+        /// The smallest exponent written with an exponent again
+        max: i32,
+    },
+}
+
+/// Options for serializing JSON values
+#[derive(Debug, Clone, Default)]
+pub struct Format {
+    /// The indentation of nested values, `None` writes everything on a single line
+    pub indent: Option<Indent>,
+
+    /// Whether the properties of objects are written sorted by their keys
+    pub sort_keys: bool,
+
+    /// The keys whose properties are written first, in this order, like `["id", "name"]`, with
+    /// the other properties after them
+    pub key_order: Vec<String>,
+
+    /// How numbers are written
+    pub numbers: NumberFormat,
+
+    /// Whether `/` is written as `\/`, so the output can't close a `<script>` tag it's embedded in
+    pub escape_slashes: bool,
+
+    /// The characters ending the lines of indented output
+    pub line_ending: LineEnding,
+
+    /// Whether a space follows the colon after keys, `None` adding one only when indenting
+    pub space_after_colon: Option<bool>,
+
+    /// Whether empty lists and objects are opened and closed on separate lines when indenting,
+    /// instead of being written as `[]` and `{}`
+    pub expand_empty: bool,
+}
+
+impl Format {
+    /// Returns the options for compact output on a single line
+    pub fn compact() -> Self {
+        Self::default()
+    }
+
+    /// Returns the options for output indented by two spaces
+    pub fn pretty() -> Self {
+        Self {
+            indent: Some(Indent::Spaces(2)),
+            ..Self::default()
+        }
+    }
+
+    /// Orders keys by their position in the key order, and then by the keys themselves if
+    /// they're sorted
+    fn compare_keys(&self, a: &str, b: &str) -> Ordering {
+        let rank = |key| self.key_order.iter().position(|first| first == key);
+        match (rank(a), rank(b)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) if self.sort_keys => a.cmp(b),
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+/// A JSON value displayed with specific formatting options
+pub struct Formatted<'a> {
+    /// The value to display
+    value: &'a Json,
+
+    /// The formatting options
+    format: &'a Format,
+}
+
+impl Display for Formatted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_value(f, self.value, self.format, 0)
+    }
+}
+
+/// Starts a new line indented to the given depth, if the output is indented
+pub(crate) fn write_newline<W: Write>(out: &mut W, format: &Format, depth: usize) -> fmt::Result {
+    match format.indent {
+        Some(Indent::Spaces(width)) => {
+            write!(
+                out,
+                "{}{:2$}",
+                format.line_ending.as_str(),
+                "",
+                width * depth
+            )
+        }
+        Some(Indent::Tab) => {
+            out.write_str(format.line_ending.as_str())?;
+            (0..depth).try_for_each(|_| out.write_char('\t'))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Writes a string with quotes, escaping the characters JSON requires to be escaped
+pub(crate) fn write_string<W: Write>(out: &mut W, string: &str) -> fmt::Result {
+    write_escaped(out, string, false, false)
+}
+
+/// Writes a string with quotes, optionally escaping forward slashes and all non-ASCII
+/// characters as well
+pub(crate) fn write_escaped<W: Write>(
+    out: &mut W,
+    string: &str,
+    escape_slashes: bool,
+    ascii_only: bool,
+) -> fmt::Result {
+    out.write_char('"')?;
+    for ch in string.chars() {
+        match ch {
+            '/' if escape_slashes => out.write_str("\\/")?,
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\u{8}' => out.write_str("\\b")?,
+            '\u{c}' => out.write_str("\\f")?,
+            '\0'..='\u{1f}' => write!(out, "\\u{:04x}", u32::from(ch))?,
+            _ if ascii_only && !ch.is_ascii() => ch
+                .encode_utf16(&mut [0; 2])
+                .iter()
+                .try_for_each(|unit| write!(out, "\\u{unit:04x}"))?,
+            _ => out.write_char(ch)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// Writes the colon between a key and its value
+pub(crate) fn write_colon<W: Write>(out: &mut W, format: &Format) -> fmt::Result {
+    match format.space_after_colon.unwrap_or(format.indent.is_some()) {
+        true => out.write_str(": "),
+        false => out.write_char(':'),
+    }
+}
+
+/// Returns whether a number has no fraction, every number from 2^52 on being whole
+pub(crate) fn is_whole(number: f64) -> bool {
+    number.abs() >= 4_503_599_627_370_496.0 || (number as i64) as f64 == number
+}
+
+/// Writes a number in the chosen format, writing numbers JSON can't represent as `null`
+fn write_number<W: Write>(out: &mut W, number: f64, format: NumberFormat) -> fmt::Result {
+    if !number.is_finite() {
+        return out.write_str("null");
+    }
+    match format {
+        // Whole numbers never get a fraction like `.0`, so they read back as integers elsewhere
+        NumberFormat::Shortest => write!(out, "{number}"),
+        NumberFormat::Fixed(_) if is_whole(number) => write!(out, "{number}"),
+        NumberFormat::Fixed(precision) => write!(out, "{number:.precision$}"),
+        NumberFormat::Scientific { min, max } => {
+            // Find the decimal exponent of the shortest digits
+            let scientific = format!("{number:e}");
+            let exponent = scientific
+                .split_once('e')
+                .and_then(|(_, exponent)| exponent.parse::<i32>().ok())
+                .unwrap_or(0);
+            match number != 0.0 && (exponent < min || exponent >= max) {
+                true => out.write_str(&scientific),
+                false => write!(out, "{number}"),
+            }
+        }
+    }
+}
+
+/// Raw fragments replacing the values at JSON pointers, with the pointer to the current value
+struct Splice<'a> {
+    /// The pointers to the replaced values with their replacements
+    fragments: &'a [(&'a str, &'a RawJson)],
+
+    /// The pointer to the value being written
+    pointer: String,
+}
+
+impl Splice<'_> {
+    /// Returns the fragment replacing the current value
+    fn fragment(&self) -> Option<&RawJson> {
+        self.fragments
+            .iter()
+            .find(|(pointer, _)| *pointer == self.pointer)
+            .map(|(_, fragment)| *fragment)
+    }
+
+    /// Returns whether a fragment replaces a value inside the current one
+    fn inside(&self) -> bool {
+        self.fragments.iter().any(|(pointer, _)| {
+            pointer
+                .strip_prefix(self.pointer.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+}
+
+/// Writes a value nested in a container, tracking its pointer if fragments are spliced in
+fn write_child<W: Write, D: Display>(
+    out: &mut W,
+    value: &Json,
+    format: &Format,
+    depth: usize,
+    splice: &mut Option<&mut Splice<'_>>,
+    token: D,
+) -> fmt::Result {
+    let Some(splice) = splice else {
+        return write_node(out, value, format, depth, None);
+    };
+    let length = splice.pointer.len();
+    push(&mut splice.pointer, &format!("{token}"));
+    let result = write_node(out, value, format, depth, Some(splice));
+    splice.pointer.truncate(length);
+    result
+}
+
+/// Writes the properties of an object nested at the given depth
+fn write_object<'a, W: Write, I: ExactSizeIterator<Item = &'a (String, Json)>>(
+    out: &mut W,
+    items: I,
+    format: &Format,
+    depth: usize,
+    mut splice: Option<&mut Splice<'_>>,
+) -> fmt::Result {
+    // Put every property on its own line when indenting
+    let empty = items.len() == 0;
+    out.write_char('{')?;
+    for (index, (key, value)) in items.enumerate() {
+        if index > 0 {
+            out.write_char(',')?;
+        }
+        write_newline(out, format, depth + 1)?;
+        write_escaped(out, key, format.escape_slashes, false)?;
+        write_colon(out, format)?;
+        write_child(out, value, format, depth + 1, &mut splice, key)?;
+    }
+    if !empty || format.expand_empty {
+        write_newline(out, format, depth)?;
+    }
+    out.write_char('}')
+}
+
+/// Writes a value nested at the given depth
+pub(crate) fn write_value<W: Write>(
+    out: &mut W,
+    value: &Json,
+    format: &Format,
+    depth: usize,
+) -> fmt::Result {
+    write_node(out, value, format, depth, None)
+}
+
+/// Writes a value nested at the given depth, replacing values with fragments if requested
+fn write_node<W: Write>(
+    out: &mut W,
+    value: &Json,
+    format: &Format,
+    depth: usize,
+    mut splice: Option<&mut Splice<'_>>,
+) -> fmt::Result {
+    // Write fragments instead of the values they replace, and stop tracking pointers outside them
+    if let Some(current) = splice.as_deref() {
+        if let Some(fragment) = current.fragment() {
+            return out.write_str(fragment.as_str());
+        }
+        if !current.inside() {
+            splice = None;
+        }
+    }
+
+    match value {
+        // Write a list, putting every value on its own line when indenting
+        Json::List(values) => {
+            out.write_char('[')?;
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    out.write_char(',')?;
+                }
+                write_newline(out, format, depth + 1)?;
+                write_child(out, value, format, depth + 1, &mut splice, index)?;
+            }
+            if !values.is_empty() || format.expand_empty {
+                write_newline(out, format, depth)?;
+            }
+            out.write_char(']')
+        }
+
+        // Write an object, ordering the properties if requested
+        Json::Object(items) if format.sort_keys || !format.key_order.is_empty() => {
+            let mut items = items.iter().collect::<Vec<_>>();
+            items.sort_by(|(a, _), (b, _)| format.compare_keys(a, b));
+            write_object(out, items.into_iter(), format, depth, splice)
+        }
+        Json::Object(items) => write_object(out, items.iter(), format, depth, splice),
+
+        // Write a string
+        Json::String(string) => write_escaped(out, string, format.escape_slashes, false),
+
+        // Write a number, keeping raw numbers exactly as they were read
+        Json::Number(number) => write_number(out, *number, format.numbers),
+        Json::NumberRaw(text) => out.write_str(text),
+
+        // Write a boolean
+        Json::Bool(value) => write!(out, "{value}"),
+
+        // Write the null value
+        Json::Null => out.write_str("null"),
+    }
+}
+
+/// A JSON value that has already been serialized, for writing into output as it is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawJson(String);
+
+impl RawJson {
+    /// Wraps serialized JSON, checking that it's a single value without building it in memory
+    pub fn new(text: String) -> Result<Self, Error> {
+        validate(text.chars())?;
+        Ok(Self(text))
+    }
+
+    /// Wraps serialized JSON without checking it, for text known to be valid like cached output
+    pub fn new_unchecked(text: String) -> Self {
+        Self(text)
+    }
+
+    /// Returns the serialized JSON
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for RawJson {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&Json> for RawJson {
+    fn from(value: &Json) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl Json {
+    /// Returns a wrapper displaying the value with the given formatting options
+    pub fn display<'a>(&'a self, format: &'a Format) -> Formatted<'a> {
+        Formatted {
+            value: self,
+            format,
+        }
+    }
+
+    /// Writes the value with the given formatting options
+    pub fn write_formatted<W: Write>(&self, out: &mut W, format: &Format) -> fmt::Result {
+        write_value(out, self, format, 0)
+    }
+
+    /// Writes the value with the values at JSON pointers replaced by already serialized fragments
+    ///
+    /// The replaced values have to exist, `null` being a good placeholder. Fragments are written
+    /// exactly as they are, without indenting them to match the rest of the output.
+    pub fn write_spliced<W: Write>(
+        &self,
+        out: &mut W,
+        format: &Format,
+        fragments: &[(&str, &RawJson)],
+    ) -> fmt::Result {
+        let mut splice = Splice {
+            fragments,
+            pointer: String::new(),
+        };
+        write_node(out, self, format, 0, Some(&mut splice))
+    }
+
+    /// Returns the value as JSON indented by two spaces
+    pub fn to_string_pretty(&self) -> String {
+        let mut out = String::new();
+        self.write_formatted(&mut out, &Format::pretty())
+            .expect("writing to a string can't fail");
+        out
+    }
+
+    /// Returns the value as compact JSON in UTF-8 bytes
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+
+    /// Returns the value as JSON indented by two spaces in UTF-8 bytes
+    pub fn to_vec_pretty(&self) -> Vec<u8> {
+        self.to_string_pretty().into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+
+    use super::{Format, Indent, LineEnding, NumberFormat, RawJson};
+    use crate::Json;
+
+    #[test]
+    fn compact_output() {
+        let value = "{\"a\": [1, {}, []], \"b\": \"x\\\"y\\n\\u0001\"}"
+            .parse::<Json>()
+            .unwrap();
+        assert_eq!(
+            format!("{value}"),
+            "{\"a\":[1,{},[]],\"b\":\"x\\\"y\\n\\u0001\"}"
+        );
+    }
+
+    #[test]
+    fn pretty_output() {
+        let value = "{\"a\": [1, {}], \"b\": null}".parse::<Json>().unwrap();
+        assert_eq!(
+            value.to_string_pretty(),
+            "{\n  \"a\": [\n    1,\n    {}\n  ],\n  \"b\": null\n}"
+        );
+        assert_eq!(format!("{value:#}"), value.to_string_pretty());
+        assert_eq!(value.to_vec_pretty(), value.to_string_pretty().as_bytes());
+        assert_eq!(value.to_vec(), b"{\"a\":[1,{}],\"b\":null}");
+        assert_eq!(
+            format!(
+                "{}",
+                value.display(&Format {
+                    indent: Some(Indent::Tab),
+                    ..Format::default()
+                })
+            ),
+            "{\n\t\"a\": [\n\t\t1,\n\t\t{}\n\t],\n\t\"b\": null\n}"
+        );
+    }
+
+    #[test]
+    fn house_styles() {
+        let value = "{\"a\": [], \"b\": {\"c\": 1}}".parse::<Json>().unwrap();
+        let format = Format {
+            line_ending: LineEnding::CrLf,
+            space_after_colon: Some(false),
+            expand_empty: true,
+            ..Format::pretty()
+        };
+        assert_eq!(
+            format!("{}", value.display(&format)),
+            "{\r\n  \"a\":[\r\n  ],\r\n  \"b\":{\r\n    \"c\":1\r\n  }\r\n}"
+        );
+        let format = Format {
+            space_after_colon: Some(true),
+            ..Format::compact()
+        };
+        assert_eq!(
+            format!("{}", value.display(&format)),
+            "{\"a\": [],\"b\": {\"c\": 1}}"
+        );
+    }
+
+    #[test]
+    fn spliced_fragments() {
+        let value = "{\"status\": \"ok\", \"data\": [null, {\"a/b\": null}]}"
+            .parse::<Json>()
+            .unwrap();
+        let cached = RawJson::new("{\"x\":[1,2]}".to_owned()).unwrap();
+        let number = RawJson::new_unchecked("1.50".to_owned());
+        let mut out = String::new();
+        value
+            .write_spliced(
+                &mut out,
+                &Format::compact(),
+                &[("/data/0", &cached), ("/data/1/a~1b", &number)],
+            )
+            .unwrap();
+        assert_eq!(
+            out,
+            "{\"status\":\"ok\",\"data\":[{\"x\":[1,2]},{\"a/b\":1.50}]}"
+        );
+        assert!(RawJson::new("[1,".to_owned()).is_err());
+    }
+
+    #[test]
+    fn sorted_keys() {
+        let value = "{\"b\": 1, \"a\": {\"d\": 2, \"c\": 3}}"
+            .parse::<Json>()
+            .unwrap();
+        let format = Format {
+            sort_keys: true,
+            ..Format::default()
+        };
+        assert_eq!(
+            format!("{}", value.display(&format)),
+            "{\"a\":{\"c\":3,\"d\":2},\"b\":1}"
+        );
+    }
+
+    #[test]
+    fn key_order() {
+        let value = "{\"z\": 1, \"name\": \"n\", \"a\": [{\"b\": 2, \"id\": 3}], \"id\": 4}"
+            .parse::<Json>()
+            .unwrap();
+        let mut format = Format {
+            key_order: Vec::from(["id".to_owned(), "name".to_owned()]),
+            ..Format::default()
+        };
+        assert_eq!(
+            format!("{}", value.display(&format)),
+            "{\"id\":4,\"name\":\"n\",\"z\":1,\"a\":[{\"id\":3,\"b\":2}]}"
+        );
+
+        // The other keys can be sorted after the listed ones
+        format.sort_keys = true;
+        assert_eq!(
+            format!("{}", value.display(&format)),
+            "{\"id\":4,\"name\":\"n\",\"a\":[{\"id\":3,\"b\":2}],\"z\":1}"
+        );
+    }
+
+    #[test]
+    fn number_formats() {
+        let write = |number: f64, numbers| {
+            let format = Format {
+                numbers,
+                ..Format::default()
+            };
+            format!("{}", Json::Number(number).display(&format))
+        };
+        assert_eq!(
+            write(0.1 + 0.2, NumberFormat::Shortest),
+            "0.30000000000000004"
+        );
+        assert_eq!(
+            write(1e21, NumberFormat::Shortest),
+            "1000000000000000000000"
+        );
+        assert_eq!(write(f64::NAN, NumberFormat::Shortest), "null");
+        assert_eq!(write(2.0 / 3.0, NumberFormat::Fixed(3)), "0.667");
+        assert_eq!(write(42.0, NumberFormat::Fixed(3)), "42");
+        assert_eq!(write(-1e17, NumberFormat::Shortest), "-100000000000000000");
+        let scientific = NumberFormat::Scientific { min: -6, max: 21 };
+        assert_eq!(write(1e21, scientific), "1e21");
+        assert_eq!(write(1.5e-7, scientific), "1.5e-7");
+        assert_eq!(write(123.5, scientific), "123.5");
+        assert_eq!(write(0.0, scientific), "0");
+        assert_eq!("-1.5e-7".parse::<Json>().unwrap(), Json::Number(-1.5e-7));
+    }
+
+    #[test]
+    fn lossless_numbers() {
+        // Edge cases, followed by random bit patterns
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let random = core::iter::from_fn(|| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            Some(f64::from_bits(state))
+        });
+        let numbers = [
+            0.0,
+            -0.0,
+            0.1,
+            f64::MIN_POSITIVE,
+            5e-324,
+            f64::MAX,
+            f64::MIN,
+            9007199254740993.0,
+            1e21,
+            1.5e-7,
+        ]
+        .into_iter()
+        .chain(random.filter(|number| number.is_finite()).take(10_000));
+
+        // Every way of writing numbers except a fixed precision reads back as the same bits
+        let formats = [
+            NumberFormat::Shortest,
+            NumberFormat::Scientific { min: -6, max: 21 },
+        ];
+        for number in numbers {
+            for numbers in formats {
+                let format = Format {
+                    numbers,
+                    ..Format::default()
+                };
+                let text = format!("{}", Json::Number(number).display(&format));
+                let Ok(Json::Number(parsed)) = text.parse::<Json>() else {
+                    panic!("{text} doesn't parse as a number");
+                };
+                assert_eq!(parsed.to_bits(), number.to_bits(), "{text}");
+            }
+        }
+    }
+
+    #[test]
+    fn escaped_slashes() {
+        let value = Json::Object(alloc::vec![(
+            "a/b".to_owned(),
+            Json::String("</script>".to_owned())
+        )]);
+        let format = Format {
+            escape_slashes: true,
+            ..Format::default()
+        };
+        let text = format!("{}", value.display(&format));
+        assert_eq!(text, "{\"a\\/b\":\"<\\/script>\"}");
+        assert_eq!(text.parse::<Json>().unwrap(), value);
+        assert_eq!(format!("{value}"), "{\"a/b\":\"</script>\"}");
+    }
+
+    #[test]
+    fn string_round_trip() {
+        let value = Json::String("quote \" slash \\ tab \t é 😀".to_owned());
+        assert_eq!(format!("{value}").parse::<Json>().unwrap(), value);
+    }
+}
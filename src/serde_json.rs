@@ -0,0 +1,159 @@
+//! Conversions between `Json` and `serde_json::Value`
+//!
+//! Integers beyond 2^53, which an `f64` can't hold exactly, are kept as raw numbers, and raw
+//! integers are converted back exactly. Whole numbers are converted back to integers, non-finite
+//! numbers, which `serde_json` can't store, become null, and the order of keys is kept.
+
+use alloc::string::ToString;
+
+use serde_json::{Map, Number, Value};
+
+use crate::Json;
+
+impl From<Value> for Json {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => Json::Null,
+            Value::Bool(value) => Json::Bool(value),
+            Value::Number(number) => from_number(&number),
+            Value::String(string) => Json::String(string),
+            Value::Array(values) => Json::List(values.into_iter().map(Json::from).collect()),
+            Value::Object(items) => Json::Object(
+                items
+                    .into_iter()
+                    .map(|(key, value)| (key, Json::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Converts a number, keeping integers an `f64` can't hold exactly as raw numbers
+fn from_number(number: &Number) -> Json {
+    const EXACT: u64 = 1 << 53;
+    let exact = match (number.as_u64(), number.as_i64()) {
+        (Some(unsigned), _) => unsigned <= EXACT,
+        (None, Some(integer)) => integer.unsigned_abs() <= EXACT,
+        (None, None) => true,
+    };
+    match exact {
+        true => Json::Number(number.as_f64().unwrap_or(f64::NAN)),
+        false => Json::NumberRaw(number.to_string()),
+    }
+}
+
+/// Converts an `f64`, keeping whole numbers as integers if they fit
+fn to_number(number: f64) -> Value {
+    let integer = number as i64;
+    let unsigned = number as u64;
+    if (integer as f64).to_bits() == number.to_bits() && integer != i64::MAX {
+        Value::Number(Number::from(integer))
+    } else if (unsigned as f64).to_bits() == number.to_bits() && unsigned != u64::MAX {
+        Value::Number(Number::from(unsigned))
+    } else {
+        Number::from_f64(number).map_or(Value::Null, Value::Number)
+    }
+}
+
+impl From<Json> for Value {
+    fn from(value: Json) -> Self {
+        match value {
+            Json::Null => Value::Null,
+            Json::Bool(value) => Value::Bool(value),
+            Json::String(string) => Value::String(string),
+            Json::List(values) => Value::Array(values.into_iter().map(Value::from).collect()),
+            Json::Object(items) => Value::Object(
+                items
+                    .into_iter()
+                    .map(|(key, value)| (key, Value::from(value)))
+                    .collect::<Map<_, _>>(),
+            ),
+
+            // Read raw integers exactly, and other raw numbers like the parser does
+            Json::NumberRaw(text) => match (text.parse::<i64>(), text.parse::<u64>()) {
+                (Ok(integer), _) if text != "-0" => Value::Number(Number::from(integer)),
+                (_, Ok(unsigned)) => Value::Number(Number::from(unsigned)),
+                _ => to_number(text.parse().unwrap_or(f64::NAN)),
+            },
+            Json::Number(number) => to_number(number),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, string::ToString, vec::Vec};
+
+    use serde_json::{Value, json};
+
+    use crate::Json;
+
+    #[test]
+    fn from_serde_json() {
+        assert_eq!(
+            Json::from(json!({"a": [1, -2.5, "x", true, null], "b": {}})),
+            Json::Object(Vec::from([
+                (
+                    "a".to_owned(),
+                    Json::List(Vec::from([
+                        Json::Number(1.0),
+                        Json::Number(-2.5),
+                        Json::String("x".to_owned()),
+                        Json::Bool(true),
+                        Json::Null
+                    ]))
+                ),
+                ("b".to_owned(), Json::Object(Vec::new()))
+            ]))
+        );
+    }
+
+    #[test]
+    fn to_serde_json() {
+        let value = Value::from(
+            "{\"a\":[1,-2.5,\"x\",true,null],\"b\":{}}"
+                .parse::<Json>()
+                .unwrap(),
+        );
+        assert_eq!(value, json!({"a": [1, -2.5, "x", true, null], "b": {}}));
+        assert_eq!(
+            value.to_string(),
+            "{\"a\":[1,-2.5,\"x\",true,null],\"b\":{}}"
+        );
+        assert_eq!(Value::from(Json::Number(f64::NAN)), Value::Null);
+        assert_eq!(
+            Value::from(Json::Number(1e19)),
+            json!(10_000_000_000_000_000_000u64)
+        );
+    }
+
+    #[test]
+    fn large_integers() {
+        for value in [
+            json!(9_007_199_254_740_993u64),
+            json!(u64::MAX),
+            json!(-9_007_199_254_740_993i64),
+            json!(i64::MIN),
+        ] {
+            let converted = Json::from(value.clone());
+            assert!(matches!(converted, Json::NumberRaw(_)), "{value}");
+            assert_eq!(Value::from(converted), value);
+        }
+        assert_eq!(
+            Json::from(json!(9_007_199_254_740_992u64)),
+            Json::Number(9_007_199_254_740_992.0)
+        );
+        assert_eq!(
+            Value::from(Json::NumberRaw("9007199254740993".to_owned())),
+            json!(9_007_199_254_740_993u64)
+        );
+    }
+
+    #[test]
+    fn key_order() {
+        let text = "{\"z\":1,\"a\":{\"y\":2,\"b\":3}}";
+        let value = Value::from(text.parse::<Json>().unwrap());
+        assert_eq!(value.to_string(), text);
+        assert_eq!(Json::from(value).to_string(), text);
+    }
+}
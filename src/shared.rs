@@ -0,0 +1,439 @@
+//! A JSON representation that can be cloned cheaply and shared between threads
+//!
+//! Lists and objects are shared between clones until they are changed, so editing a clone
+//! only copies the lists and objects on the path to the changed value. Keys can be shared
+//! between documents as well, with a [`KeyInterner`].
+
+use alloc::{borrow::Cow, collections::BTreeSet, string::String, sync::Arc, vec::Vec};
+use core::str::FromStr;
+
+use crate::{
+    Error, Json, ParseOptions, Source, StrSource,
+    parser::{Items, ParseState, Scratch, Storage},
+    pointer::{index, tokens},
+    read_located,
+};
+
+/// A JSON value whose strings, lists and objects are reference counted
+///
+/// Cloning only increments reference counts, so documents can be cached and handed to other
+/// threads without copying them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedJson {
+    /// A list of data
+    List(Arc<Vec<SharedJson>>),
+
+    /// An object
+    Object(Arc<Vec<(Arc<str>, SharedJson)>>),
+
+    /// A string
+    String(Arc<str>),
+
+    /// A number
+    Number(f64),
+
+    /// A number kept as its source text, see [`Json::NumberRaw`]
+    NumberRaw(Arc<str>),
+
+    /// A boolean
+    Bool(bool),
+
+    /// A null value
+    Null,
+}
+
+impl SharedJson {
+    /// Returns the value referenced by a JSON pointer like `/items/0/name`
+    pub fn pointer(&self, pointer: &str) -> Option<&SharedJson> {
+        tokens(pointer)?.try_fold(self, |value, token| match value {
+            SharedJson::Object(items) => items
+                .iter()
+                .rev()
+                .find(|(key, _)| **key == *token)
+                .map(|(_, value)| value),
+            SharedJson::List(values) => values.get(index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// Returns a mutable reference to the value referenced by a JSON pointer
+    ///
+    /// Lists and objects on the path that are shared with clones are copied first, so the
+    /// clones keep their values.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut SharedJson> {
+        tokens(pointer)?.try_fold(self, |value, token| match value {
+            SharedJson::Object(items) => Arc::make_mut(items)
+                .iter_mut()
+                .rev()
+                .find(|(key, _)| **key == *token)
+                .map(|(_, value)| value),
+            SharedJson::List(values) => Arc::make_mut(values).get_mut(index(&token)?),
+            _ => None,
+        })
+    }
+}
+
+impl From<&Json> for SharedJson {
+    fn from(value: &Json) -> Self {
+        match value {
+            Json::List(values) => {
+                SharedJson::List(Arc::new(values.iter().map(SharedJson::from).collect()))
+            }
+            Json::Object(items) => SharedJson::Object(Arc::new(
+                items
+                    .iter()
+                    .map(|(key, value)| (Arc::from(key.as_str()), SharedJson::from(value)))
+                    .collect(),
+            )),
+            Json::String(string) => SharedJson::String(Arc::from(string.as_str())),
+            Json::Number(number) => SharedJson::Number(*number),
+            Json::NumberRaw(text) => SharedJson::NumberRaw(Arc::from(text.as_str())),
+            Json::Bool(value) => SharedJson::Bool(*value),
+            Json::Null => SharedJson::Null,
+        }
+    }
+}
+
+/// A set of keys shared by the documents converted or parsed with it, so documents with the
+/// same keys keep only one copy of each
+///
+/// Keep one interner for many documents, in a `Mutex` to share it between threads.
+#[derive(Debug, Clone, Default)]
+pub struct KeyInterner {
+    /// The keys handed out
+    keys: BTreeSet<Arc<str>>,
+}
+
+impl KeyInterner {
+    /// Creates an interner without keys
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the shared copy of a key, adding it if it's new
+    pub fn intern(&mut self, key: &str) -> Arc<str> {
+        if let Some(key) = self.keys.get(key) {
+            return Arc::clone(key);
+        }
+        let key = Arc::<str>::from(key);
+        self.keys.insert(Arc::clone(&key));
+        key
+    }
+
+    /// Returns the number of keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns whether there are no keys
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Forgets the keys no document uses anymore
+    pub fn remove_unused(&mut self) {
+        self.keys.retain(|key| Arc::strong_count(key) > 1);
+    }
+}
+
+impl SharedJson {
+    /// Converts a value, taking its keys from an interner
+    pub fn from_json_interned(value: &Json, interner: &mut KeyInterner) -> Self {
+        match value {
+            Json::List(values) => SharedJson::List(Arc::new(
+                values
+                    .iter()
+                    .map(|value| SharedJson::from_json_interned(value, interner))
+                    .collect(),
+            )),
+            Json::Object(items) => SharedJson::Object(Arc::new(
+                items
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = interner.intern(key);
+                        (key, SharedJson::from_json_interned(value, interner))
+                    })
+                    .collect(),
+            )),
+            value => SharedJson::from(value),
+        }
+    }
+
+    /// Parses a complete JSON value, taking its keys from an interner as they're read and
+    /// adding the line and column to errors
+    pub fn parse_interned(text: &str, interner: &mut KeyInterner) -> Result<Self, Error> {
+        let options = ParseOptions::default();
+        parse_interned(
+            StrSource::new(text),
+            &mut ParseState::new(&options),
+            interner,
+        )
+    }
+}
+
+/// Parses a complete JSON value from a source, taking its keys from an interner and adding the
+/// line and column to errors
+pub(crate) fn parse_interned<S: Source>(
+    source: S,
+    state: &mut ParseState<'_>,
+    interner: &mut KeyInterner,
+) -> Result<SharedJson, Error> {
+    let mut storage = Interned {
+        interner,
+        values: Vec::new(),
+        items: Vec::new(),
+    };
+    read_located(source, state, |iter, state| {
+        Json::parse_value(iter, state, &mut storage, 0)
+    })
+}
+
+/// Stores values as [`SharedJson`], taking the keys of objects from an interner without
+/// allocating them first
+struct Interned<'a> {
+    /// The interner the keys are taken from
+    interner: &'a mut KeyInterner,
+
+    /// The elements of the lists being read, the innermost list's last
+    values: Vec<SharedJson>,
+
+    /// The properties of the objects being read, the innermost object's last
+    items: Vec<(Arc<str>, SharedJson)>,
+}
+
+impl Storage for Interned<'_> {
+    type Value = SharedJson;
+    type Key = Arc<str>;
+
+    fn string(&mut self, text: &str) -> SharedJson {
+        SharedJson::String(Arc::from(text))
+    }
+
+    fn key(&mut self, text: &str) -> Arc<str> {
+        self.interner.intern(text)
+    }
+
+    fn scalar(&mut self, value: Json) -> SharedJson {
+        SharedJson::from(&value)
+    }
+
+    fn list(&mut self, values: Vec<SharedJson>) -> SharedJson {
+        SharedJson::List(Arc::new(values))
+    }
+
+    fn object(&mut self, items: Vec<(Arc<str>, SharedJson)>) -> SharedJson {
+        SharedJson::Object(Arc::new(items))
+    }
+
+    fn values<'s>(&'s mut self, _: &'s mut Scratch) -> &'s mut Vec<SharedJson> {
+        &mut self.values
+    }
+
+    fn items<'s>(&'s mut self, _: &'s mut Scratch) -> &'s mut Items<Self> {
+        &mut self.items
+    }
+
+    fn to_json(value: &SharedJson) -> Cow<'_, Json> {
+        Cow::Owned(Json::from(value))
+    }
+}
+
+impl From<Json> for SharedJson {
+    fn from(value: Json) -> Self {
+        SharedJson::from(&value)
+    }
+}
+
+impl From<&SharedJson> for Json {
+    fn from(value: &SharedJson) -> Self {
+        match value {
+            SharedJson::List(values) => Json::List(values.iter().map(Json::from).collect()),
+            SharedJson::Object(items) => Json::Object(
+                items
+                    .iter()
+                    .map(|(key, value)| (String::from(&**key), Json::from(value)))
+                    .collect(),
+            ),
+            SharedJson::String(string) => Json::String(String::from(&**string)),
+            SharedJson::Number(number) => Json::Number(*number),
+            SharedJson::NumberRaw(text) => Json::NumberRaw(String::from(&**text)),
+            SharedJson::Bool(value) => Json::Bool(*value),
+            SharedJson::Null => Json::Null,
+        }
+    }
+}
+
+impl FromStr for SharedJson {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<Json>().map(SharedJson::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, sync::Arc, vec::Vec};
+
+    use super::{KeyInterner, SharedJson, parse_interned};
+    use crate::{
+        Error, IntegerOverflow, Json, ParseHooks, ParseOptions, Parser, Path, StrSource,
+        parser::ParseState,
+    };
+
+    #[test]
+    fn shared_conversion() {
+        let json = "{\"a\":[1,\"x\",true,null]}".parse::<Json>().unwrap();
+        let shared = SharedJson::from(&json);
+        assert_eq!(Json::from(&shared), json);
+        assert_eq!(
+            "{\"a\":[1,\"x\",true,null]}".parse::<SharedJson>().unwrap(),
+            shared
+        );
+    }
+
+    #[test]
+    fn cheap_clones() {
+        /// Only compiles for types that can be shared between threads
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let shared = "{\"a\":[1,2,3]}".parse::<SharedJson>().unwrap();
+        let clone = shared.clone();
+        assert_send_sync(&clone);
+        let (SharedJson::Object(original), SharedJson::Object(cloned)) = (&shared, &clone) else {
+            panic!("expected objects");
+        };
+        assert!(Arc::ptr_eq(original, cloned));
+    }
+
+    #[test]
+    fn interned_keys() {
+        let mut interner = KeyInterner::new();
+        let first =
+            SharedJson::parse_interned("{\"id\":1,\"tags\":[{\"id\":2}]}", &mut interner).unwrap();
+        let second = SharedJson::parse_interned("{\"id\":3}", &mut interner).unwrap();
+        assert_eq!(interner.len(), 2);
+
+        // Both documents point to the same copy of the key
+        let (SharedJson::Object(first_items), SharedJson::Object(second_items)) = (&first, &second)
+        else {
+            panic!("expected objects");
+        };
+        assert!(Arc::ptr_eq(&first_items[0].0, &second_items[0].0));
+
+        // Keys are forgotten once no document uses them
+        drop(first);
+        interner.remove_unused();
+        assert_eq!(interner.len(), 1);
+
+        // Parsing with interned keys reads the same values as parsing does
+        let mut parser = Parser::default();
+        for text in [
+            "{\"a\": [1, -2.5e3, \"x\\ny\", {\"b\": null}], \"c\": {}, \"d\": [true]}",
+            "[]",
+            "\"é\"",
+        ] {
+            let expected = SharedJson::from(text.parse::<Json>().unwrap());
+            assert_eq!(
+                parser.parse_interned(text, &mut interner).unwrap(),
+                expected
+            );
+        }
+        assert!(interner.len() >= 4);
+
+        // Errors have positions, and the nesting is limited like when parsing
+        for text in ["{\"a\" 1}", "[1,]", "[1] x", "", &"[".repeat(1_000)] {
+            assert!(matches!(
+                parser.parse_interned(text, &mut interner),
+                Err(Error::At { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn interning_like_parsing() {
+        // Numbers are kept as text like the options ask
+        let mut interner = KeyInterner::new();
+        let mut parser = Parser::new(ParseOptions {
+            raw_numbers: true,
+            ..ParseOptions::default()
+        });
+        assert_eq!(
+            Json::from(
+                &parser
+                    .parse_interned("[1.50, 1e400]", &mut interner)
+                    .unwrap()
+            ),
+            Json::List(Vec::from([
+                Json::NumberRaw("1.50".to_owned()),
+                Json::NumberRaw("1e400".to_owned())
+            ]))
+        );
+        parser.options = ParseOptions {
+            integer_overflow: IntegerOverflow::Raw,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            parser
+                .parse_interned("{\"id\": 9007199254740993}", &mut interner)
+                .unwrap(),
+            SharedJson::Object(Arc::new(Vec::from([(
+                interner.intern("id"),
+                SharedJson::NumberRaw(Arc::from("9007199254740993"))
+            )])))
+        );
+
+        // The callbacks see every key and value
+        struct Counter(usize);
+        impl ParseHooks for Counter {
+            fn on_key(&mut self, _: &Path, key: &str) -> Result<(), Error> {
+                match key {
+                    "$where" => Err(Error::Aborted("keys can't start with $")),
+                    _ => Ok(()),
+                }
+            }
+
+            fn on_value(&mut self, _: &Path, _: &Json) -> Result<(), Error> {
+                self.0 += 1;
+                Ok(())
+            }
+        }
+        let options = ParseOptions::default();
+        let mut counter = Counter(0);
+        parse_interned(
+            StrSource::new("{\"a\": [1, {\"b\": null}]}"),
+            &mut ParseState::with_hooks(&options, &mut counter),
+            &mut interner,
+        )
+        .unwrap();
+        assert_eq!(counter.0, 5);
+        assert!(
+            parse_interned(
+                StrSource::new("[{\"$where\": 1}]"),
+                &mut ParseState::with_hooks(&options, &mut counter),
+                &mut interner,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let original = "{\"a\":[1,2],\"b\":{\"c\":3}}"
+            .parse::<SharedJson>()
+            .unwrap();
+        let mut edited = original.clone();
+        *edited.pointer_mut("/a/1").unwrap() = SharedJson::Bool(true);
+
+        // The edited list was copied, the untouched object is still shared
+        assert_eq!(original.pointer("/a/1"), Some(&SharedJson::Number(2.0)));
+        assert_eq!(edited.pointer("/a/1"), Some(&SharedJson::Bool(true)));
+        let (Some(SharedJson::Object(before)), Some(SharedJson::Object(after))) =
+            (original.pointer("/b"), edited.pointer("/b"))
+        else {
+            panic!("expected objects");
+        };
+        assert!(Arc::ptr_eq(before, after));
+        assert!(edited.pointer_mut("/a/5").is_none());
+    }
+}
@@ -0,0 +1,192 @@
+//! Ordering values, and sorting, deduplicating and grouping lists
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::cmp::Ordering;
+
+use crate::Json;
+
+/// Returns the position of a value's type in jq's ordering of values
+fn rank(value: &Json) -> u8 {
+    match value {
+        Json::Null => 0,
+        Json::Bool(false) => 1,
+        Json::Bool(true) => 2,
+        Json::Number(_) | Json::NumberRaw(_) => 3,
+        Json::String(_) => 4,
+        Json::List(_) => 5,
+        Json::Object(_) => 6,
+    }
+}
+
+impl Json {
+    /// Orders values the way jq does: by type first, then numbers by value, strings by code
+    /// point and lists element by element
+    pub fn compare(&self, other: &Json) -> Ordering {
+        match (&*self.plain(), &*other.plain()) {
+            (Json::Number(a), Json::Number(b)) => a.total_cmp(b),
+            (Json::String(a), Json::String(b)) => a.cmp(b),
+            (Json::List(a), Json::List(b)) => a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| a.compare(b))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            (a, b) => rank(a).cmp(&rank(b)),
+        }
+    }
+
+    /// Sorts the elements of a list with a comparison function, keeping equal elements in
+    /// order, and leaves other values as they are
+    pub fn sort_array_by<F: FnMut(&Json, &Json) -> Ordering>(&mut self, compare: F) {
+        if let Json::List(values) = self {
+            values.sort_by(compare);
+        }
+    }
+
+    /// Sorts the elements of a list by the values at a JSON pointer like `/name`, in the order
+    /// of [`Json::compare`], with elements lacking the value first
+    pub fn sort_array_by_pointer(&mut self, pointer: &str) {
+        self.sort_array_by(|a, b| {
+            let (a, b) = (a.pointer(pointer), b.pointer(pointer));
+            a.unwrap_or(&Json::Null).compare(b.unwrap_or(&Json::Null))
+        });
+    }
+
+    /// Removes elements of a list equal to an earlier element, leaving other values as they are
+    ///
+    /// Values are compared like [`Json::equivalent`] does, so the order of keys doesn't matter.
+    pub fn dedup(&mut self) {
+        self.dedup_by_pointer("");
+    }
+
+    /// Removes elements of a list whose value at a JSON pointer like `/id` equals that of an
+    /// earlier element, keeping elements lacking the value
+    pub fn dedup_by_pointer(&mut self, pointer: &str) {
+        let Json::List(values) = self else {
+            return;
+        };
+        let mut kept = Vec::<Json>::with_capacity(values.len());
+        for value in values.drain(..) {
+            let key = value.pointer(pointer);
+            let earlier = |kept: &Json| {
+                kept.pointer(pointer)
+                    .zip(key)
+                    .is_some_and(|(earlier, key)| earlier.equivalent(key))
+            };
+            if key.is_none() || !kept.iter().any(earlier) {
+                kept.push(value);
+            }
+        }
+        *values = kept;
+    }
+
+    /// Groups the elements of a list by their value at a JSON pointer like `/country`, returning
+    /// an object from each distinct value to the list of elements with it, or `None` for other
+    /// values
+    ///
+    /// Groups are in the order their values first appear. String values are used as keys as
+    /// they are, other values in their compact form, and elements lacking the value are grouped
+    /// under `null`.
+    pub fn group_by(&self, pointer: &str) -> Option<Json> {
+        let Json::List(values) = self else {
+            return None;
+        };
+        let mut groups = Vec::<(String, Json)>::new();
+        for value in values {
+            // Find the key of the element's group
+            let key = match value.pointer(pointer).unwrap_or(&Json::Null) {
+                Json::String(key) => key.clone(),
+                key => key.to_string(),
+            };
+
+            // Add the element to its group, starting the group if it's new
+            match groups.iter_mut().find(|(name, _)| *name == key) {
+                Some((_, Json::List(group))) => group.push(value.clone()),
+                _ => groups.push((key, Json::List(Vec::from([value.clone()])))),
+            }
+        }
+        Some(Json::Object(groups))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Json;
+
+    #[test]
+    fn sorting() {
+        let mut users = "[{\"name\":\"carol\",\"age\":30},{\"name\":\"alice\",\"age\":25},\
+            {\"age\":40},{\"name\":\"bob\",\"age\":25}]"
+            .parse::<Json>()
+            .unwrap();
+        users.sort_array_by_pointer("/name");
+        assert_eq!(
+            users,
+            "[{\"age\":40},{\"name\":\"alice\",\"age\":25},{\"name\":\"bob\",\"age\":25},\
+            {\"name\":\"carol\",\"age\":30}]"
+                .parse::<Json>()
+                .unwrap()
+        );
+
+        // Sorting is stable, and a comparison function can sort in any order
+        users.sort_array_by_pointer("/age");
+        assert_eq!(
+            users.pointer("/0/name"),
+            Some(&Json::String("alice".into()))
+        );
+        assert_eq!(users.pointer("/1/name"), Some(&Json::String("bob".into())));
+        users.sort_array_by(|a, b| {
+            b.pointer("/age")
+                .unwrap()
+                .compare(a.pointer("/age").unwrap())
+        });
+        assert_eq!(users.pointer("/0/age"), Some(&Json::Number(40.0)));
+    }
+
+    #[test]
+    fn deduplicating() {
+        let mut values = "[1,[2],1,\"1\",[2],{\"a\":1,\"b\":2},{\"b\":2,\"a\":1}]"
+            .parse::<Json>()
+            .unwrap();
+        values.dedup();
+        assert_eq!(
+            values,
+            "[1,[2],\"1\",{\"a\":1,\"b\":2}]".parse::<Json>().unwrap()
+        );
+
+        // Keyed deduplication keeps the first element with each key
+        let mut users = "[{\"id\":1,\"v\":1},{\"id\":2},{\"id\":1,\"v\":2},{},{},\
+            {\"id\":{\"a\":1,\"b\":2}},{\"id\":{\"b\":2,\"a\":1}}]"
+            .parse::<Json>()
+            .unwrap();
+        users.dedup_by_pointer("/id");
+        assert_eq!(
+            users,
+            "[{\"id\":1,\"v\":1},{\"id\":2},{},{},{\"id\":{\"a\":1,\"b\":2}}]"
+                .parse::<Json>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn grouping() {
+        let orders = "[{\"country\":\"NL\",\"id\":1},{\"country\":\"DE\",\"id\":2},\
+            {\"country\":\"NL\",\"id\":3},{\"country\":1,\"id\":4},{\"id\":5}]"
+            .parse::<Json>()
+            .unwrap();
+        assert_eq!(
+            orders.group_by("/country"),
+            Some(
+                "{\"NL\":[{\"country\":\"NL\",\"id\":1},{\"country\":\"NL\",\"id\":3}],\
+                \"DE\":[{\"country\":\"DE\",\"id\":2}],\"1\":[{\"country\":1,\"id\":4}],\
+                \"null\":[{\"id\":5}]}"
+                    .parse::<Json>()
+                    .unwrap()
+            )
+        );
+        assert_eq!(Json::Null.group_by("/country"), None);
+    }
+}
@@ -0,0 +1,423 @@
+//! Sources of characters the parser reads from
+
+use core::iter::Peekable;
+#[cfg(feature = "std")]
+use std::io::{self, ErrorKind, Read};
+
+use itertools::PeekingNext;
+
+use crate::Error;
+
+/// Where the parser takes its characters from
+///
+/// Implement this to parse from other inputs with [`Json::from_source`](crate::Json::from_source).
+pub trait Source {
+    /// Returns the next character without taking it
+    fn peek(&mut self) -> Option<char>;
+
+    /// Takes the next character
+    fn next(&mut self) -> Option<char>;
+
+    /// Returns the position of the next character, in the units of the input
+    fn position(&self) -> usize;
+}
+
+impl<S: Source + ?Sized> Source for &mut S {
+    fn peek(&mut self) -> Option<char> {
+        (**self).peek()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        (**self).next()
+    }
+
+    fn position(&self) -> usize {
+        (**self).position()
+    }
+}
+
+/// Reads the characters of a string, counting positions in bytes
+#[derive(Debug, Clone)]
+pub struct StrSource<'a> {
+    /// The string the characters are read from
+    text: &'a str,
+
+    /// The byte offset of the next character
+    offset: usize,
+}
+
+impl<'a> StrSource<'a> {
+    /// Starts reading at the start of a string
+    pub fn new(text: &'a str) -> Self {
+        Self { text, offset: 0 }
+    }
+}
+
+impl<'a> From<&'a str> for StrSource<'a> {
+    fn from(text: &'a str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl Source for StrSource<'_> {
+    fn peek(&mut self) -> Option<char> {
+        self.text[self.offset..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.offset += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn position(&self) -> usize {
+        self.offset
+    }
+}
+
+/// Decodes the first character of UTF-8 bytes, returning it and its length
+///
+/// Invalid bytes become U+FFFD, like they do in `String::from_utf8_lossy`.
+fn decode(bytes: &[u8]) -> Option<(char, usize)> {
+    let start = &bytes[..bytes.len().min(4)];
+    let (valid, invalid) = match core::str::from_utf8(start) {
+        Ok(text) => (text, 0),
+        Err(error) => (
+            // The prefix before the error is valid UTF-8, so this can't fail
+            core::str::from_utf8(&start[..error.valid_up_to()]).unwrap_or_default(),
+            error
+                .error_len()
+                .unwrap_or(start.len() - error.valid_up_to()),
+        ),
+    };
+    match valid.chars().next() {
+        Some(ch) => Some((ch, ch.len_utf8())),
+        None if invalid > 0 => Some((char::REPLACEMENT_CHARACTER, invalid)),
+        None => None,
+    }
+}
+
+/// Reads the characters of UTF-8 bytes, counting positions in bytes
+#[derive(Debug, Clone)]
+pub struct SliceSource<'a> {
+    /// The bytes the characters are read from
+    bytes: &'a [u8],
+
+    /// The offset of the next character
+    offset: usize,
+}
+
+impl<'a> SliceSource<'a> {
+    /// Starts reading at the start of the bytes
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+}
+
+impl<'a> From<&'a [u8]> for SliceSource<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl Source for SliceSource<'_> {
+    fn peek(&mut self) -> Option<char> {
+        decode(&self.bytes[self.offset..]).map(|(ch, _)| ch)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let (ch, length) = decode(&self.bytes[self.offset..])?;
+        self.offset += length;
+        Some(ch)
+    }
+
+    fn position(&self) -> usize {
+        self.offset
+    }
+}
+
+/// Reads the characters of an iterator, counting positions in characters
+#[derive(Debug, Clone)]
+pub struct IterSource<I: Iterator<Item = char>> {
+    /// The iterator the characters are taken from
+    iter: Peekable<I>,
+
+    /// The number of characters taken
+    position: usize,
+}
+
+impl<I: Iterator<Item = char>> IterSource<I> {
+    /// Starts reading at the next character of an iterator
+    pub fn new(iter: I) -> Self {
+        Self {
+            iter: iter.peekable(),
+            position: 0,
+        }
+    }
+}
+
+impl<I: Iterator<Item = char>> Source for IterSource<I> {
+    fn peek(&mut self) -> Option<char> {
+        self.iter.peek().copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.iter.next()?;
+        self.position += 1;
+        Some(ch)
+    }
+
+    fn position(&self) -> usize {
+        self.position
+    }
+}
+
+/// Reads the characters of UTF-8 text from a reader, counting positions in bytes and keeping
+/// the error that stopped it
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ReaderSource<R: Read> {
+    /// The reader the bytes are read from
+    reader: R,
+
+    /// The bytes read, but not yet returned
+    buffer: [u8; 1024],
+
+    /// The index of the next byte to return
+    start: usize,
+
+    /// The number of bytes in the buffer
+    end: usize,
+
+    /// Whether the reader has no bytes left
+    done: bool,
+
+    /// The number of bytes returned
+    position: usize,
+
+    /// The error returned by the reader, if any
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> ReaderSource<R> {
+    /// Starts reading at the current position of a reader
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: [0; 1024],
+            start: 0,
+            end: 0,
+            done: false,
+            position: 0,
+            error: None,
+        }
+    }
+
+    /// Returns the error that stopped reading, if any
+    ///
+    /// Parsing fails as if the input ended there, so check this after a parse error.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Makes sure the buffer holds a whole character, unless the input ends first
+    fn fill(&mut self) {
+        if self.end - self.start >= 4 || self.done {
+            return;
+        }
+
+        // Move the remaining bytes to the front and read more after them
+        self.buffer.copy_within(self.start..self.end, 0);
+        (self.start, self.end) = (0, self.end - self.start);
+        while self.end < 4 {
+            match self.reader.read(&mut self.buffer[self.end..]) {
+                Ok(0) => self.done = true,
+                Ok(count) => {
+                    self.end += count;
+                    continue;
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    self.error = Some(error);
+                    self.done = true;
+                }
+            }
+            break;
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Source for ReaderSource<R> {
+    fn peek(&mut self) -> Option<char> {
+        self.fill();
+        decode(&self.buffer[self.start..self.end]).map(|(ch, _)| ch)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.fill();
+        let (ch, length) = decode(&self.buffer[self.start..self.end])?;
+        self.start += length;
+        self.position += length;
+        Some(ch)
+    }
+
+    fn position(&self) -> usize {
+        self.position
+    }
+}
+
+/// Ends a source after a number of characters, remembering whether it had more
+pub(crate) struct Limited<S> {
+    /// The source the characters are taken from
+    source: S,
+
+    /// The number of characters that may still be taken
+    remaining: usize,
+
+    /// Whether the source had characters left when the limit was reached
+    exceeded: bool,
+}
+
+impl<S: Source> Limited<S> {
+    /// Limits a source to a number of characters, if any
+    pub(crate) fn new(source: S, limit: Option<usize>) -> Self {
+        Self {
+            source,
+            remaining: limit.unwrap_or(usize::MAX),
+            exceeded: false,
+        }
+    }
+
+    /// Replaces the result of parsing by an error if the source was cut off
+    pub(crate) fn check<T>(&self, result: Result<T, Error>) -> Result<T, Error> {
+        if self.exceeded {
+            Err(Error::TooLarge)
+        } else {
+            result
+        }
+    }
+}
+
+impl<S: Source> Source for Limited<S> {
+    fn peek(&mut self) -> Option<char> {
+        if self.remaining == 0 {
+            self.exceeded |= self.source.peek().is_some();
+            return None;
+        }
+        self.source.peek()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        if self.remaining == 0 {
+            self.peek();
+            return None;
+        }
+        let ch = self.source.next()?;
+        self.remaining -= 1;
+        Some(ch)
+    }
+
+    fn position(&self) -> usize {
+        self.source.position()
+    }
+}
+
+/// Lets the parsing helpers take the characters of a source like those of an iterator
+pub(crate) struct SourceChars<S>(pub(crate) S);
+
+impl<S: Source> SourceChars<S> {
+    /// Returns the next character without taking it
+    pub(crate) fn peek(&mut self) -> Option<char> {
+        self.0.peek()
+    }
+}
+
+impl<S: Source> Iterator for SourceChars<S> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+impl<S: Source> PeekingNext for SourceChars<S> {
+    fn peeking_next<F: FnOnce(&Self::Item) -> bool>(&mut self, accept: F) -> Option<Self::Item> {
+        let ch = self.0.peek()?;
+        if accept(&ch) { self.0.next() } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SliceSource, Source, StrSource};
+    use crate::Json;
+
+    /// A downstream source: text with `#` comments until the end of the line removed
+    struct Commented<'a>(StrSource<'a>);
+
+    impl Source for Commented<'_> {
+        fn peek(&mut self) -> Option<char> {
+            // Skip a comment, keeping the newline that ends it
+            if self.0.peek() == Some('#') {
+                while self.0.peek().is_some_and(|ch| ch != '\n') {
+                    self.0.next();
+                }
+            }
+            self.0.peek()
+        }
+
+        fn next(&mut self) -> Option<char> {
+            self.peek()?;
+            self.0.next()
+        }
+
+        fn position(&self) -> usize {
+            self.0.position()
+        }
+    }
+
+    #[test]
+    fn sources() {
+        let text = "{\"name\":\"é\",\"list\":[1,2]}";
+        let expected = text.parse::<Json>().unwrap();
+        assert_eq!(Json::from_source(StrSource::new(text)).unwrap(), expected);
+        assert_eq!(
+            Json::from_source(SliceSource::new(text.as_bytes())).unwrap(),
+            expected
+        );
+
+        // Positions count bytes, and invalid UTF-8 is replaced
+        let mut source = SliceSource::new("é1".as_bytes());
+        assert_eq!((source.next(), source.position()), (Some('é'), 2));
+        assert_eq!(
+            Json::from_source(SliceSource::new(b"\"\xff\"")).unwrap(),
+            Json::String("\u{fffd}".into())
+        );
+        assert!(Json::from_source(SliceSource::new(b"[1,\xff]")).is_err());
+
+        // New sources plug into the same parser
+        let source = Commented(StrSource::new("[1, # one\n 2]"));
+        assert_eq!(
+            Json::from_source(source).unwrap(),
+            "[1,2]".parse::<Json>().unwrap()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn readers() {
+        use super::ReaderSource;
+
+        // Characters split across reads are put together
+        let text = "[\"naïve ☃\",".repeat(100) + "null" + &"]".repeat(100);
+        let mut source = ReaderSource::new(text.as_bytes());
+        assert_eq!(
+            Json::from_source(&mut source).unwrap(),
+            text.parse::<Json>().unwrap()
+        );
+        assert_eq!(source.position(), text.len());
+        assert!(source.error().is_none());
+    }
+}
@@ -0,0 +1,507 @@
+//! Parsing into a tree that remembers where every value came from in the source text
+
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Display, Formatter};
+
+use itertools::PeekingNext;
+
+use crate::{
+    Error, Json, MAX_DEPTH, ParseOptions, Span,
+    pointer::{index, push, tokens},
+};
+
+/// A value together with the span of its source text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    /// The value
+    pub value: T,
+
+    /// Where the value is in the source text
+    pub span: Span,
+}
+
+/// A JSON value whose nested values and keys know their spans
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpannedJson {
+    /// A list of values
+    List(Vec<Spanned<SpannedJson>>),
+
+    /// The properties of an object, in the order of the source text
+    Object(Vec<SpannedProperty>),
+
+    /// A string
+    String(String),
+
+    /// A number
+    Number(f64),
+
+    /// A boolean
+    Bool(bool),
+
+    /// The null value
+    Null,
+}
+
+/// A key of an object with its value, both with their spans
+pub type SpannedProperty = (Spanned<String>, Spanned<SpannedJson>);
+
+/// Something suspicious in a document that doesn't stop it from being parsed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// An object has a key more than once, of which only the last value is used by lookups
+    DuplicateKey {
+        /// The JSON pointer to the repeated key
+        pointer: String,
+
+        /// The span of the first occurrence of the key
+        first: Span,
+
+        /// The span of the repeated key
+        duplicate: Span,
+    },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::DuplicateKey {
+                pointer,
+                first,
+                duplicate,
+            } => write!(
+                f,
+                "duplicate key {pointer} at line {}, column {}, first defined at line {}, column {}",
+                duplicate.line, duplicate.column, first.line, first.column
+            ),
+        }
+    }
+}
+
+/// The characters of the source text with the position of the next one
+struct Source<'a> {
+    /// The source text
+    text: &'a str,
+
+    /// The byte offset of the next character
+    offset: usize,
+
+    /// The line of the next character, starting at 1
+    line: usize,
+
+    /// The column of the next character, starting at 1
+    column: usize,
+
+    /// Whether `//` and `/* */` comments are skipped like whitespace
+    comments: bool,
+
+    /// The number of lists and objects around the next value
+    depth: usize,
+
+    /// How deeply lists and objects may be nested, or `None` for no limit
+    max_depth: Option<usize>,
+}
+
+impl Source<'_> {
+    /// Returns the next character without taking it
+    fn peek(&self) -> Option<char> {
+        self.text[self.offset..].chars().next()
+    }
+
+    /// Returns a span from a starting position to the current offset
+    fn span_from(&self, (start, line, column): (usize, usize, usize)) -> Span {
+        Span {
+            start,
+            end: self.offset,
+            line,
+            column,
+        }
+    }
+
+    /// Returns the offset, line and column of the next character
+    fn here(&self) -> (usize, usize, usize) {
+        (self.offset, self.line, self.column)
+    }
+
+    /// Skips whitespace and comments if they're allowed, returning the next character
+    fn skip_whitespace(&mut self) -> Option<char> {
+        loop {
+            Json::skip_whitespace(&mut *self);
+            let rest = &self.text[self.offset..];
+            if self.comments && rest.starts_with("//") {
+                // Skip to the end of the line
+                while self.peeking_next(|&ch| ch != '\n').is_some() {}
+            } else if self.comments && rest.starts_with("/*") {
+                // Skip past the closing */, or to the end of the text if there is none
+                self.offset += 2;
+                self.column += 2;
+                while !self.text[self.offset..].starts_with("*/") && self.next().is_some() {}
+                self.nth(1);
+            } else {
+                return self.peek();
+            }
+        }
+    }
+
+    /// Reads a value with its span
+    fn value(&mut self) -> Result<Spanned<SpannedJson>, Error> {
+        // Refuse to open another list or object beyond the deepest nesting allowed
+        if matches!(self.peek(), Some('[' | '{'))
+            && self.max_depth.is_some_and(|max| self.depth >= max)
+        {
+            return Err(Error::NestingTooDeep);
+        }
+
+        let start = self.here();
+        let value = match self.peek() {
+            Some('[') => {
+                self.depth += 1;
+                let values = self.list();
+                self.depth -= 1;
+                SpannedJson::List(values?)
+            }
+            Some('{') => {
+                self.depth += 1;
+                let items = self.object();
+                self.depth -= 1;
+                SpannedJson::Object(items?)
+            }
+            Some('"') => SpannedJson::String(Json::read_string(&mut *self)?),
+            Some(ch) if ch.is_alphabetic() => {
+                Json::read_literal(&mut *self)?.map_or(SpannedJson::Null, SpannedJson::Bool)
+            }
+            Some('0'..='9' | '.' | '-' | '+') => {
+                SpannedJson::Number(Json::read_number(&mut *self)?)
+            }
+            Some(_) => return Err(Error::InvalidValue),
+            None => return Err(Error::UnexpectedEndOfFile),
+        };
+        Ok(Spanned {
+            value,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Reads the values of a list
+    fn list(&mut self) -> Result<Vec<Spanned<SpannedJson>>, Error> {
+        self.next();
+        let mut values = Vec::new();
+        if self.skip_whitespace() == Some(']') {
+            self.next();
+            return Ok(values);
+        }
+        loop {
+            values.push(self.value()?);
+            match self.skip_whitespace() {
+                Some(']') => {
+                    self.next();
+                    return Ok(values);
+                }
+                Some(',') => {
+                    self.next();
+                    self.skip_whitespace();
+                }
+                Some(_) => return Err(Error::MissingSeparator),
+                None => return Err(Error::UnclosedList),
+            }
+        }
+    }
+
+    /// Reads the properties of an object
+    fn object(&mut self) -> Result<Vec<SpannedProperty>, Error> {
+        self.next();
+        let mut items = Vec::new();
+        if self.skip_whitespace() == Some('}') {
+            self.next();
+            return Ok(items);
+        }
+        loop {
+            // Read the key and the value
+            let start = self.here();
+            let key = Spanned {
+                value: Json::read_string(&mut *self)?,
+                span: self.span_from(start),
+            };
+            self.skip_whitespace();
+            if self.next() != Some(':') {
+                return Err(Error::MissingSeparator);
+            }
+            self.skip_whitespace();
+            items.push((key, self.value()?));
+
+            // Find the separator or the end of the object
+            match self.skip_whitespace() {
+                Some('}') => {
+                    self.next();
+                    return Ok(items);
+                }
+                Some(',') => {
+                    self.next();
+                    self.skip_whitespace();
+                }
+                Some(_) => return Err(Error::MissingSeparator),
+                None => return Err(Error::UnclosedObject),
+            }
+        }
+    }
+}
+
+impl Iterator for Source<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ch = self.peek()?;
+        self.offset += ch.len_utf8();
+
+        // Move to the start of the next line after a newline, to the next column otherwise
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(ch)
+    }
+}
+
+impl PeekingNext for Source<'_> {
+    fn peeking_next<F: FnOnce(&Self::Item) -> bool>(&mut self, accept: F) -> Option<Self::Item> {
+        match self.peek() {
+            Some(ch) if accept(&ch) => self.next(),
+            _ => None,
+        }
+    }
+}
+
+impl Spanned<SpannedJson> {
+    /// Returns the value without the spans
+    pub fn to_json(&self) -> Json {
+        match &self.value {
+            SpannedJson::List(values) => Json::List(values.iter().map(Self::to_json).collect()),
+            SpannedJson::Object(items) => Json::Object(
+                items
+                    .iter()
+                    .map(|(key, value)| (key.value.clone(), value.to_json()))
+                    .collect(),
+            ),
+            SpannedJson::String(string) => Json::String(string.clone()),
+            SpannedJson::Number(number) => Json::Number(*number),
+            SpannedJson::Bool(value) => Json::Bool(*value),
+            SpannedJson::Null => Json::Null,
+        }
+    }
+
+    /// Looks up a value by a JSON pointer, like [`Json::pointer`]
+    pub fn pointer(&self, pointer: &str) -> Option<&Self> {
+        tokens(pointer)?.try_fold(self, |value, token| match &value.value {
+            SpannedJson::List(values) => values.get(index(&token)?),
+            SpannedJson::Object(items) => items
+                .iter()
+                .rev()
+                .find(|(key, _)| key.value == token)
+                .map(|(_, value)| value),
+            _ => None,
+        })
+    }
+
+    /// Returns the warnings about this value and the values in it
+    pub fn warnings(&self) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        self.find_warnings(&mut String::new(), &mut warnings);
+        warnings
+    }
+
+    /// Adds the warnings about this value to the list, given its JSON pointer
+    fn find_warnings(&self, pointer: &mut String, warnings: &mut Vec<Warning>) {
+        let len = pointer.len();
+        match &self.value {
+            SpannedJson::List(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    push(pointer, &index.to_string());
+                    value.find_warnings(pointer, warnings);
+                    pointer.truncate(len);
+                }
+            }
+            SpannedJson::Object(items) => {
+                let mut seen = BTreeMap::new();
+                for (key, value) in items {
+                    push(pointer, &key.value);
+
+                    // Report every repetition of a key against its first occurrence
+                    if let Some(&first) = seen.get(key.value.as_str()) {
+                        warnings.push(Warning::DuplicateKey {
+                            pointer: pointer.clone(),
+                            first,
+                            duplicate: key.span,
+                        });
+                    } else {
+                        seen.insert(key.value.as_str(), key.span);
+                    }
+                    value.find_warnings(pointer, warnings);
+                    pointer.truncate(len);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parses a complete JSON value with spans, optionally allowing comments and limiting the
+/// nesting
+pub(crate) fn parse(
+    text: &str,
+    comments: bool,
+    max_depth: Option<usize>,
+) -> Result<Spanned<SpannedJson>, Error> {
+    let mut source = Source {
+        text,
+        offset: 0,
+        line: 1,
+        column: 1,
+        comments,
+        depth: 0,
+        max_depth,
+    };
+    let result = match source.skip_whitespace() {
+        Some(_) => source.value(),
+        None => Err(Error::EmptyInput),
+    };
+    let result = result.and_then(|value| match source.skip_whitespace() {
+        Some(_) => Err(Error::TrailingCharacters),
+        None => Ok(value),
+    });
+    // Point at the last character read, like the parser does
+    result.map_err(|error| Error::At {
+        line: source.line,
+        column: source.column - 1,
+        error: Box::new(error),
+    })
+}
+
+impl Json {
+    /// Parses a complete JSON value, keeping the span of every value and key
+    ///
+    /// Errors have the line and column at which parsing stopped, and nesting is limited to 128
+    /// levels, like with [`Json::from_chars_located`].
+    pub fn parse_spanned(text: &str) -> Result<Spanned<SpannedJson>, Error> {
+        parse(text, false, Some(MAX_DEPTH))
+    }
+
+    /// Parses a complete JSON value with spans, limiting the nesting to the options'
+    /// [`max_depth`](ParseOptions::max_depth)
+    ///
+    /// The other options don't apply, as the text is already in memory and spanned numbers are
+    /// always plain.
+    pub fn parse_spanned_with(
+        text: &str,
+        options: &ParseOptions,
+    ) -> Result<Spanned<SpannedJson>, Error> {
+        parse(text, false, options.max_depth)
+    }
+
+    /// Parses a complete JSON value, also returning warnings like duplicate keys
+    pub fn parse_with_warnings(text: &str) -> Result<(Json, Vec<Warning>), Error> {
+        let root = parse(text, false, Some(MAX_DEPTH))?;
+        Ok((root.to_json(), root.warnings()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SpannedJson, Warning};
+    use crate::{Error, Json, Span};
+
+    #[test]
+    fn spans() {
+        let text = "{\n  \"é\": [1, true],\n  \"b\": \"x\"\n}";
+        let root = Json::parse_spanned(text).unwrap();
+        assert_eq!(root.to_json(), text.parse::<Json>().unwrap());
+        assert_eq!(
+            root.span,
+            Span {
+                start: 0,
+                end: text.len(),
+                line: 1,
+                column: 1
+            }
+        );
+
+        // Nested values and keys point at their own text
+        let list = root.pointer("/é").unwrap();
+        assert_eq!(&text[list.span.start..list.span.end], "[1, true]");
+        assert_eq!((list.span.line, list.span.column), (2, 8));
+        let value = root.pointer("/é/1").unwrap();
+        assert_eq!(value.value, SpannedJson::Bool(true));
+        assert_eq!((value.span.line, value.span.column), (2, 12));
+        let SpannedJson::Object(items) = &root.value else {
+            panic!("root isn't an object");
+        };
+        assert_eq!(&text[items[1].0.span.start..items[1].0.span.end], "\"b\"");
+    }
+
+    #[test]
+    fn errors() {
+        let error = Json::parse_spanned("[1,\n  ?]").unwrap_err();
+        assert!(matches!(
+            error,
+            Error::At {
+                line: 2,
+                column: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn errors_like_the_parser() {
+        for text in [
+            "[1,\n  ?]",
+            "{\"a\" 1}",
+            "[tru]",
+            "\n[1, 2",
+            "\"a\tb\"",
+            "[01]",
+            "{\"a\": [1,]}",
+            "[1] x",
+            "",
+            &"[".repeat(1_000_000),
+        ] {
+            let position = |error| match error {
+                Error::At { line, column, .. } => (line, column),
+                _ => panic!("{text:?} has no position"),
+            };
+            assert_eq!(
+                position(Json::parse_spanned(text).unwrap_err()),
+                position(Json::from_chars_located(text.chars()).unwrap_err()),
+                "{text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let (value, warnings) =
+            Json::parse_with_warnings("{\"a\": 1,\n \"b\": [{\"c\": 1, \"c\": 2}],\n \"a\": 3}")
+                .unwrap();
+        assert_eq!(value.pointer("/a"), Some(&Json::Number(3.0)));
+        let [
+            Warning::DuplicateKey {
+                pointer,
+                first,
+                duplicate,
+            },
+            Warning::DuplicateKey { pointer: outer, .. },
+        ] = warnings.as_slice()
+        else {
+            panic!("expected two warnings, found {warnings:?}");
+        };
+        assert_eq!(pointer, "/b/0/c");
+        assert_eq!((first.line, first.column), (2, 9));
+        assert_eq!((duplicate.line, duplicate.column), (2, 17));
+        assert_eq!(outer, "/a");
+    }
+}
@@ -0,0 +1,148 @@
+//! Parsing of multiple concatenated top-level JSON values
+
+use alloc::boxed::Box;
+
+use crate::{
+    Chars, Error, IterSource, Json, ParseOptions,
+    parser::{Owned, ParseState},
+    source::SourceChars,
+};
+
+/// An iterator parsing back-to-back JSON values, like `{"a":1}{"b":2}` or `1 2 3`
+pub struct JsonStream<I: Iterator<Item = char>> {
+    /// The characters that haven't been read yet
+    iter: SourceChars<IterSource<I>>,
+
+    /// Whether an error occured, after which no more values can be read
+    failed: bool,
+}
+
+impl<I: Iterator<Item = char>> JsonStream<I> {
+    /// Creates an iterator parsing concatenated JSON values from characters
+    pub fn new(iter: I) -> Self {
+        Self {
+            iter: SourceChars(IterSource::new(iter)),
+            failed: false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for JsonStream<I> {
+    type Item = Result<Json, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Stop after an error, because the start of the next value is unknown
+        if self.failed {
+            return None;
+        }
+
+        // Find the start of the next value, stop if there are no chars left
+        Json::skip_whitespace(&mut self.iter);
+        self.iter.peek()?;
+
+        // Parse the value
+        let result = Json::parse_value(
+            &mut self.iter,
+            &mut ParseState::new(&ParseOptions::default()),
+            &mut Owned,
+            0,
+        );
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+/// An iterator parsing whitespace-separated top-level JSON values, adding the index of the
+/// value to errors
+pub struct JsonValues<I: Iterator<Item = char>> {
+    /// The values, parsed one at a time
+    stream: JsonStream<I>,
+
+    /// The index of the next value, starting at 0
+    index: usize,
+}
+
+impl<I: Iterator<Item = char>> Iterator for JsonValues<I> {
+    type Item = Result<Json, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.stream.next()?;
+        self.index += 1;
+        Some(result.map_err(|error| Error::Document {
+            index: self.index - 1,
+            error: Box::new(error),
+        }))
+    }
+}
+
+impl Json {
+    /// Parses whitespace-separated JSON values from characters one at a time, like the
+    /// documents of a log, adding the index of the failing value to errors
+    pub fn iter_values<I: Iterator<Item = char>>(iter: I) -> JsonValues<I> {
+        JsonValues {
+            stream: JsonStream::new(iter),
+            index: 0,
+        }
+    }
+
+    /// Parses concatenated JSON values from characters
+    pub fn stream<I: Iterator<Item = char>>(iter: I) -> JsonStream<I> {
+        JsonStream::new(iter)
+    }
+
+    /// Parses concatenated JSON values from UTF-8 bytes
+    pub fn stream_from_bytes<I: Iterator<Item = u8>>(iter: I) -> JsonStream<Chars<I>> {
+        JsonStream::new(Chars::new(iter))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, string::ToString, vec::Vec};
+
+    use crate::{Error, Json};
+
+    #[test]
+    fn concatenated_parsing() {
+        let values = Json::stream("{\"a\":1}{\"b\":2} [] \"c\"\n3 null".chars())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            values,
+            [
+                Json::Object(Vec::from([("a".to_owned(), Json::Number(1.0))])),
+                Json::Object(Vec::from([("b".to_owned(), Json::Number(2.0))])),
+                Json::List(Vec::new()),
+                Json::String("c".to_owned()),
+                Json::Number(3.0),
+                Json::Null
+            ]
+        );
+        assert_eq!(Json::stream("  ".chars()).count(), 0);
+    }
+
+    #[test]
+    fn indexed_values() {
+        let mut values = Json::iter_values("{\"a\":1}\n[2]\n{\"b\" 3}\n".chars());
+        assert_eq!(
+            values.next().unwrap().unwrap().pointer("/a"),
+            Some(&Json::Number(1.0))
+        );
+        assert!(values.next().unwrap().is_ok());
+        let error = values.next().unwrap().unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Document { index: 2, ref error } if matches!(**error, Error::MissingSeparator)
+        ));
+        assert_eq!(error.to_string(), "missing separator in document 2");
+        assert!(values.next().is_none());
+    }
+
+    #[test]
+    fn stops_after_error() {
+        let mut values = Json::stream("1 [2 x 3".chars());
+        assert_eq!(values.next().unwrap().unwrap(), Json::Number(1.0));
+        assert!(values.next().unwrap().is_err());
+        assert!(values.next().is_none());
+    }
+}
@@ -0,0 +1,103 @@
+//! Expansion of `${NAME}` placeholders in strings, for configuration files
+
+use alloc::string::String;
+
+use crate::Json;
+
+/// Expands the placeholders in a string, returning `None` if it has none
+fn expand<F: FnMut(&str) -> Option<String>>(text: &str, lookup: &mut F) -> Option<String> {
+    let mut rest = text.split_once("${")?;
+    let mut out = String::with_capacity(text.len());
+    loop {
+        // Copy the text before the placeholder, and the rest if it isn't closed
+        let (before, after) = rest;
+        out.push_str(before);
+        let Some((placeholder, after)) = after.split_once('}') else {
+            out.push_str("${");
+            out.push_str(after);
+            return Some(out);
+        };
+
+        // Replace the placeholder by its value or default, or keep it if it has neither
+        let (name, default) = match placeholder.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (placeholder, None),
+        };
+        match lookup(name) {
+            Some(value) => out.push_str(&value),
+            None => match default {
+                Some(default) => out.push_str(default),
+                None => {
+                    out.push_str("${");
+                    out.push_str(placeholder);
+                    out.push('}');
+                }
+            },
+        }
+
+        // Continue after the placeholder
+        match after.split_once("${") {
+            Some(next) => rest = next,
+            None => {
+                out.push_str(after);
+                return Some(out);
+            }
+        }
+    }
+}
+
+impl Json {
+    /// Replaces `${NAME}` placeholders in every string value with what `lookup` returns for the
+    /// name, e.g. `|name| std::env::var(name).ok()`
+    ///
+    /// `${NAME:-default}` falls back to the default if `lookup` returns `None`, and other
+    /// placeholders without a value are left as they are. Keys aren't changed.
+    pub fn expand_placeholders<F: FnMut(&str) -> Option<String>>(&mut self, mut lookup: F) {
+        self.expand_with(&mut lookup);
+    }
+
+    /// Replaces the placeholders in this value and the values in it
+    fn expand_with<F: FnMut(&str) -> Option<String>>(&mut self, lookup: &mut F) {
+        match self {
+            Json::List(values) => values
+                .iter_mut()
+                .for_each(|value| value.expand_with(lookup)),
+            Json::Object(items) => items
+                .iter_mut()
+                .for_each(|(_, value)| value.expand_with(lookup)),
+            Json::String(text) => {
+                if let Some(expanded) = expand(text, lookup) {
+                    *text = expanded;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::ToOwned;
+
+    use crate::Json;
+
+    #[test]
+    fn placeholders() {
+        let mut config = "{\"${HOST}\":\"${HOST}:${PORT:-8080}\",\
+            \"paths\":[\"${HOME}/data\",\"${MISSING}\",\"${open\"],\"cost\":\"$5\"}"
+            .parse::<Json>()
+            .unwrap();
+        config.expand_placeholders(|name| match name {
+            "HOST" => Some("localhost".to_owned()),
+            "HOME" => Some("/home/me".to_owned()),
+            _ => None,
+        });
+        assert_eq!(
+            config,
+            "{\"${HOST}\":\"localhost:8080\",\
+            \"paths\":[\"/home/me/data\",\"${MISSING}\",\"${open\"],\"cost\":\"$5\"}"
+                .parse::<Json>()
+                .unwrap()
+        );
+    }
+}
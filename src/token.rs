@@ -0,0 +1,261 @@
+//! Splitting JSON text into tokens for syntax highlighting and formatting
+
+/// A range of the source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// The byte offset of the first character
+    pub start: usize,
+
+    /// The byte offset after the last character
+    pub end: usize,
+
+    /// The line of the first character, starting at 1
+    pub line: usize,
+
+    /// The column of the first character in characters, starting at 1
+    pub column: usize,
+}
+
+/// What a token is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A string followed by a colon
+    Key,
+
+    /// Any other string
+    String,
+
+    /// A number
+    Number,
+
+    /// `true`, `false` or `null`
+    Literal,
+
+    /// One of `{`, `}`, `[`, `]`, `:` and `,`
+    Punctuation,
+
+    /// Spaces, tabs and line breaks
+    Whitespace,
+
+    /// A `//` or `/* */` comment
+    Comment,
+
+    /// Text that can't be part of JSON, like an unclosed string or an unknown word
+    Invalid,
+}
+
+/// A piece of the source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    /// What the token is
+    pub kind: TokenKind,
+
+    /// The text of the token
+    pub text: &'a str,
+
+    /// Where the token is in the source text
+    pub span: Span,
+}
+
+/// The tokens of a text, which together are exactly the text
+///
+/// Tokenizing never fails, as editors highlight documents while they're being typed: anything
+/// that isn't valid becomes an [`TokenKind::Invalid`] token and the tokens after it are still found.
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    /// The source text
+    text: &'a str,
+
+    /// The span of the next token, of which only the start is known
+    next: Span,
+}
+
+impl<'a> Tokens<'a> {
+    /// Creates an iterator over the tokens of a text
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            next: Span {
+                start: 0,
+                end: 0,
+                line: 1,
+                column: 1,
+            },
+        }
+    }
+}
+
+/// Returns the length of the text at the start of `rest` whose characters match
+fn length(rest: &str, matches: impl Fn(char) -> bool) -> usize {
+    rest.find(|ch| !matches(ch)).unwrap_or(rest.len())
+}
+
+/// Returns whether the text is a number as JSON allows it
+pub(crate) fn is_number(text: &str) -> bool {
+    // Split into the integer, the fraction and the exponent
+    let text = text.strip_prefix('-').unwrap_or(text);
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(index) => (&text[..index], Some(&text[index + 1..])),
+        None => (text, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (mantissa, None),
+    };
+    let exponent = exponent.map(|exponent| exponent.strip_prefix(['+', '-']).unwrap_or(exponent));
+
+    // Integers can't have leading zeros, and every part needs digits
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit());
+    digits(integer)
+        && (integer == "0" || !integer.starts_with('0'))
+        && fraction.is_none_or(digits)
+        && exponent.is_none_or(digits)
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.text[self.next.start..];
+        let first = rest.chars().next()?;
+
+        // Find the length and kind of the token starting here
+        let (len, kind) = match first {
+            ' ' | '\t' | '\n' | '\r' => (
+                length(rest, |ch| matches!(ch, ' ' | '\t' | '\n' | '\r')),
+                TokenKind::Whitespace,
+            ),
+            '{' | '}' | '[' | ']' | ':' | ',' => (1, TokenKind::Punctuation),
+            '"' => {
+                // Find the closing quote, which has to be on the same line
+                let mut escaped = false;
+                let end = rest[1..].find(|ch| {
+                    let end = matches!(ch, '"' | '\n') && !escaped;
+                    escaped = ch == '\\' && !escaped;
+                    end
+                });
+                match end.map(|end| end + 1) {
+                    Some(end) if rest[end..].starts_with('"') => {
+                        let after = rest[end + 1..].trim_start_matches([' ', '\t', '\n', '\r']);
+                        let kind = if after.starts_with(':') {
+                            TokenKind::Key
+                        } else {
+                            TokenKind::String
+                        };
+                        (end + 1, kind)
+                    }
+                    end => (end.unwrap_or(rest.len()), TokenKind::Invalid),
+                }
+            }
+            '/' if rest.starts_with("//") => (length(rest, |ch| ch != '\n'), TokenKind::Comment),
+            '/' if rest.starts_with("/*") => match rest[2..].find("*/") {
+                Some(end) => (end + 4, TokenKind::Comment),
+                None => (rest.len(), TokenKind::Invalid),
+            },
+            '-' | '0'..='9' => {
+                let len = length(rest, |ch| ch.is_ascii_digit() || "+-.eE".contains(ch));
+                let kind = if is_number(&rest[..len]) {
+                    TokenKind::Number
+                } else {
+                    TokenKind::Invalid
+                };
+                (len, kind)
+            }
+            _ if first.is_alphanumeric() || first == '_' => {
+                let len = length(rest, |ch| ch.is_alphanumeric() || ch == '_');
+                let kind = match &rest[..len] {
+                    "true" | "false" | "null" => TokenKind::Literal,
+                    _ => TokenKind::Invalid,
+                };
+                (len, kind)
+            }
+            _ => (first.len_utf8(), TokenKind::Invalid),
+        };
+
+        // Move the start of the next token past this one
+        let text = &rest[..len];
+        let span = Span {
+            end: self.next.start + len,
+            ..self.next
+        };
+        self.next.start = span.end;
+        match text.rfind('\n') {
+            Some(index) => {
+                self.next.line += text.matches('\n').count();
+                self.next.column = text[index + 1..].chars().count() + 1;
+            }
+            None => self.next.column += text.chars().count(),
+        }
+        Some(Token { kind, text, span })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Span, TokenKind, Tokens};
+
+    #[test]
+    fn tokens() {
+        let text = "{\"a\" : [1.5e3, true], // é\n\"b\": \"x\" /* c */}";
+        let tokens = Tokens::new(text)
+            .filter(|token| token.kind != TokenKind::Whitespace)
+            .map(|token| (token.kind, token.text));
+        assert!(tokens.eq([
+            (TokenKind::Punctuation, "{"),
+            (TokenKind::Key, "\"a\""),
+            (TokenKind::Punctuation, ":"),
+            (TokenKind::Punctuation, "["),
+            (TokenKind::Number, "1.5e3"),
+            (TokenKind::Punctuation, ","),
+            (TokenKind::Literal, "true"),
+            (TokenKind::Punctuation, "]"),
+            (TokenKind::Punctuation, ","),
+            (TokenKind::Comment, "// é"),
+            (TokenKind::Key, "\"b\""),
+            (TokenKind::Punctuation, ":"),
+            (TokenKind::String, "\"x\""),
+            (TokenKind::Comment, "/* c */"),
+            (TokenKind::Punctuation, "}"),
+        ]));
+
+        // The spans point at the tokens
+        let b = Tokens::new(text)
+            .find(|token| token.text == "\"b\"")
+            .unwrap();
+        assert_eq!(
+            b.span,
+            Span {
+                start: 28,
+                end: 31,
+                line: 2,
+                column: 1
+            }
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let text = "[01, nul, \"open\n, \"\\\"\"]";
+        let tokens = Tokens::new(text)
+            .filter(|token| token.kind != TokenKind::Whitespace)
+            .map(|token| (token.kind, token.text));
+        assert!(tokens.eq([
+            (TokenKind::Punctuation, "["),
+            (TokenKind::Invalid, "01"),
+            (TokenKind::Punctuation, ","),
+            (TokenKind::Invalid, "nul"),
+            (TokenKind::Punctuation, ","),
+            (TokenKind::Invalid, "\"open"),
+            (TokenKind::Punctuation, ","),
+            (TokenKind::String, "\"\\\"\""),
+            (TokenKind::Punctuation, "]"),
+        ]));
+
+        // The tokens cover the whole text without gaps
+        let end = Tokens::new(text).try_fold(0, |start, token| {
+            (token.span.start == start && &text[start..token.span.end] == token.text)
+                .then_some(token.span.end)
+        });
+        assert_eq!(end, Some(text.len()));
+    }
+}
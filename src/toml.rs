@@ -0,0 +1,137 @@
+//! Conversion between JSON values and TOML documents
+//!
+//! Tables become objects, arrays become lists and datetimes become their RFC 3339 strings.
+//! TOML has no null value, so null values can't be converted to TOML.
+
+use alloc::string::{String, ToString};
+
+use toml::{Table, Value};
+
+use crate::{Error, Json};
+
+/// Converts a TOML value to a JSON value
+fn from_value(value: Value) -> Json {
+    match value {
+        Value::String(string) => Json::String(string),
+        Value::Integer(number) => Json::Number(number as f64),
+        Value::Float(number) => Json::Number(number),
+        Value::Boolean(value) => Json::Bool(value),
+        Value::Datetime(datetime) => Json::String(datetime.to_string()),
+        Value::Array(values) => Json::List(values.into_iter().map(from_value).collect()),
+        Value::Table(table) => from_table(table),
+    }
+}
+
+/// Converts a TOML table to a JSON object
+fn from_table(table: Table) -> Json {
+    Json::Object(
+        table
+            .into_iter()
+            .map(|(key, value)| (key, from_value(value)))
+            .collect(),
+    )
+}
+
+/// Converts a JSON value to a TOML value
+fn to_value(value: &Json) -> Result<Value, Error> {
+    Ok(match value {
+        // Convert whole numbers to integers if they fit, to floats otherwise
+        Json::NumberRaw(_) => to_value(&value.plain())?,
+        Json::Number(number) => {
+            let integer = *number as i64;
+            if (integer as f64).to_bits() == number.to_bits() && integer != i64::MAX {
+                Value::Integer(integer)
+            } else {
+                Value::Float(*number)
+            }
+        }
+
+        // Convert the other values directly
+        Json::String(string) => Value::String(string.clone()),
+        Json::Bool(value) => Value::Boolean(*value),
+        Json::List(values) => Value::Array(values.iter().map(to_value).collect::<Result<_, _>>()?),
+        Json::Object(items) => Value::Table(to_table(items)?),
+
+        // TOML has no null value
+        Json::Null => return Err(Error::InvalidValue),
+    })
+}
+
+/// Converts the properties of a JSON object to a TOML table
+fn to_table(items: &[(String, Json)]) -> Result<Table, Error> {
+    items
+        .iter()
+        .map(|(key, value)| Ok((key.clone(), to_value(value)?)))
+        .collect()
+}
+
+impl Json {
+    /// Parses a TOML document as an object
+    pub fn from_toml_str(toml: &str) -> Result<Self, Error> {
+        toml.parse::<Table>()
+            .map(from_table)
+            .map_err(|_| Error::InvalidValue)
+    }
+
+    /// Converts an object to a TOML document
+    pub fn to_toml_string(&self) -> Result<String, Error> {
+        match self {
+            Json::Object(items) => Ok(to_table(items)?.to_string()),
+            _ => Err(Error::InvalidValue),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, vec::Vec};
+
+    use crate::Json;
+
+    #[test]
+    fn toml_parsing() {
+        assert_eq!(
+            Json::from_toml_str(
+                "title = \"x\"\n[server]\nport = 8080\nratio = 0.5\n\
+                started = 1979-05-27T07:32:00Z\ntags = [\"a\", true]\n"
+            )
+            .unwrap(),
+            Json::Object(Vec::from([
+                (
+                    "server".to_owned(),
+                    Json::Object(Vec::from([
+                        ("port".to_owned(), Json::Number(8080.0)),
+                        ("ratio".to_owned(), Json::Number(0.5)),
+                        (
+                            "started".to_owned(),
+                            Json::String("1979-05-27T07:32:00Z".to_owned())
+                        ),
+                        (
+                            "tags".to_owned(),
+                            Json::List(Vec::from([Json::String("a".to_owned()), Json::Bool(true)]))
+                        ),
+                    ]))
+                ),
+                ("title".to_owned(), Json::String("x".to_owned())),
+            ]))
+        );
+        assert!(Json::from_toml_str("a = ").is_err());
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let value = "{\"a\":1,\"b\":{\"c\":[1.5,\"d\"]}}"
+            .parse::<Json>()
+            .unwrap();
+        let toml = value.to_toml_string().unwrap();
+        assert_eq!(Json::from_toml_str(&toml).unwrap(), value);
+        assert!(Json::Null.to_toml_string().is_err());
+        assert!(
+            "{\"a\":null}"
+                .parse::<Json>()
+                .unwrap()
+                .to_toml_string()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,49 @@
+//! Reading and writing UUIDs with `uuid`
+
+use alloc::string::ToString;
+
+use uuid::Uuid;
+
+use crate::Json;
+
+impl Json {
+    /// Returns a string holding a UUID, in any of the forms `uuid` accepts, as a UUID
+    pub fn as_uuid(&self) -> Option<Uuid> {
+        match self {
+            Json::String(text) => Uuid::parse_str(text).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl From<Uuid> for Json {
+    /// Writes the UUID in its lowercase hyphenated form
+    fn from(uuid: Uuid) -> Self {
+        Json::String(uuid.hyphenated().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::ToOwned;
+
+    use uuid::Uuid;
+
+    use crate::Json;
+
+    #[test]
+    fn uuids() {
+        let text = Json::String("67e55044-10b1-426f-9247-bb680e5fe0c8".to_owned());
+        let uuid = text.as_uuid().unwrap();
+        assert_eq!(Json::from(uuid), text);
+        assert_eq!(
+            Json::String("67E5504410B1426F9247BB680E5FE0C8".to_owned()).as_uuid(),
+            Some(uuid)
+        );
+        assert_eq!(Json::from(Uuid::nil()).as_uuid(), Some(Uuid::nil()));
+
+        // Reject strings that aren't UUIDs and other values
+        assert_eq!(Json::String("67e55044-10b1".to_owned()).as_uuid(), None);
+        assert_eq!(Json::Number(1.0).as_uuid(), None);
+    }
+}
@@ -0,0 +1,106 @@
+//! JavaScript bindings through `wasm-bindgen`
+//!
+//! Lists become arrays, objects become plain objects and the other values become their
+//! primitive JavaScript counterparts. `undefined` is read as null.
+
+use alloc::{
+    format,
+    string::{String, ToString as _},
+    vec::Vec,
+};
+
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::{JsCast as _, JsError, JsValue, prelude::wasm_bindgen};
+
+use crate::Json;
+
+impl From<&Json> for JsValue {
+    fn from(value: &Json) -> Self {
+        match value {
+            Json::List(values) => values.iter().map(JsValue::from).collect::<Array>().into(),
+            Json::Object(items) => {
+                let object = Object::new();
+                for (key, value) in items {
+                    Reflect::set(&object, &JsValue::from_str(key), &JsValue::from(value))
+                        .expect("setting a property of a plain object can't fail");
+                }
+                object.into()
+            }
+            Json::String(string) => JsValue::from_str(string),
+            Json::Number(number) => JsValue::from_f64(*number),
+            Json::NumberRaw(_) => JsValue::from(&*value.plain()),
+            Json::Bool(value) => JsValue::from_bool(*value),
+            Json::Null => JsValue::NULL,
+        }
+    }
+}
+
+impl TryFrom<&JsValue> for Json {
+    type Error = JsError;
+
+    fn try_from(value: &JsValue) -> Result<Self, Self::Error> {
+        // Convert primitives
+        if value.is_null() || value.is_undefined() {
+            return Ok(Json::Null);
+        }
+        if let Some(value) = value.as_bool() {
+            return Ok(Json::Bool(value));
+        }
+        if let Some(number) = value.as_f64() {
+            return Ok(Json::Number(number));
+        }
+        if let Some(string) = value.as_string() {
+            return Ok(Json::String(string));
+        }
+
+        // Convert arrays to lists
+        if let Some(array) = value.dyn_ref::<Array>() {
+            return array
+                .iter()
+                .map(|value| Json::try_from(&value))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Json::List);
+        }
+
+        // Convert other objects to objects using their own enumerable properties
+        if let Some(object) = value.dyn_ref::<Object>() {
+            return Object::entries(object)
+                .iter()
+                .map(|entry| {
+                    let entry = Array::from(&entry);
+                    let key = entry
+                        .get(0)
+                        .as_string()
+                        .ok_or_else(|| JsError::new("property names have to be strings"))?;
+                    Ok((key, Json::try_from(&entry.get(1))?))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Json::Object);
+        }
+
+        // Functions, symbols and big integers have no JSON equivalent
+        Err(JsError::new("value can't be converted to JSON"))
+    }
+}
+
+/// Parses JSON text into a JavaScript value
+#[wasm_bindgen(js_name = parse)]
+pub fn parse(text: &str) -> Result<JsValue, JsError> {
+    text.parse::<Json>()
+        .map(|value| JsValue::from(&value))
+        .map_err(|error| JsError::new(&format!("{error}")))
+}
+
+/// Serializes a JavaScript value as JSON text
+#[wasm_bindgen(js_name = stringify)]
+pub fn stringify(value: &JsValue) -> Result<String, JsError> {
+    Ok(Json::try_from(value)?.to_string())
+}
+
+/// Returns the value referenced by a JSON pointer, or `undefined` if it doesn't exist
+#[wasm_bindgen(js_name = pointer)]
+pub fn pointer(value: &JsValue, pointer: &str) -> Result<JsValue, JsError> {
+    Ok(Json::try_from(value)?
+        .pointer(pointer)
+        .map_or(JsValue::UNDEFINED, JsValue::from))
+}
@@ -0,0 +1,270 @@
+//! Writing JSON piece by piece, without building the value in memory
+
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter, Write};
+
+use crate::{
+    Event, EventSink, Format, Json, RawJson,
+    ser::{write_colon, write_escaped, write_newline, write_value},
+};
+
+/// A mistake in the order of the calls to a [`JsonWriter`], or a failure of its output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriterError {
+    /// The output couldn't be written
+    Write,
+
+    /// A key was written outside of an object, or twice in a row
+    UnexpectedKey,
+
+    /// A value was written in an object without a key
+    MissingKey,
+
+    /// An object was ended after a key without a value
+    MissingValue,
+
+    /// There is no list or object to end
+    NothingToEnd,
+
+    /// A value was written after the top level value was complete
+    AlreadyComplete,
+
+    /// The writer was finished before the top level value was complete
+    Unfinished,
+}
+
+impl Display for WriterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WriterError::Write => write!(f, "the output couldn't be written"),
+            WriterError::UnexpectedKey => write!(f, "a key isn't allowed here"),
+            WriterError::MissingKey => write!(f, "a value in an object needs a key"),
+            WriterError::MissingValue => write!(f, "the last key has no value"),
+            WriterError::NothingToEnd => write!(f, "there is no list or object to end"),
+            WriterError::AlreadyComplete => write!(f, "the value is already complete"),
+            WriterError::Unfinished => write!(f, "the value isn't complete"),
+        }
+    }
+}
+
+impl core::error::Error for WriterError {}
+
+impl From<fmt::Error> for WriterError {
+    fn from(_: fmt::Error) -> Self {
+        WriterError::Write
+    }
+}
+
+/// A list or object the writer is inside of
+struct Container {
+    /// Whether the container is an object
+    object: bool,
+
+    /// Whether nothing has been written into the container yet
+    empty: bool,
+}
+
+/// Writes a JSON value piece by piece, handling separators and escaping
+///
+/// The calls are checked to form a single valid value, so output can be generated from other
+/// data without building a [`Json`] tree first.
+pub struct JsonWriter<W: Write> {
+    /// Where the text goes
+    out: W,
+
+    /// The formatting options
+    format: Format,
+
+    /// The containers around the current position, innermost last
+    stack: Vec<Container>,
+
+    /// Whether a key has been written and its value is next
+    after_key: bool,
+
+    /// Whether the top level value is complete
+    complete: bool,
+}
+
+impl<W: Write> JsonWriter<W> {
+    /// Creates a writer with the given formatting options
+    pub fn new(out: W, format: Format) -> Self {
+        Self {
+            out,
+            format,
+            stack: Vec::new(),
+            after_key: false,
+            complete: false,
+        }
+    }
+
+    /// Writes the separator before the next value or key, checking that it's allowed
+    fn separate(&mut self, key: bool) -> Result<(), WriterError> {
+        // The value of a property follows its key directly
+        if self.after_key {
+            if key {
+                return Err(WriterError::UnexpectedKey);
+            }
+            self.after_key = false;
+            return Ok(());
+        }
+
+        // Values in lists and keys in objects are separated by commas
+        let depth = self.stack.len();
+        match self.stack.last_mut() {
+            None if self.complete => Err(WriterError::AlreadyComplete),
+            None if key => Err(WriterError::UnexpectedKey),
+            None => Ok(()),
+            Some(container) if container.object != key => Err(match key {
+                true => WriterError::UnexpectedKey,
+                false => WriterError::MissingKey,
+            }),
+            Some(container) => {
+                if !core::mem::replace(&mut container.empty, false) {
+                    self.out.write_char(',')?;
+                }
+                Ok(write_newline(&mut self.out, &self.format, depth)?)
+            }
+        }
+    }
+
+    /// Marks the top level value complete if nothing is open anymore
+    fn ended_value(&mut self) {
+        self.complete = self.stack.is_empty();
+    }
+
+    /// Starts an object, whose properties follow as keys and values
+    pub fn begin_object(&mut self) -> Result<(), WriterError> {
+        self.separate(false)?;
+        self.out.write_char('{')?;
+        self.stack.push(Container {
+            object: true,
+            empty: true,
+        });
+        Ok(())
+    }
+
+    /// Starts a list, whose values follow
+    pub fn begin_list(&mut self) -> Result<(), WriterError> {
+        self.separate(false)?;
+        self.out.write_char('[')?;
+        self.stack.push(Container {
+            object: false,
+            empty: true,
+        });
+        Ok(())
+    }
+
+    /// Writes the key of the next property of an object
+    pub fn key(&mut self, key: &str) -> Result<(), WriterError> {
+        self.separate(true)?;
+        write_escaped(&mut self.out, key, self.format.escape_slashes, false)?;
+        write_colon(&mut self.out, &self.format)?;
+        self.after_key = true;
+        Ok(())
+    }
+
+    /// Writes a whole value
+    pub fn value(&mut self, value: &Json) -> Result<(), WriterError> {
+        self.separate(false)?;
+        write_value(&mut self.out, value, &self.format, self.stack.len())?;
+        self.ended_value();
+        Ok(())
+    }
+
+    /// Writes an already serialized value as it is
+    pub fn raw(&mut self, value: &RawJson) -> Result<(), WriterError> {
+        self.separate(false)?;
+        self.out.write_str(value.as_str())?;
+        self.ended_value();
+        Ok(())
+    }
+
+    /// Ends the innermost list or object
+    pub fn end(&mut self) -> Result<(), WriterError> {
+        if self.after_key {
+            return Err(WriterError::MissingValue);
+        }
+        let container = self.stack.pop().ok_or(WriterError::NothingToEnd)?;
+        if !container.empty || self.format.expand_empty {
+            write_newline(&mut self.out, &self.format, self.stack.len())?;
+        }
+        self.out
+            .write_char(if container.object { '}' } else { ']' })?;
+        self.ended_value();
+        Ok(())
+    }
+
+    /// Returns the output after checking that the value is complete
+    pub fn finish(self) -> Result<W, WriterError> {
+        match self.complete {
+            true => Ok(self.out),
+            false => Err(WriterError::Unfinished),
+        }
+    }
+}
+
+impl<W: Write> EventSink for JsonWriter<W> {
+    type Error = WriterError;
+
+    fn event(&mut self, event: Event) -> Result<(), Self::Error> {
+        match event {
+            Event::StartObject => self.begin_object(),
+            Event::StartList => self.begin_list(),
+            Event::EndObject | Event::EndList => self.end(),
+            Event::Key(key) => self.key(&key),
+            Event::String(string) => self.value(&Json::String(string)),
+            Event::Number(number) => self.value(&Json::Number(number)),
+            Event::Bool(value) => self.value(&Json::Bool(value)),
+            Event::Null => self.value(&Json::Null),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::{JsonWriter, WriterError};
+    use crate::{Format, Json};
+
+    #[test]
+    fn writing() {
+        let mut writer = JsonWriter::new(String::new(), Format::pretty());
+        writer.begin_object().unwrap();
+        writer.key("a").unwrap();
+        writer.begin_list().unwrap();
+        writer.value(&Json::Number(1.0)).unwrap();
+        writer.begin_object().unwrap();
+        writer.end().unwrap();
+        writer.end().unwrap();
+        writer.key("b\"").unwrap();
+        writer.value(&Json::Null).unwrap();
+        writer.end().unwrap();
+        let text = writer.finish().unwrap();
+        assert_eq!(
+            text,
+            "{\n  \"a\": [\n    1,\n    {}\n  ],\n  \"b\\\"\": null\n}"
+        );
+        assert_eq!(text.parse::<Json>().unwrap().to_string_pretty(), text);
+    }
+
+    #[test]
+    fn misuse() {
+        let mut writer = JsonWriter::new(String::new(), Format::compact());
+        assert_eq!(writer.key("a"), Err(WriterError::UnexpectedKey));
+        assert_eq!(writer.end(), Err(WriterError::NothingToEnd));
+        writer.begin_object().unwrap();
+        assert_eq!(writer.value(&Json::Null), Err(WriterError::MissingKey));
+        writer.key("a").unwrap();
+        assert_eq!(writer.key("b"), Err(WriterError::UnexpectedKey));
+        assert_eq!(writer.end(), Err(WriterError::MissingValue));
+        writer.value(&Json::Bool(true)).unwrap();
+        writer.end().unwrap();
+        assert_eq!(writer.value(&Json::Null), Err(WriterError::AlreadyComplete));
+        assert_eq!(writer.finish().unwrap(), "{\"a\":true}");
+
+        let mut writer = JsonWriter::new(String::new(), Format::compact());
+        writer.begin_list().unwrap();
+        assert_eq!(writer.finish(), Err(WriterError::Unfinished));
+    }
+}
@@ -0,0 +1,146 @@
+//! Conversion between JSON values and YAML documents
+//!
+//! Anchors are resolved while loading, so aliased nodes become copies of the anchored value.
+//! Mapping keys have to be scalars, which are converted to strings.
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+};
+
+use yaml_rust2::{Yaml, YamlEmitter, YamlLoader, yaml::Hash};
+
+use crate::{Error, Json};
+
+/// Converts a YAML node to a JSON value
+fn from_yaml(yaml: Yaml) -> Result<Json, Error> {
+    Ok(match yaml {
+        // Convert scalars
+        Yaml::Null => Json::Null,
+        Yaml::Boolean(value) => Json::Bool(value),
+        Yaml::Integer(number) => Json::Number(number as f64),
+        Yaml::Real(number) => Json::Number(Yaml::Real(number).as_f64().ok_or(Error::InvalidValue)?),
+        Yaml::String(string) => Json::String(string),
+
+        // Convert sequences to lists
+        Yaml::Array(values) => Json::List(
+            values
+                .into_iter()
+                .map(from_yaml)
+                .collect::<Result<_, _>>()?,
+        ),
+
+        // Convert mappings to objects, using the string form of scalar keys
+        Yaml::Hash(items) => Json::Object(
+            items
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Yaml::String(key) | Yaml::Real(key) => key,
+                        Yaml::Integer(key) => key.to_string(),
+                        Yaml::Boolean(key) => key.to_string(),
+                        Yaml::Null => "null".to_owned(),
+                        _ => return Err(Error::InvalidValue),
+                    };
+                    Ok((key, from_yaml(value)?))
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+
+        // Unresolved aliases and invalid values can't be converted
+        Yaml::Alias(_) | Yaml::BadValue => return Err(Error::InvalidValue),
+    })
+}
+
+/// Converts a JSON value to a YAML node
+fn to_yaml(value: &Json) -> Yaml {
+    match value {
+        // Convert whole numbers to integers if they fit, to reals otherwise
+        Json::NumberRaw(_) => to_yaml(&value.plain()),
+        Json::Number(number) => {
+            let integer = *number as i64;
+            if (integer as f64).to_bits() == number.to_bits() && integer != i64::MAX {
+                Yaml::Integer(integer)
+            } else {
+                Yaml::Real(number.to_string())
+            }
+        }
+
+        // Convert the other values directly
+        Json::Null => Yaml::Null,
+        Json::Bool(value) => Yaml::Boolean(*value),
+        Json::String(string) => Yaml::String(string.clone()),
+        Json::List(values) => Yaml::Array(values.iter().map(to_yaml).collect()),
+        Json::Object(items) => {
+            let mut hash = Hash::new();
+            for (key, value) in items {
+                hash.insert(Yaml::String(key.clone()), to_yaml(value));
+            }
+            Yaml::Hash(hash)
+        }
+    }
+}
+
+impl Json {
+    /// Parses the first document of a YAML stream, returning null if there are no documents
+    pub fn from_yaml_str(yaml: &str) -> Result<Self, Error> {
+        match YamlLoader::load_from_str(yaml)
+            .map_err(|_| Error::InvalidValue)?
+            .into_iter()
+            .next()
+        {
+            Some(document) => from_yaml(document),
+            None => Ok(Json::Null),
+        }
+    }
+
+    /// Converts the value to a YAML document
+    pub fn to_yaml_string(&self) -> String {
+        let mut out = String::new();
+        YamlEmitter::new(&mut out)
+            .dump(&to_yaml(self))
+            .expect("writing to a string can't fail");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::ToOwned, vec::Vec};
+
+    use crate::Json;
+
+    #[test]
+    fn yaml_parsing() {
+        assert_eq!(
+            Json::from_yaml_str(
+                "base: &base\n  port: 80\n  ratio: 0.5\nother: *base\nlist:\n  - a\n  - ~\n  - yes\n1: true\n"
+            )
+            .unwrap(),
+            "{\"base\":{\"port\":80,\"ratio\":0.5},\"other\":{\"port\":80,\"ratio\":0.5},\
+                \"list\":[\"a\",null,\"yes\"],\"1\":true}"
+                .parse::<Json>()
+                .unwrap()
+        );
+        assert_eq!(Json::from_yaml_str("").unwrap(), Json::Null);
+        assert!(Json::from_yaml_str("a: [").is_err());
+        assert!(Json::from_yaml_str("? [a]\n: b\n").is_err());
+    }
+
+    #[test]
+    fn yaml_round_trip() {
+        let value = Json::Object(Vec::from([
+            ("a".to_owned(), Json::Number(1.0)),
+            ("b".to_owned(), Json::Number(-2.5)),
+            (
+                "c".to_owned(),
+                Json::List(Vec::from([
+                    Json::String("true".to_owned()),
+                    Json::Null,
+                    Json::Object(Vec::new()),
+                ])),
+            ),
+        ]));
+        assert_eq!(Json::from_yaml_str(&value.to_yaml_string()).unwrap(), value);
+    }
+}